@@ -5,7 +5,7 @@
 use crate::config::Config;
 use crate::errors::Result;
 use crate::fuzzy::find_best_match;
-use crate::monitoring::ActivityEvent;
+use crate::monitoring::{ActivityEvent, ActivityLog};
 use crate::state::StateManager;
 use crate::vcs::{init_backend_from_current_dir, VcsType};
 use colored::*;
@@ -71,7 +71,7 @@ fn show_worktree_activity(name: &str, state_manager: &StateManager) -> Result<()
         return Ok(());
     }
 
-    let events: Vec<ActivityEvent> = serde_json::from_str(&content)?;
+    let events = ActivityLog::load(&activity_log)?.events;
 
     if events.is_empty() {
         println!("  {}", "No events found".yellow());
@@ -120,7 +120,7 @@ fn show_all_activity(
             continue;
         }
 
-        let events: Vec<ActivityEvent> = serde_json::from_str(&content)?;
+        let events = ActivityLog::load(&activity_log)?.events;
         if events.is_empty() {
             continue;
         }
@@ -172,6 +172,12 @@ fn display_event(event: &ActivityEvent) {
         ActivityEvent::WorktreeRemoved { timestamp } => {
             (*timestamp, "🗑️", "Worktree removed".to_string())
         }
+        ActivityEvent::WorktreeRenamed { timestamp, from } => {
+            (*timestamp, "✏️", format!("Renamed from {}", from.yellow()))
+        }
+        ActivityEvent::WorktreeAdopted { timestamp, branch } => {
+            (*timestamp, "📥", format!("Adopted on {}", branch.yellow()))
+        }
         ActivityEvent::WorktreeSwitched { timestamp, from } => {
             let desc = if let Some(f) = from {
                 format!("Switched from {}", f.yellow())
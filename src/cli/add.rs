@@ -1,17 +1,18 @@
 use crate::config::Config;
 use crate::docker::compose::ComposeGenerator;
 use crate::docker::container::ContainerManager;
-use crate::docker::ports::PortAllocator;
 use crate::env::copy::{CopyAction, CopyManager};
 use crate::env::symlinks::{SymlinkAction, SymlinkManager};
 use crate::env::validation;
 use crate::errors::Result;
 use crate::hooks::{HookExecutor, HookType};
 use crate::monitoring::{self, ActivityEvent};
+use crate::restack;
 use crate::state::StateManager;
-use crate::vcs::{init_backend_from_current_dir, RegistryCache, VcsType};
+use crate::vcs::{init_backend_from_current_dir, RegistryCache, VcsType, WorktreeLayout};
 use colored::*;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::path::Path;
 
 /// Parameters collected from interactive prompts
 struct WorktreeParams {
@@ -87,6 +88,14 @@ pub fn run(
     let has_pre_create_hooks =
         config.hooks.pre_create.is_some() || !config.hooks.pre_create_conditions.is_empty();
 
+    // Ask about untrusted repository hooks before anything is created, rather
+    // than leaving a half set up worktree behind
+    let has_post_create_hooks =
+        config.hooks.post_create.is_some() || !config.hooks.post_create_conditions.is_empty();
+    if (has_pre_create_hooks || has_post_create_hooks) && !no_hooks {
+        crate::trust::ensure_trusted(&repo_root)?;
+    }
+
     if has_pre_create_hooks && !no_hooks {
         eprintln!("Running pre_create hook...");
 
@@ -104,14 +113,16 @@ pub fn run(
 
         let temp_worktree = crate::vcs::Worktree {
             name: name.clone(),
-            path: repo_root.join(&name), // Estimated path
+            path: WorktreeLayout::load(&repo_root)?.path_for(&name),
             branch: effective_branch,
             commit: String::new(), // Not known yet
             parent: None,          // Will be set later
         };
 
         let state_dir = repo_root.join(".hn-state").join(&name);
-        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root)
+            .with_template(template.clone())
+            .with_profile(profile.clone());
         hook_executor.run_hook(HookType::PreCreate, &temp_worktree, &state_dir)?;
         eprintln!("✓ Pre-create hook completed successfully");
     } else if has_pre_create_hooks && no_hooks {
@@ -173,53 +184,25 @@ pub fn run(
     let state_manager = StateManager::new(&repo_root)?;
     let state_dir = state_manager.create_state_dir(&name)?;
 
-    // Setup symlinks for shared resources
-    if !config.shared_resources.is_empty() {
-        let actions = SymlinkManager::setup(&config.shared_resources, &repo_root, &worktree.path)?;
-
-        for action in actions {
-            match action {
-                SymlinkAction::Created { source, target: _ } => {
-                    eprintln!(
-                        "✓ Shared {} (symlinked)",
-                        source.file_name().unwrap().to_string_lossy()
-                    );
-                }
-                SymlinkAction::Skipped { resource, reason } => {
-                    eprintln!("⚠ Skipped {} ({})", resource, reason);
-                }
-            }
+    // Remember which parent commit the worktree starts from, so `hn restack`
+    // knows which commits are its own. Restack reads it from the main
+    // repository's state, even when the worktree is added from another one.
+    if let Some(parent) = worktree.parent.as_deref() {
+        if let Ok(parent) = backend.get_workspace_by_name(parent) {
+            let restack_dir = backend.main_repo_root()?.join(".hn-state").join(&name);
+            restack::record_base(backend.as_ref(), &restack_dir, &worktree, &parent.commit)?;
         }
     }
 
-    // Setup file copies from shared.copy configuration
-    if let Some(ref shared) = config.shared {
-        if !shared.copy.is_empty() {
-            let actions = CopyManager::setup(&shared.copy, &repo_root, &worktree.path)?;
-
-            for action in actions {
-                match action {
-                    CopyAction::Copied { source, target: _ } => {
-                        eprintln!(
-                            "✓ Copied {} to worktree",
-                            source.file_name().unwrap().to_string_lossy()
-                        );
-                    }
-                    CopyAction::Skipped { resource, reason } => {
-                        eprintln!("⚠ Skipped copying {} ({})", resource, reason);
-                    }
-                }
-            }
-        }
-    }
+    // Setup symlinks and copies for shared resources
+    setup_shared_resources(&config, &repo_root, &worktree.path)?;
 
     // Run post_create hook if configured (regular or conditional)
-    let has_post_create_hooks =
-        config.hooks.post_create.is_some() || !config.hooks.post_create_conditions.is_empty();
-
     if has_post_create_hooks && !no_hooks {
         eprintln!("Running post_create hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root)
+            .with_template(template.clone())
+            .with_profile(profile.clone());
         hook_executor.run_hook(HookType::PostCreate, &worktree, &state_dir)?;
         eprintln!("✓ Hook completed successfully");
     } else if has_post_create_hooks && no_hooks {
@@ -248,29 +231,21 @@ pub fn run(
     if config.docker.enabled {
         eprintln!("\nSetting up Docker...");
 
-        // Allocate ports
+        // Allocate ports and shared service isolation
         let state_dir_path = repo_root.join(".hn-state");
-        let mut port_allocator = PortAllocator::new(&state_dir_path)?;
-
-        // Get services from config or use defaults
-        let services: Vec<&str> = config
-            .docker
-            .ports
-            .base
-            .keys()
-            .map(|s| s.as_str())
-            .collect();
-
-        let ports = port_allocator.allocate(&name, &services)?;
+        let setup =
+            super::docker::prepare_worktree(&config, &state_dir_path, &name, &worktree.path)?;
 
         // Display allocated ports
-        for (service, port) in &ports {
+        for (service, port) in &setup.ports {
             eprintln!("  {} port: {}", service, port);
         }
+        for (key, value) in &setup.isolation_env {
+            eprintln!("  {}={}", key, value);
+        }
 
         // Generate docker-compose.override.yml
-        let compose_gen = ComposeGenerator::new(&config.docker, &state_dir_path);
-        compose_gen.save(&name, &worktree.path, &ports)?;
+        ComposeGenerator::new(&config.docker, &state_dir_path).write(&name, &setup.compose)?;
         eprintln!("✓ Generated docker-compose.override.yml");
 
         // Auto-start containers if configured
@@ -278,8 +253,25 @@ pub fn run(
             eprintln!("Starting Docker containers...");
             let container_mgr = ContainerManager::new(&config.docker, &state_dir_path)?;
 
-            match container_mgr.start(&name, &worktree.path) {
-                Ok(_) => eprintln!("✓ Containers started"),
+            let start_hook = |hook_type| {
+                super::docker::run_docker_hook(
+                    hook_type,
+                    &config,
+                    &repo_root,
+                    &container_mgr,
+                    &name,
+                    no_hooks,
+                )
+            };
+            let started = start_hook(HookType::PreDockerStart)
+                .and_then(|_| container_mgr.start(&name, &worktree.path));
+            match started {
+                Ok(_) => {
+                    eprintln!("✓ Containers started");
+                    if let Err(e) = start_hook(HookType::PostDockerStart) {
+                        eprintln!("⚠ post_docker_start hook failed: {}", e);
+                    }
+                }
                 Err(e) => eprintln!("⚠ Failed to start containers: {}", e),
             }
         }
@@ -291,6 +283,55 @@ pub fn run(
     Ok(())
 }
 
+/// Symlink `shared_resources` and copy `shared.copy` files into a worktree
+pub(crate) fn setup_shared_resources(
+    config: &Config,
+    repo_root: &Path,
+    worktree_path: &Path,
+) -> Result<()> {
+    // Setup symlinks for shared resources
+    if !config.shared_resources.is_empty() {
+        let actions = SymlinkManager::setup(&config.shared_resources, repo_root, worktree_path)?;
+
+        for action in actions {
+            match action {
+                SymlinkAction::Created { source, target: _ } => {
+                    eprintln!(
+                        "✓ Shared {} (symlinked)",
+                        source.file_name().unwrap().to_string_lossy()
+                    );
+                }
+                SymlinkAction::Skipped { resource, reason } => {
+                    eprintln!("⚠ Skipped {} ({})", resource, reason);
+                }
+            }
+        }
+    }
+
+    // Setup file copies from shared.copy configuration
+    if let Some(ref shared) = config.shared {
+        if !shared.copy.is_empty() {
+            let actions = CopyManager::setup(&shared.copy, repo_root, worktree_path)?;
+
+            for action in actions {
+                match action {
+                    CopyAction::Copied { source, target: _ } => {
+                        eprintln!(
+                            "✓ Copied {} to worktree",
+                            source.file_name().unwrap().to_string_lossy()
+                        );
+                    }
+                    CopyAction::Skipped { resource, reason } => {
+                        eprintln!("⚠ Skipped copying {} ({})", resource, reason);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Interactive prompts for creating a worktree
 fn interactive_prompts(
     branch: Option<String>,
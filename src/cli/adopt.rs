@@ -0,0 +1,294 @@
+// Adopt command: Register worktrees created outside hn
+use crate::config::Config;
+use crate::docker::compose::ComposeGenerator;
+use crate::errors::{HnError, Result};
+use crate::hooks::{HookExecutor, HookType};
+use crate::monitoring::{self, ActivityEvent};
+use crate::restack;
+use crate::state::StateManager;
+use crate::vcs::traits::VcsBackend;
+use crate::vcs::{init_backend_from_current_dir, AheadBehind, RegistryCache, VcsType, Worktree};
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Whether hn manages a worktree
+///
+/// Worktrees hn creates get a state directory in the main repository, or
+/// a parent when created from inside another worktree (their state then
+/// lives with that worktree).
+pub fn is_adopted(worktree: &Worktree, main_root: &Path) -> bool {
+    worktree.path == main_root
+        || worktree.parent.is_some()
+        || main_root.join(".hn-state").join(&worktree.name).is_dir()
+}
+
+/// Register worktrees created with plain `git worktree add` or
+/// `jj workspace add` (`hn adopt`)
+pub fn run(
+    path: Option<PathBuf>,
+    all: bool,
+    run_hooks: bool,
+    no_hooks: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
+    let backend = if let Some(vcs) = vcs_type {
+        crate::vcs::init_backend_with_detection(&env::current_dir()?, Some(vcs))?
+    } else {
+        init_backend_from_current_dir()?
+    };
+    let main_root = backend.main_repo_root()?;
+    let worktrees = backend.list_workspaces()?;
+
+    let targets: Vec<&Worktree> = if all {
+        worktrees
+            .iter()
+            .filter(|wt| !is_adopted(wt, &main_root))
+            .collect()
+    } else {
+        let path = path.unwrap_or(env::current_dir()?);
+        let path = path.canonicalize().unwrap_or(path);
+        let worktree = worktrees
+            .iter()
+            .find(|wt| wt.path.canonicalize().is_ok_and(|p| p == path))
+            .ok_or_else(|| {
+                HnError::WorktreeNotFound(format!(
+                    "{} (not a worktree of this repository)",
+                    path.display()
+                ))
+            })?;
+        if worktree.path == main_root {
+            return Err(HnError::CommandFailed(
+                "The main repository doesn't need adopting".to_string(),
+            ));
+        }
+        if is_adopted(worktree, &main_root) {
+            eprintln!("'{}' is already managed by hn", worktree.name);
+            return Ok(());
+        }
+        vec![worktree]
+    };
+
+    if targets.is_empty() {
+        eprintln!("Nothing to adopt: every worktree is managed by hn");
+        return Ok(());
+    }
+
+    let config = Config::load(&main_root)?;
+    let hooks = run_hooks && !no_hooks && HookType::PostCreate.is_configured(&config.hooks);
+    if hooks {
+        crate::trust::ensure_trusted(&main_root)?;
+    }
+
+    // Only worktrees hn knew about before can be parents of ones sitting on
+    // the same commit, so adopting several at once can't make a cycle
+    let known: HashSet<&str> = worktrees
+        .iter()
+        .filter(|wt| is_adopted(wt, &main_root))
+        .map(|wt| wt.name.as_str())
+        .collect();
+
+    for worktree in targets {
+        adopt(
+            backend.as_ref(),
+            worktree,
+            &worktrees,
+            &known,
+            &main_root,
+            &config,
+            hooks,
+        )?;
+    }
+
+    if let Ok(cache) = RegistryCache::new(&main_root.join(".hn-state"), None) {
+        let _ = cache.invalidate(); // Ignore cache invalidation errors
+    }
+
+    Ok(())
+}
+
+fn adopt(
+    backend: &dyn VcsBackend,
+    worktree: &Worktree,
+    worktrees: &[Worktree],
+    known: &HashSet<&str>,
+    main_root: &Path,
+    config: &Config,
+    run_hooks: bool,
+) -> Result<()> {
+    eprintln!(
+        "Adopting '{}' ({})...",
+        worktree.name,
+        worktree.path.display()
+    );
+
+    let state_root = main_root.join(".hn-state");
+
+    let mut worktree = worktree.clone();
+    let parent = if backend.records_parents() {
+        let parent = infer_parent(backend, &worktree, worktrees, known, main_root);
+        match parent {
+            Some(parent) => eprintln!("  Parent: {} (from merge-base)", parent.name),
+            None => eprintln!("  No parent worktree found; branched from the main repository"),
+        }
+        parent
+    } else {
+        None
+    };
+    worktree.parent = parent.map(|parent| parent.name.clone());
+
+    // The state directory and the parent are what mark a worktree as
+    // adopted, so the directory goes again if setting up fails and the
+    // parent is recorded last; a failed adoption can then be retried
+    let state_manager = StateManager::new(main_root)?;
+    let state_dir = state_manager.create_state_dir(&worktree.name)?;
+    if let Err(e) = set_up(
+        &worktree,
+        &state_root,
+        &state_dir,
+        main_root,
+        config,
+        run_hooks,
+    ) {
+        if let Err(cleanup) = state_manager.remove_state_dir(&worktree.name) {
+            eprintln!("⚠ Couldn't remove {}: {}", state_dir.display(), cleanup);
+        }
+        return Err(e);
+    }
+
+    if let Some(parent) = parent {
+        let recorded = restack::record_base(backend, &state_dir, &worktree, &parent.commit)
+            .and_then(|()| backend.reparent_workspace(&worktree, &parent.name));
+        if let Err(e) = recorded {
+            eprintln!("⚠ Couldn't record parent '{}': {}", parent.name, e);
+        }
+    }
+
+    let _ = monitoring::log_activity(
+        &state_root,
+        &worktree.name,
+        ActivityEvent::WorktreeAdopted {
+            timestamp: monitoring::now(),
+            branch: worktree.branch.clone(),
+        },
+    );
+    eprintln!("✓ Adopted '{}'", worktree.name);
+    Ok(())
+}
+
+/// Shared resources, Docker setup and, with `run_hooks`, the post_create
+/// hook for a worktree being adopted
+fn set_up(
+    worktree: &Worktree,
+    state_root: &Path,
+    state_dir: &Path,
+    main_root: &Path,
+    config: &Config,
+    run_hooks: bool,
+) -> Result<()> {
+    super::add::setup_shared_resources(config, main_root, &worktree.path)?;
+
+    if config.docker.enabled {
+        let setup =
+            super::docker::prepare_worktree(config, state_root, &worktree.name, &worktree.path)?;
+        for (service, port) in &setup.ports {
+            eprintln!("  {} port: {}", service, port);
+        }
+        ComposeGenerator::new(&config.docker, state_root).write(&worktree.name, &setup.compose)?;
+        eprintln!("✓ Generated docker-compose.override.yml");
+    }
+
+    if run_hooks {
+        eprintln!("Running post_create hook...");
+        HookExecutor::new(config.hooks.clone(), false, main_root).run_hook(
+            HookType::PostCreate,
+            worktree,
+            state_dir,
+        )?;
+        eprintln!("✓ Hook completed successfully");
+    }
+
+    Ok(())
+}
+
+/// The worktree this one most likely branched from, going by the merge-base
+/// with each other worktree
+///
+/// Returns None when that's the main repository, which hn doesn't record as
+/// a parent.
+fn infer_parent<'a>(
+    backend: &dyn VcsBackend,
+    worktree: &Worktree,
+    worktrees: &'a [Worktree],
+    known: &HashSet<&str>,
+    main_root: &Path,
+) -> Option<&'a Worktree> {
+    let candidates: Vec<(&'a Worktree, AheadBehind, bool)> = worktrees
+        .iter()
+        .filter(|wt| wt.name != worktree.name)
+        .filter_map(|wt| {
+            let counts = backend.ahead_behind(worktree, &wt.commit).ok()??;
+            Some((wt, counts, known.contains(wt.name.as_str())))
+        })
+        .collect();
+
+    let scores: Vec<(&str, AheadBehind, bool)> = candidates
+        .iter()
+        .map(|(wt, counts, known)| (wt.name.as_str(), *counts, *known))
+        .collect();
+    let parent = closest(&scores)?;
+
+    candidates
+        .iter()
+        .find(|(wt, _, _)| wt.name == parent && wt.path != main_root)
+        .map(|(wt, _, _)| *wt)
+}
+
+/// Pick the closest candidate from its ahead/behind counts
+///
+/// That's the one the worktree has the fewest commits beyond, then the one
+/// that moved on least. Candidates built on top of the worktree are its
+/// children rather than its parent, and one on the very same commit only
+/// counts if hn already knew it.
+fn closest<'a>(candidates: &[(&'a str, AheadBehind, bool)]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .filter(|(_, counts, known)| counts.ahead > 0 || (counts.behind == 0 && *known))
+        .min_by_key(|(_, counts, _)| (counts.ahead, counts.behind))
+        .map(|(name, _, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(ahead: usize, behind: usize) -> AheadBehind {
+        AheadBehind { ahead, behind }
+    }
+
+    #[test]
+    fn test_closest_prefers_latest_fork_point() {
+        // feature forked from main and has one commit; child forked from feature
+        let candidates = [
+            ("main", counts(2, 0), true),
+            ("feature", counts(1, 0), true),
+        ];
+        assert_eq!(closest(&candidates), Some("feature"));
+    }
+
+    #[test]
+    fn test_closest_skips_descendants_and_unknown_twins() {
+        // "child" builds on the worktree, so it isn't the parent
+        let candidates = [("main", counts(1, 0), true), ("child", counts(0, 1), true)];
+        assert_eq!(closest(&candidates), Some("main"));
+
+        // A fresh worktree is on the same commit as where it forked from
+        let candidates = [
+            ("main", counts(1, 0), true),
+            ("feature", counts(0, 0), true),
+        ];
+        assert_eq!(closest(&candidates), Some("feature"));
+        let candidates = [("main", counts(1, 0), true), ("other", counts(0, 0), false)];
+        assert_eq!(closest(&candidates), Some("main"));
+    }
+}
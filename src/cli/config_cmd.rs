@@ -1,5 +1,6 @@
 // Config command: Manage hannahanna configuration
 use crate::config::Config;
+use crate::docker::compose::{self, ComposeFile};
 use crate::errors::{HnError, Result};
 use colored::Colorize;
 use std::fs;
@@ -40,6 +41,17 @@ hooks:
     # npm install
     # make setup
 
+  # Hooks can also be named steps; independent steps run in parallel
+  # post_create:
+  #   - name: install
+  #     run: npm install
+  #   - name: db
+  #     run: make db
+  #   - name: seed
+  #     needs: [install, db]
+  #     run: make seed
+  #     continue_on_error: true
+
   # Run before worktree removal
   pre_remove: |
     echo "Cleaning up worktree..."
@@ -61,30 +73,74 @@ hooks:
   # post_integrate: |
   #   echo "Integration complete!"
 
-  # Conditional hooks based on branch name patterns
+  # Run around `hn sync`; post_sync also runs when the sync fails and gets
+  # HNHN_SYNC_SOURCE, HNHN_SYNC_STRATEGY and HNHN_SYNC_RESULT
+  # pre_sync: |
+  #   echo "Syncing with $HNHN_SYNC_SOURCE..."
+  # post_sync: |
+  #   [ "$HNHN_SYNC_RESULT" = success ] && npm install
+
+  # Run before `hn return` leaves the worktree (HNHN_RETURN_TARGET is the parent)
+  # pre_return: |
+  #   make test
+
+  # Run after `hn snapshot restore` (HNHN_SNAPSHOT is the snapshot name)
+  # post_snapshot_restore: |
+  #   npm install
+
+  # Run around container start/stop (HNHN_DOCKER_PROJECT is the compose project)
+  # pre_docker_start: |
+  #   make build-images
+  # post_docker_start: |
+  #   make migrate
+  # post_docker_stop: |
+  #   echo "Containers for $HNHN_DOCKER_PROJECT stopped"
+
+  # Conditional hooks: expressions over branch, name, tags, template, profile,
+  # vcs and path, combined with &&, || and !, with =~ for regex matching
   # post_create_conditions:
   #   - condition: "branch.startsWith('feature-')"
   #     command: "make setup-dev"
-  #   - condition: "branch.contains('api')"
-  #     command: "docker compose up -d api-deps"
+  #   - condition: "branch =~ '^release/' && tags.contains('ci')"
+  #     command: "make release-checks"
+  #   - condition: "exists('package.json') && !profile"
+  #     command: "npm install"
 
   # Hook execution timeout in seconds (default: 300 = 5 minutes)
   # Prevents hooks from hanging indefinitely
   timeout_seconds: 300
 
+  # Hook output logs kept per worktree, see `hn hooks logs` (default: 20)
+  log_retention: 20
+
+  # Show hook output live, prefixed with the hook name (default: false)
+  stream_output: false
+
 # Docker configuration (optional)
 docker:
   enabled: false
+  # auto, docker, docker-compose, podman, podman-compose or nerdctl
+  engine: auto
   compose_file: docker-compose.yml
+  # Extra compose files layered over compose_file (further -f files)
+  # compose_overlays:
+  #   - docker-compose.dev.yml
 
-  # Port allocation strategy
+  # Port allocation: sequential (lowest free port from base) or hash
+  # (stable per-worktree ports derived from the worktree name)
   ports:
-    base: 3000
-    services:
+    strategy: sequential
+    # Avoid ports used by other repositories on this machine
+    global: false
+    base:
       app: 3000
       postgres: 5432
       redis: 6379
 
+  # per-worktree (default), shared (one copy of every service for the repo)
+  # or hybrid (only shared.services are shared)
+  strategy: per-worktree
+
   # Shared resources across all worktrees
   shared:
     volumes:
@@ -92,6 +148,12 @@ docker:
       - redis-data
     networks:
       - myapp-net
+    # Services run once for the repo with strategy: hybrid
+    # services: [postgres, redis]
+    # Per-worktree isolation: database, db-index, prefix or none
+    # (default guessed from the service name)
+    # isolation:
+    #   postgres: database
 
   # Isolated resources per worktree
   isolated:
@@ -99,6 +161,45 @@ docker:
       - app-cache
       - logs
 
+  # Stop containers of worktrees idle this long when `hn docker reap` runs
+  # idle_stop_after: 2h
+
+  # Wait for containers on start (always done by hn docker start --wait)
+  # healthcheck:
+  #   enabled: false
+  #   timeout: 30s
+  #   interval: 2s
+  #   # Per-service readiness probes: tcp, http (path, status) or command
+  #   probes:
+  #     app:
+  #       type: http
+  #       path: /health
+  #     postgres:
+  #       type: tcp
+
+  # Local reverse proxy (hn proxy): <worktree>.<domain>:<port> is routed
+  # to the worktree's port of <service>
+  # proxy:
+  #   port: 8080
+  #   domain: localhost
+  #   service: app
+
+  # Per-service ports, env, labels and volume mounts for the override file
+  # services:
+  #   app:
+  #     ports: [3000, 9229]  # default: the ports in the compose file
+  #     env:
+  #       DEBUG_PORT: "{{port.app-9229}}"
+  #     labels:
+  #       com.example.worktree: "{{worktree_name}}"
+  #     volumes: ["app-cache:/app/cache"]
+
+# Where new worktrees are created (default: next to the repository)
+# Placeholders: {name}, {repo}, {repo_root}
+# worktrees:
+#   root: ~/wt/{repo}/{name}
+#   # root: "{repo_root}/../{repo}-{name}"
+
 # Command aliases
 aliases:
   # Short aliases for common commands
@@ -146,6 +247,27 @@ pub fn init() -> Result<()> {
 }
 
 /// Validate config file syntax
+/// Warn about services the Docker config refers to that the compose files
+/// don't define, e.g. after a service was renamed
+fn warn_undefined_services(config: &Config, repo_root: &Path) {
+    let compose = match ComposeFile::discover(&config.docker, repo_root) {
+        Ok(compose) => compose,
+        Err(e) => {
+            println!("    {} {}", "⚠".bright_yellow(), e);
+            return;
+        }
+    };
+
+    for (setting, service) in compose::undefined_services(&config.docker, &compose) {
+        println!(
+            "    {} {}: service '{}' is not defined in the compose files",
+            "⚠".bright_yellow(),
+            setting,
+            service
+        );
+    }
+}
+
 pub fn validate() -> Result<()> {
     let config_path = Path::new(CONFIG_FILE);
 
@@ -162,7 +284,7 @@ pub fn validate() -> Result<()> {
     print!("Validating {}...", CONFIG_FILE);
 
     let current_dir = std::env::current_dir()?;
-    match Config::load(&current_dir) {
+    match Config::load(&current_dir).and_then(|config| config.validate().map(|_| config)) {
         Ok(config) => {
             println!(" {}", "✓".bright_green());
             println!("\n{}", "Configuration is valid!".bright_green().bold());
@@ -198,53 +320,33 @@ pub fn validate() -> Result<()> {
             }
 
             // Hooks
-            let hooks_configured = config.hooks.pre_create.is_some()
-                || config.hooks.post_create.is_some()
-                || config.hooks.pre_remove.is_some()
-                || config.hooks.post_remove.is_some()
-                || config.hooks.post_switch.is_some()
-                || config.hooks.pre_integrate.is_some()
-                || config.hooks.post_integrate.is_some()
-                || !config.hooks.pre_create_conditions.is_empty()
-                || !config.hooks.post_create_conditions.is_empty()
-                || !config.hooks.pre_remove_conditions.is_empty()
-                || !config.hooks.post_remove_conditions.is_empty()
-                || !config.hooks.post_switch_conditions.is_empty()
-                || !config.hooks.pre_integrate_conditions.is_empty()
-                || !config.hooks.post_integrate_conditions.is_empty();
+            let configured_hooks: Vec<&str> = config
+                .hooks
+                .all_hooks()
+                .into_iter()
+                .filter(|(_, script)| script.is_some())
+                .map(|(name, _)| name)
+                .collect();
+            let hooks_configured = !configured_hooks.is_empty()
+                || config
+                    .hooks
+                    .all_conditional_hooks()
+                    .iter()
+                    .any(|(_, hooks)| !hooks.is_empty());
 
             if hooks_configured {
                 println!("  • Lifecycle hooks configured");
-                if config.hooks.pre_create.is_some() {
-                    println!("    - pre_create");
-                }
-                if config.hooks.post_create.is_some() {
-                    println!("    - post_create");
-                }
-                if config.hooks.pre_remove.is_some() {
-                    println!("    - pre_remove");
-                }
-                if config.hooks.post_remove.is_some() {
-                    println!("    - post_remove");
-                }
-                if config.hooks.post_switch.is_some() {
-                    println!("    - post_switch");
-                }
-                if config.hooks.pre_integrate.is_some() {
-                    println!("    - pre_integrate");
-                }
-                if config.hooks.post_integrate.is_some() {
-                    println!("    - post_integrate");
+                for name in configured_hooks {
+                    println!("    - {}", name);
                 }
 
                 // Show conditional hooks count
-                let conditional_count = config.hooks.pre_create_conditions.len()
-                    + config.hooks.post_create_conditions.len()
-                    + config.hooks.pre_remove_conditions.len()
-                    + config.hooks.post_remove_conditions.len()
-                    + config.hooks.post_switch_conditions.len()
-                    + config.hooks.pre_integrate_conditions.len()
-                    + config.hooks.post_integrate_conditions.len();
+                let conditional_count: usize = config
+                    .hooks
+                    .all_conditional_hooks()
+                    .iter()
+                    .map(|(_, hooks)| hooks.len())
+                    .sum();
                 if conditional_count > 0 {
                     println!("    - {} conditional hooks", conditional_count);
                 }
@@ -256,10 +358,19 @@ pub fn validate() -> Result<()> {
             if config.docker.enabled {
                 println!("  • Docker integration enabled");
                 println!("    - Compose file: {}", config.docker.compose_file);
+                for overlay in &config.docker.compose_overlays {
+                    println!("    - Overlay: {}", overlay);
+                }
+                warn_undefined_services(&config, &current_dir);
             } else {
                 println!("  • Docker integration disabled");
             }
 
+            // Worktree layout
+            if let Some(ref root) = config.worktrees.root {
+                println!("  • Worktree root: {}", root);
+            }
+
             Ok(())
         }
         Err(e) => {
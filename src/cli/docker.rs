@@ -1,9 +1,121 @@
 use crate::config::Config;
+use crate::diff::{diff, DiffLine};
+use crate::docker::compose::{self, ComposeFile, ComposeGenerator};
 use crate::docker::container::ContainerManager;
-use crate::errors::Result;
-use crate::monitoring::{self, ActivityEvent};
+use crate::docker::ports::PortAllocator;
+use crate::docker::shared::{self, DockerStrategy, SharedRegistry};
+use crate::errors::{HnError, Result};
+use crate::hooks::{HookExecutor, HookType};
+use crate::monitoring::{self, ActivityEvent, ActivityLog};
 use crate::state::StateManager;
+use crate::vcs::{init_backend_from_current_dir, init_backend_with_detection, WorktreeLayout};
+use colored::*;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Resolve the on-disk path of a worktree
+///
+/// Asks the VCS first so relocated worktrees are found, and falls back to the
+/// configured layout for worktrees the VCS doesn't know about.
+fn resolve_worktree_path(repo_root: &Path, name: &str) -> Result<PathBuf> {
+    if let Ok(backend) = init_backend_from_current_dir() {
+        if let Ok(worktree) = backend.get_workspace_by_name(name) {
+            return Ok(worktree.path);
+        }
+    }
+
+    Ok(WorktreeLayout::load(repo_root)?.path_for(name))
+}
+
+/// Run a docker lifecycle hook for a worktree, if one is configured
+///
+/// Hooks get the usual `HNHN_*` variables plus `HNHN_DOCKER_PROJECT`, the
+/// compose project name of the worktree's containers.
+pub(crate) fn run_docker_hook(
+    hook_type: HookType,
+    config: &Config,
+    repo_root: &Path,
+    manager: &ContainerManager,
+    name: &str,
+    no_hooks: bool,
+) -> Result<()> {
+    if no_hooks || !hook_type.is_configured(&config.hooks) {
+        return Ok(());
+    }
+
+    let worktree = init_backend_with_detection(repo_root, None)?.get_workspace_by_name(name)?;
+    let state_dir = StateManager::new(repo_root)?.get_state_dir(name);
+
+    eprintln!("Running {} hook...", hook_type.as_str());
+    HookExecutor::new(config.hooks.clone(), no_hooks, repo_root)
+        .with_env("HNHN_DOCKER_PROJECT", manager.get_project_name(name))
+        .run_hook(hook_type, &worktree, &state_dir)
+}
+
+/// A worktree's Docker setup: its ports, its isolation inside the shared
+/// services and the override file they produce
+pub(crate) struct DockerSetup {
+    pub ports: HashMap<String, u16>,
+    pub isolation_env: BTreeMap<String, String>,
+    pub compose: String,
+}
+
+/// Allocate (or look up) a worktree's ports and shared service isolation,
+/// write its `docker.env` and render its override file
+///
+/// The override file itself is left for the caller to write.
+pub(crate) fn prepare_worktree(
+    config: &Config,
+    state_dir: &Path,
+    name: &str,
+    worktree_path: &Path,
+) -> Result<DockerSetup> {
+    // Services publishing ports get host ports, one per container port.
+    // Shared services run in the repo-level project and don't get any.
+    let strategy = DockerStrategy::from_config(&config.docker)?;
+    let compose_file = ComposeFile::discover(&config.docker, worktree_path).ok();
+    let services: Vec<String> = compose::port_services(&config.docker, compose_file.as_ref())
+        .into_iter()
+        .filter(|s| !strategy.is_shared(&config.docker, s))
+        .collect();
+    let services: Vec<&str> = services.iter().map(|s| s.as_str()).collect();
+    let plan = compose::port_plan(&config.docker, compose_file.as_ref(), &services);
+
+    // Extra ports of a service start from their container port
+    let mut ports_config = config.docker.ports.clone();
+    for (key, container_port) in &plan {
+        ports_config
+            .base
+            .entry(key.clone())
+            .or_insert(*container_port);
+    }
+    let keys: Vec<&str> = plan.iter().map(|(key, _)| key.as_str()).collect();
+    let mut port_allocator = PortAllocator::from_config(state_dir, &ports_config)?;
+    // Existing worktrees pick up services and base ports changed since
+    let ports = port_allocator.reconcile(name, &keys)?;
+
+    // Give the worktree its own slice of the shared services
+    let shared_services = strategy.shared_services_in(&config.docker, worktree_path);
+    let mut registry = SharedRegistry::load(state_dir)?;
+    let (isolation_env, isolation_values) =
+        shared::isolation_env(&config.docker, &shared_services, name, &mut registry)?;
+    if !shared_services.is_empty() {
+        registry.save(state_dir)?;
+        shared::write_env_file(state_dir, name, &isolation_env)?;
+    }
+
+    let compose = ComposeGenerator::new(&config.docker, state_dir)
+        .with_isolation(isolation_env.clone(), isolation_values)
+        .generate(name, worktree_path, &ports)?;
+
+    Ok(DockerSetup {
+        ports,
+        isolation_env,
+        compose,
+    })
+}
 
 /// Show Docker container status for all worktrees
 pub fn ps() -> Result<()> {
@@ -17,7 +129,7 @@ pub fn ps() -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let state_mgr = StateManager::new(&state_dir)?;
+    let state_mgr = StateManager::new(&repo_root)?;
 
     println!("{:<20} {:<15} {:<10}", "WORKTREE", "STATUS", "CONTAINERS");
     println!("{}", "-".repeat(45));
@@ -25,8 +137,15 @@ pub fn ps() -> Result<()> {
     // Get all worktrees from state
     let worktrees = state_mgr.list_worktrees()?;
 
+    // Services shared by all worktrees run in their own project
+    if DockerStrategy::from_config(&config.docker)? != DockerStrategy::PerWorktree {
+        let count = manager.shared_container_count();
+        let status_str = if count > 0 { "Running" } else { "Stopped" };
+        println!("{:<20} {:<15} {:<10}", "(shared)", status_str, count);
+    }
+
     for worktree in worktrees {
-        let worktree_path = repo_root.join("worktrees").join(&worktree);
+        let worktree_path = resolve_worktree_path(&repo_root, &worktree)?;
         match manager.get_status(&worktree, &worktree_path) {
             Ok(status) => {
                 let status_str = if status.running { "Running" } else { "Stopped" };
@@ -44,8 +163,77 @@ pub fn ps() -> Result<()> {
     Ok(())
 }
 
+/// Show the services and container ports discovered from the compose files,
+/// with a worktree's host ports when a worktree is given
+pub fn services(name: Option<String>) -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+
+    let dir = match &name {
+        Some(name) => resolve_worktree_path(&repo_root, name)?,
+        None => repo_root.clone(),
+    };
+    let compose_file = ComposeFile::discover(&config.docker, &dir)?;
+    let strategy = DockerStrategy::from_config(&config.docker)?;
+    let with_ports = compose::port_services(&config.docker, Some(&compose_file));
+    let host_ports = match &name {
+        Some(name) => PortAllocator::from_config(&state_dir, &config.docker.ports)?
+            .get_ports(name)
+            .unwrap_or_default(),
+        None => HashMap::new(),
+    };
+
+    let files: Vec<&str> = compose::compose_files(&config.docker)
+        .map(|f| f.as_str())
+        .collect();
+    println!("Services in {}:", files.join(" + "));
+    println!();
+    println!(
+        "{:<20} {:<20} {:<20}",
+        "SERVICE", "CONTAINER PORTS", "HOST PORTS"
+    );
+    println!("{}", "-".repeat(60));
+
+    let mut services: Vec<&String> = compose_file.services.keys().collect();
+    services.extend(
+        with_ports
+            .iter()
+            .filter(|s| !compose_file.services.contains_key(*s)),
+    );
+    services.sort();
+    for service in services {
+        if strategy.is_shared(&config.docker, service) {
+            println!("{:<20} {:<20} {:<20}", service, "-", "(shared)");
+            continue;
+        }
+        if !with_ports.contains(service) {
+            println!("{:<20} {:<20} {:<20}", service, "-", "-");
+            continue;
+        }
+
+        let container_ports =
+            compose::container_ports(&config.docker, Some(&compose_file), service);
+        let host: Vec<String> = container_ports
+            .iter()
+            .enumerate()
+            .filter_map(|(i, port)| host_ports.get(&compose::port_key(service, i, *port)))
+            .map(|port| port.to_string())
+            .collect();
+        let container: Vec<String> = container_ports.iter().map(|p| p.to_string()).collect();
+        let host = if host.is_empty() {
+            "-".to_string()
+        } else {
+            host.join(", ")
+        };
+        println!("{:<20} {:<20} {:<20}", service, container.join(", "), host);
+    }
+
+    Ok(())
+}
+
 /// Start Docker containers for a worktree
-pub fn start(name: String) -> Result<()> {
+pub fn start(name: String, wait: bool, no_hooks: bool) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
     let config = Config::load(&repo_root)?;
     let state_dir = repo_root.join(".hn-state");
@@ -57,14 +245,27 @@ pub fn start(name: String) -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let worktree_path = repo_root.join("worktrees").join(&name);
+    let worktree_path = resolve_worktree_path(&repo_root, &name)?;
 
     if !worktree_path.exists() {
         return Err(crate::errors::HnError::WorktreeNotFound(name));
     }
 
+    run_docker_hook(
+        HookType::PreDockerStart,
+        &config,
+        &repo_root,
+        &manager,
+        &name,
+        no_hooks,
+    )?;
+
     println!("Starting containers for '{}'...", name);
     manager.start(&name, &worktree_path)?;
+    // start() already waits when health checks are enabled
+    if wait && !config.docker.healthcheck.enabled {
+        manager.wait_until_ready(&name)?;
+    }
     println!("✓ Containers started for '{}'", name);
 
     // Log docker start activity
@@ -77,11 +278,69 @@ pub fn start(name: String) -> Result<()> {
         },
     );
 
+    run_docker_hook(
+        HookType::PostDockerStart,
+        &config,
+        &repo_root,
+        &manager,
+        &name,
+        no_hooks,
+    )
+}
+
+/// Start the services shared by all worktrees
+pub fn start_shared() -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+
+    if !config.docker.enabled {
+        return Err(crate::errors::HnError::DockerError(
+            "Docker support is not enabled in .hannahanna.yml".to_string(),
+        ));
+    }
+    if DockerStrategy::from_config(&config.docker)? == DockerStrategy::PerWorktree {
+        return Err(crate::errors::HnError::DockerError(
+            "No shared services: docker.strategy is 'per-worktree'".to_string(),
+        ));
+    }
+
+    let manager = ContainerManager::new(&config.docker, &state_dir)?;
+    println!("Starting shared services...");
+    manager.start_shared()?;
+    println!(
+        "✓ Shared services started ({})",
+        manager.shared_project_name()
+    );
+
+    Ok(())
+}
+
+/// Stop the services shared by all worktrees
+pub fn stop_shared() -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+
+    if !config.docker.enabled {
+        return Err(crate::errors::HnError::DockerError(
+            "Docker support is not enabled in .hannahanna.yml".to_string(),
+        ));
+    }
+
+    let manager = ContainerManager::new(&config.docker, &state_dir)?;
+    println!("Stopping shared services...");
+    manager.stop_shared()?;
+    println!(
+        "✓ Shared services stopped ({})",
+        manager.shared_project_name()
+    );
+
     Ok(())
 }
 
 /// Stop Docker containers for a worktree
-pub fn stop(name: String) -> Result<()> {
+pub fn stop(name: String, no_hooks: bool) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
     let config = Config::load(&repo_root)?;
     let state_dir = repo_root.join(".hn-state");
@@ -93,30 +352,156 @@ pub fn stop(name: String) -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let worktree_path = repo_root.join("worktrees").join(&name);
+    let worktree_path = resolve_worktree_path(&repo_root, &name)?;
 
     if !worktree_path.exists() {
         return Err(crate::errors::HnError::WorktreeNotFound(name));
     }
 
     println!("Stopping containers for '{}'...", name);
-    manager.stop(&name, &worktree_path)?;
+    stop_worktree(
+        &config,
+        &repo_root,
+        &manager,
+        &name,
+        &worktree_path,
+        no_hooks,
+    )?;
     println!("✓ Containers stopped for '{}'", name);
+    Ok(())
+}
+
+/// Stop a worktree's containers, record it and run the post_docker_stop hook
+fn stop_worktree(
+    config: &Config,
+    repo_root: &Path,
+    manager: &ContainerManager,
+    name: &str,
+    worktree_path: &Path,
+    no_hooks: bool,
+) -> Result<()> {
+    manager.stop(name, worktree_path)?;
 
     // Log docker stop activity
     let _ = monitoring::log_activity(
-        &state_dir,
-        &name,
+        &repo_root.join(".hn-state"),
+        name,
         ActivityEvent::DockerStopped {
             timestamp: monitoring::now(),
         },
     );
 
+    run_docker_hook(
+        HookType::PostDockerStop,
+        config,
+        repo_root,
+        manager,
+        name,
+        no_hooks,
+    )
+}
+
+/// Stop the containers of worktrees idle for longer than `after` (default
+/// `docker.idle_stop_after`), going by their last switch or docker start
+pub fn reap(after: Option<String>, dry_run: bool, no_hooks: bool) -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+
+    if !config.docker.enabled {
+        return Err(HnError::DockerError(
+            "Docker support is not enabled in .hannahanna.yml".to_string(),
+        ));
+    }
+
+    let after = after
+        .or_else(|| config.docker.idle_stop_after.clone())
+        .ok_or_else(|| {
+            HnError::ConfigError(
+                "No idle policy: set docker.idle_stop_after (e.g. '2h') or pass --after"
+                    .to_string(),
+            )
+        })?;
+    let idle_limit = monitoring::parse_duration(&after)?;
+
+    let manager = ContainerManager::new(&config.docker, &state_dir)?;
+    let state_mgr = StateManager::new(&repo_root)?;
+    let now = monitoring::now();
+
+    // Worktrees with running containers that haven't been used recently
+    let mut idle = Vec::new();
+    let mut worktrees = state_mgr.list_worktrees()?;
+    worktrees.sort();
+    for worktree in worktrees {
+        let containers = manager.project_containers(&manager.get_project_name(&worktree));
+        if containers.is_empty() {
+            continue;
+        }
+        let log_path = monitoring::get_activity_log_path(&state_dir, &worktree);
+        let last_used = ActivityLog::load(&log_path)
+            .ok()
+            .and_then(|log| log.last_used());
+        match last_used {
+            Some(last_used) if now.saturating_sub(last_used) > idle_limit => {
+                idle.push((worktree, now - last_used, containers.len()));
+            }
+            Some(_) => {}
+            None => eprintln!(
+                "Skipping '{}': no recorded activity to judge idleness by",
+                worktree
+            ),
+        }
+    }
+
+    if idle.is_empty() {
+        println!("No worktrees idle for more than {}.", after);
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("Would stop containers idle for more than {}:", after);
+    } else {
+        println!("Stopping containers idle for more than {}:", after);
+    }
+    println!("{:<20} {:<10} {:<10}", "WORKTREE", "IDLE", "CONTAINERS");
+    println!("{}", "-".repeat(40));
+    for (worktree, idle_secs, count) in &idle {
+        println!(
+            "{:<20} {:<10} {:<10}",
+            worktree,
+            super::monitor::format_age(*idle_secs),
+            count
+        );
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for (worktree, _, _) in &idle {
+        let result = resolve_worktree_path(&repo_root, worktree).and_then(|path| {
+            stop_worktree(&config, &repo_root, &manager, worktree, &path, no_hooks)
+        });
+        match result {
+            Ok(()) => println!("✓ Stopped '{}'", worktree),
+            Err(e) => {
+                failures += 1;
+                eprintln!("✗ Failed to stop '{}': {}", worktree, e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(HnError::DockerError(format!(
+            "Failed to stop {} idle worktree(s)",
+            failures
+        )));
+    }
     Ok(())
 }
 
 /// Restart Docker containers for a worktree
-pub fn restart(name: String) -> Result<()> {
+pub fn restart(name: String, no_hooks: bool) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
     let config = Config::load(&repo_root)?;
     let state_dir = repo_root.join(".hn-state");
@@ -128,17 +513,34 @@ pub fn restart(name: String) -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let worktree_path = repo_root.join("worktrees").join(&name);
+    let worktree_path = resolve_worktree_path(&repo_root, &name)?;
 
     if !worktree_path.exists() {
         return Err(crate::errors::HnError::WorktreeNotFound(name));
     }
 
+    // A restart ends with running containers, so it gets the start hooks
+    run_docker_hook(
+        HookType::PreDockerStart,
+        &config,
+        &repo_root,
+        &manager,
+        &name,
+        no_hooks,
+    )?;
+
     println!("Restarting containers for '{}'...", name);
     manager.restart(&name, &worktree_path)?;
     println!("✓ Containers restarted for '{}'", name);
 
-    Ok(())
+    run_docker_hook(
+        HookType::PostDockerStart,
+        &config,
+        &repo_root,
+        &manager,
+        &name,
+        no_hooks,
+    )
 }
 
 /// View logs for a worktree's containers
@@ -154,7 +556,7 @@ pub fn logs(name: String, service: Option<String>) -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let worktree_path = repo_root.join("worktrees").join(&name);
+    let worktree_path = resolve_worktree_path(&repo_root, &name)?;
 
     if !worktree_path.exists() {
         return Err(crate::errors::HnError::WorktreeNotFound(name));
@@ -196,8 +598,8 @@ pub fn exec(name: String, service: Option<String>, command: Vec<String>) -> Resu
         ));
     }
 
-    let _manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let worktree_path = repo_root.join("worktrees").join(&name);
+    let manager = ContainerManager::new(&config.docker, &state_dir)?;
+    let worktree_path = resolve_worktree_path(&repo_root, &name)?;
 
     if !worktree_path.exists() {
         return Err(crate::errors::HnError::WorktreeNotFound(name.clone()));
@@ -222,26 +624,12 @@ pub fn exec(name: String, service: Option<String>, command: Vec<String>) -> Resu
         name, service_name
     );
 
-    // Try modern "docker compose" first, fallback to legacy "docker-compose"
-    let compose_cmd = if std::process::Command::new("docker")
-        .args(["compose", "version"])
-        .output()
-        .is_ok()
-    {
-        vec!["docker", "compose"]
-    } else {
-        vec!["docker-compose"]
-    };
-
     // Build exec command
-    let mut cmd = std::process::Command::new(compose_cmd[0]);
-    if compose_cmd.len() > 1 {
-        cmd.arg(compose_cmd[1]);
-    }
-    cmd.arg("exec")
-        .arg(&service_name)
-        .args(&command)
-        .current_dir(&worktree_path);
+    let mut args = vec!["exec".to_string(), service_name];
+    args.extend(command);
+    let (program, full_args) = manager.get_compose_command(&args);
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(full_args).current_dir(&worktree_path);
 
     // Execute command
     let status = cmd.status()?;
@@ -256,6 +644,110 @@ pub fn exec(name: String, service: Option<String>, command: Vec<String>) -> Resu
     Ok(())
 }
 
+/// Rebuild override files after config or port changes
+///
+/// Prints a diff for every file that changed and, with `restart`, brings
+/// the running containers of those worktrees up again with the new file.
+pub fn regenerate(name: Option<String>, all: bool, restart: bool, no_hooks: bool) -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+
+    if !config.docker.enabled {
+        return Err(HnError::DockerError(
+            "Docker support is not enabled in .hannahanna.yml".to_string(),
+        ));
+    }
+
+    let backend = init_backend_with_detection(&repo_root, None)?;
+    let worktrees = if all {
+        let main_root = backend.main_repo_root().ok();
+        backend
+            .list_workspaces()?
+            .into_iter()
+            .filter(|wt| Some(&wt.path) != main_root.as_ref())
+            .map(|wt| (wt.name, wt.path))
+            .collect::<Vec<_>>()
+    } else {
+        let name = match name {
+            Some(name) => name,
+            None => backend.get_current_workspace()?.name,
+        };
+        let worktree_path = resolve_worktree_path(&repo_root, &name)?;
+        if !worktree_path.exists() {
+            return Err(HnError::WorktreeNotFound(name));
+        }
+        vec![(name, worktree_path)]
+    };
+
+    let generator = ComposeGenerator::new(&config.docker, &state_dir);
+    let manager = ContainerManager::new(&config.docker, &state_dir)?;
+    let mut changed = Vec::new();
+
+    for (name, worktree_path) in &worktrees {
+        let setup = prepare_worktree(&config, &state_dir, name, worktree_path)?;
+        let old = fs::read_to_string(generator.override_path(name)).unwrap_or_default();
+
+        if old == setup.compose {
+            println!("{} {} is up to date", "✓".green(), name);
+            continue;
+        }
+
+        println!("{} Regenerated override for {}:", "✓".green(), name);
+        let old_lines: Vec<String> = old.lines().map(String::from).collect();
+        let new_lines: Vec<String> = setup.compose.lines().map(String::from).collect();
+        for line in diff(&old_lines, &new_lines) {
+            match line {
+                DiffLine::Added(text) => println!("  {}", format!("+ {}", text).green()),
+                DiffLine::Removed(text) => println!("  {}", format!("- {}", text).red()),
+                DiffLine::Same(_) => {}
+            }
+        }
+
+        generator.write(name, &setup.compose)?;
+        changed.push((name, worktree_path));
+    }
+
+    if !restart {
+        if changed
+            .iter()
+            .any(|(name, path)| matches!(manager.get_status(name, path), Ok(s) if s.running))
+        {
+            println!("\nRunning containers keep the old configuration until restarted (--restart)");
+        }
+        return Ok(());
+    }
+
+    for (name, worktree_path) in changed {
+        if !manager.get_status(name, worktree_path)?.running {
+            continue;
+        }
+
+        run_docker_hook(
+            HookType::PreDockerStart,
+            &config,
+            &repo_root,
+            &manager,
+            name,
+            no_hooks,
+        )?;
+        println!("Restarting containers for '{}'...", name);
+        // `up -d` recreates the containers whose configuration changed
+        manager.start(name, worktree_path)?;
+        println!("✓ Containers restarted for '{}'", name);
+        run_docker_hook(
+            HookType::PostDockerStart,
+            &config,
+            &repo_root,
+            &manager,
+            name,
+            no_hooks,
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Clean up orphaned Docker containers
 pub fn prune() -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
@@ -269,7 +761,7 @@ pub fn prune() -> Result<()> {
     }
 
     let manager = ContainerManager::new(&config.docker, &state_dir)?;
-    let state_mgr = StateManager::new(&state_dir)?;
+    let state_mgr = StateManager::new(&repo_root)?;
 
     // Get active worktrees
     let active_worktrees = state_mgr.list_worktrees()?;
@@ -0,0 +1,127 @@
+// Hook log viewing
+//
+// Shows the output hooks wrote to `<state_dir>/hook-logs/`, optionally
+// following new output as hooks run.
+
+use crate::config::Config;
+use crate::errors::Result;
+use crate::fuzzy::find_best_match;
+use crate::hooks::logs::{self, LogEntry};
+use crate::state::StateManager;
+use crate::vcs::{init_backend_from_current_dir, VcsType};
+use colored::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Show hook logs for a worktree
+pub fn logs(
+    worktree: String,
+    hook: Option<String>,
+    follow: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
+    let backend = if let Some(vcs) = vcs_type {
+        crate::vcs::init_backend_with_detection(&std::env::current_dir()?, Some(vcs))?
+    } else {
+        init_backend_from_current_dir()?
+    };
+
+    let worktrees = backend.list_workspaces()?;
+    let names: Vec<String> = worktrees.iter().map(|w| w.name.clone()).collect();
+    let name = find_best_match(&worktree, &names)?;
+
+    let repo_root = Config::find_repo_root(&std::env::current_dir()?)?;
+    let state_dir = StateManager::new(&repo_root)?.get_state_dir(&name);
+
+    if follow {
+        return follow_logs(&state_dir, hook.as_deref());
+    }
+
+    let entries = logs::list_logs(&state_dir, hook.as_deref())?;
+    if entries.is_empty() {
+        match hook {
+            Some(hook) => eprintln!("No {} hook logs for '{}'", hook, name),
+            None => eprintln!("No hook logs for '{}'", name),
+        }
+        return Ok(());
+    }
+
+    for entry in &entries {
+        print_header(entry);
+        let content = std::fs::read_to_string(&entry.path)?;
+        print!("{}", content);
+        if !content.ends_with('\n') {
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the newest log and then any new output, like `tail -f`
+///
+/// Logs created while following (e.g. the next step of a multi-step hook)
+/// are picked up too. Runs until interrupted.
+fn follow_logs(state_dir: &Path, hook: Option<&str>) -> Result<()> {
+    // Older runs have finished, so start from the end of everything except
+    // the newest log
+    let mut offsets: HashMap<PathBuf, u64> = HashMap::new();
+    let mut entries = logs::list_logs(state_dir, hook)?;
+    entries.pop();
+    for entry in entries {
+        let len = std::fs::metadata(&entry.path).map(|m| m.len()).unwrap_or(0);
+        offsets.insert(entry.path, len);
+    }
+
+    let mut current: Option<PathBuf> = None;
+    loop {
+        for entry in logs::list_logs(state_dir, hook)? {
+            let offset = offsets.entry(entry.path.clone()).or_insert(0);
+            let chunk = read_from(&entry.path, offset)?;
+            if chunk.is_empty() {
+                continue;
+            }
+
+            // Label output whenever it switches to another log
+            if current.as_ref() != Some(&entry.path) {
+                print_header(&entry);
+                current = Some(entry.path.clone());
+            }
+            print!("{}", chunk);
+        }
+        std::io::stdout().flush()?;
+        std::thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// Read whatever was appended to a log since `offset`, advancing it
+fn read_from(path: &Path, offset: &mut u64) -> Result<String> {
+    // The log may have been rotated away since it was listed
+    let Ok(mut file) = File::open(path) else {
+        return Ok(String::new());
+    };
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    *offset += bytes.len() as u64;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn print_header(entry: &LogEntry) {
+    let file_name = entry
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    println!(
+        "{}",
+        format!("==> {} ({}) <==", entry.label(), file_name)
+            .bold()
+            .cyan()
+    );
+}
@@ -1,9 +1,11 @@
+use crate::cli::output::{self, InfoOutput, OutputFormat, WorktreeRecord};
 use crate::config::Config;
+use crate::conflict::ConflictState;
 use crate::docker::container::ContainerManager;
 use crate::docker::ports::PortAllocator;
 use crate::errors::{HnError, Result};
 use crate::fuzzy;
-use crate::vcs::{init_backend_from_current_dir, VcsType};
+use crate::vcs::{self, init_backend_from_current_dir, AheadBehind, VcsType};
 use chrono::{DateTime, Local};
 use colored::Colorize;
 use std::env;
@@ -13,7 +15,7 @@ use std::time::SystemTime;
 /// Show detailed information about a worktree
 ///
 /// If no name is provided, shows info for the current worktree
-pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
+pub fn run(name: Option<String>, format: OutputFormat, vcs_type: Option<VcsType>) -> Result<()> {
     let backend = if let Some(vcs) = vcs_type {
         crate::vcs::init_backend_with_detection(&env::current_dir()?, Some(vcs))?
     } else {
@@ -39,11 +41,41 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
     // Get all worktrees for parent/children relationships
     let all_worktrees = backend.list_workspaces()?;
 
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    // State lives in the main repository, even when run from a worktree
+    let state_root = backend.main_repo_root()?.join(".hn-state");
+
+    if !format.is_table() {
+        let config = Config::load(&repo_root)?;
+        let children = all_worktrees
+            .iter()
+            .filter(|wt| wt.parent.as_ref() == Some(&worktree.name))
+            .map(|wt| wt.name.clone())
+            .collect();
+        return output::print(
+            format,
+            &InfoOutput {
+                schema_version: output::SCHEMA_VERSION,
+                vcs: backend.vcs_type().as_str().to_string(),
+                worktree: WorktreeRecord::collect(
+                    &worktree,
+                    backend.divergence(
+                        &worktree,
+                        vcs::parent_branch(&worktree, &all_worktrees),
+                        backend.default_branch().as_deref(),
+                    ),
+                    backend.as_ref(),
+                    &state_root,
+                    &config,
+                ),
+                children,
+            },
+        );
+    }
+
     // Get status
     let status = backend.get_workspace_status(&worktree.path)?;
 
-    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
-
     // Print header
     println!(
         "{}",
@@ -95,8 +127,69 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
         println!("{}", format!("⚠ {}", changes).yellow());
     }
 
+    // Ahead/behind counts against upstream, parent and default branch
+    let parent_branch = vcs::parent_branch(&worktree, &all_worktrees);
+    let divergence = backend.divergence(
+        &worktree,
+        parent_branch,
+        backend.default_branch().as_deref(),
+    );
+    let comparisons = [
+        (backend.upstream_branch(&worktree), divergence.upstream),
+        (parent_branch.map(str::to_string), divergence.parent),
+        (
+            divergence.default_branch_name.clone(),
+            divergence.default_branch,
+        ),
+    ];
+    let comparisons: Vec<(String, AheadBehind)> = comparisons
+        .into_iter()
+        .filter_map(|(base, counts)| Some((base?, counts?)))
+        .collect();
+    if !comparisons.is_empty() {
+        println!("{}:", "Ahead/Behind".bright_white());
+        for (base, counts) in comparisons {
+            let counts = if counts.behind > 0 {
+                counts.to_string().yellow()
+            } else {
+                counts.to_string().normal()
+            };
+            println!("  {:<20} {}", base, counts);
+        }
+    }
+
+    // Sync or integrate waiting on conflict resolution
+    let conflict_state_dir = state_root.join(&worktree.name);
+    if let Some(conflict) =
+        ConflictState::load_active(&conflict_state_dir, backend.as_ref(), &worktree.path)
+    {
+        println!(
+            "{}: {}",
+            "Conflicts".bright_white(),
+            format!("⚠ {}", conflict.summary()).red()
+        );
+        for file in &conflict.files {
+            println!("  - {}", file);
+        }
+        // Integrations are finished through `hn sync` too
+        let command = if conflict.command == "restack" {
+            "restack"
+        } else {
+            "sync"
+        };
+        println!(
+            "  {}",
+            format!(
+                "Resolve and {1}, then run `hn {0} --continue` (or `hn {0} --abort`)",
+                command,
+                backend.vcs_type().resolve_hint()
+            )
+            .dimmed()
+        );
+    }
+
     // Age (time since creation)
-    let state_dir = repo_root.join(".hn-state").join(&worktree.name);
+    let state_dir = state_root.join(&worktree.name);
     if state_dir.exists() {
         if let Ok(metadata) = fs::metadata(&state_dir) {
             if let Ok(created) = metadata.created() {
@@ -141,7 +234,7 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
         println!("{}:", "Children".bright_white());
         for child in &children {
             // Get age of child
-            let child_state_dir = repo_root.join(".hn-state").join(&child.name);
+            let child_state_dir = state_root.join(&child.name);
             let age_str = if child_state_dir.exists() {
                 if let Ok(metadata) = fs::metadata(&child_state_dir) {
                     if let Ok(created) = metadata.created() {
@@ -169,10 +262,8 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
         println!();
         println!("{}:", "Docker".bright_white());
 
-        let state_dir_root = repo_root.join(".hn-state");
-
         // Port allocations
-        match PortAllocator::new(&state_dir_root) {
+        match PortAllocator::new(&state_root) {
             Ok(allocator) => {
                 if let Ok(ports) = allocator.get_ports(&worktree.name) {
                     if !ports.is_empty() {
@@ -199,7 +290,7 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
         }
 
         // Container status with memory/CPU
-        match ContainerManager::new(&config.docker, &state_dir_root) {
+        match ContainerManager::new(&config.docker, &state_root) {
             Ok(manager) => {
                 if let Ok(docker_status) = manager.get_status(&worktree.name, &worktree.path) {
                     if docker_status.running {
@@ -210,7 +301,7 @@ pub fn run(name: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
                         );
 
                         // Try to get container stats (memory/CPU)
-                        if let Ok(stats) = get_container_stats(&worktree.name) {
+                        if let Ok(stats) = get_container_stats(&manager, &worktree.name) {
                             println!("    {}: {}", "Memory".bright_white(), stats.memory);
                             println!("    {}: {}", "CPU".bright_white(), stats.cpu);
                         }
@@ -315,40 +406,12 @@ struct ContainerStats {
     cpu: String,
 }
 
-/// Get container stats from docker stats (if docker is available)
-fn get_container_stats(worktree_name: &str) -> Result<ContainerStats> {
+/// Get container stats from the engine's `stats` (if it is available)
+fn get_container_stats(manager: &ContainerManager, worktree_name: &str) -> Result<ContainerStats> {
     use std::process::Command;
 
-    // Generate project name using same logic as ContainerManager
-    let project_name = worktree_name
-        .to_lowercase()
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { '-' })
-        .collect::<String>();
-
     // Get list of containers for this project
-    let ps_output = Command::new("docker")
-        .args([
-            "ps",
-            "--filter",
-            &format!("label=com.docker.compose.project={}", project_name),
-            "--format",
-            "{{.Names}}",
-        ])
-        .output();
-
-    let container_names: Vec<String> = if let Ok(output) = ps_output {
-        if output.status.success() {
-            String::from_utf8_lossy(&output.stdout)
-                .lines()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new()
-    };
+    let container_names = manager.project_containers(&manager.get_project_name(worktree_name));
 
     if container_names.is_empty() {
         return Err(HnError::DockerError(
@@ -357,7 +420,7 @@ fn get_container_stats(worktree_name: &str) -> Result<ContainerStats> {
     }
 
     // Get stats for all containers in this project
-    let stats_output = Command::new("docker")
+    let stats_output = Command::new(manager.engine().runtime())
         .args([
             "stats",
             "--no-stream",
@@ -416,18 +479,23 @@ fn get_container_stats(worktree_name: &str) -> Result<ContainerStats> {
     ))
 }
 
-/// Parse memory value from docker stats format (e.g., "123.4MiB", "2GiB") to MiB
+/// Parse memory value from stats format to MiB: binary units from Docker and
+/// nerdctl (e.g., "123.4MiB", "2GiB"), decimal ones from Podman ("5.2MB", "1GB")
 fn parse_memory_value(s: &str) -> Option<f64> {
+    const UNITS: [(&str, f64); 7] = [
+        ("GiB", 1024.0),
+        ("MiB", 1.0),
+        ("KiB", 1.0 / 1024.0),
+        ("GB", 1e9 / 1048576.0),
+        ("MB", 1e6 / 1048576.0),
+        ("kB", 1e3 / 1048576.0),
+        ("B", 1.0 / 1048576.0),
+    ];
+
     let s = s.trim();
-    if let Some(value_str) = s.strip_suffix("GiB") {
-        value_str.parse::<f64>().ok().map(|v| v * 1024.0)
-    } else if let Some(value_str) = s.strip_suffix("MiB") {
-        value_str.parse::<f64>().ok()
-    } else if let Some(value_str) = s.strip_suffix("KiB") {
-        value_str.parse::<f64>().ok().map(|v| v / 1024.0)
-    } else if let Some(value_str) = s.strip_suffix("B") {
-        value_str.parse::<f64>().ok().map(|v| v / (1024.0 * 1024.0))
-    } else {
-        None
-    }
+    UNITS.iter().find_map(|(unit, factor)| {
+        s.strip_suffix(unit)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .map(|value| value * factor)
+    })
 }
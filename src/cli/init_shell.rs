@@ -34,6 +34,13 @@ hn() {
             # Return failed, show error message
             command hn return "${@:2}"
         fi
+    elif [ "$1" = "monitor" ] && [[ " $* " == *" --live "* ]]; then
+        # The dashboard draws on stderr; a worktree picked with Enter is
+        # printed on the last line of stdout
+        local path=$(command hn "$@" | tail -n 1)
+        if [ -n "$path" ] && [ -d "$path" ]; then
+            cd "$path"
+        fi
     else
         # Pass through all other commands
         command hn "$@"
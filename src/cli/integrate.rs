@@ -1,12 +1,14 @@
 // Integrate command: Merge a source worktree/branch into a target worktree/branch
 use crate::config::Config;
+use crate::conflict::{ConflictOperation, ConflictState};
 use crate::errors::{HnError, Result};
 use crate::fuzzy;
 use crate::hooks::{HookExecutor, HookType};
 use crate::state::StateManager;
-use crate::vcs::{init_backend_from_current_dir, VcsType};
+use crate::vcs::traits::{MergeOptions, MergeOutcome};
+use crate::vcs::{init_backend_from_current_dir, VcsType, Worktree};
 use std::env;
-use std::process::Command;
+use std::path::Path;
 
 pub fn run(
     source: String,
@@ -14,6 +16,7 @@ pub fn run(
     no_ff: bool,
     squash: bool,
     strategy: Option<String>,
+    abort_on_conflict: bool,
     vcs_type: Option<VcsType>,
 ) -> Result<()> {
     // Validate flag combinations
@@ -78,19 +81,19 @@ pub fn run(
         ))));
     }
 
-    // Load config and run pre_integrate hook
+    // Load config and run pre_integrate hook; state, including any
+    // conflict record, lives in the main repository's .hn-state
     let repo_root = Config::find_repo_root(&target_worktree.path)?;
     let config = Config::load(&repo_root)?;
+    let state_dir =
+        StateManager::new(&backend.main_repo_root()?)?.get_state_dir(&target_worktree.name);
 
     let has_pre_integrate_hooks =
         config.hooks.pre_integrate.is_some() || !config.hooks.pre_integrate_conditions.is_empty();
 
     if has_pre_integrate_hooks {
-        let state_manager = StateManager::new(&repo_root)?;
-        let state_dir = state_manager.get_state_dir(&target_worktree.name);
-
         eprintln!("Running pre_integrate hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), false);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), false, &repo_root);
         hook_executor.run_hook(HookType::PreIntegrate, &target_worktree, &state_dir)?;
         eprintln!("✓ Pre-integrate hook completed successfully");
     }
@@ -98,69 +101,70 @@ pub fn run(
     // Change to target worktree directory
     env::set_current_dir(&target_worktree.path)?;
 
-    // Build the git merge command
-    let mut cmd = Command::new("git");
-    cmd.arg("merge");
-
-    // Add merge strategy if specified
-    if let Some(strat) = strategy {
-        cmd.arg("--strategy").arg(strat);
-    }
-
-    // Add no-ff flag if specified
-    if no_ff {
-        cmd.arg("--no-ff");
-    }
-
-    // Add squash flag if specified
-    if squash {
-        cmd.arg("--squash");
-    }
-
-    // Add source branch
-    cmd.arg(&source_branch);
-
     eprintln!(
         "\n→ Merging '{}' into '{}'...",
         source_branch, target_worktree.branch
     );
 
-    let output = cmd.output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
+    let options = MergeOptions {
+        no_ff,
+        squash,
+        strategy,
+        ..Default::default()
+    };
+    let outcome = backend
+        .merge(&target_worktree.path, &source_branch, &options)
+        .map_err(|e| {
+            HnError::Git(git2::Error::from_str(&format!(
+                "Failed to merge '{}' into '{}': {}",
+                source_branch, target_worktree.branch, e
+            )))
+        })?;
+
+    if outcome == MergeOutcome::Conflict {
+        let files = backend.conflicted_files(&target_worktree.path)?;
+        eprintln!("\n⚠ Merge conflicts in {} file(s):", files.len());
+        for file in &files {
+            eprintln!("  {}", file);
+        }
 
-        // Check if it's a conflict
-        if stderr.contains("CONFLICT") || stdout.contains("CONFLICT") {
-            eprintln!("\n⚠ Merge conflicts detected:");
-            eprintln!("{}", stdout);
-            eprintln!("{}", stderr);
-            eprintln!(
-                "\nResolve conflicts manually in: {}",
-                target_worktree.path.display()
-            );
-            eprintln!("Then run: git commit");
-            return Err(HnError::Git(git2::Error::from_str(
-                "Merge conflicts need manual resolution",
-            )));
+        if abort_on_conflict {
+            backend.abort_operation(&target_worktree.path, ConflictOperation::Merge)?;
+            eprintln!("✓ Merge aborted (--abort-on-conflict)");
+            return Err(HnError::Git(git2::Error::from_str(&format!(
+                "Integration aborted: conflicts in {} file(s)",
+                files.len()
+            ))));
         }
 
-        return Err(HnError::Git(git2::Error::from_str(&format!(
-            "Failed to merge '{}' into '{}': {}{}",
-            source_branch, target_worktree.branch, stdout, stderr
-        ))));
-    }
+        // Record the conflict so list/info can flag the target
+        ConflictState {
+            command: "integrate".to_string(),
+            operation: ConflictOperation::Merge,
+            source: source_branch.clone(),
+            files,
+            autostash: None,
+            previous_commit: target_worktree.commit.clone(),
+            started_at: crate::monitoring::now(),
+        }
+        .save(&state_dir)?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if !stdout.is_empty() {
-        eprintln!("{}", stdout);
+        eprintln!(
+            "\nResolve conflicts manually in: {}",
+            target_worktree.path.display()
+        );
+        eprintln!("Then continue from that worktree:");
+        eprintln!("  cd {}", target_worktree.path.display());
+        eprintln!("  hn sync --continue   (or: hn sync --abort)");
+        return Err(HnError::Git(git2::Error::from_str(
+            "Merge conflicts need manual resolution",
+        )));
     }
 
     if squash {
         eprintln!("✓ Squash merge successful (changes staged but not committed)");
         eprintln!(
-            "  Run 'git commit' in {} to complete the merge",
+            "  Commit in {} to complete the merge",
             target_worktree.path.display()
         );
     } else {
@@ -184,20 +188,11 @@ pub fn run(
             );
 
             for child in &children {
-                // Update parent using git config
-                let reparent_output = Command::new("git")
-                    .arg("-C")
-                    .arg(&child.path)
-                    .arg("config")
-                    .arg("worktree.parent")
-                    .arg(&target_worktree.name)
-                    .output();
-
-                match reparent_output {
-                    Ok(output) if output.status.success() => {
+                match backend.reparent_workspace(child, &target_worktree.name) {
+                    Ok(()) => {
                         eprintln!("  ✓ Reparented '{}'", child.name);
                     }
-                    _ => {
+                    Err(_) => {
                         eprintln!(
                             "  ⚠ Failed to reparent '{}' (you may need to update manually)",
                             child.name
@@ -208,17 +203,25 @@ pub fn run(
         }
     }
 
-    // Run post_integrate hook
+    post_integrate(&config, &repo_root, &target_worktree, &state_dir)
+}
+
+/// Run the post_integrate hook in the worktree merged into
+///
+/// Also used by `hn sync --continue` when it finishes an integration.
+pub(crate) fn post_integrate(
+    config: &Config,
+    repo_root: &Path,
+    target_worktree: &Worktree,
+    state_dir: &Path,
+) -> Result<()> {
     let has_post_integrate_hooks =
         config.hooks.post_integrate.is_some() || !config.hooks.post_integrate_conditions.is_empty();
 
     if has_post_integrate_hooks {
-        let state_manager = StateManager::new(&repo_root)?;
-        let state_dir = state_manager.get_state_dir(&target_worktree.name);
-
         eprintln!("Running post_integrate hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), false);
-        hook_executor.run_hook(HookType::PostIntegrate, &target_worktree, &state_dir)?;
+        let hook_executor = HookExecutor::new(config.hooks.clone(), false, repo_root);
+        hook_executor.run_hook(HookType::PostIntegrate, target_worktree, state_dir)?;
         eprintln!("✓ Post-integrate hook completed successfully");
     }
 
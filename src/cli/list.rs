@@ -1,19 +1,32 @@
+use crate::cli::adopt;
+use crate::cli::output::{self, ListOutput, OutputFormat, WorktreeRecord};
+use crate::config::Config;
+use crate::conflict::ConflictState;
 use crate::errors::Result;
 use crate::tags;
-use crate::vcs::{init_backend_from_current_dir, short_commit, RegistryCache, VcsType, Worktree};
-use std::collections::HashMap;
+use crate::vcs::{
+    self, init_backend_from_current_dir, short_commit, Divergence, RegistryCache, VcsType, Worktree,
+};
+use std::collections::{HashMap, HashSet};
 
-pub fn run(tree: bool, tag: Option<String>, vcs_type: Option<VcsType>) -> Result<()> {
+pub fn run(
+    tree: bool,
+    tag: Option<String>,
+    stale: Option<usize>,
+    format: OutputFormat,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
     let backend = if let Some(vcs) = vcs_type {
         crate::vcs::init_backend_with_detection(&std::env::current_dir()?, Some(vcs))?
     } else {
         init_backend_from_current_dir()?
     };
 
-    // Try to get worktrees from cache first
-    let repo_root = backend.repo_root()?;
+    // State lives in the main repository, even when listing from a worktree
+    let repo_root = backend.main_repo_root()?;
     let state_dir = repo_root.join(".hn-state");
 
+    // Try to get worktrees from cache first
     let mut worktrees = if let Ok(cache) = RegistryCache::new(&state_dir, None) {
         if let Ok(Some(cached_worktrees)) = cache.get() {
             // Cache hit!
@@ -29,32 +42,113 @@ pub fn run(tree: bool, tag: Option<String>, vcs_type: Option<VcsType>) -> Result
         backend.list_workspaces()?
     };
 
+    // Parents are looked up before filtering, so filtered-out parents still count
+    let all_worktrees = worktrees.clone();
+
     // Filter by tag if specified
     if let Some(ref filter_tag) = tag {
         let tagged_worktrees = tags::get_worktrees_by_tag(&state_dir, filter_tag)?;
         worktrees.retain(|wt| tagged_worktrees.contains(&wt.name));
 
-        if worktrees.is_empty() {
+        if worktrees.is_empty() && format.is_table() {
             eprintln!("No worktrees found with tag '{}'", filter_tag);
             return Ok(());
         }
     }
 
+    // Looked up once rather than for every worktree, as it can mean running
+    // hg or jj
+    let default_branch = backend.default_branch();
+
+    // Keep only worktrees behind the default branch by more than N commits;
+    // that takes only the counts against the default branch
+    if let Some(threshold) = stale {
+        worktrees.retain(|wt| {
+            default_branch
+                .as_deref()
+                .filter(|branch| *branch != wt.branch)
+                .and_then(|branch| backend.ahead_behind(wt, branch).ok().flatten())
+                .is_some_and(|counts| counts.behind > threshold)
+        });
+
+        if worktrees.is_empty() && format.is_table() {
+            eprintln!(
+                "No worktrees behind {} by more than {} commits",
+                default_branch.as_deref().unwrap_or("the default branch"),
+                threshold
+            );
+            return Ok(());
+        }
+    }
+
+    // Ahead/behind counts against upstream, parent and default branch, for
+    // the worktrees that are shown only
+    let mut divergences: HashMap<String, Divergence> = worktrees
+        .iter()
+        .map(|wt| {
+            let parent_branch = vcs::parent_branch(wt, &all_worktrees);
+            (
+                wt.name.clone(),
+                backend.divergence(wt, parent_branch, default_branch.as_deref()),
+            )
+        })
+        .collect();
+
+    if !format.is_table() {
+        let config = Config::load(&repo_root)?;
+        let records = worktrees
+            .iter()
+            .map(|wt| {
+                let divergence = divergences.remove(&wt.name).unwrap_or_default();
+                WorktreeRecord::collect(wt, divergence, backend.as_ref(), &state_dir, &config)
+            })
+            .collect();
+        return output::print(
+            format,
+            &ListOutput {
+                schema_version: output::SCHEMA_VERSION,
+                worktrees: records,
+            },
+        );
+    }
+
+    // Worktrees stuck in a sync or integrate that hit conflicts
+    let conflicts: HashMap<String, ConflictState> = worktrees
+        .iter()
+        .filter_map(|wt| {
+            ConflictState::load_active(&state_dir.join(&wt.name), backend.as_ref(), &wt.path)
+                .map(|state| (wt.name.clone(), state))
+        })
+        .collect();
+
+    // Worktrees created outside hn, e.g. with plain `git worktree add`
+    let unadopted: HashSet<&str> = worktrees
+        .iter()
+        .filter(|wt| !adopt::is_adopted(wt, &repo_root))
+        .map(|wt| wt.name.as_str())
+        .collect();
+
     if tree {
         // Tree view with parent/child relationships
-        display_tree_view(&worktrees);
+        display_tree_view(&worktrees, &conflicts, &divergences, &unadopted);
     } else {
         // Standard table view
-        println!("{:<20} {:<25} {:<10}", "NAME", "BRANCH", "COMMIT");
-        println!("{}", "-".repeat(60));
+        println!(
+            "{:<20} {:<25} {:<10} AHEAD/BEHIND",
+            "NAME", "BRANCH", "COMMIT"
+        );
+        println!("{}", "-".repeat(80));
 
         // Print each worktree
-        for wt in worktrees {
+        for wt in &worktrees {
             println!(
-                "{:<20} {:<25} {:<10}",
+                "{:<20} {:<25} {:<10} {}{}{}",
                 wt.name,
                 wt.branch,
-                short_commit(&wt.commit)
+                short_commit(&wt.commit),
+                divergences[&wt.name].summary(),
+                conflict_marker(conflicts.get(&wt.name)),
+                adoption_marker(unadopted.contains(wt.name.as_str()))
             );
         }
     }
@@ -62,8 +156,38 @@ pub fn run(tree: bool, tag: Option<String>, vcs_type: Option<VcsType>) -> Result
     Ok(())
 }
 
+/// Flag for worktrees waiting on conflict resolution
+fn conflict_marker(conflict: Option<&ConflictState>) -> String {
+    match conflict {
+        Some(state) => format!(" ⚠ conflicts: {}", state.summary()),
+        None => String::new(),
+    }
+}
+
+/// Flag for worktrees hn doesn't manage yet
+fn adoption_marker(unadopted: bool) -> &'static str {
+    if unadopted {
+        " (unadopted: run hn adopt)"
+    } else {
+        ""
+    }
+}
+
+/// Ahead/behind summary appended to tree view entries
+fn divergence_marker(divergence: Option<&Divergence>) -> String {
+    match divergence.map(Divergence::summary) {
+        Some(summary) if !summary.is_empty() => format!(" {}", summary),
+        _ => String::new(),
+    }
+}
+
 /// Display worktrees in a tree structure based on parent/child relationships
-fn display_tree_view(worktrees: &[Worktree]) {
+fn display_tree_view(
+    worktrees: &[Worktree],
+    conflicts: &HashMap<String, ConflictState>,
+    divergences: &HashMap<String, Divergence>,
+    unadopted: &HashSet<&str>,
+) {
     // Build parent-to-children map
     let mut children_map: HashMap<Option<String>, Vec<&Worktree>> = HashMap::new();
 
@@ -77,12 +201,16 @@ fn display_tree_view(worktrees: &[Worktree]) {
     // Display root worktrees (those without parents)
     if let Some(roots) = children_map.get(&None) {
         for root in roots {
-            // Display root without tree characters
+            // Display root without tree characters; unadopted worktrees
+            // have no parent, so they're always roots
             println!(
-                "{} ({}) [{}]",
+                "{} ({}) [{}]{}{}{}",
                 root.name,
                 root.branch,
-                short_commit(&root.commit)
+                short_commit(&root.commit),
+                divergence_marker(divergences.get(&root.name)),
+                conflict_marker(conflicts.get(&root.name)),
+                adoption_marker(unadopted.contains(root.name.as_str()))
             );
 
             // Display children of this root
@@ -90,7 +218,14 @@ fn display_tree_view(worktrees: &[Worktree]) {
                 let child_count = children.len();
                 for (i, child) in children.iter().enumerate() {
                     let is_last_child = i == child_count - 1;
-                    display_worktree_node(child, "", is_last_child, &children_map);
+                    display_worktree_node(
+                        child,
+                        "",
+                        is_last_child,
+                        &children_map,
+                        conflicts,
+                        divergences,
+                    );
                 }
             }
         }
@@ -103,6 +238,8 @@ fn display_worktree_node(
     prefix: &str,
     is_last: bool,
     children_map: &HashMap<Option<String>, Vec<&Worktree>>,
+    conflicts: &HashMap<String, ConflictState>,
+    divergences: &HashMap<String, Divergence>,
 ) {
     // Choose the appropriate tree characters
     let branch_char = if is_last { "└──" } else { "├──" };
@@ -110,12 +247,14 @@ fn display_worktree_node(
 
     // Display current worktree
     println!(
-        "{}{} {} ({}) [{}]",
+        "{}{} {} ({}) [{}]{}{}",
         prefix,
         branch_char,
         wt.name,
         wt.branch,
-        short_commit(&wt.commit)
+        short_commit(&wt.commit),
+        divergence_marker(divergences.get(&wt.name)),
+        conflict_marker(conflicts.get(&wt.name))
     );
 
     // Display children
@@ -124,7 +263,14 @@ fn display_worktree_node(
         for (i, child) in children.iter().enumerate() {
             let is_last_child = i == child_count - 1;
             let child_prefix = format!("{}{}", prefix, continuation);
-            display_worktree_node(child, &child_prefix, is_last_child, children_map);
+            display_worktree_node(
+                child,
+                &child_prefix,
+                is_last_child,
+                children_map,
+                conflicts,
+                divergences,
+            );
         }
     }
 }
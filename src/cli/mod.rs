@@ -1,16 +1,22 @@
 pub mod activity;
 pub mod add;
+pub mod adopt;
 pub mod config_cmd;
 pub mod docker;
 pub mod each;
+pub mod hooks;
 pub mod info;
 pub mod init_shell;
 pub mod integrate;
 pub mod list;
 pub mod monitor;
+pub mod output;
 pub mod ports;
+pub mod proxy;
 pub mod prune;
 pub mod remove;
+pub mod rename;
+pub mod restack;
 pub mod return_cmd;
 pub mod setup;
 pub mod snapshot;
@@ -20,4 +26,5 @@ pub mod switch;
 pub mod sync;
 pub mod tag;
 pub mod templates;
+pub mod trust;
 pub mod workspace;
@@ -1,31 +1,56 @@
 // Real-time monitoring dashboard for worktrees (v0.6)
 //
-// This is a basic implementation that provides snapshot monitoring.
-// Live dashboard features will be enhanced in future versions.
+// Without --live this prints a one-off snapshot. With --live it opens an
+// auto-refreshing terminal dashboard (drawn on stderr so that a worktree
+// selected with Enter can be printed to stdout for the shell wrapper).
 
+use crate::clock::{Clock, SystemClock};
 use crate::config::Config;
-use crate::errors::Result;
+use crate::docker::container::ContainerManager;
+use crate::docker::ports::PortAllocator;
+use crate::errors::{HnError, Result};
+use crate::hooks::HookType;
+use crate::monitoring::{self, ActivityEvent, ActivityLog};
 use crate::state::StateManager;
+use crate::vcs::traits::VcsBackend;
 use crate::vcs::{init_backend_from_current_dir, VcsType};
 use colored::*;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default refresh interval for the live dashboard
+const DEFAULT_REFRESH_SECS: u64 = 5;
+
+/// Upper bound on how long the event loop blocks waiting for input
+const INPUT_POLL: Duration = Duration::from_millis(250);
 
 /// Run the monitoring dashboard
-pub fn run(live: bool, _refresh_secs: Option<u64>, vcs_type: Option<VcsType>) -> Result<()> {
+pub fn run(
+    live: bool,
+    refresh_secs: Option<u64>,
+    no_hooks: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
     let backend = if let Some(vcs) = vcs_type {
         crate::vcs::init_backend_with_detection(&std::env::current_dir()?, Some(vcs))?
     } else {
         init_backend_from_current_dir()?
     };
 
-    let repo_root = Config::find_repo_root(&std::env::current_dir()?)?;
-    let state_manager = StateManager::new(&repo_root)?;
+    // State lives in the main repository, even when run from a worktree
+    let repo_root = backend.main_repo_root()?;
 
     if live {
-        eprintln!("{}", "Live monitoring mode coming soon!".yellow());
-        eprintln!("{}", "For now, showing snapshot view...".dimmed());
-        println!();
+        return run_live(backend, &repo_root, refresh_secs, no_hooks, vcs_type);
     }
 
+    let state_manager = StateManager::new(&repo_root)?;
+
     // Get worktrees
     let worktrees = backend.list_workspaces()?;
     let config = Config::load(&repo_root)?;
@@ -101,9 +126,896 @@ pub fn run(live: bool, _refresh_secs: Option<u64>, vcs_type: Option<VcsType>) ->
 
 /// Truncate string to max length
 fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
+    if s.chars().count() <= max_len {
         s.to_string()
     } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
+        let kept: String = s.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", kept)
+    }
+}
+
+/// One worktree as shown in the live dashboard
+#[derive(Debug, Clone, Default)]
+struct WorktreeRow {
+    name: String,
+    path: PathBuf,
+    branch: String,
+    /// `None` when the status could not be read
+    dirty: Option<bool>,
+    /// Commits (ahead, behind) the upstream branch, if there is one
+    ahead_behind: Option<(usize, usize)>,
+    /// Running containers, `None` when docker is disabled
+    containers: Option<usize>,
+    ports: Vec<(String, u16)>,
+    last_event: Option<String>,
+}
+
+/// Something that can produce the dashboard rows
+///
+/// Abstracted so the dashboard logic can be tested without a repository.
+trait RowSource {
+    fn collect(&self) -> Result<Vec<WorktreeRow>>;
+}
+
+/// Collects rows from the VCS backend and the state directory
+struct RepoRowSource {
+    backend: Box<dyn VcsBackend>,
+    state_dir: PathBuf,
+    config: Config,
+    clock: Arc<dyn Clock>,
+}
+
+impl RowSource for RepoRowSource {
+    fn collect(&self) -> Result<Vec<WorktreeRow>> {
+        let worktrees = self.backend.list_workspaces()?;
+
+        let container_manager = if self.config.docker.enabled {
+            ContainerManager::new(&self.config.docker, &self.state_dir).ok()
+        } else {
+            None
+        };
+        let allocator = PortAllocator::new(&self.state_dir).ok();
+        let now = self.clock.unix_now();
+
+        Ok(worktrees
+            .into_iter()
+            .map(|wt| {
+                let dirty = self
+                    .backend
+                    .get_workspace_status(&wt.path)
+                    .ok()
+                    .map(|status| !status.is_clean());
+
+                let ahead_behind = self
+                    .backend
+                    .upstream_branch(&wt)
+                    .and_then(|upstream| self.backend.ahead_behind(&wt, &upstream).ok().flatten())
+                    .map(|counts| (counts.ahead, counts.behind));
+
+                let containers = container_manager.as_ref().map(|manager| {
+                    manager
+                        .get_status(&wt.name, &wt.path)
+                        .map(|s| s.container_count)
+                        .unwrap_or(0)
+                });
+
+                let mut ports: Vec<(String, u16)> = allocator
+                    .as_ref()
+                    .and_then(|a| a.get_ports(&wt.name).ok())
+                    .map(|ports| ports.into_iter().collect())
+                    .unwrap_or_default();
+                ports.sort();
+
+                let log_path = monitoring::get_activity_log_path(&self.state_dir, &wt.name);
+                let last_event = ActivityLog::load(&log_path)
+                    .ok()
+                    .and_then(|log| log.last_events(1).first().map(|e| describe_event(e, now)));
+
+                WorktreeRow {
+                    name: wt.name,
+                    path: wt.path,
+                    branch: wt.branch,
+                    dirty,
+                    ahead_behind,
+                    containers,
+                    ports,
+                    last_event,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Short, uncolored description of an activity event
+fn describe_event(event: &ActivityEvent, now: u64) -> String {
+    let (timestamp, what) = match event {
+        ActivityEvent::WorktreeCreated { timestamp, .. } => (*timestamp, "created".to_string()),
+        ActivityEvent::WorktreeRemoved { timestamp } => (*timestamp, "removed".to_string()),
+        ActivityEvent::WorktreeRenamed { timestamp, from } => {
+            (*timestamp, format!("renamed from {}", from))
+        }
+        ActivityEvent::WorktreeAdopted { timestamp, .. } => (*timestamp, "adopted".to_string()),
+        ActivityEvent::WorktreeSwitched { timestamp, .. } => (*timestamp, "switched".to_string()),
+        ActivityEvent::DockerStarted { timestamp, .. } => {
+            (*timestamp, "docker started".to_string())
+        }
+        ActivityEvent::DockerStopped { timestamp } => (*timestamp, "docker stopped".to_string()),
+        ActivityEvent::HookExecuted {
+            timestamp,
+            hook,
+            success,
+            ..
+        } => {
+            let result = if *success { "ok" } else { "failed" };
+            (*timestamp, format!("{} {}", hook, result))
+        }
+        ActivityEvent::IntegrationPerformed {
+            timestamp, source, ..
+        } => (*timestamp, format!("integrated {}", source)),
+        ActivityEvent::SnapshotCreated {
+            timestamp,
+            snapshot_name,
+        } => (*timestamp, format!("snapshot {}", snapshot_name)),
+        ActivityEvent::SnapshotRestored {
+            timestamp,
+            snapshot_name,
+        } => (*timestamp, format!("restored {}", snapshot_name)),
+    };
+
+    format!(
+        "{} ({} ago)",
+        what,
+        format_age(now.saturating_sub(timestamp))
+    )
+}
+
+/// Format an age in seconds as a compact string (e.g. "5m", "3h")
+pub(crate) fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// What the event loop should do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    None,
+    Quit,
+    Switch(String),
+    DockerStart(String),
+    DockerStop(String),
+    Remove(String),
+}
+
+/// State of the live dashboard, independent of the terminal
+struct LiveMonitor<S: RowSource> {
+    source: S,
+    clock: Arc<dyn Clock>,
+    interval: Duration,
+    docker_enabled: bool,
+    last_refresh: Option<Instant>,
+    rows: Vec<WorktreeRow>,
+    selected: usize,
+    message: Option<String>,
+    /// Worktree awaiting a y/n confirmation before removal
+    pending_remove: Option<String>,
+}
+
+impl<S: RowSource> LiveMonitor<S> {
+    fn new(source: S, clock: Arc<dyn Clock>, interval: Duration, docker_enabled: bool) -> Self {
+        Self {
+            source,
+            clock,
+            interval,
+            docker_enabled,
+            last_refresh: None,
+            rows: Vec::new(),
+            selected: 0,
+            message: None,
+            pending_remove: None,
+        }
+    }
+
+    /// Whether the refresh interval has elapsed since the last refresh
+    fn needs_refresh(&self) -> bool {
+        match self.last_refresh {
+            Some(last) => self.clock.now().duration_since(last) >= self.interval,
+            None => true,
+        }
+    }
+
+    /// Time left until the next scheduled refresh
+    fn time_until_refresh(&self) -> Duration {
+        match self.last_refresh {
+            Some(last) => self
+                .interval
+                .saturating_sub(self.clock.now().duration_since(last)),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Refresh if the interval has elapsed; returns true if rows were reloaded
+    fn tick(&mut self) -> bool {
+        if self.needs_refresh() {
+            self.refresh();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reload rows now, keeping the same worktree selected
+    fn refresh(&mut self) {
+        let selected_name = self.selected_row().map(|row| row.name.clone());
+
+        match self.source.collect() {
+            Ok(rows) => {
+                self.rows = rows;
+                self.selected = selected_name
+                    .and_then(|name| self.rows.iter().position(|row| row.name == name))
+                    .unwrap_or(self.selected)
+                    .min(self.rows.len().saturating_sub(1));
+            }
+            Err(e) => self.message = Some(format!("Refresh failed: {}", e)),
+        }
+
+        self.last_refresh = Some(self.clock.now());
+    }
+
+    fn selected_row(&self) -> Option<&WorktreeRow> {
+        self.rows.get(self.selected)
+    }
+
+    fn handle_key(&mut self, code: KeyCode) -> Action {
+        if let Some(name) = self.pending_remove.take() {
+            if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Action::Remove(name);
+            }
+            self.message = Some("Removal cancelled".to_string());
+            return Action::None;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.rows.len().saturating_sub(1));
+            }
+            KeyCode::Char(' ') => self.refresh(),
+            KeyCode::Enter | KeyCode::Char('s') => {
+                if let Some(row) = self.selected_row() {
+                    return Action::Switch(row.name.clone());
+                }
+            }
+            KeyCode::Char('u') | KeyCode::Char('x') => {
+                if !self.docker_enabled {
+                    self.message = Some("Docker support is not enabled".to_string());
+                } else if let Some(row) = self.selected_row() {
+                    let name = row.name.clone();
+                    return if code == KeyCode::Char('u') {
+                        Action::DockerStart(name)
+                    } else {
+                        Action::DockerStop(name)
+                    };
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(name) = self.selected_row().map(|row| row.name.clone()) {
+                    self.message = Some(format!("Remove '{}'? (y/n)", name));
+                    self.pending_remove = Some(name);
+                }
+            }
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    /// Render the dashboard as plain lines, truncated to `width` columns
+    fn render(&self, width: usize) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "Hannahanna Monitor  {} worktree(s)  refresh {}s",
+                self.rows.len(),
+                self.interval.as_secs()
+            ),
+            format_columns(
+                "NAME",
+                "BRANCH",
+                "STATE",
+                "SYNC",
+                "DOCKER",
+                "PORTS",
+                "LAST ACTIVITY",
+            ),
+        ];
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let marker = if index == self.selected { ">" } else { " " };
+            let state = match row.dirty {
+                Some(true) => "dirty",
+                Some(false) => "clean",
+                None => "?",
+            };
+            let sync = row
+                .ahead_behind
+                .map(|(ahead, behind)| format!("+{} -{}", ahead, behind))
+                .unwrap_or_else(|| "-".to_string());
+            let docker = match row.containers {
+                Some(0) => "stopped".to_string(),
+                Some(n) => format!("{} up", n),
+                None => "-".to_string(),
+            };
+            let ports = if row.ports.is_empty() {
+                "-".to_string()
+            } else {
+                row.ports
+                    .iter()
+                    .map(|(service, port)| format!("{}:{}", service, port))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+
+            lines.push(format_columns(
+                &format!("{} {}", marker, row.name),
+                &row.branch,
+                state,
+                &sync,
+                &docker,
+                &ports,
+                row.last_event.as_deref().unwrap_or("-"),
+            ));
+        }
+
+        if self.rows.is_empty() {
+            lines.push("  (no worktrees)".to_string());
+        }
+
+        lines.push(String::new());
+        lines.push(self.message.clone().unwrap_or_default());
+        lines.push(
+            "↑/↓ select  enter switch  u/x docker up/down  r remove  space refresh  q quit"
+                .to_string(),
+        );
+
+        lines
+            .into_iter()
+            .map(|line| line.chars().take(width).collect())
+            .collect()
+    }
+
+    /// Index of the selected row within the rendered lines
+    fn selected_line(&self) -> Option<usize> {
+        if self.rows.is_empty() {
+            None
+        } else {
+            Some(2 + self.selected)
+        }
+    }
+}
+
+fn format_columns(
+    name: &str,
+    branch: &str,
+    state: &str,
+    sync: &str,
+    docker: &str,
+    ports: &str,
+    activity: &str,
+) -> String {
+    format!(
+        "{:<22} {:<20} {:<6} {:<9} {:<8} {:<24} {}",
+        truncate(name, 22),
+        truncate(branch, 20),
+        state,
+        sync,
+        docker,
+        truncate(ports, 24),
+        activity
+    )
+}
+
+/// Run the interactive dashboard until the user quits or switches away
+fn run_live(
+    backend: Box<dyn VcsBackend>,
+    repo_root: &Path,
+    refresh_secs: Option<u64>,
+    no_hooks: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
+    if !std::io::stderr().is_terminal() {
+        return Err(HnError::ConfigError(
+            "hn monitor --live requires an interactive terminal".to_string(),
+        ));
+    }
+
+    let config = Config::load(repo_root)?;
+    let interval = Duration::from_secs(refresh_secs.unwrap_or(DEFAULT_REFRESH_SECS).max(1));
+    let docker_enabled = config.docker.enabled;
+
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let source = RepoRowSource {
+        backend,
+        state_dir: repo_root.join(".hn-state"),
+        config: config.clone(),
+        clock: clock.clone(),
+    };
+    let mut app = LiveMonitor::new(source, clock, interval, docker_enabled);
+
+    let mut terminal = TerminalGuard::enter()?;
+    let mut redraw = true;
+
+    let switch_to = loop {
+        if app.tick() {
+            redraw = true;
+        }
+        if redraw {
+            terminal.draw(&app)?;
+            redraw = false;
+        }
+
+        if !event::poll(app.time_until_refresh().min(INPUT_POLL))? {
+            continue;
+        }
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Resize(_, _) => {
+                redraw = true;
+                continue;
+            }
+            _ => continue,
+        };
+        redraw = true;
+
+        // Raw mode swallows SIGINT, so treat Ctrl-C as quit
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            break None;
+        }
+
+        match app.handle_key(key.code) {
+            Action::None => {}
+            Action::Quit => break None,
+            Action::Switch(name) => break Some(name),
+            Action::DockerStart(name) => docker_action(
+                &mut app,
+                &mut terminal,
+                &config,
+                repo_root,
+                &name,
+                true,
+                no_hooks,
+            )?,
+            Action::DockerStop(name) => docker_action(
+                &mut app,
+                &mut terminal,
+                &config,
+                repo_root,
+                &name,
+                false,
+                no_hooks,
+            )?,
+            Action::Remove(name) => {
+                // Removal may run hooks that print, so hand the terminal back
+                terminal.suspend()?;
+                let result = super::remove::run(name.clone(), false, no_hooks, vcs_type);
+                terminal.resume()?;
+                app.message = Some(match result {
+                    Ok(()) => format!("✓ Removed worktree '{}'", name),
+                    Err(e) => format!("✗ {}", e),
+                });
+                app.refresh();
+            }
+        }
+    };
+
+    drop(terminal);
+
+    match switch_to {
+        Some(name) => super::switch::run(name, vcs_type),
+        None => Ok(()),
+    }
+}
+
+/// Start or stop a worktree's containers from the dashboard
+fn docker_action<S: RowSource>(
+    app: &mut LiveMonitor<S>,
+    terminal: &mut TerminalGuard,
+    config: &Config,
+    repo_root: &Path,
+    name: &str,
+    start: bool,
+    no_hooks: bool,
+) -> Result<()> {
+    let Some(path) = app
+        .rows
+        .iter()
+        .find(|row| row.name == name)
+        .map(|row| row.path.clone())
+    else {
+        return Ok(());
+    };
+    let state_dir = repo_root.join(".hn-state");
+
+    let (verb, done) = if start {
+        ("Starting", "started")
+    } else {
+        ("Stopping", "stopped")
+    };
+    app.message = Some(format!("{} containers for '{}'...", verb, name));
+    terminal.draw(app)?;
+
+    // Docker hooks print, so hand the terminal back while they run
+    let hooks: &[HookType] = if start {
+        &[HookType::PreDockerStart, HookType::PostDockerStart]
+    } else {
+        &[HookType::PostDockerStop]
+    };
+    let run_hooks = !no_hooks && hooks.iter().any(|h| h.is_configured(&config.hooks));
+    if run_hooks {
+        terminal.suspend()?;
+    }
+
+    let result = ContainerManager::new(&config.docker, &state_dir).and_then(|manager| {
+        let hook = |hook_type| {
+            super::docker::run_docker_hook(hook_type, config, repo_root, &manager, name, no_hooks)
+        };
+        if start {
+            hook(HookType::PreDockerStart)?;
+            manager.start(name, &path)?;
+            hook(HookType::PostDockerStart)
+        } else {
+            manager.stop(name, &path)?;
+            hook(HookType::PostDockerStop)
+        }
+    });
+
+    if run_hooks {
+        terminal.resume()?;
+    }
+
+    app.message = Some(match result {
+        Ok(()) => {
+            let event = if start {
+                ActivityEvent::DockerStarted {
+                    timestamp: monitoring::now(),
+                    services: vec![],
+                }
+            } else {
+                ActivityEvent::DockerStopped {
+                    timestamp: monitoring::now(),
+                }
+            };
+            let _ = monitoring::log_activity(&state_dir, name, event);
+            format!("✓ Containers {} for '{}'", done, name)
+        }
+        Err(e) => format!("✗ {}", e),
+    });
+    app.refresh();
+
+    Ok(())
+}
+
+/// Puts stderr into raw mode on an alternate screen and restores it on drop
+struct TerminalGuard {
+    active: bool,
+}
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        let mut guard = Self { active: false };
+        guard.resume()?;
+        Ok(guard)
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            std::io::stderr(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn suspend(&mut self) -> Result<()> {
+        if self.active {
+            execute!(
+                std::io::stderr(),
+                cursor::Show,
+                terminal::LeaveAlternateScreen
+            )?;
+            terminal::disable_raw_mode()?;
+            self.active = false;
+        }
+        Ok(())
+    }
+
+    fn draw<S: RowSource>(&self, app: &LiveMonitor<S>) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let mut out = std::io::stderr().lock();
+
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        let lines = app.render(width as usize);
+        let selected = app.selected_line();
+        for (index, line) in lines.iter().take(height as usize).enumerate() {
+            let attribute = if index == 0 || index == 1 {
+                Attribute::Bold
+            } else if Some(index) == selected {
+                Attribute::Reverse
+            } else {
+                Attribute::Reset
+            };
+            queue!(
+                out,
+                cursor::MoveTo(0, index as u16),
+                SetAttribute(attribute),
+                Print(line),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = self.suspend();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::test_support::FakeClock;
+    use std::cell::{Cell, RefCell};
+
+    struct FakeSource {
+        rows: RefCell<Vec<WorktreeRow>>,
+        calls: Cell<usize>,
+    }
+
+    impl FakeSource {
+        fn new(names: &[&str]) -> Self {
+            Self {
+                rows: RefCell::new(names.iter().map(|name| row(name)).collect()),
+                calls: Cell::new(0),
+            }
+        }
+    }
+
+    impl RowSource for &FakeSource {
+        fn collect(&self) -> Result<Vec<WorktreeRow>> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(self.rows.borrow().clone())
+        }
+    }
+
+    fn row(name: &str) -> WorktreeRow {
+        WorktreeRow {
+            name: name.to_string(),
+            path: PathBuf::from(format!("/tmp/{}", name)),
+            branch: name.to_string(),
+            dirty: Some(false),
+            ..Default::default()
+        }
+    }
+
+    fn monitor<'a>(source: &'a FakeSource, clock: &FakeClock) -> LiveMonitor<&'a FakeSource> {
+        LiveMonitor::new(
+            source,
+            Arc::new(clock.clone()),
+            Duration::from_secs(5),
+            true,
+        )
+    }
+
+    #[test]
+    fn test_repo_rows_age_events_by_the_clock() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=hn", "-c", "user.email=hn@example.com"])
+                .args(args)
+                .current_dir(temp.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+
+        let backend =
+            crate::vcs::init_backend_with_detection(temp.path(), Some(VcsType::Git)).unwrap();
+        let name = backend.list_workspaces().unwrap()[0].name.clone();
+        let state_dir = temp.path().join(".hn-state");
+        let clock = FakeClock::new();
+        monitoring::log_activity(
+            &state_dir,
+            &name,
+            ActivityEvent::WorktreeCreated {
+                timestamp: clock.unix_now() - 120,
+                branch: "main".to_string(),
+                template: None,
+            },
+        )
+        .unwrap();
+
+        let source = RepoRowSource {
+            backend,
+            state_dir,
+            config: Config::default(),
+            clock: Arc::new(clock.clone()),
+        };
+        let event = |source: &RepoRowSource| source.collect().unwrap()[0].last_event.clone();
+        assert_eq!(event(&source).as_deref(), Some("created (2m ago)"));
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(event(&source).as_deref(), Some("created (1h ago)"));
+    }
+
+    #[test]
+    fn test_refreshes_on_interval() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&["a", "b"]);
+        let mut app = monitor(&source, &clock);
+
+        // First tick always loads
+        assert!(app.tick());
+        assert_eq!(source.calls.get(), 1);
+        assert_eq!(app.rows.len(), 2);
+
+        clock.advance(Duration::from_secs(3));
+        assert!(!app.tick());
+        assert_eq!(app.time_until_refresh(), Duration::from_secs(2));
+
+        clock.advance(Duration::from_secs(2));
+        assert!(app.tick());
+        assert_eq!(source.calls.get(), 2);
+
+        // A manual refresh restarts the interval
+        clock.advance(Duration::from_secs(4));
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(source.calls.get(), 3);
+        clock.advance(Duration::from_secs(4));
+        assert!(!app.tick());
+    }
+
+    #[test]
+    fn test_selection_follows_worktree_across_refresh() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&["a", "b", "c"]);
+        let mut app = monitor(&source, &clock);
+        app.tick();
+
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char('j'));
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected_row().unwrap().name, "c");
+
+        // "a" disappears; "c" stays selected at its new index
+        source.rows.borrow_mut().remove(0);
+        clock.advance(Duration::from_secs(5));
+        app.tick();
+        assert_eq!(app.selected, 1);
+        assert_eq!(app.selected_row().unwrap().name, "c");
+
+        // Selected worktree removed: selection is clamped
+        source.rows.borrow_mut().pop();
+        app.refresh();
+        assert_eq!(app.selected_row().unwrap().name, "b");
+    }
+
+    #[test]
+    fn test_key_actions() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&["a", "b"]);
+        let mut app = monitor(&source, &clock);
+        app.tick();
+
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.handle_key(KeyCode::Enter), Action::Switch("b".into()));
+        assert_eq!(
+            app.handle_key(KeyCode::Char('u')),
+            Action::DockerStart("b".into())
+        );
+        assert_eq!(
+            app.handle_key(KeyCode::Char('x')),
+            Action::DockerStop("b".into())
+        );
+        assert_eq!(app.handle_key(KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn test_docker_keys_require_docker() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&["a"]);
+        let mut app = LiveMonitor::new(
+            &source,
+            Arc::new(clock.clone()),
+            Duration::from_secs(5),
+            false,
+        );
+        app.tick();
+
+        assert_eq!(app.handle_key(KeyCode::Char('u')), Action::None);
+        assert!(app.message.as_deref().unwrap().contains("not enabled"));
+    }
+
+    #[test]
+    fn test_remove_requires_confirmation() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&["a"]);
+        let mut app = monitor(&source, &clock);
+        app.tick();
+
+        assert_eq!(app.handle_key(KeyCode::Char('r')), Action::None);
+        assert_eq!(app.handle_key(KeyCode::Char('n')), Action::None);
+        assert_eq!(app.message.as_deref(), Some("Removal cancelled"));
+
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            app.handle_key(KeyCode::Char('y')),
+            Action::Remove("a".into())
+        );
+    }
+
+    #[test]
+    fn test_render_shows_row_details() {
+        let clock = FakeClock::new();
+        let source = FakeSource::new(&[]);
+        source.rows.borrow_mut().push(WorktreeRow {
+            dirty: Some(true),
+            ahead_behind: Some((2, 1)),
+            containers: Some(3),
+            ports: vec![("app".to_string(), 3000), ("db".to_string(), 3001)],
+            last_event: Some("switched (5m ago)".to_string()),
+            ..row("feature-x")
+        });
+        let mut app = monitor(&source, &clock);
+        app.tick();
+
+        let lines = app.render(200);
+        let line = &lines[app.selected_line().unwrap()];
+        assert!(line.starts_with("> feature-x"));
+        assert!(line.contains("dirty"));
+        assert!(line.contains("+2 -1"));
+        assert!(line.contains("3 up"));
+        assert!(line.contains("app:3000,db:3001"));
+        assert!(line.contains("switched (5m ago)"));
+
+        // Lines never exceed the terminal width
+        assert!(app.render(20).iter().all(|l| l.chars().count() <= 20));
+    }
+
+    #[test]
+    fn test_describe_event() {
+        let event = ActivityEvent::HookExecuted {
+            timestamp: 1000,
+            hook: "post_create".to_string(),
+            duration_ms: 10,
+            success: false,
+        };
+        assert_eq!(
+            describe_event(&event, 1000 + 7200),
+            "post_create failed (2h ago)"
+        );
+        assert_eq!(format_age(42), "42s");
+        assert_eq!(format_age(90), "1m");
+        assert_eq!(format_age(200_000), "2d");
     }
 }
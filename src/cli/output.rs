@@ -0,0 +1,280 @@
+// Machine-readable output for list/info/stats (v0.6)
+//
+// The structured formats share a single, versioned schema so that editor
+// plugins and CI scripts don't have to scrape the colored tables. Bump
+// SCHEMA_VERSION whenever a field is renamed or removed; adding fields is
+// considered backwards compatible.
+
+use crate::config::Config;
+use crate::conflict::ConflictState;
+use crate::docker::container::ContainerManager;
+use crate::docker::ports::PortAllocator;
+use crate::errors::{HnError, Result};
+use crate::tags;
+use crate::vcs::traits::VcsBackend;
+use crate::vcs::{Divergence, WorkspaceStatus, Worktree};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Version of the JSON/YAML output schema
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Output format selected with the global `--format` flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, colored tables
+    #[default]
+    Table,
+    /// JSON document
+    Json,
+    /// YAML document
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Returns true for the human-readable table format
+    pub fn is_table(self) -> bool {
+        self == OutputFormat::Table
+    }
+}
+
+/// Working directory status
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusRecord {
+    pub clean: bool,
+    pub modified: usize,
+    pub added: usize,
+    pub deleted: usize,
+    pub untracked: usize,
+}
+
+impl From<&WorkspaceStatus> for StatusRecord {
+    fn from(status: &WorkspaceStatus) -> Self {
+        Self {
+            clean: status.is_clean(),
+            modified: status.modified,
+            added: status.added,
+            deleted: status.deleted,
+            untracked: status.untracked,
+        }
+    }
+}
+
+/// Docker container state (only present when docker is enabled)
+#[derive(Debug, Clone, Serialize)]
+pub struct DockerRecord {
+    pub running: bool,
+    pub container_count: usize,
+}
+
+/// A worktree together with everything hn knows about it
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeRecord {
+    #[serde(flatten)]
+    pub worktree: Worktree,
+    pub status: Option<StatusRecord>,
+    pub tags: Vec<String>,
+    pub ports: BTreeMap<String, u16>,
+    pub docker: Option<DockerRecord>,
+    /// Disk usage of the worktree directory in bytes
+    pub disk_usage: Option<u64>,
+    /// Sync or integrate stopped on conflicts, if any
+    pub conflict: Option<ConflictState>,
+    /// Ahead/behind counts against upstream, parent and default branch
+    pub divergence: Divergence,
+}
+
+impl WorktreeRecord {
+    /// Gather status, tags, ports, docker state and disk usage for a
+    /// worktree whose ahead/behind counts the caller already has
+    ///
+    /// Failures to read individual pieces are not fatal; the corresponding
+    /// field is left empty instead.
+    pub fn collect(
+        worktree: &Worktree,
+        divergence: Divergence,
+        backend: &dyn VcsBackend,
+        state_dir: &Path,
+        config: &Config,
+    ) -> Self {
+        let status = backend
+            .get_workspace_status(&worktree.path)
+            .ok()
+            .map(|s| StatusRecord::from(&s));
+
+        let tags = tags::get_worktree_tags(state_dir, &worktree.name).unwrap_or_default();
+
+        let ports = PortAllocator::new(state_dir)
+            .and_then(|allocator| allocator.get_ports(&worktree.name))
+            .map(|ports| ports.into_iter().collect())
+            .unwrap_or_default();
+
+        let docker = if config.docker.enabled {
+            ContainerManager::new(&config.docker, state_dir)
+                .and_then(|manager| manager.get_status(&worktree.name, &worktree.path))
+                .ok()
+                .map(|s| DockerRecord {
+                    running: s.running,
+                    container_count: s.container_count,
+                })
+        } else {
+            None
+        };
+
+        let disk_usage = super::stats::get_dir_size(&worktree.path).ok();
+
+        let conflict =
+            ConflictState::load_active(&state_dir.join(&worktree.name), backend, &worktree.path);
+
+        Self {
+            worktree: worktree.clone(),
+            status,
+            tags,
+            ports,
+            docker,
+            disk_usage,
+            conflict,
+            divergence,
+        }
+    }
+}
+
+/// `hn list` document
+#[derive(Debug, Serialize)]
+pub struct ListOutput {
+    pub schema_version: u32,
+    pub worktrees: Vec<WorktreeRecord>,
+}
+
+/// `hn info` document
+#[derive(Debug, Serialize)]
+pub struct InfoOutput {
+    pub schema_version: u32,
+    pub vcs: String,
+    pub worktree: WorktreeRecord,
+    pub children: Vec<String>,
+}
+
+/// Per-worktree entry of the `hn stats` document
+#[derive(Debug, Serialize)]
+pub struct StatsEntry {
+    #[serde(flatten)]
+    pub worktree: WorktreeRecord,
+    /// Size of the worktree's state directory in bytes
+    pub state_dir_size: Option<u64>,
+}
+
+/// `hn stats` document
+#[derive(Debug, Serialize)]
+pub struct StatsOutput {
+    pub schema_version: u32,
+    pub worktrees: Vec<StatsEntry>,
+    pub total_disk_usage: u64,
+}
+
+/// Serialize a document in the requested structured format
+pub fn render<T: Serialize>(format: OutputFormat, value: &T) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => serde_yml::to_string(value)
+            .map_err(|e| HnError::ConfigError(format!("Failed to serialize output: {}", e))),
+        OutputFormat::Table => Err(HnError::ConfigError(
+            "Table output cannot be rendered as a document".to_string(),
+        )),
+    }
+}
+
+/// Print a document in the requested structured format to stdout
+pub fn print<T: Serialize>(format: OutputFormat, value: &T) -> Result<()> {
+    let rendered = render(format, value)?;
+    println!("{}", rendered.trim_end());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcs::AheadBehind;
+    use std::path::PathBuf;
+
+    fn sample_record() -> WorktreeRecord {
+        WorktreeRecord {
+            worktree: Worktree {
+                name: "feature-x".to_string(),
+                path: PathBuf::from("/tmp/feature-x"),
+                branch: "feature-x".to_string(),
+                commit: "abc1234def".to_string(),
+                parent: Some("main".to_string()),
+            },
+            status: Some(StatusRecord {
+                clean: false,
+                modified: 1,
+                added: 0,
+                deleted: 0,
+                untracked: 2,
+            }),
+            tags: vec!["ci".to_string()],
+            ports: BTreeMap::from([("app".to_string(), 3000)]),
+            docker: None,
+            disk_usage: Some(1024),
+            conflict: None,
+            divergence: Divergence {
+                default_branch: Some(AheadBehind {
+                    ahead: 2,
+                    behind: 5,
+                }),
+                default_branch_name: Some("main".to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_json_schema_is_flat_and_versioned() {
+        let output = ListOutput {
+            schema_version: SCHEMA_VERSION,
+            worktrees: vec![sample_record()],
+        };
+
+        let json = render(OutputFormat::Json, &output).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        let wt = &value["worktrees"][0];
+        assert_eq!(wt["name"], "feature-x");
+        assert_eq!(wt["parent"], "main");
+        assert_eq!(wt["status"]["untracked"], 2);
+        assert_eq!(wt["tags"][0], "ci");
+        assert_eq!(wt["ports"]["app"], 3000);
+        assert!(wt["docker"].is_null());
+        assert_eq!(wt["disk_usage"], 1024);
+        assert_eq!(wt["divergence"]["default_branch"]["behind"], 5);
+        assert!(wt["divergence"]["upstream"].is_null());
+    }
+
+    #[test]
+    fn test_yaml_output() {
+        let output = ListOutput {
+            schema_version: SCHEMA_VERSION,
+            worktrees: vec![sample_record()],
+        };
+
+        let yaml = render(OutputFormat::Yaml, &output).unwrap();
+        assert!(yaml.contains("schema_version: 1"));
+        assert!(yaml.contains("name: feature-x"));
+    }
+
+    #[test]
+    fn test_table_cannot_be_rendered() {
+        assert!(render(
+            OutputFormat::Table,
+            &ListOutput {
+                schema_version: SCHEMA_VERSION,
+                worktrees: vec![],
+            }
+        )
+        .is_err());
+    }
+}
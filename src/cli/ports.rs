@@ -1,6 +1,6 @@
 use crate::config::Config;
-use crate::docker::ports::PortAllocator;
-use crate::errors::Result;
+use crate::docker::ports::{GlobalPortRegistry, PortAllocator};
+use crate::errors::{HnError, Result};
 use std::env;
 
 /// List all port allocations across worktrees
@@ -28,6 +28,54 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
+/// List the machine-wide registry, reclaiming entries of deleted repositories
+pub fn list_global() -> Result<()> {
+    let path = GlobalPortRegistry::default_path().ok_or_else(|| {
+        HnError::ConfigError(
+            "Could not determine data directory for the global port registry".to_string(),
+        )
+    })?;
+
+    let reclaimed = GlobalPortRegistry::reclaim_stale(&path)?;
+    let registry = GlobalPortRegistry::load(&path)?;
+
+    if registry.ports.is_empty() {
+        println!("No global port allocations found.");
+        println!("Enable the global registry with docker.ports.global: true");
+    } else {
+        println!(
+            "{:<8} {:<40} {:<20} {:<15}",
+            "PORT", "REPOSITORY", "WORKTREE", "SERVICE"
+        );
+        println!("{}", "-".repeat(85));
+
+        for (port, owner) in &registry.ports {
+            println!(
+                "{:<8} {:<40} {:<20} {:<15}",
+                port,
+                owner.repo.display(),
+                owner.worktree,
+                owner.service
+            );
+        }
+    }
+
+    if !reclaimed.is_empty() {
+        println!("\nReclaimed {} stale allocation(s):", reclaimed.len());
+        for (port, owner) in reclaimed {
+            println!(
+                "  {} ({} {} in {})",
+                port,
+                owner.worktree,
+                owner.service,
+                owner.repo.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// Show port allocations for a specific worktree
 pub fn show(name: String) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
@@ -50,9 +98,10 @@ pub fn show(name: String) -> Result<()> {
 /// Release port allocations for a worktree
 pub fn release(name: String) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
     let state_dir = repo_root.join(".hn-state");
 
-    let mut allocator = PortAllocator::new(&state_dir)?;
+    let mut allocator = PortAllocator::from_config(&state_dir, &config.docker.ports)?;
     allocator.release(&name)?;
 
     println!("Released port allocations for '{}'", name);
@@ -60,12 +109,13 @@ pub fn release(name: String) -> Result<()> {
     Ok(())
 }
 
-/// Reassign ports for a worktree (release old, allocate new)
+/// Reassign ports for a worktree, moving each service off its current port
 pub fn reassign(name: String) -> Result<()> {
     let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?;
     let state_dir = repo_root.join(".hn-state");
 
-    let mut allocator = PortAllocator::new(&state_dir)?;
+    let mut allocator = PortAllocator::from_config(&state_dir, &config.docker.ports)?;
 
     // Get current allocations to know which services were allocated
     let current_allocations = allocator.get_ports(&name)?;
@@ -78,20 +128,13 @@ pub fn reassign(name: String) -> Result<()> {
         return Ok(());
     }
 
-    // Extract service names from current allocations
-    let services: Vec<&str> = current_allocations.keys().map(|s| s.as_str()).collect();
-
     println!("Reassigning ports for '{}'...", name);
     println!("Current allocations:");
     for (service, port) in &current_allocations {
         println!("  {}: {}", service, port);
     }
 
-    // Release current allocations
-    allocator.release(&name)?;
-
-    // Allocate new ports for the same services
-    let new_allocations = allocator.allocate(&name, &services)?;
+    let new_allocations = allocator.reassign(&name)?;
 
     println!("\nNew allocations:");
     for (service, port) in &new_allocations {
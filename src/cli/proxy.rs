@@ -0,0 +1,78 @@
+// Local reverse proxy
+//
+// `hn proxy` serves http://<worktree>.<domain>:<port> from each worktree's
+// allocated port, `hn proxy routes` shows the routes it would serve.
+
+use crate::config::Config;
+use crate::docker::proxy::{self, ReverseProxy};
+use crate::errors::{HnError, Result};
+use colored::*;
+use std::env;
+use std::net::TcpListener;
+
+/// Run the proxy in the foreground until interrupted
+pub fn run(port: Option<u16>, domain: Option<String>) -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let mut config = Config::load(&repo_root)?.docker.proxy;
+    let state_dir = repo_root.join(".hn-state");
+
+    if let Some(port) = port {
+        config.port = port;
+    }
+    if let Some(domain) = domain {
+        proxy::validate_domain(&domain)?;
+        config.domain = domain;
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", config.port)).map_err(|e| {
+        HnError::DockerError(format!(
+            "Failed to listen on 127.0.0.1:{}: {}. Set docker.proxy.port or use --port.",
+            config.port, e
+        ))
+    })?;
+
+    println!(
+        "{} Proxying http://*.{}:{} to '{}' ports (Ctrl-C to stop)",
+        "✓".green(),
+        config.domain,
+        config.port,
+        config.service
+    );
+    for route in proxy::routes(&state_dir, &config)? {
+        println!(
+            "  http://{}:{} → localhost:{}",
+            route.host, config.port, route.port
+        );
+    }
+
+    ReverseProxy::new(&state_dir, &config).serve(listener)
+}
+
+/// List the hostnames the proxy routes and their ports
+pub fn routes() -> Result<()> {
+    let repo_root = Config::find_repo_root(&env::current_dir()?)?;
+    let config = Config::load(&repo_root)?.docker.proxy;
+    let state_dir = repo_root.join(".hn-state");
+
+    let routes = proxy::routes(&state_dir, &config)?;
+    if routes.is_empty() {
+        println!(
+            "No routes: no worktree has a '{}' port allocated.",
+            config.service
+        );
+        return Ok(());
+    }
+
+    println!("{:<40} {:<20} {:<10}", "URL", "WORKTREE", "PORT");
+    println!("{}", "-".repeat(70));
+    for route in routes {
+        println!(
+            "{:<40} {:<20} {:<10}",
+            format!("http://{}:{}", route.host, config.port),
+            route.worktree,
+            route.port
+        );
+    }
+
+    Ok(())
+}
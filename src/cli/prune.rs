@@ -11,8 +11,14 @@ pub fn run() -> Result<()> {
     // Find repository root
     let repo_root = Config::find_repo_root(&std::env::current_dir()?)?;
 
-    // Get list of active worktrees
-    let worktrees = git.list_worktrees()?;
+    // Get list of active worktrees. Names are derived from the configured
+    // worktrees.root layout, so relocated worktrees keep their state.
+    // Worktrees whose directory has disappeared no longer count as active.
+    let worktrees: Vec<_> = git
+        .list_worktrees()?
+        .into_iter()
+        .filter(|wt| wt.path.exists())
+        .collect();
     let active_names: Vec<String> = worktrees.iter().map(|wt| wt.name.clone()).collect();
 
     // Initialize state manager
@@ -50,7 +56,7 @@ pub fn run() -> Result<()> {
     let mut total_orphaned_stashes = 0;
 
     for worktree in &worktrees {
-        match snapshot::cleanup_orphaned_stashes(&state_dir, &worktree.path) {
+        match snapshot::cleanup_orphaned_stashes(&git, &state_dir, &worktree.path) {
             Ok(count) if count > 0 => {
                 println!(
                     "  Cleaned {} orphaned stash{} from '{}'",
@@ -2,6 +2,7 @@ use crate::config::Config;
 use crate::docker::compose::ComposeGenerator;
 use crate::docker::container::ContainerManager;
 use crate::docker::ports::PortAllocator;
+use crate::docker::shared::SharedRegistry;
 use crate::env::validation;
 use crate::errors::{HnError, Result};
 use crate::fuzzy;
@@ -86,7 +87,7 @@ pub fn run(name: String, force: bool, no_hooks: bool, vcs_type: Option<VcsType>)
 
     if has_pre_remove_hooks && !no_hooks {
         println!("Running pre_remove hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root);
         hook_executor.run_hook(HookType::PreRemove, &worktree, &state_dir)?;
         println!("✓ Hook completed successfully");
     } else if has_pre_remove_hooks && no_hooks {
@@ -102,13 +103,29 @@ pub fn run(name: String, force: bool, no_hooks: bool, vcs_type: Option<VcsType>)
         // Stop containers
         let container_mgr = ContainerManager::new(&config.docker, &state_dir_path)?;
         match container_mgr.stop(&matched_name, &worktree.path) {
-            Ok(_) => println!("✓ Containers stopped"),
+            Ok(_) => {
+                println!("✓ Containers stopped");
+                if let Err(e) = super::docker::run_docker_hook(
+                    HookType::PostDockerStop,
+                    &config,
+                    &repo_root,
+                    &container_mgr,
+                    &matched_name,
+                    no_hooks,
+                ) {
+                    println!("⚠ post_docker_stop hook failed: {}", e);
+                }
+            }
             Err(e) => println!("⚠ Failed to stop containers: {}", e),
         }
 
         // Release ports
-        let mut port_allocator = PortAllocator::new(&state_dir_path)?;
+        let mut port_allocator = PortAllocator::from_config(&state_dir_path, &config.docker.ports)?;
         port_allocator.release(&matched_name)?;
+        let mut shared_registry = SharedRegistry::load(&state_dir_path)?;
+        if shared_registry.release(&matched_name) {
+            shared_registry.save(&state_dir_path)?;
+        }
         println!("✓ Ports released");
 
         // Remove override file
@@ -144,7 +161,7 @@ pub fn run(name: String, force: bool, no_hooks: bool, vcs_type: Option<VcsType>)
 
     if has_post_remove_hooks && !no_hooks {
         println!("Running post_remove hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root);
         hook_executor.run_hook(HookType::PostRemove, &worktree, &state_dir)?;
         println!("✓ Hook completed successfully");
     } else if has_post_remove_hooks && no_hooks {
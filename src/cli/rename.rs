@@ -0,0 +1,304 @@
+// Rename command: Move a worktree to a new name, keeping everything hn knows
+// about it
+use crate::config::Config;
+use crate::docker::compose::{self, ComposeGenerator};
+use crate::docker::container::ContainerManager;
+use crate::docker::ports::PortAllocator;
+use crate::docker::shared::{self, DockerStrategy, SharedRegistry};
+use crate::env::validation;
+use crate::errors::{HnError, Result};
+use crate::fuzzy;
+use crate::monitoring;
+use crate::restack::RestackPlan;
+use crate::snapshot;
+use crate::state::StateManager;
+use crate::tags;
+use crate::vcs::traits::VcsBackend;
+use crate::vcs::{init_backend_from_current_dir, RegistryCache, VcsType, Worktree};
+use std::env;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+/// One step of carrying a worktree's state over to its new name
+type Step<'a> = Box<dyn Fn() -> Result<()> + 'a>;
+
+pub fn run(
+    old_name: String,
+    new_name: String,
+    rename_branch: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
+    validation::validate_worktree_name(&new_name)?;
+
+    let cwd = env::current_dir()?;
+    let backend = if let Some(vcs) = vcs_type {
+        crate::vcs::init_backend_with_detection(&cwd, Some(vcs))?
+    } else {
+        init_backend_from_current_dir()?
+    };
+
+    // The worktree being renamed may be the one we're in, so work from the
+    // main repository, which stays put
+    let repo_root = backend.main_repo_root()?;
+    let backend = crate::vcs::init_backend_with_detection(&repo_root, Some(backend.vcs_type()))?;
+
+    let worktrees = backend.list_workspaces()?;
+    let worktree_names: Vec<String> = worktrees.iter().map(|wt| wt.name.clone()).collect();
+    let matched_name = fuzzy::find_best_match(&old_name, &worktree_names)?;
+    if matched_name != old_name {
+        eprintln!("Matched '{}' to '{}'", old_name, matched_name);
+    }
+
+    let worktree = backend.get_workspace_by_name(&matched_name)?;
+    if worktree.path == repo_root {
+        return Err(HnError::CommandFailed(
+            "The main repository can't be renamed".to_string(),
+        ));
+    }
+    if worktree_names.contains(&new_name) {
+        return Err(HnError::WorktreeAlreadyExists(new_name));
+    }
+
+    let state_manager = StateManager::new(&repo_root)?;
+    let state_dir = repo_root.join(".hn-state");
+    if state_manager.get_state_dir(&new_name).exists() {
+        return Err(HnError::StateError(format!(
+            "A state directory for '{}' already exists. Remove it with 'hn state clean' first.",
+            new_name
+        )));
+    }
+    if RestackPlan::load(&state_dir)?.is_some() {
+        return Err(HnError::StateError(
+            "A restack is waiting on conflicts. Run 'hn restack --continue' or 'hn restack --abort' first."
+                .to_string(),
+        ));
+    }
+
+    let config = Config::load(&repo_root)?;
+    check_state_writable(&state_dir, &config)?;
+
+    // The containers run under a project named after the worktree, so the
+    // old project has to come down, stopped containers included, before the
+    // name changes
+    let mut was_running = false;
+    if config.docker.enabled {
+        let manager = ContainerManager::new(&config.docker, &state_dir)?;
+        was_running = manager
+            .get_status(&matched_name, &worktree.path)
+            .is_ok_and(|status| status.running);
+        if was_running {
+            eprintln!("Stopping containers for '{}'...", matched_name);
+            manager.stop(&matched_name, &worktree.path)?;
+        } else if let Err(e) = manager.stop(&matched_name, &worktree.path) {
+            eprintln!(
+                "⚠ Couldn't take down stopped containers of '{}': {}",
+                matched_name, e
+            );
+        }
+    }
+
+    let renamed = backend.rename_workspace(&matched_name, &new_name, rename_branch)?;
+    eprintln!(
+        "✓ Moved {} to {}",
+        worktree.path.display(),
+        renamed.path.display()
+    );
+    if rename_branch {
+        eprintln!("✓ Renamed branch {} to {}", worktree.branch, renamed.branch);
+    }
+
+    // Everything hn keeps about the worktree is keyed by its name. Each step
+    // comes with its undo, and the activity log, which needs none, goes last
+    let (old, new) = (matched_name.as_str(), new_name.as_str());
+    let (old_branch, new_branch) = (worktree.branch.as_str(), renamed.branch.as_str());
+    let rename_ports = |from: &str, to: &str| {
+        PortAllocator::from_config(&state_dir, &config.docker.ports)?.rename(from, to)
+    };
+    let rename_shared = |from: &str, to: &str| {
+        let mut shared_registry = SharedRegistry::load(&state_dir)?;
+        if shared_registry.rename(from, to) {
+            shared_registry.save(&state_dir)?;
+        }
+        Ok(())
+    };
+    let steps: Vec<(Step, Step)> = vec![
+        (
+            Box::new(|| state_manager.rename_state_dir(old, new)),
+            Box::new(|| state_manager.rename_state_dir(new, old)),
+        ),
+        (
+            Box::new(|| tags::rename_worktree(&state_dir, old, new)),
+            Box::new(|| tags::rename_worktree(&state_dir, new, old)),
+        ),
+        (
+            Box::new(|| snapshot::rename_worktree(&state_dir, old, new, old_branch, new_branch)),
+            Box::new(|| snapshot::rename_worktree(&state_dir, new, old, new_branch, old_branch)),
+        ),
+        (
+            Box::new(|| rename_ports(old, new)),
+            Box::new(|| rename_ports(new, old)),
+        ),
+        (
+            Box::new(|| rename_shared(old, new)),
+            Box::new(|| rename_shared(new, old)),
+        ),
+        (
+            Box::new(|| monitoring::record_rename(&state_dir, old, new)),
+            Box::new(|| Ok(())),
+        ),
+    ];
+    if let Err(e) = carry_over(&steps) {
+        move_back(backend.as_ref(), &worktree, new, rename_branch);
+        return Err(e);
+    }
+    eprintln!("✓ Moved state, tags, snapshots and port allocations");
+
+    let children: Vec<_> = worktrees
+        .iter()
+        .filter(|wt| wt.parent.as_deref() == Some(matched_name.as_str()))
+        .collect();
+    for child in &children {
+        match backend.reparent_workspace(child, &new_name) {
+            Ok(()) => eprintln!("✓ Reparented '{}'", child.name),
+            Err(e) => eprintln!(
+                "⚠ Failed to reparent '{}' (you may need to update it manually): {}",
+                child.name, e
+            ),
+        }
+    }
+
+    if config.docker.enabled {
+        let setup = super::docker::prepare_worktree(&config, &state_dir, &new_name, &renamed.path)?;
+        ComposeGenerator::new(&config.docker, &state_dir).write(&new_name, &setup.compose)?;
+        eprintln!("✓ Regenerated docker-compose override");
+        warn_left_behind(&config, &matched_name, &new_name, &renamed.path)?;
+        if was_running {
+            eprintln!(
+                "  Containers were stopped; start them again with: hn docker start {}",
+                new_name
+            );
+        }
+    }
+
+    if let Ok(cache) = RegistryCache::new(&state_dir, None) {
+        let _ = cache.invalidate(); // Ignore cache invalidation errors
+    }
+
+    println!("Renamed worktree '{}' to '{}'", matched_name, new_name);
+    if cwd.starts_with(&worktree.path) {
+        eprintln!(
+            "  Your shell is still in the old directory; cd {}",
+            renamed.path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Check that the state a rename carries over can be read and written
+/// before anything is moved
+fn check_state_writable(state_dir: &Path, config: &Config) -> Result<()> {
+    tags::TagIndex::load(state_dir)?;
+    snapshot::SnapshotIndex::load(&snapshot::get_snapshot_index_path(state_dir))?;
+    SharedRegistry::load(state_dir)?;
+    PortAllocator::from_config(state_dir, &config.docker.ports)?;
+
+    let unwritable = |path: &Path, e: std::io::Error| {
+        HnError::StateError(format!(
+            "Can't rename: {} isn't writable: {}",
+            path.display(),
+            e
+        ))
+    };
+    tempfile::tempfile_in(state_dir).map_err(|e| unwritable(state_dir, e))?;
+    for file in [
+        "tag-index.json",
+        "snapshots.json",
+        "port-registry.yaml",
+        "shared-registry.yaml",
+    ] {
+        let path = state_dir.join(file);
+        if path.exists() {
+            OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .map_err(|e| unwritable(&path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run each step, undoing the ones already done if one fails
+fn carry_over(steps: &[(Step, Step)]) -> Result<()> {
+    for (done, (apply, _)) in steps.iter().enumerate() {
+        if let Err(e) = apply() {
+            for (_, undo) in steps[..done].iter().rev() {
+                if let Err(undo_error) = undo() {
+                    eprintln!("⚠ Couldn't undo part of the rename: {}", undo_error);
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Put a worktree back where it was after its state couldn't follow it
+fn move_back(backend: &dyn VcsBackend, worktree: &Worktree, new_name: &str, rename_branch: bool) {
+    // The branch only gets its old name back if that was the worktree's name
+    let restore_branch = rename_branch && worktree.branch == worktree.name;
+    match backend.rename_workspace(new_name, &worktree.name, restore_branch) {
+        Ok(_) => {
+            eprintln!("✓ Moved the worktree back to {}", worktree.path.display());
+            if rename_branch && !restore_branch {
+                eprintln!(
+                    "⚠ Branch '{}' keeps its new name; rename it back to '{}' by hand",
+                    new_name, worktree.branch
+                );
+            }
+        }
+        Err(e) => eprintln!(
+            "⚠ Couldn't move the worktree back to {}: {}",
+            worktree.path.display(),
+            e
+        ),
+    }
+}
+
+/// Point out the Docker data named after the old worktree name, which the
+/// renamed worktree no longer uses
+fn warn_left_behind(config: &Config, old_name: &str, new_name: &str, path: &Path) -> Result<()> {
+    let mut left_behind: Vec<String> = config
+        .docker
+        .isolated
+        .volumes
+        .iter()
+        .map(|volume| {
+            format!(
+                "volume '{}'",
+                compose::isolated_volume_name(old_name, volume)
+            )
+        })
+        .collect();
+
+    let shared_services =
+        DockerStrategy::from_config(&config.docker)?.shared_services_in(&config.docker, path);
+    let renamed = shared::named_data(&config.docker, &shared_services, new_name);
+    left_behind.extend(
+        shared::named_data(&config.docker, &shared_services, old_name)
+            .into_iter()
+            .filter(|data| !renamed.contains(data)),
+    );
+
+    if !left_behind.is_empty() {
+        eprintln!(
+            "⚠ Docker data named after '{}' isn't carried over to '{}':",
+            old_name, new_name
+        );
+        for data in &left_behind {
+            eprintln!("  {}", data);
+        }
+        eprintln!("  Copy over anything you still need, then remove it.");
+    }
+    Ok(())
+}
@@ -0,0 +1,292 @@
+// Restack command: Rebase child worktrees onto their parents
+use crate::conflict::{ConflictOperation, ConflictState};
+use crate::errors::{HnError, Result};
+use crate::restack::{self, RestackPlan, RestackStep};
+use crate::state::StateManager;
+use crate::vcs::traits::{MergeOutcome, VcsBackend};
+use crate::vcs::{init_backend, VcsType, Worktree};
+use std::path::{Path, PathBuf};
+
+/// The main repository's `.hn-state` directory
+fn state_root(backend: &dyn VcsBackend) -> Result<PathBuf> {
+    let repo_root = backend.main_repo_root()?;
+    StateManager::new(&repo_root)?;
+    Ok(repo_root.join(".hn-state"))
+}
+
+fn find<'a>(worktrees: &'a [Worktree], name: &str) -> Result<&'a Worktree> {
+    worktrees
+        .iter()
+        .find(|wt| wt.name == name)
+        .ok_or_else(|| HnError::WorktreeNotFound(name.to_string()))
+}
+
+/// Rebase the current worktree's descendants, or those of every worktree
+/// with `all`, onto their parents (`hn restack`)
+pub fn run(all: bool, dry_run: bool, vcs_type: Option<VcsType>) -> Result<()> {
+    let backend = init_backend(vcs_type)?;
+    let state_root = state_root(backend.as_ref())?;
+
+    if let Some(pending) = RestackPlan::load(&state_root)? {
+        return Err(HnError::StateError(format!(
+            "A restack is waiting on conflicts in '{}'. Run 'hn restack --continue' or 'hn restack --abort' first.",
+            pending.steps.first().map_or("?", |step| step.worktree.as_str())
+        )));
+    }
+
+    let worktrees = backend.list_workspaces()?;
+    let current = if all {
+        None
+    } else {
+        Some(backend.get_current_workspace()?)
+    };
+    let roots = match current {
+        Some(ref current) => vec![current.name.as_str()],
+        None => restack::roots(&worktrees),
+    };
+
+    let steps = restack::plan(&worktrees, &roots);
+    if steps.is_empty() {
+        match current {
+            Some(current) => eprintln!(
+                "Nothing to restack: '{}' has no child worktrees (use --all for every worktree)",
+                current.name
+            ),
+            None => eprintln!("Nothing to restack: no worktree has a parent"),
+        }
+        return Ok(());
+    }
+
+    if dry_run {
+        print_plan(backend.as_ref(), &worktrees, &steps)?;
+        return Ok(());
+    }
+
+    // Rebasing needs clean worktrees; better to find out before starting
+    let dirty: Vec<&str> = steps
+        .iter()
+        .map(|step| find(&worktrees, &step.worktree))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|wt| {
+            backend
+                .get_workspace_status(&wt.path)
+                .is_ok_and(|status| !status.is_clean())
+        })
+        .map(|wt| wt.name.as_str())
+        .collect();
+    if !dirty.is_empty() {
+        return Err(HnError::CommandFailed(format!(
+            "Worktrees with uncommitted changes can't be restacked: {}. Commit or stash them first.",
+            dirty.join(", ")
+        )));
+    }
+
+    execute(
+        backend.as_ref(),
+        &worktrees,
+        &steps,
+        &state_root,
+        crate::monitoring::now(),
+        0,
+    )
+}
+
+/// Print what a restack would do (`--dry-run`)
+fn print_plan(
+    backend: &dyn VcsBackend,
+    worktrees: &[Worktree],
+    steps: &[RestackStep],
+) -> Result<()> {
+    println!("Restack plan:");
+    for (i, step) in steps.iter().enumerate() {
+        let worktree = find(worktrees, &step.worktree)?;
+        let state = match backend.ahead_behind(worktree, &step.onto)? {
+            Some(counts) if counts.behind == 0 => "up to date".to_string(),
+            Some(counts) => format!("{} behind", counts.behind),
+            None => format!("'{}' not found", step.onto),
+        };
+        println!(
+            "  {}. rebase {} onto {} (parent {}): {}",
+            i + 1,
+            step.worktree,
+            step.onto,
+            step.parent,
+            state
+        );
+    }
+    println!("\nWorktrees are rebased in this order; a parent that moves moves its children too.");
+    Ok(())
+}
+
+/// Rebase each worktree in turn, stopping at the first conflict
+fn execute(
+    backend: &dyn VcsBackend,
+    worktrees: &[Worktree],
+    steps: &[RestackStep],
+    state_root: &Path,
+    started_at: u64,
+    mut restacked: usize,
+) -> Result<()> {
+    for (i, step) in steps.iter().enumerate() {
+        let worktree = find(worktrees, &step.worktree)?;
+        let state_dir = state_root.join(&worktree.name);
+        // Looked up afresh, as an earlier step may have just rebased it
+        let parent_commit = backend.get_workspace_by_name(&step.parent)?.commit;
+
+        if let Some(counts) = backend.ahead_behind(worktree, &step.onto)? {
+            if counts.behind == 0 {
+                restack::record_base(backend, &state_dir, worktree, &parent_commit)?;
+                eprintln!("✓ {} is already on top of {}", step.worktree, step.onto);
+                continue;
+            }
+        }
+
+        eprintln!("→ Rebasing {} onto {}...", step.worktree, step.onto);
+        let outcome = match restack::load_base(&state_dir)? {
+            Some(base) => backend.rebase_onto(&worktree.path, &step.onto, &base)?,
+            None => backend.rebase(&worktree.path, &step.onto)?,
+        };
+        match outcome {
+            MergeOutcome::Success => {
+                restack::record_base(backend, &state_dir, worktree, &parent_commit)?;
+                restacked += 1;
+            }
+            MergeOutcome::Conflict => {
+                let files = backend.conflicted_files(&worktree.path)?;
+                ConflictState {
+                    command: "restack".to_string(),
+                    operation: ConflictOperation::Rebase,
+                    source: step.onto.clone(),
+                    files: files.clone(),
+                    autostash: None,
+                    previous_commit: worktree.commit.clone(),
+                    started_at: crate::monitoring::now(),
+                }
+                .save(&state_dir)?;
+                RestackPlan {
+                    steps: steps[i..].to_vec(),
+                    started_at,
+                    restacked,
+                }
+                .save(state_root)?;
+
+                eprintln!("\n⚠ Rebase conflicts in {} file(s):", files.len());
+                for file in &files {
+                    eprintln!("  {}", file);
+                }
+                eprintln!(
+                    "\nResolve the conflicts in {} and {}, then run:",
+                    worktree.path.display(),
+                    backend.vcs_type().resolve_hint()
+                );
+                eprintln!("  hn restack --continue");
+                eprintln!("Or stop the restack here with:");
+                eprintln!("  hn restack --abort");
+                return Err(HnError::StateError(format!(
+                    "Restack stopped on conflicts in '{}'",
+                    step.worktree
+                )));
+            }
+        }
+    }
+
+    RestackPlan::clear(state_root)?;
+    eprintln!("✓ Restacked {} worktree(s)", restacked);
+    Ok(())
+}
+
+/// Finish the rebase a restack stopped on and carry on with the rest
+/// (`hn restack --continue`)
+pub fn continue_restack(vcs_type: Option<VcsType>) -> Result<()> {
+    let backend = init_backend(vcs_type)?;
+    let state_root = state_root(backend.as_ref())?;
+    let pending = RestackPlan::load(&state_root)?
+        .ok_or_else(|| HnError::StateError("No restack in progress".to_string()))?;
+    let Some(step) = pending.steps.first() else {
+        RestackPlan::clear(&state_root)?;
+        return Ok(());
+    };
+
+    let worktrees = backend.list_workspaces()?;
+    let worktree = find(&worktrees, &step.worktree)?;
+    let state_dir = state_root.join(&worktree.name);
+
+    let remaining = backend.conflicted_files(&worktree.path)?;
+    if !remaining.is_empty() {
+        eprintln!("\n⚠ Unresolved conflicts in {} file(s):", remaining.len());
+        for file in &remaining {
+            eprintln!("  {}", file);
+        }
+        return Err(HnError::StateError(format!(
+            "Resolve the remaining conflicts in '{}' and {} first",
+            worktree.name,
+            backend.vcs_type().resolve_hint()
+        )));
+    }
+
+    // The rebase may have been finished by hand already
+    if backend.operation_in_progress(&worktree.path) == Some(ConflictOperation::Rebase) {
+        eprintln!("→ Continuing rebase of {}...", worktree.name);
+        let outcome =
+            backend.continue_operation(&worktree.path, ConflictOperation::Rebase, &step.onto)?;
+        if outcome == MergeOutcome::Conflict {
+            // A later commit of the rebase conflicts too
+            let files = backend.conflicted_files(&worktree.path)?;
+            if let Some(mut conflict) = ConflictState::load(&state_dir)? {
+                conflict.files = files.clone();
+                conflict.save(&state_dir)?;
+            }
+            eprintln!("\n⚠ Rebase conflicts in {} file(s):", files.len());
+            for file in &files {
+                eprintln!("  {}", file);
+            }
+            eprintln!("\nResolve them and run 'hn restack --continue' again.");
+            return Err(HnError::StateError(format!(
+                "Restack stopped on conflicts in '{}'",
+                worktree.name
+            )));
+        }
+    }
+
+    ConflictState::clear(&state_dir)?;
+    let parent_commit = backend.get_workspace_by_name(&step.parent)?.commit;
+    restack::record_base(backend.as_ref(), &state_dir, worktree, &parent_commit)?;
+    eprintln!("✓ Rebased {} onto {}", step.worktree, step.onto);
+
+    execute(
+        backend.as_ref(),
+        &worktrees,
+        &pending.steps[1..],
+        &state_root,
+        pending.started_at,
+        pending.restacked + 1,
+    )
+}
+
+/// Abort the rebase a restack stopped on and drop the rest of the restack
+/// (`hn restack --abort`)
+pub fn abort(vcs_type: Option<VcsType>) -> Result<()> {
+    let backend = init_backend(vcs_type)?;
+    let state_root = state_root(backend.as_ref())?;
+    let pending = RestackPlan::load(&state_root)?
+        .ok_or_else(|| HnError::StateError("No restack in progress".to_string()))?;
+
+    if let Some(step) = pending.steps.first() {
+        let worktree = backend.get_workspace_by_name(&step.worktree)?;
+        if backend.operation_in_progress(&worktree.path) == Some(ConflictOperation::Rebase) {
+            backend.abort_operation(&worktree.path, ConflictOperation::Rebase)?;
+        }
+        ConflictState::clear(&state_root.join(&worktree.name))?;
+        eprintln!("✓ Aborted the rebase of {}", worktree.name);
+    }
+
+    RestackPlan::clear(&state_root)?;
+    if pending.steps.len() > 1 {
+        eprintln!(
+            "  Skipped {} remaining worktree(s); worktrees restacked earlier keep their new base.",
+            pending.steps.len() - 1
+        );
+    }
+    Ok(())
+}
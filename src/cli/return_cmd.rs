@@ -1,8 +1,11 @@
 // Return command: Switch back to parent worktree with optional merge
+use crate::config::Config;
 use crate::errors::{HnError, Result};
+use crate::hooks::{HookExecutor, HookType};
+use crate::state::StateManager;
+use crate::vcs::traits::{MergeOptions, MergeOutcome};
 use crate::vcs::{init_backend_from_current_dir, VcsType};
 use std::env;
-use std::process::Command;
 
 pub fn run(
     merge: bool,
@@ -36,6 +39,7 @@ pub fn run(
     // Check if current worktree has a parent
     let parent_name = current_worktree
         .parent
+        .clone()
         .ok_or_else(|| HnError::NoParent(current_worktree.name.clone()))?;
 
     // Get parent worktree info
@@ -53,6 +57,20 @@ pub fn run(
     eprintln!("Current worktree: {}", current_worktree.name);
     eprintln!("Parent worktree: {}", parent_name);
 
+    // Run pre_return hook in the worktree being left
+    let repo_root = Config::find_repo_root(&current_worktree.path)?;
+    let config = Config::load(&repo_root)?;
+    if !no_hooks && HookType::PreReturn.is_configured(&config.hooks) {
+        let state_dir = StateManager::new(&repo_root)?.get_state_dir(&current_worktree.name);
+
+        eprintln!("Running pre_return hook...");
+        HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root)
+            .with_env("HNHN_RETURN_TARGET", parent_name.as_str())
+            .with_env("HNHN_RETURN_MERGE", merge.to_string())
+            .run_hook(HookType::PreReturn, &current_worktree, &state_dir)?;
+        eprintln!("✓ Pre-return hook completed successfully");
+    }
+
     // If merge requested, merge current branch into parent
     if merge {
         eprintln!(
@@ -64,23 +82,28 @@ pub fn run(
         env::set_current_dir(&parent.path)?;
 
         // Perform the merge
-        let mut cmd = Command::new("git");
-        cmd.arg("merge");
-
-        if no_ff {
-            cmd.arg("--no-ff");
-        }
-
-        cmd.arg(&current_worktree.branch);
-
-        let output = cmd.output()?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(HnError::Git(git2::Error::from_str(&format!(
+        let options = MergeOptions {
+            no_ff,
+            ..Default::default()
+        };
+        let merge_error = |detail: String| {
+            HnError::Git(git2::Error::from_str(&format!(
                 "Failed to merge '{}' into '{}': {}",
-                current_worktree.branch, parent_name, stderr
-            ))));
+                current_worktree.branch, parent_name, detail
+            )))
+        };
+        match backend.merge(&parent.path, &current_worktree.branch, &options) {
+            Ok(MergeOutcome::Success) => {}
+            Ok(MergeOutcome::Conflict) => {
+                let files = backend.conflicted_files(&parent.path)?;
+                return Err(merge_error(format!(
+                    "conflicts in {} file(s) to resolve in {}: {}",
+                    files.len(),
+                    parent.path.display(),
+                    files.join(", ")
+                )));
+            }
+            Err(e) => return Err(merge_error(e.to_string())),
         }
 
         eprintln!("✓ Merge successful");
@@ -328,15 +328,23 @@ fn validate_environment() -> Result<()> {
         }
     }
 
-    // Check docker (optional)
-    let docker_output = Command::new("docker").arg("--version").output();
+    // Check the container engine (optional)
+    let engine = crate::docker::engine::ContainerEngine::detect();
+    let docker_output = Command::new(engine.runtime()).arg("--version").output();
     match docker_output {
         Ok(output) if output.status.success() => {
             let version = String::from_utf8_lossy(&output.stdout);
-            println!("  ✓ docker: {} (optional)", version.trim().bright_green());
+            println!(
+                "  ✓ {}: {} (optional)",
+                engine.compose_name(),
+                version.trim().bright_green()
+            );
         }
         _ => {
-            println!("  {} docker: not found (optional)", "ℹ".bright_blue());
+            println!(
+                "  {} container engine: not found (optional)",
+                "ℹ".bright_blue()
+            );
         }
     }
 
@@ -2,8 +2,10 @@
 
 use crate::config::Config;
 use crate::errors::Result;
+use crate::hooks::{HookExecutor, HookType};
 use crate::monitoring::{self, ActivityEvent};
 use crate::snapshot::{self, Snapshot};
+use crate::state::StateManager;
 use crate::vcs::{self, VcsType};
 use colored::*;
 
@@ -40,7 +42,7 @@ pub fn create(
         worktree.cyan()
     );
 
-    let snapshot = snapshot::create_snapshot(&wt.path, &wt.name, name, description, &state_dir)?;
+    let snapshot = snapshot::create_snapshot(backend.as_ref(), wt, name, description, &state_dir)?;
 
     // Log snapshot creation activity
     let _ = monitoring::log_activity(
@@ -177,7 +179,12 @@ fn print_snapshot(snap: &Snapshot) {
 }
 
 /// Restore a snapshot
-pub fn restore(worktree: &str, snapshot: &str, vcs_type: Option<VcsType>) -> Result<()> {
+pub fn restore(
+    worktree: &str,
+    snapshot: &str,
+    no_hooks: bool,
+    vcs_type: Option<VcsType>,
+) -> Result<()> {
     let cwd = std::env::current_dir()?;
     let repo_root = Config::find_repo_root(&cwd)?;
 
@@ -200,7 +207,7 @@ pub fn restore(worktree: &str, snapshot: &str, vcs_type: Option<VcsType>) -> Res
     println!();
     println!("{} snapshot '{}'...", "Restoring".bold(), snapshot.cyan());
 
-    snapshot::restore_snapshot(&wt.path, &wt.name, snapshot, &state_dir)?;
+    snapshot::restore_snapshot(backend.as_ref(), wt, snapshot, &state_dir)?;
 
     // Log snapshot restore activity
     let _ = monitoring::log_activity(
@@ -212,6 +219,22 @@ pub fn restore(worktree: &str, snapshot: &str, vcs_type: Option<VcsType>) -> Res
         },
     );
 
+    // Run post_snapshot_restore hook against the restored commit
+    let config = Config::load(&repo_root)?;
+    if !no_hooks && HookType::PostSnapshotRestore.is_configured(&config.hooks) {
+        let restored = backend
+            .list_workspaces()?
+            .into_iter()
+            .find(|w| w.name == worktree)
+            .ok_or_else(|| crate::errors::HnError::WorktreeNotFound(worktree.to_string()))?;
+        let wt_state_dir = StateManager::new(&repo_root)?.get_state_dir(&restored.name);
+
+        println!("Running post_snapshot_restore hook...");
+        HookExecutor::new(config.hooks.clone(), no_hooks, &repo_root)
+            .with_env("HNHN_SNAPSHOT", snapshot)
+            .run_hook(HookType::PostSnapshotRestore, &restored, &wt_state_dir)?;
+    }
+
     println!();
     println!(
         "{} Snapshot '{}' restored successfully!",
@@ -226,12 +249,13 @@ pub fn restore(worktree: &str, snapshot: &str, vcs_type: Option<VcsType>) -> Res
 }
 
 /// Delete a snapshot
-pub fn delete(worktree: &str, snapshot: &str) -> Result<()> {
+pub fn delete(worktree: &str, snapshot: &str, vcs_type: Option<VcsType>) -> Result<()> {
     let cwd = std::env::current_dir()?;
     let repo_root = Config::find_repo_root(&cwd)?;
+    let backend = vcs::init_backend_with_detection(&repo_root, vcs_type)?;
     let state_dir = repo_root.join(".hn-state");
 
-    snapshot::delete_snapshot(worktree, snapshot, &state_dir)?;
+    snapshot::delete_snapshot(backend.as_ref(), worktree, snapshot, &state_dir)?;
 
     println!();
     println!(
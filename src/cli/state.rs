@@ -37,7 +37,11 @@ pub fn list() -> Result<()> {
     // Get active worktrees
     let backend = init_backend_from_current_dir()?;
     let worktrees = backend.list_workspaces()?;
-    let active_names: Vec<String> = worktrees.iter().map(|wt| wt.name.clone()).collect();
+    let active_names: Vec<String> = worktrees
+        .iter()
+        .filter(|wt| wt.path.exists())
+        .map(|wt| wt.name.clone())
+        .collect();
 
     println!("{}", "State Directories".bright_cyan().bold());
     println!("{}", "=".repeat(70));
@@ -93,7 +97,11 @@ pub fn clean() -> Result<()> {
     // Get active worktrees
     let backend = init_backend_from_current_dir()?;
     let worktrees = backend.list_workspaces()?;
-    let active_names: Vec<String> = worktrees.iter().map(|wt| wt.name.clone()).collect();
+    let active_names: Vec<String> = worktrees
+        .iter()
+        .filter(|wt| wt.path.exists())
+        .map(|wt| wt.name.clone())
+        .collect();
 
     // Find orphaned state directories
     let orphaned = state_manager.list_orphaned(&active_names)?;
@@ -178,7 +186,7 @@ pub fn size(name: Option<String>) -> Result<()> {
             }
         }
 
-        sizes.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by size descending
+        sizes.sort_by_key(|s| std::cmp::Reverse(s.1)); // Sort by size descending
 
         for (name, size) in &sizes {
             println!("  {}: {}", name.bright_cyan(), format_size(*size));
@@ -1,5 +1,6 @@
 // Resource usage statistics (v0.6)
 
+use crate::cli::output::{self, OutputFormat, StatsEntry, StatsOutput, WorktreeRecord};
 use crate::config::Config;
 use crate::errors::Result;
 use crate::monitoring::{self, get_metrics_path, MetricsHistory, MetricsSnapshot};
@@ -8,7 +9,7 @@ use colored::*;
 use std::env;
 use std::fs;
 
-fn get_dir_size(path: &std::path::Path) -> Result<u64> {
+pub(crate) fn get_dir_size(path: &std::path::Path) -> Result<u64> {
     let mut size = 0;
     if path.is_dir() {
         for entry in fs::read_dir(path)? {
@@ -46,6 +47,7 @@ pub fn run(
     disk_only: bool,
     show_history: bool,
     history_days: Option<u64>,
+    format: OutputFormat,
     vcs_type: Option<VcsType>,
 ) -> Result<()> {
     let cwd = env::current_dir()?;
@@ -60,10 +62,6 @@ pub fn run(
     let worktrees = vcs_backend.list_workspaces()?;
     let state_dir = repo_root.join(".hn-state");
 
-    println!();
-    println!("{}", "Resource Usage Statistics".bold());
-    println!("{}", "═".repeat(80));
-
     // Filter worktrees if name specified
     let filtered_worktrees: Vec<_> = if let Some(ref filter_name) = name {
         worktrees
@@ -74,6 +72,53 @@ pub fn run(
         worktrees.iter().collect()
     };
 
+    if !format.is_table() {
+        let config = Config::load(&repo_root)?;
+        let default_branch = vcs_backend.default_branch();
+        let entries: Vec<StatsEntry> = filtered_worktrees
+            .iter()
+            .filter(|wt| show_all || wt.parent.is_some())
+            .map(|wt| {
+                let divergence = vcs_backend.divergence(
+                    wt,
+                    vcs::parent_branch(wt, &worktrees),
+                    default_branch.as_deref(),
+                );
+                let record = WorktreeRecord::collect(
+                    wt,
+                    divergence,
+                    vcs_backend.as_ref(),
+                    &state_dir,
+                    &config,
+                );
+                let wt_state_dir = state_dir.join(&wt.name);
+                let state_dir_size = if wt_state_dir.exists() {
+                    get_dir_size(&wt_state_dir).ok()
+                } else {
+                    None
+                };
+                StatsEntry {
+                    worktree: record,
+                    state_dir_size,
+                }
+            })
+            .collect();
+        let total_disk_usage = entries.iter().filter_map(|e| e.worktree.disk_usage).sum();
+
+        return output::print(
+            format,
+            &StatsOutput {
+                schema_version: output::SCHEMA_VERSION,
+                worktrees: entries,
+                total_disk_usage,
+            },
+        );
+    }
+
+    println!();
+    println!("{}", "Resource Usage Statistics".bold());
+    println!("{}", "═".repeat(80));
+
     if filtered_worktrees.is_empty() {
         println!("{}", "No worktrees found".yellow());
         println!();
@@ -82,7 +82,7 @@ pub fn run(name: String, vcs_type: Option<VcsType>) -> Result<()> {
         let state_dir = state_manager.get_state_dir(&matched_name);
 
         eprintln!("Running post_switch hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), false);
+        let hook_executor = HookExecutor::new(config.hooks.clone(), false, &repo_root);
         hook_executor.run_hook(HookType::PostSwitch, &worktree, &state_dir)?;
         eprintln!("✓ Hook completed successfully");
     }
@@ -1,11 +1,13 @@
 // Sync command: Sync current worktree with another branch (typically main)
 use crate::config::Config;
+use crate::conflict::{ConflictOperation, ConflictState};
 use crate::errors::{HnError, Result};
 use crate::hooks::{HookExecutor, HookType};
 use crate::state::StateManager;
-use crate::vcs::{init_backend_from_current_dir, VcsType};
+use crate::vcs::traits::{MergeOptions, MergeOutcome, VcsBackend};
+use crate::vcs::{init_backend, short_commit, VcsType, Worktree};
 use std::env;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SyncStrategy {
@@ -24,23 +26,173 @@ impl SyncStrategy {
             ))),
         }
     }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Merge => "Merge",
+            Self::Rebase => "Rebase",
+        }
+    }
+
+    fn operation(&self) -> ConflictOperation {
+        match self {
+            Self::Merge => ConflictOperation::Merge,
+            Self::Rebase => ConflictOperation::Rebase,
+        }
+    }
 }
 
+impl From<ConflictOperation> for SyncStrategy {
+    fn from(operation: ConflictOperation) -> Self {
+        match operation {
+            ConflictOperation::Merge => Self::Merge,
+            ConflictOperation::Rebase => Self::Rebase,
+        }
+    }
+}
+
+/// Hook settings shared by a sync and its `--continue`
+struct SyncHooks {
+    config: Config,
+    repo_root: PathBuf,
+    state_dir: PathBuf,
+    source: String,
+    strategy: SyncStrategy,
+    no_hooks: bool,
+}
+
+impl SyncHooks {
+    fn enabled(&self, hook_type: HookType) -> bool {
+        !self.no_hooks && hook_type.is_configured(&self.config.hooks)
+    }
+
+    fn executor(&self) -> HookExecutor {
+        HookExecutor::new(self.config.hooks.clone(), self.no_hooks, &self.repo_root)
+            .with_env("HNHN_SYNC_SOURCE", self.source.as_str())
+            .with_env("HNHN_SYNC_STRATEGY", self.strategy.as_str())
+    }
+
+    /// Run pre_integrate, which predates the sync hooks and still fires
+    /// around a sync, then pre_sync
+    fn pre_sync(&self, worktree: &Worktree) -> Result<()> {
+        if self.enabled(HookType::PreIntegrate) {
+            eprintln!("Running pre_integrate hook...");
+            self.executor()
+                .run_hook(HookType::PreIntegrate, worktree, &self.state_dir)?;
+            eprintln!("✓ Pre-integrate hook completed successfully");
+        }
+        if self.enabled(HookType::PreSync) {
+            eprintln!("Running pre_sync hook...");
+            self.executor()
+                .run_hook(HookType::PreSync, worktree, &self.state_dir)?;
+            eprintln!("✓ Pre-sync hook completed successfully");
+        }
+        Ok(())
+    }
+
+    /// Tell post_sync how the sync went, whichever way it went; post_integrate
+    /// only runs once the sync went through, as it always has
+    fn post_sync(
+        &self,
+        backend: &dyn VcsBackend,
+        result: &str,
+        previous_commit: &str,
+    ) -> Result<()> {
+        let post_integrate = result == "success" && self.enabled(HookType::PostIntegrate);
+        if !self.enabled(HookType::PostSync) && !post_integrate {
+            return Ok(());
+        }
+        // The worktree has moved to a new commit if the sync went through
+        let synced_worktree = backend.get_current_workspace()?;
+        if self.enabled(HookType::PostSync) {
+            eprintln!("Running post_sync hook...");
+            self.executor()
+                .with_env("HNHN_SYNC_RESULT", result)
+                .with_env("HNHN_SYNC_PREVIOUS_COMMIT", previous_commit)
+                .run_hook(HookType::PostSync, &synced_worktree, &self.state_dir)?;
+            eprintln!("✓ Post-sync hook completed successfully");
+        }
+        if post_integrate {
+            eprintln!("Running post_integrate hook...");
+            self.executor()
+                .run_hook(HookType::PostIntegrate, &synced_worktree, &self.state_dir)?;
+            eprintln!("✓ Post-integrate hook completed successfully");
+        }
+        Ok(())
+    }
+
+    /// post_sync for a sync that didn't go through; the sync error matters more
+    fn post_sync_after_failure(&self, backend: &dyn VcsBackend, result: &str, previous: &str) {
+        if let Err(e) = self.post_sync(backend, result, previous) {
+            eprintln!("⚠ Warning: post_sync hook failed: {}", e);
+        }
+    }
+}
+
+/// State directory of a worktree in the main repository's `.hn-state`
+fn state_dir_for(backend: &dyn VcsBackend, worktree: &Worktree) -> Result<PathBuf> {
+    Ok(StateManager::new(&backend.main_repo_root()?)?.get_state_dir(&worktree.name))
+}
+
+fn load_hooks(
+    worktree: &Worktree,
+    state_dir: &Path,
+    source: &str,
+    strategy: SyncStrategy,
+    no_hooks: bool,
+) -> Result<SyncHooks> {
+    let repo_root = Config::find_repo_root(&worktree.path)?;
+    let config = Config::load(&repo_root)?;
+    Ok(SyncHooks {
+        config,
+        repo_root,
+        state_dir: state_dir.to_path_buf(),
+        source: source.to_string(),
+        strategy,
+        no_hooks,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     source_branch: Option<String>,
     strategy: Option<String>,
     autostash: bool,
     no_commit: bool,
+    abort_on_conflict: bool,
+    no_hooks: bool,
     vcs_type: Option<VcsType>,
 ) -> Result<()> {
-    let backend = if let Some(vcs) = vcs_type {
-        crate::vcs::init_backend_with_detection(&env::current_dir()?, Some(vcs))?
-    } else {
-        init_backend_from_current_dir()?
-    };
+    let backend = init_backend(vcs_type)?;
 
     // Get current worktree
     let current_worktree = backend.get_current_workspace()?;
+    let state_dir = state_dir_for(backend.as_ref(), &current_worktree)?;
+
+    // Don't start a new sync on top of one that's waiting for conflicts
+    if let Some(pending) = ConflictState::load(&state_dir)? {
+        refuse_restack(&pending, &current_worktree)?;
+        if pending.command == "sync"
+            || backend
+                .operation_in_progress(&current_worktree.path)
+                .is_some()
+        {
+            return Err(HnError::Git(git2::Error::from_str(&format!(
+                "Worktree '{}' has an unfinished {}. Run 'hn sync --continue' or 'hn sync --abort' first.",
+                current_worktree.name,
+                pending.summary()
+            ))));
+        }
+        // Finished with plain git since
+        ConflictState::clear(&state_dir)?;
+    }
 
     // Default source branch to "main"
     let source = source_branch.unwrap_or_else(|| "main".to_string());
@@ -69,202 +221,269 @@ pub fn run(
     // Change to current worktree directory
     env::set_current_dir(&current_worktree.path)?;
 
+    // Load config and run pre_sync hook before anything is stashed, so a
+    // failing hook leaves the worktree as it was
+    let hooks = load_hooks(
+        &current_worktree,
+        &state_dir,
+        &source,
+        sync_strategy,
+        no_hooks,
+    )?;
+    hooks.pre_sync(&current_worktree)?;
+
     // Stash if needed and autostash is enabled
-    let mut stashed = false;
+    let mut stash_message = None;
     if !status.is_clean() && autostash {
         eprintln!("\n→ Stashing uncommitted changes...");
-        let stash_output = Command::new("git")
-            .arg("stash")
-            .arg("push")
-            .arg("-m")
-            .arg(format!(
-                "hn sync autostash - {}",
-                chrono::Utc::now().to_rfc3339()
-            ))
-            .output()?;
-
-        if !stash_output.status.success() {
-            let stderr = String::from_utf8_lossy(&stash_output.stderr);
-            return Err(HnError::Git(git2::Error::from_str(&format!(
-                "Failed to stash changes: {}",
-                stderr
-            ))));
-        }
-        stashed = true;
+        let message = format!("hn sync autostash - {}", chrono::Utc::now().to_rfc3339());
+        backend.stash(&current_worktree.path, &message, false)?;
+        stash_message = Some(message);
         eprintln!("✓ Changes stashed");
     }
 
     // Fetch the latest from the source branch
     eprintln!("\n→ Fetching latest changes from {}...", source);
-    let fetch_output = Command::new("git")
-        .arg("fetch")
-        .arg("origin")
-        .arg(&source)
-        .output()?;
-
-    if !fetch_output.status.success() {
-        let stderr = String::from_utf8_lossy(&fetch_output.stderr);
-        eprintln!("⚠ Warning: Failed to fetch from origin: {}", stderr);
-        eprintln!("  Continuing with local branch...");
-    } else {
-        eprintln!("✓ Fetch complete");
-    }
-
-    // Load config and run pre_integrate hook
-    let repo_root = Config::find_repo_root(&current_worktree.path)?;
-    let config = Config::load(&repo_root)?;
-
-    let has_pre_integrate_hooks =
-        config.hooks.pre_integrate.is_some() || !config.hooks.pre_integrate_conditions.is_empty();
-
-    if has_pre_integrate_hooks {
-        let state_manager = StateManager::new(&repo_root)?;
-        let state_dir = state_manager.get_state_dir(&current_worktree.name);
-
-        eprintln!("Running pre_integrate hook...");
-        let hook_executor = HookExecutor::new(config.hooks.clone(), false);
-        hook_executor.run_hook(HookType::PreIntegrate, &current_worktree, &state_dir)?;
-        eprintln!("✓ Pre-integrate hook completed successfully");
+    match backend.fetch(&current_worktree.path, &source) {
+        Ok(()) => eprintln!("✓ Fetch complete"),
+        Err(e) => {
+            eprintln!("⚠ Warning: {}", e);
+            eprintln!("  Continuing with local branch...");
+        }
     }
 
     // Perform sync based on strategy
     let sync_result = match sync_strategy {
-        SyncStrategy::Merge => sync_merge(&source, no_commit),
-        SyncStrategy::Rebase => sync_rebase(&source),
+        SyncStrategy::Merge => {
+            eprintln!("\n→ Merging {} into current branch...", source);
+            let options = MergeOptions {
+                no_commit,
+                ..Default::default()
+            };
+            backend.merge(&current_worktree.path, &source, &options)
+        }
+        SyncStrategy::Rebase => {
+            eprintln!("\n→ Rebasing current branch onto {}...", source);
+            backend.rebase(&current_worktree.path, &source)
+        }
     };
+    let previous_commit = current_worktree.commit.as_str();
 
-    // Handle the result
     match sync_result {
-        Ok(_) => {
+        Ok(MergeOutcome::Success) => {
             eprintln!("✓ Sync successful");
-
-            // Run post_integrate hook
-            let has_post_integrate_hooks = config.hooks.post_integrate.is_some()
-                || !config.hooks.post_integrate_conditions.is_empty();
-
-            if has_post_integrate_hooks {
-                let state_manager = StateManager::new(&repo_root)?;
-                let state_dir = state_manager.get_state_dir(&current_worktree.name);
-
-                eprintln!("Running post_integrate hook...");
-                let hook_executor = HookExecutor::new(config.hooks.clone(), false);
-                hook_executor.run_hook(HookType::PostIntegrate, &current_worktree, &state_dir)?;
-                eprintln!("✓ Post-integrate hook completed successfully");
+            if let Some(ref message) = stash_message {
+                restore_autostash(backend.as_ref(), &current_worktree.path, message)?;
             }
-
-            // Pop stash if we stashed
-            if stashed {
-                eprintln!("\n→ Restoring stashed changes...");
-                let pop_output = Command::new("git").arg("stash").arg("pop").output()?;
-
-                if !pop_output.status.success() {
-                    let stderr = String::from_utf8_lossy(&pop_output.stderr);
-                    eprintln!("⚠ Warning: Failed to restore stashed changes: {}", stderr);
-                    eprintln!(
-                        "  Your changes are still in the stash. Run 'git stash pop' manually."
-                    );
-                    return Err(HnError::Git(git2::Error::from_str(
-                        "Failed to restore stashed changes",
-                    )));
+            hooks.post_sync(backend.as_ref(), "success", previous_commit)
+        }
+        Ok(MergeOutcome::Conflict) => {
+            let files = backend.conflicted_files(&current_worktree.path)?;
+            print_conflicts(sync_strategy.label(), &files);
+
+            if abort_on_conflict {
+                backend.abort_operation(&current_worktree.path, sync_strategy.operation())?;
+                eprintln!("✓ {} aborted (--abort-on-conflict)", sync_strategy.label());
+                if let Some(ref message) = stash_message {
+                    restore_autostash(backend.as_ref(), &current_worktree.path, message)?;
                 }
-                eprintln!("✓ Changes restored");
+                hooks.post_sync_after_failure(backend.as_ref(), "conflict", previous_commit);
+                return Err(HnError::Git(git2::Error::from_str(&format!(
+                    "Sync aborted: conflicts in {} file(s)",
+                    files.len()
+                ))));
             }
 
-            Ok(())
+            ConflictState {
+                command: "sync".to_string(),
+                operation: sync_strategy.operation(),
+                source: source.clone(),
+                files,
+                autostash: stash_message.clone(),
+                previous_commit: current_worktree.commit.clone(),
+                started_at: crate::monitoring::now(),
+            }
+            .save(&state_dir)?;
+
+            eprintln!(
+                "\nResolve the conflicts and {}, then run:",
+                backend.vcs_type().resolve_hint()
+            );
+            eprintln!("  hn sync --continue");
+            eprintln!("Or go back to where you were with:");
+            eprintln!("  hn sync --abort");
+            if stash_message.is_some() {
+                eprintln!(
+                    "\nYour uncommitted changes are stashed and will be restored either way."
+                );
+            }
+
+            hooks.post_sync_after_failure(backend.as_ref(), "conflict", previous_commit);
+            Err(HnError::Git(git2::Error::from_str(&format!(
+                "{} conflicts need manual resolution",
+                sync_strategy.label()
+            ))))
         }
         Err(e) => {
+            hooks.post_sync_after_failure(backend.as_ref(), "failed", previous_commit);
+
             // If sync failed and we stashed, inform user
-            if stashed {
-                eprintln!("\n⚠ Note: Your changes are stashed. Run 'git stash pop' to restore them after resolving conflicts.");
+            if let Some(ref message) = stash_message {
+                eprintln!(
+                    "\n⚠ Note: Your changes are stashed as '{}'. Restore them manually.",
+                    message
+                );
             }
             Err(e)
         }
     }
 }
 
-fn sync_merge(source: &str, no_commit: bool) -> Result<()> {
-    eprintln!("\n→ Merging {} into current branch...", source);
+/// Finish a sync that stopped on conflicts (`hn sync --continue`)
+pub fn continue_sync(no_hooks: bool, vcs_type: Option<VcsType>) -> Result<()> {
+    let backend = init_backend(vcs_type)?;
+    let current_worktree = backend.get_current_workspace()?;
+    let state_dir = state_dir_for(backend.as_ref(), &current_worktree)?;
+    let mut pending = ConflictState::load(&state_dir)?.ok_or_else(|| {
+        HnError::Git(git2::Error::from_str(&format!(
+            "No sync in progress in worktree '{}'",
+            current_worktree.name
+        )))
+    })?;
+    refuse_restack(&pending, &current_worktree)?;
 
-    let mut cmd = Command::new("git");
-    cmd.arg("merge");
+    env::set_current_dir(&current_worktree.path)?;
 
-    if no_commit {
-        cmd.arg("--no-commit");
+    let remaining = backend.conflicted_files(&current_worktree.path)?;
+    if !remaining.is_empty() {
+        print_conflicts("Unresolved", &remaining);
+        return Err(HnError::Git(git2::Error::from_str(&format!(
+            "Resolve the remaining conflicts and {} first",
+            backend.vcs_type().resolve_hint()
+        ))));
     }
 
-    cmd.arg(source);
-
-    let output = cmd.output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        // Check if it's a conflict
-        if stderr.contains("CONFLICT") || stdout.contains("CONFLICT") {
-            eprintln!("\n⚠ Merge conflicts detected:");
-            eprintln!("{}", stdout);
-            eprintln!("{}", stderr);
-            eprintln!("\nResolve conflicts manually, then run: git commit");
-            return Err(HnError::Git(git2::Error::from_str(
-                "Merge conflicts need manual resolution",
-            )));
+    let strategy = SyncStrategy::from(pending.operation);
+    if backend.operation_in_progress(&current_worktree.path) == Some(pending.operation) {
+        eprintln!("→ Continuing {}...", strategy.as_str());
+        let outcome = backend.continue_operation(
+            &current_worktree.path,
+            pending.operation,
+            &pending.source,
+        )?;
+        if outcome == MergeOutcome::Conflict {
+            // A later commit of the rebase conflicts too
+            pending.files = backend.conflicted_files(&current_worktree.path)?;
+            pending.save(&state_dir)?;
+            print_conflicts(strategy.label(), &pending.files);
+            eprintln!("\nResolve them and run 'hn sync --continue' again.");
+            return Err(HnError::Git(git2::Error::from_str(&format!(
+                "{} conflicts need manual resolution",
+                strategy.label()
+            ))));
         }
+    }
 
-        return Err(HnError::Git(git2::Error::from_str(&format!(
-            "Failed to merge: {}{}",
-            stdout, stderr
-        ))));
+    ConflictState::clear(&state_dir)?;
+    eprintln!("✓ {} of {} completed", pending.command, pending.source);
+
+    if let Some(ref message) = pending.autostash {
+        restore_autostash(backend.as_ref(), &current_worktree.path, message)?;
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if !stdout.is_empty() {
-        eprintln!("{}", stdout);
+    if pending.command == "sync" {
+        let hooks = load_hooks(
+            &current_worktree,
+            &state_dir,
+            &pending.source,
+            strategy,
+            no_hooks,
+        )?;
+        hooks.post_sync(backend.as_ref(), "success", &pending.previous_commit)?;
+    } else if pending.command == "integrate" && !no_hooks {
+        let repo_root = Config::find_repo_root(&current_worktree.path)?;
+        let config = Config::load(&repo_root)?;
+        let merged_worktree = backend.get_current_workspace()?;
+        super::integrate::post_integrate(&config, &repo_root, &merged_worktree, &state_dir)?;
     }
 
     Ok(())
 }
 
-fn sync_rebase(source: &str) -> Result<()> {
-    eprintln!("\n→ Rebasing current branch onto {}...", source);
-
-    let mut cmd = Command::new("git");
-    cmd.arg("rebase").arg(source);
+/// Give up on a sync that stopped on conflicts (`hn sync --abort`)
+pub fn abort(vcs_type: Option<VcsType>) -> Result<()> {
+    let backend = init_backend(vcs_type)?;
+    let current_worktree = backend.get_current_workspace()?;
+    let state_dir = state_dir_for(backend.as_ref(), &current_worktree)?;
+    let pending = ConflictState::load(&state_dir)?.ok_or_else(|| {
+        HnError::Git(git2::Error::from_str(&format!(
+            "No sync in progress in worktree '{}'",
+            current_worktree.name
+        )))
+    })?;
+    refuse_restack(&pending, &current_worktree)?;
 
-    let output = cmd.output()?;
+    env::set_current_dir(&current_worktree.path)?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
+    if backend.operation_in_progress(&current_worktree.path) == Some(pending.operation) {
+        backend.abort_operation(&current_worktree.path, pending.operation)?;
+    }
+    ConflictState::clear(&state_dir)?;
+    eprintln!(
+        "✓ Aborted {}; worktree is back at {}",
+        pending.command,
+        short_commit(&pending.previous_commit)
+    );
+
+    if let Some(ref message) = pending.autostash {
+        restore_autostash(backend.as_ref(), &current_worktree.path, message)?;
+    }
 
-        // Check if it's a conflict
-        if stderr.contains("CONFLICT")
-            || stdout.contains("CONFLICT")
-            || stderr.contains("could not apply")
-        {
-            eprintln!("\n⚠ Rebase conflicts detected:");
-            eprintln!("{}", stdout);
-            eprintln!("{}", stderr);
-            eprintln!("\nResolve conflicts manually, then run:");
-            eprintln!("  git add <resolved-files>");
-            eprintln!("  git rebase --continue");
-            eprintln!("\nOr abort the rebase:");
-            eprintln!("  git rebase --abort");
-            return Err(HnError::Git(git2::Error::from_str(
-                "Rebase conflicts need manual resolution",
-            )));
-        }
+    Ok(())
+}
 
-        return Err(HnError::Git(git2::Error::from_str(&format!(
-            "Failed to rebase: {}{}",
-            stdout, stderr
-        ))));
+/// A restack keeps a plan of the worktrees it has yet to rebase, which only
+/// `hn restack --continue` and `--abort` carry on with or clean up
+fn refuse_restack(pending: &ConflictState, worktree: &Worktree) -> Result<()> {
+    if pending.command == "restack" {
+        return Err(HnError::StateError(format!(
+            "Worktree '{}' is part of an unfinished restack. Run 'hn restack --continue' or 'hn restack --abort' instead.",
+            worktree.name
+        )));
     }
+    Ok(())
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if !stdout.is_empty() {
-        eprintln!("{}", stdout);
+fn print_conflicts(what: &str, files: &[String]) {
+    eprintln!("\n⚠ {} conflicts in {} file(s):", what, files.len());
+    for file in files {
+        eprintln!("  {}", file);
     }
+}
 
-    Ok(())
+/// Reapply the autostash a sync created, found by its message
+fn restore_autostash(backend: &dyn VcsBackend, worktree_path: &Path, message: &str) -> Result<()> {
+    eprintln!("\n→ Restoring stashed changes...");
+    match backend.unstash(worktree_path, message, false) {
+        Ok(true) => {
+            eprintln!("✓ Changes restored");
+            Ok(())
+        }
+        Ok(false) => {
+            eprintln!(
+                "⚠ Warning: Could not find the sync autostash; it may have been restored already."
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("⚠ Warning: {}", e);
+            eprintln!(
+                "  Your changes are still stashed as '{}'. Restore them manually.",
+                message
+            );
+            Err(HnError::Git(git2::Error::from_str(
+                "Failed to restore stashed changes",
+            )))
+        }
+    }
 }
@@ -1,5 +1,6 @@
 // Template management CLI commands (v0.5)
 
+use crate::cli::output::{self, OutputFormat};
 use crate::config::Config;
 use crate::errors::{HnError, Result};
 use crate::templates;
@@ -8,18 +9,16 @@ use std::env;
 use std::fs;
 
 /// List all available templates
-pub fn list(json: bool) -> Result<()> {
+pub fn list(format: OutputFormat) -> Result<()> {
     let cwd = env::current_dir()?;
     let repo_root = Config::find_repo_root(&cwd)?;
 
     // Load templates
     let templates_list = templates::list_templates(&repo_root)?;
 
-    if json {
-        // JSON output
-        let json_output = serde_json::to_string_pretty(&templates_list)?;
-        println!("{}", json_output);
-        return Ok(());
+    if !format.is_table() {
+        // JSON/YAML output
+        return output::print(format, &templates_list);
     }
 
     // Table output
@@ -0,0 +1,120 @@
+// Hook trust management
+//
+// `hn trust` reviews and approves the current repository's hooks (`--yes`
+// skips the confirmation), `hn trust --revoke` withdraws the approval and
+// `hn trust list` shows every approved repository.
+
+use crate::config::Config;
+use crate::errors::{HnError, Result};
+use crate::trust::{self, RepoHooks, TrustStore};
+use colored::*;
+use std::io::IsTerminal;
+use std::path::Path;
+
+fn store_path() -> Result<std::path::PathBuf> {
+    TrustStore::default_path().ok_or_else(|| {
+        HnError::ConfigError("Could not determine home directory for trust store".to_string())
+    })
+}
+
+/// Trust (or with `revoke`, stop trusting) the current repository's hooks
+///
+/// The hooks are printed for review and only trusted once confirmed, unless
+/// `yes` is set.
+pub fn run(revoke: bool, yes: bool) -> Result<()> {
+    let repo_root = Config::find_repo_root(&std::env::current_dir()?)?;
+    let repo = trust::repo_key(&repo_root);
+    let path = store_path()?;
+    let mut store = TrustStore::load(&path)?;
+
+    if revoke {
+        if store.revoke(&repo) {
+            store.save(&path)?;
+            println!("{} Revoked trust for {}", "✓".green(), repo.display());
+        } else {
+            println!("{} was not trusted", repo.display());
+        }
+        return Ok(());
+    }
+
+    let hooks = RepoHooks::load(&repo_root)?;
+    if hooks.is_empty() {
+        println!("{} defines no hooks", repo.display());
+        return Ok(());
+    }
+    if store.is_trusted(&repo, &hooks) {
+        println!(
+            "{} Hooks in {} are already trusted",
+            "✓".green(),
+            repo.display()
+        );
+        return Ok(());
+    }
+
+    trust::print_review(&repo, store.get(&repo), &hooks);
+    if !yes {
+        if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+            return Err(HnError::HookError(
+                "Cannot confirm trust without a terminal. Re-run with --yes to trust these hooks"
+                    .to_string(),
+            ));
+        }
+        let approved = dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Trust these hooks?")
+            .default(false)
+            .interact()?;
+        if !approved {
+            println!("Hooks were not trusted");
+            return Ok(());
+        }
+    }
+
+    store.trust(&repo, &hooks);
+    store.save(&path)?;
+    println!("{} Trusted hooks for {}", "✓".green(), repo.display());
+    Ok(())
+}
+
+/// List repositories with trusted hooks
+pub fn list() -> Result<()> {
+    let store = TrustStore::load(&store_path()?)?;
+    if store.repos.is_empty() {
+        println!("No trusted repositories");
+        return Ok(());
+    }
+
+    println!(
+        "{:<50} {:<14} {:<17} {}",
+        "REPOSITORY".bold(),
+        "HASH".bold(),
+        "TRUSTED".bold(),
+        "STATUS".bold()
+    );
+    for (repo, trusted) in &store.repos {
+        let status = if !Path::new(repo).exists() {
+            "missing".dimmed()
+        } else {
+            match RepoHooks::load(Path::new(repo)) {
+                Ok(hooks) if hooks.hash == trusted.hash => "trusted".green(),
+                Ok(_) => "changed".yellow(),
+                Err(_) => "invalid config".red(),
+            }
+        };
+        let when = chrono::DateTime::from_timestamp(trusted.trusted_at as i64, 0)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        println!(
+            "{:<50} {:<14} {:<17} {}",
+            repo,
+            &trusted.hash[..12.min(trusted.hash.len())],
+            when,
+            status
+        );
+    }
+
+    Ok(())
+}
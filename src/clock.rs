@@ -6,6 +6,9 @@ pub trait Clock: Send + Sync {
     /// Get the current instant
     fn now(&self) -> Instant;
 
+    /// Get the current wall-clock time in seconds since the Unix epoch
+    fn unix_now(&self) -> u64;
+
     /// Sleep for the given duration
     fn sleep(&self, duration: Duration);
 }
@@ -19,6 +22,10 @@ impl Clock for SystemClock {
         Instant::now()
     }
 
+    fn unix_now(&self) -> u64 {
+        crate::monitoring::now()
+    }
+
     fn sleep(&self, duration: Duration) {
         std::thread::sleep(duration);
     }
@@ -31,9 +38,13 @@ pub mod test_support {
     use std::sync::Arc;
     use std::sync::Mutex;
 
+    /// Wall-clock time a fake clock starts at
+    pub const FAKE_UNIX_START: u64 = 1_700_000_000;
+
     /// Fake clock for testing that doesn't use real time
     #[derive(Clone)]
     pub struct FakeClock {
+        start: Instant,
         time: Arc<Mutex<Instant>>,
     }
 
@@ -45,8 +56,10 @@ pub mod test_support {
 
     impl FakeClock {
         pub fn new() -> Self {
+            let start = Instant::now();
             Self {
-                time: Arc::new(Mutex::new(Instant::now())),
+                start,
+                time: Arc::new(Mutex::new(start)),
             }
         }
 
@@ -62,6 +75,10 @@ pub mod test_support {
             *self.time.lock().unwrap()
         }
 
+        fn unix_now(&self) -> u64 {
+            FAKE_UNIX_START + self.now().duration_since(self.start).as_secs()
+        }
+
         fn sleep(&self, duration: Duration) {
             // In tests, we advance time manually instead of sleeping
             self.advance(duration);
@@ -1,4 +1,4 @@
-use crate::errors::Result;
+use crate::errors::{HnError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -17,6 +17,8 @@ pub struct Config {
     #[serde(default)]
     pub sparse: SparseConfig,
     #[serde(default)]
+    pub worktrees: WorktreesConfig,
+    #[serde(default)]
     pub aliases: HashMap<String, String>,
     #[serde(default)]
     pub profiles: HashMap<String, ConfigProfile>,
@@ -86,20 +88,39 @@ where
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct HooksConfig {
     // Worktree lifecycle hooks
-    pub pre_create: Option<String>,
-    pub post_create: Option<String>,
-    pub pre_remove: Option<String>,
-    pub post_remove: Option<String>,
-    pub post_switch: Option<String>,
+    pub pre_create: Option<HookScript>,
+    pub post_create: Option<HookScript>,
+    pub pre_remove: Option<HookScript>,
+    pub post_remove: Option<HookScript>,
+    pub post_switch: Option<HookScript>,
 
     // Integration hooks
-    pub pre_integrate: Option<String>,
-    pub post_integrate: Option<String>,
+    pub pre_integrate: Option<HookScript>,
+    pub post_integrate: Option<HookScript>,
+
+    // Sync, return and snapshot hooks
+    pub pre_sync: Option<HookScript>,
+    pub post_sync: Option<HookScript>,
+    pub pre_return: Option<HookScript>,
+    pub post_snapshot_restore: Option<HookScript>,
+
+    // Docker lifecycle hooks
+    pub pre_docker_start: Option<HookScript>,
+    pub post_docker_start: Option<HookScript>,
+    pub post_docker_stop: Option<HookScript>,
 
     /// Hook execution timeout in seconds (default: 300 = 5 minutes)
     #[serde(default = "default_hook_timeout")]
     pub timeout_seconds: u64,
 
+    /// Number of hook output logs kept per worktree (default: 20)
+    #[serde(default = "default_hook_log_retention")]
+    pub log_retention: usize,
+
+    /// Print hook output to the terminal while hooks run (default: false)
+    #[serde(default)]
+    pub stream_output: bool,
+
     /// Conditional hooks that run based on branch name patterns
     #[serde(default)]
     pub pre_create_conditions: Vec<ConditionalHook>,
@@ -115,14 +136,84 @@ pub struct HooksConfig {
     pub pre_integrate_conditions: Vec<ConditionalHook>,
     #[serde(default)]
     pub post_integrate_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub pre_sync_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub post_sync_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub pre_return_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub post_snapshot_restore_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub pre_docker_start_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub post_docker_start_conditions: Vec<ConditionalHook>,
+    #[serde(default)]
+    pub post_docker_stop_conditions: Vec<ConditionalHook>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ConditionalHook {
     /// Condition to evaluate (e.g., "branch.startsWith('feature/')")
     pub condition: String,
-    /// Command to run if condition matches
-    pub command: String,
+    /// Command (or steps) to run if condition matches
+    pub command: HookScript,
+}
+
+/// A hook body: a single shell command or a list of named steps
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum HookScript {
+    Command(String),
+    Steps(Vec<HookStep>),
+}
+
+impl HookScript {
+    /// True if there is nothing to run
+    pub fn is_empty(&self) -> bool {
+        match self {
+            HookScript::Command(command) => command.trim().is_empty(),
+            HookScript::Steps(steps) => steps.is_empty(),
+        }
+    }
+}
+
+impl From<String> for HookScript {
+    fn from(command: String) -> Self {
+        HookScript::Command(command)
+    }
+}
+
+impl From<&str> for HookScript {
+    fn from(command: &str) -> Self {
+        HookScript::Command(command.to_string())
+    }
+}
+
+/// One named step of a multi-step hook
+///
+/// Steps without a dependency between them run in parallel.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HookStep {
+    pub name: String,
+    /// Shell command to run
+    #[serde(alias = "command")]
+    pub run: String,
+    /// Steps that must finish before this one starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub needs: Vec<String>,
+    /// Overrides `hooks.timeout_seconds` for this step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    /// Working directory, relative to the worktree unless absolute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// Extra environment variables for this step
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Keep going (and let dependents run) if this step fails
+    #[serde(default)]
+    pub continue_on_error: bool,
 }
 
 impl Default for HooksConfig {
@@ -135,7 +226,16 @@ impl Default for HooksConfig {
             post_switch: None,
             pre_integrate: None,
             post_integrate: None,
+            pre_sync: None,
+            post_sync: None,
+            pre_return: None,
+            post_snapshot_restore: None,
+            pre_docker_start: None,
+            post_docker_start: None,
+            post_docker_stop: None,
             timeout_seconds: default_hook_timeout(),
+            log_retention: default_hook_log_retention(),
+            stream_output: false,
             pre_create_conditions: Vec::new(),
             post_create_conditions: Vec::new(),
             pre_remove_conditions: Vec::new(),
@@ -143,14 +243,109 @@ impl Default for HooksConfig {
             post_switch_conditions: Vec::new(),
             pre_integrate_conditions: Vec::new(),
             post_integrate_conditions: Vec::new(),
+            pre_sync_conditions: Vec::new(),
+            post_sync_conditions: Vec::new(),
+            pre_return_conditions: Vec::new(),
+            post_snapshot_restore_conditions: Vec::new(),
+            pre_docker_start_conditions: Vec::new(),
+            post_docker_start_conditions: Vec::new(),
+            post_docker_stop_conditions: Vec::new(),
         }
     }
 }
 
+impl HooksConfig {
+    /// Conditional hooks of every hook type, keyed by their config field name
+    pub fn all_conditional_hooks(&self) -> Vec<(&'static str, &[ConditionalHook])> {
+        vec![
+            ("pre_create_conditions", &self.pre_create_conditions),
+            ("post_create_conditions", &self.post_create_conditions),
+            ("pre_remove_conditions", &self.pre_remove_conditions),
+            ("post_remove_conditions", &self.post_remove_conditions),
+            ("post_switch_conditions", &self.post_switch_conditions),
+            ("pre_integrate_conditions", &self.pre_integrate_conditions),
+            ("post_integrate_conditions", &self.post_integrate_conditions),
+            ("pre_sync_conditions", &self.pre_sync_conditions),
+            ("post_sync_conditions", &self.post_sync_conditions),
+            ("pre_return_conditions", &self.pre_return_conditions),
+            (
+                "post_snapshot_restore_conditions",
+                &self.post_snapshot_restore_conditions,
+            ),
+            (
+                "pre_docker_start_conditions",
+                &self.pre_docker_start_conditions,
+            ),
+            (
+                "post_docker_start_conditions",
+                &self.post_docker_start_conditions,
+            ),
+            (
+                "post_docker_stop_conditions",
+                &self.post_docker_stop_conditions,
+            ),
+        ]
+    }
+
+    /// Unconditional hooks of every hook type, keyed by their config field name
+    pub fn all_hooks(&self) -> Vec<(&'static str, Option<&HookScript>)> {
+        vec![
+            ("pre_create", self.pre_create.as_ref()),
+            ("post_create", self.post_create.as_ref()),
+            ("pre_remove", self.pre_remove.as_ref()),
+            ("post_remove", self.post_remove.as_ref()),
+            ("post_switch", self.post_switch.as_ref()),
+            ("pre_integrate", self.pre_integrate.as_ref()),
+            ("post_integrate", self.post_integrate.as_ref()),
+            ("pre_sync", self.pre_sync.as_ref()),
+            ("post_sync", self.post_sync.as_ref()),
+            ("pre_return", self.pre_return.as_ref()),
+            ("post_snapshot_restore", self.post_snapshot_restore.as_ref()),
+            ("pre_docker_start", self.pre_docker_start.as_ref()),
+            ("post_docker_start", self.post_docker_start.as_ref()),
+            ("post_docker_stop", self.post_docker_stop.as_ref()),
+        ]
+    }
+
+    /// Check that every hook condition parses and every step graph is sound
+    pub fn validate(&self, prefix: &str) -> Result<()> {
+        let prefixed = |location: String| {
+            move |e: HnError| match e {
+                HnError::ConfigError(msg) => {
+                    HnError::ConfigError(format!("{}.{}: {}", prefix, location, msg))
+                }
+                other => other,
+            }
+        };
+
+        for (field, script) in self.all_hooks() {
+            if let Some(HookScript::Steps(steps)) = script {
+                crate::hooks::steps::validate_steps(steps).map_err(prefixed(field.to_string()))?;
+            }
+        }
+
+        for (field, hooks) in self.all_conditional_hooks() {
+            for (index, hook) in hooks.iter().enumerate() {
+                let location = format!("{}[{}]", field, index);
+                if let HookScript::Steps(ref steps) = hook.command {
+                    crate::hooks::steps::validate_steps(steps)
+                        .map_err(prefixed(location.clone()))?;
+                }
+                crate::hooks::Condition::parse(&hook.condition).map_err(prefixed(location))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 fn default_hook_timeout() -> u64 {
     300 // 5 minutes
 }
 
+fn default_hook_log_retention() -> usize {
+    20
+}
+
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct SparseConfig {
     /// Enable sparse checkout by default for new worktrees
@@ -162,14 +357,31 @@ pub struct SparseConfig {
     pub paths: Vec<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct WorktreesConfig {
+    /// Path pattern for new worktrees
+    /// Placeholders: {name}, {repo}, {repo_root}
+    /// Example: "~/wt/{repo}/{name}" or "{repo_root}/../{repo}-{name}"
+    /// Defaults to a sibling directory of the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DockerConfig {
     #[serde(default)]
     pub enabled: bool,
     #[serde(default = "default_strategy")]
     pub strategy: String,
+    /// Container engine: auto, docker, docker-compose, podman,
+    /// podman-compose or nerdctl
+    #[serde(default = "default_engine")]
+    pub engine: String,
     #[serde(default = "default_compose_file")]
     pub compose_file: String,
+    /// Extra compose files layered over `compose_file`, passed as further `-f`
+    #[serde(default)]
+    pub compose_overlays: Vec<String>,
     #[serde(default)]
     pub ports: PortsConfig,
     #[serde(default)]
@@ -180,10 +392,63 @@ pub struct DockerConfig {
     pub auto_start: bool,
     #[serde(default)]
     pub auto_stop_others: bool,
+    /// Stop a worktree's containers once it has been idle this long
+    /// (e.g. "2h"), when `hn docker reap` runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_stop_after: Option<String>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Per-service settings for the generated override file
+    #[serde(default, deserialize_with = "deserialize_docker_services")]
+    pub services: HashMap<String, DockerServiceConfig>,
     #[serde(default)]
     pub healthcheck: HealthCheckConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+}
+
+/// Override settings for one compose service
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct DockerServiceConfig {
+    /// Container ports to publish; each gets its own host port per worktree.
+    /// Defaults to the ports of the service in the compose file.
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// Environment for this service, on top of `docker.env`
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Volume mounts (`volume:/path`), e.g. of isolated volumes
+    #[serde(default)]
+    pub volumes: Vec<String>,
+}
+
+/// `docker.services` is a map of service settings; a plain list of service
+/// names is accepted too and means default settings for each
+fn deserialize_docker_services<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, DockerServiceConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Services {
+        Names(Vec<String>),
+        Settings(HashMap<String, Option<DockerServiceConfig>>),
+    }
+
+    Ok(match Services::deserialize(deserializer)? {
+        Services::Names(names) => names
+            .into_iter()
+            .map(|name| (name, DockerServiceConfig::default()))
+            .collect(),
+        Services::Settings(services) => services
+            .into_iter()
+            .map(|(name, settings)| (name, settings.unwrap_or_default()))
+            .collect(),
+    })
 }
 
 impl Default for DockerConfig {
@@ -191,14 +456,41 @@ impl Default for DockerConfig {
         Self {
             enabled: false,
             strategy: default_strategy(),
+            engine: default_engine(),
             compose_file: default_compose_file(),
+            compose_overlays: Vec::new(),
             ports: PortsConfig::default(),
             shared: DockerSharedConfig::default(),
             isolated: DockerIsolatedConfig::default(),
             auto_start: false,
             auto_stop_others: false,
+            idle_stop_after: None,
             env: HashMap::new(),
+            services: HashMap::new(),
             healthcheck: HealthCheckConfig::default(),
+            proxy: ProxyConfig::default(),
+        }
+    }
+}
+
+/// Local reverse proxy (`hn proxy`) routing `<worktree>.<domain>` to the
+/// worktree's allocated port of `service`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProxyConfig {
+    #[serde(default = "default_proxy_port")]
+    pub port: u16,
+    #[serde(default = "default_proxy_domain")]
+    pub domain: String,
+    #[serde(default = "default_proxy_service")]
+    pub service: String,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            port: default_proxy_port(),
+            domain: default_proxy_domain(),
+            service: default_proxy_service(),
         }
     }
 }
@@ -211,6 +503,10 @@ pub struct PortsConfig {
     pub base: HashMap<String, u16>,
     #[serde(default)]
     pub range: Option<[u16; 2]>,
+    /// Also record allocations in the machine-wide registry, so repositories
+    /// don't hand out each other's ports
+    #[serde(default)]
+    pub global: bool,
 }
 
 impl Default for PortsConfig {
@@ -224,6 +520,7 @@ impl Default for PortsConfig {
             strategy: default_port_strategy(),
             base,
             range: Some([3000, 9999]),
+            global: false,
         }
     }
 }
@@ -234,6 +531,27 @@ pub struct DockerSharedConfig {
     pub volumes: Vec<String>,
     #[serde(default)]
     pub networks: Vec<String>,
+    /// Services run once for the whole repository with `strategy: hybrid`
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// How each shared service is split between worktrees, when the
+    /// default guessed from the service name doesn't fit
+    #[serde(default)]
+    pub isolation: HashMap<String, SharedIsolation>,
+}
+
+/// How worktrees are kept apart inside a shared service
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SharedIsolation {
+    /// A database per worktree (`HNHN_<SERVICE>_DATABASE`)
+    Database,
+    /// A numbered database per worktree, e.g. Redis (`HNHN_<SERVICE>_DB_INDEX`)
+    DbIndex,
+    /// A key prefix per worktree (`HNHN_<SERVICE>_PREFIX`)
+    Prefix,
+    /// No isolation, worktrees share everything
+    None,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
@@ -248,6 +566,13 @@ pub struct HealthCheckConfig {
     pub enabled: bool,
     #[serde(default = "default_healthcheck_timeout")]
     pub timeout: String,
+    /// Pause between checks, unless a probe sets its own
+    #[serde(default = "default_healthcheck_interval")]
+    pub interval: String,
+    /// Readiness probes per compose service, for services whose compose
+    /// definition has no healthcheck
+    #[serde(default)]
+    pub probes: HashMap<String, HealthProbeConfig>,
 }
 
 impl Default for HealthCheckConfig {
@@ -255,14 +580,58 @@ impl Default for HealthCheckConfig {
         Self {
             enabled: false,
             timeout: default_healthcheck_timeout(),
+            interval: default_healthcheck_interval(),
+            probes: HashMap::new(),
         }
     }
 }
 
+/// Readiness probe for one service
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HealthProbeConfig {
+    #[serde(flatten)]
+    pub check: ProbeCheck,
+    /// How long to keep trying (default: `docker.healthcheck.timeout`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// Pause between attempts (default: `docker.healthcheck.interval`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+}
+
+/// What a readiness probe checks (`type:`)
+///
+/// `port` names the allocated port to probe, `<service>` by default (or
+/// e.g. `app-9229` for a service's second port).
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProbeCheck {
+    /// Connect to the allocated host port
+    Tcp {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<String>,
+    },
+    /// GET a path on the allocated host port and expect a status
+    Http {
+        #[serde(default = "default_probe_path")]
+        path: String,
+        #[serde(default = "default_probe_status")]
+        status: u16,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<String>,
+    },
+    /// Run a shell command in the service's container and expect exit 0
+    Command { command: String },
+}
+
 fn default_strategy() -> String {
     "per-worktree".to_string()
 }
 
+fn default_engine() -> String {
+    "auto".to_string()
+}
+
 fn default_compose_file() -> String {
     "docker-compose.yml".to_string()
 }
@@ -275,6 +644,30 @@ fn default_healthcheck_timeout() -> String {
     "30s".to_string()
 }
 
+fn default_healthcheck_interval() -> String {
+    "2s".to_string()
+}
+
+fn default_probe_path() -> String {
+    "/".to_string()
+}
+
+fn default_probe_status() -> u16 {
+    200
+}
+
+fn default_proxy_port() -> u16 {
+    8080
+}
+
+fn default_proxy_domain() -> String {
+    "localhost".to_string()
+}
+
+fn default_proxy_service() -> String {
+    "app".to_string()
+}
+
 impl Config {
     /// Load config from .hannahanna.yml in repository root
     /// This is kept for backwards compatibility, but internally uses load_hierarchy
@@ -321,7 +714,7 @@ impl Config {
     }
 
     /// Load a single config file from path, returning None if it doesn't exist
-    fn load_from_path(path: &Path) -> Result<Option<Self>> {
+    pub(crate) fn load_from_path(path: &Path) -> Result<Option<Self>> {
         if !path.exists() {
             return Ok(None);
         }
@@ -377,10 +770,37 @@ impl Config {
         if other.hooks.post_integrate.is_some() {
             self.hooks.post_integrate = other.hooks.post_integrate;
         }
+        if other.hooks.pre_sync.is_some() {
+            self.hooks.pre_sync = other.hooks.pre_sync;
+        }
+        if other.hooks.post_sync.is_some() {
+            self.hooks.post_sync = other.hooks.post_sync;
+        }
+        if other.hooks.pre_return.is_some() {
+            self.hooks.pre_return = other.hooks.pre_return;
+        }
+        if other.hooks.post_snapshot_restore.is_some() {
+            self.hooks.post_snapshot_restore = other.hooks.post_snapshot_restore;
+        }
+        if other.hooks.pre_docker_start.is_some() {
+            self.hooks.pre_docker_start = other.hooks.pre_docker_start;
+        }
+        if other.hooks.post_docker_start.is_some() {
+            self.hooks.post_docker_start = other.hooks.post_docker_start;
+        }
+        if other.hooks.post_docker_stop.is_some() {
+            self.hooks.post_docker_stop = other.hooks.post_docker_stop;
+        }
         // Override timeout only if explicitly set (different from default)
         if other.hooks.timeout_seconds != default_hook_timeout() {
             self.hooks.timeout_seconds = other.hooks.timeout_seconds;
         }
+        if other.hooks.log_retention != default_hook_log_retention() {
+            self.hooks.log_retention = other.hooks.log_retention;
+        }
+        if other.hooks.stream_output {
+            self.hooks.stream_output = true;
+        }
         // Append conditional hooks (arrays append)
         self.hooks
             .pre_create_conditions
@@ -403,6 +823,27 @@ impl Config {
         self.hooks
             .post_integrate_conditions
             .extend(other.hooks.post_integrate_conditions);
+        self.hooks
+            .pre_sync_conditions
+            .extend(other.hooks.pre_sync_conditions);
+        self.hooks
+            .post_sync_conditions
+            .extend(other.hooks.post_sync_conditions);
+        self.hooks
+            .pre_return_conditions
+            .extend(other.hooks.pre_return_conditions);
+        self.hooks
+            .post_snapshot_restore_conditions
+            .extend(other.hooks.post_snapshot_restore_conditions);
+        self.hooks
+            .pre_docker_start_conditions
+            .extend(other.hooks.pre_docker_start_conditions);
+        self.hooks
+            .post_docker_start_conditions
+            .extend(other.hooks.post_docker_start_conditions);
+        self.hooks
+            .post_docker_stop_conditions
+            .extend(other.hooks.post_docker_stop_conditions);
 
         // Merge docker config (override primitives, append arrays)
         if other.docker.enabled {
@@ -411,15 +852,24 @@ impl Config {
         if other.docker.strategy != default_strategy() {
             self.docker.strategy = other.docker.strategy;
         }
+        if other.docker.engine != default_engine() {
+            self.docker.engine = other.docker.engine;
+        }
         if other.docker.compose_file != default_compose_file() {
             self.docker.compose_file = other.docker.compose_file;
         }
+        if !other.docker.compose_overlays.is_empty() {
+            self.docker.compose_overlays = other.docker.compose_overlays;
+        }
         if other.docker.auto_start {
             self.docker.auto_start = true;
         }
         if other.docker.auto_stop_others {
             self.docker.auto_stop_others = true;
         }
+        if other.docker.idle_stop_after.is_some() {
+            self.docker.idle_stop_after = other.docker.idle_stop_after;
+        }
 
         // Merge docker ports
         if other.docker.ports.strategy != default_port_strategy() {
@@ -431,6 +881,9 @@ impl Config {
         if other.docker.ports.range.is_some() {
             self.docker.ports.range = other.docker.ports.range;
         }
+        if other.docker.ports.global {
+            self.docker.ports.global = true;
+        }
 
         // Merge docker shared resources (append arrays)
         self.docker
@@ -441,6 +894,14 @@ impl Config {
             .shared
             .networks
             .extend(other.docker.shared.networks);
+        for service in other.docker.shared.services {
+            if !self.docker.shared.services.contains(&service) {
+                self.docker.shared.services.push(service);
+            }
+        }
+        for (service, isolation) in other.docker.shared.isolation {
+            self.docker.shared.isolation.insert(service, isolation);
+        }
 
         // Merge docker isolated resources (append arrays)
         self.docker
@@ -453,6 +914,11 @@ impl Config {
             self.docker.env.insert(key, value);
         }
 
+        // Merge docker service settings (override per service)
+        for (service, settings) in other.docker.services {
+            self.docker.services.insert(service, settings);
+        }
+
         // Merge healthcheck
         if other.docker.healthcheck.enabled {
             self.docker.healthcheck.enabled = true;
@@ -460,6 +926,23 @@ impl Config {
         if other.docker.healthcheck.timeout != default_healthcheck_timeout() {
             self.docker.healthcheck.timeout = other.docker.healthcheck.timeout;
         }
+        if other.docker.healthcheck.interval != default_healthcheck_interval() {
+            self.docker.healthcheck.interval = other.docker.healthcheck.interval;
+        }
+        for (service, probe) in other.docker.healthcheck.probes {
+            self.docker.healthcheck.probes.insert(service, probe);
+        }
+
+        // Merge proxy
+        if other.docker.proxy.port != default_proxy_port() {
+            self.docker.proxy.port = other.docker.proxy.port;
+        }
+        if other.docker.proxy.domain != default_proxy_domain() {
+            self.docker.proxy.domain = other.docker.proxy.domain;
+        }
+        if other.docker.proxy.service != default_proxy_service() {
+            self.docker.proxy.service = other.docker.proxy.service;
+        }
 
         // Merge sparse config
         if other.sparse.enabled {
@@ -467,6 +950,11 @@ impl Config {
         }
         self.sparse.paths.extend(other.sparse.paths);
 
+        // Merge worktree layout
+        if other.worktrees.root.is_some() {
+            self.worktrees.root = other.worktrees.root;
+        }
+
         // Merge aliases (later configs override earlier ones)
         for (key, value) in other.aliases {
             self.aliases.insert(key, value);
@@ -526,6 +1014,35 @@ impl Config {
         }
     }
 
+    /// Check semantic constraints that deserialization alone can't catch
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref root) = self.worktrees.root {
+            crate::vcs::layout::validate_pattern(root)?;
+        }
+
+        self.hooks.validate("hooks")?;
+        crate::docker::shared::DockerStrategy::from_config(&self.docker)?;
+        crate::docker::ports::PortStrategy::parse(&self.docker.ports.strategy)?;
+        crate::docker::engine::ContainerEngine::parse(&self.docker.engine)?;
+        crate::docker::proxy::validate_domain(&self.docker.proxy.domain)?;
+        crate::docker::health::validate(&self.docker.healthcheck)?;
+        if let Some(ref idle) = self.docker.idle_stop_after {
+            crate::monitoring::parse_duration(idle).map_err(|_| {
+                HnError::ConfigError(format!(
+                    "docker.idle_stop_after: invalid duration '{}', e.g. '2h' or '1d'",
+                    idle
+                ))
+            })?;
+        }
+        for (name, profile) in &self.profiles {
+            if let Some(ref hooks) = profile.hooks {
+                hooks.validate(&format!("profiles.{}.hooks", name))?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Apply a named profile to this config (v0.6)
     pub fn apply_profile(&mut self, profile_name: &str) -> Result<()> {
         let profile = self.profiles.get(profile_name).ok_or_else(|| {
@@ -609,7 +1126,7 @@ hooks:
             config.shared_resources[0].compatibility,
             Some("package-lock.json".to_string())
         );
-        assert_eq!(config.hooks.post_create, Some("npm install".to_string()));
+        assert_eq!(config.hooks.post_create, Some("npm install".into()));
     }
 
     #[test]
@@ -0,0 +1,167 @@
+// Merge/rebase conflict tracking
+//
+// When `hn sync` or `hn integrate` stops on conflicts, the operation is
+// recorded in the worktree's state directory (`conflict.json`). `hn sync
+// --continue` and `--abort` use the record to finish the operation, and
+// `hn list` / `hn info` use it to flag worktrees stuck mid-merge.
+
+use crate::errors::{HnError, Result};
+use crate::vcs::traits::VcsBackend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const CONFLICT_FILE: &str = "conflict.json";
+
+/// The merge or rebase that stopped on conflicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictOperation {
+    Merge,
+    Rebase,
+}
+
+impl ConflictOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictOperation::Merge => "merge",
+            ConflictOperation::Rebase => "rebase",
+        }
+    }
+}
+
+/// An hn command waiting for conflicts to be resolved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConflictState {
+    /// hn command that started the operation (`sync` or `integrate`)
+    pub command: String,
+    pub operation: ConflictOperation,
+    /// Branch being merged in or rebased onto
+    pub source: String,
+    /// Files that had conflicts when the operation stopped
+    pub files: Vec<String>,
+    /// Message of the autostash to restore once the sync is finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autostash: Option<String>,
+    /// Commit the worktree was on before the operation
+    pub previous_commit: String,
+    /// Unix timestamp of when the operation stopped
+    pub started_at: u64,
+}
+
+impl ConflictState {
+    /// Load the recorded conflict for a worktree state directory
+    pub fn load(state_dir: &Path) -> Result<Option<Self>> {
+        let path = state_dir.join(CONFLICT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let state = serde_json::from_str(&content)
+            .map_err(|e| HnError::StateError(format!("Failed to parse conflict state: {}", e)))?;
+        Ok(Some(state))
+    }
+
+    /// The recorded conflict, if the VCS is still in the middle of the operation
+    ///
+    /// Records left behind after the user finished the merge with the VCS
+    /// directly are ignored.
+    pub fn load_active(
+        state_dir: &Path,
+        backend: &dyn VcsBackend,
+        worktree_path: &Path,
+    ) -> Option<Self> {
+        let state = Self::load(state_dir).ok().flatten()?;
+        backend.operation_in_progress(worktree_path).map(|_| state)
+    }
+
+    pub fn save(&self, state_dir: &Path) -> Result<()> {
+        fs::create_dir_all(state_dir)?;
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(state_dir.join(CONFLICT_FILE), content)?;
+        Ok(())
+    }
+
+    /// Forget the recorded conflict, if any
+    pub fn clear(state_dir: &Path) -> Result<()> {
+        let path = state_dir.join(CONFLICT_FILE);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// One-line description, e.g. "sync stopped in rebase onto main (2 files)"
+    pub fn summary(&self) -> String {
+        let preposition = match self.operation {
+            ConflictOperation::Merge => "of",
+            ConflictOperation::Rebase => "onto",
+        };
+        format!(
+            "{} stopped in {} {} {} ({} file{})",
+            self.command,
+            self.operation.as_str(),
+            preposition,
+            self.source,
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> ConflictState {
+        ConflictState {
+            command: "sync".to_string(),
+            operation: ConflictOperation::Rebase,
+            source: "main".to_string(),
+            files: vec!["a.txt".to_string()],
+            autostash: Some("hn sync autostash - now".to_string()),
+            previous_commit: "abc123".to_string(),
+            started_at: 1,
+        }
+    }
+
+    #[test]
+    fn test_save_load_clear() {
+        let temp = TempDir::new().unwrap();
+        let state_dir = temp.path().join("feature");
+
+        assert_eq!(ConflictState::load(&state_dir).unwrap(), None);
+
+        sample().save(&state_dir).unwrap();
+        assert_eq!(ConflictState::load(&state_dir).unwrap(), Some(sample()));
+
+        ConflictState::clear(&state_dir).unwrap();
+        assert_eq!(ConflictState::load(&state_dir).unwrap(), None);
+        ConflictState::clear(&state_dir).unwrap();
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            sample().summary(),
+            "sync stopped in rebase onto main (1 file)"
+        );
+    }
+
+    #[test]
+    fn test_stale_record_is_not_active() {
+        let temp = TempDir::new().unwrap();
+        git2::Repository::init(temp.path()).unwrap();
+        let state_dir = temp.path().join("state");
+        sample().save(&state_dir).unwrap();
+
+        let backend = crate::vcs::git::GitBackend::open(temp.path()).unwrap();
+        assert_eq!(backend.operation_in_progress(temp.path()), None);
+        assert_eq!(
+            ConflictState::load_active(&state_dir, &backend, temp.path()),
+            None
+        );
+    }
+}
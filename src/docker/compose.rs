@@ -1,95 +1,486 @@
 // Docker Compose override generation
 // Auto-generates per-worktree docker-compose.override.yml files
 
-use crate::config::DockerConfig;
-use crate::errors::Result;
-use std::collections::HashMap;
+use super::shared::{self, DockerStrategy};
+use crate::config::{DockerConfig, PortsConfig};
+use crate::errors::{HnError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Name of the shared project's network inside worktree overrides
+const SHARED_NETWORK: &str = "hn-shared";
+
+/// Label identifying the worktree a container belongs to
+pub const WORKTREE_LABEL: &str = "dev.hannahanna.worktree";
+
+/// The parts of a compose file hn reads
+#[derive(Debug, Default, Deserialize)]
+pub struct ComposeFile {
+    #[serde(default)]
+    pub services: BTreeMap<String, ComposeService>,
+}
+
+/// A service of a compose file, as far as hn is concerned
+#[derive(Debug, Default, Deserialize)]
+pub struct ComposeService {
+    #[serde(default)]
+    pub ports: Vec<ComposePort>,
+}
+
+/// A `ports:` entry in short (`"8080:80"`, `80`) or long (`target: 80`) syntax
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ComposePort {
+    Number(u16),
+    Short(String),
+    Long { target: u16 },
+}
+
+impl ComposePort {
+    /// Port inside the container
+    pub fn container_port(&self) -> Option<u16> {
+        match self {
+            ComposePort::Number(port) | ComposePort::Long { target: port } => Some(*port),
+            // [ip:][host:]container[-range][/protocol]
+            ComposePort::Short(spec) => spec
+                .split('/')
+                .next()?
+                .rsplit(':')
+                .next()?
+                .split('-')
+                .next()?
+                .trim()
+                .parse()
+                .ok(),
+        }
+    }
+}
+
+impl ComposeFile {
+    pub fn load(compose_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(compose_path).map_err(|e| {
+            HnError::DockerError(format!("Failed to read {}: {}", compose_path.display(), e))
+        })?;
+        serde_yml::from_str(&content).map_err(|e| {
+            HnError::DockerError(format!("Failed to parse {}: {}", compose_path.display(), e))
+        })
+    }
+
+    /// Load `compose_file` from `dir` with `compose_overlays` layered over it,
+    /// the way `docker compose -f ... -f ...` sees them
+    pub fn discover(config: &DockerConfig, dir: &Path) -> Result<Self> {
+        let mut compose = Self::load(&dir.join(&config.compose_file))?;
+        for overlay in &config.compose_overlays {
+            for (name, service) in Self::load(&dir.join(overlay))?.services {
+                // Compose appends the ports of overlays to those of the base file
+                compose
+                    .services
+                    .entry(name)
+                    .or_default()
+                    .ports
+                    .extend(service.ports);
+            }
+        }
+        Ok(compose)
+    }
+
+    /// Container ports a service publishes
+    pub fn container_ports(&self, service: &str) -> Vec<u16> {
+        let mut ports: Vec<u16> = Vec::new();
+        for port in self
+            .services
+            .get(service)
+            .into_iter()
+            .flat_map(|s| s.ports.iter().filter_map(|p| p.container_port()))
+        {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+        ports
+    }
+}
+
+/// The compose file and its overlays, in `-f` order
+pub fn compose_files(config: &DockerConfig) -> impl Iterator<Item = &String> {
+    std::iter::once(&config.compose_file).chain(&config.compose_overlays)
+}
+
+/// Service names defined in the compose files in `dir`, sorted
+pub fn service_names(config: &DockerConfig, dir: &Path) -> Result<Vec<String>> {
+    Ok(ComposeFile::discover(config, dir)?
+        .services
+        .into_keys()
+        .collect())
+}
+
+/// Services that get host ports
+///
+/// With a compose file these are the services publishing ports in it, plus
+/// any given ports in `docker.services`. Without one, the services listed
+/// in `docker.ports.base` and `docker.services`.
+pub fn port_services(config: &DockerConfig, compose: Option<&ComposeFile>) -> Vec<String> {
+    let configured = config
+        .services
+        .iter()
+        .filter(|(_, settings)| compose.is_none() || !settings.ports.is_empty())
+        .map(|(service, _)| service.clone());
+
+    let services: BTreeSet<String> = match compose {
+        Some(compose) => compose
+            .services
+            .keys()
+            .filter(|service| !compose.container_ports(service).is_empty())
+            .cloned()
+            .chain(configured)
+            .collect(),
+        None => config
+            .ports
+            .base
+            .keys()
+            .cloned()
+            .chain(configured)
+            .collect(),
+    };
+    services.into_iter().collect()
+}
+
+/// Services the config refers to that the compose files don't define, as
+/// (setting, service) pairs
+pub fn undefined_services(config: &DockerConfig, compose: &ComposeFile) -> Vec<(String, String)> {
+    // Base ports hn ships with aren't something the config refers to
+    let default_base = PortsConfig::default().base;
+    let base = config
+        .ports
+        .base
+        .iter()
+        .filter(|(service, port)| default_base.get(*service) != Some(*port))
+        .map(|(service, _)| service)
+        .collect::<Vec<_>>();
+
+    let settings = [
+        ("docker.ports.base", base),
+        ("docker.services", config.services.keys().collect()),
+        (
+            "docker.shared.services",
+            config.shared.services.iter().collect(),
+        ),
+        (
+            "docker.shared.isolation",
+            config.shared.isolation.keys().collect(),
+        ),
+    ];
+
+    let mut undefined: Vec<(String, String)> = settings
+        .into_iter()
+        .flat_map(|(setting, services)| {
+            services
+                .into_iter()
+                .filter(|service| !compose.services.contains_key(*service))
+                .map(move |service| (setting.to_string(), service.clone()))
+        })
+        .collect();
+    undefined.sort();
+    undefined
+}
+
+/// Container port for well-known services the config and compose file say
+/// nothing about
+fn default_container_port(service: &str) -> u16 {
+    match service {
+        "app" => 3000,
+        "postgres" => 5432,
+        "redis" => 6379,
+        "mysql" => 3306,
+        "mongodb" => 27017,
+        _ => 8080,
+    }
+}
+
+/// Container ports of a service: from `docker.services`, else from the
+/// compose file, else the well-known default
+pub fn container_ports(
+    config: &DockerConfig,
+    compose: Option<&ComposeFile>,
+    service: &str,
+) -> Vec<u16> {
+    if let Some(settings) = config.services.get(service) {
+        if !settings.ports.is_empty() {
+            return settings.ports.clone();
+        }
+    }
+    if let Some(ports) = compose.map(|c| c.container_ports(service)) {
+        if !ports.is_empty() {
+            return ports;
+        }
+    }
+    vec![default_container_port(service)]
+}
+
+/// Name a host port is allocated under: the service name for its first
+/// port and `<service>-<container port>` for any others
+pub fn port_key(service: &str, index: usize, container_port: u16) -> String {
+    if index == 0 {
+        service.to_string()
+    } else {
+        format!("{}-{}", service, container_port)
+    }
+}
+
+/// Docker volume name of a worktree's copy of an isolated volume
+pub fn isolated_volume_name(worktree_name: &str, volume: &str) -> String {
+    format!("{}-{}", worktree_name, volume)
+}
+
+/// Host ports to allocate for a worktree's services, with the container
+/// port each one maps to
+pub fn port_plan(
+    config: &DockerConfig,
+    compose: Option<&ComposeFile>,
+    services: &[&str],
+) -> Vec<(String, u16)> {
+    services
+        .iter()
+        .flat_map(|service| {
+            container_ports(config, compose, service)
+                .into_iter()
+                .enumerate()
+                .map(move |(i, port)| (port_key(service, i, port), port))
+        })
+        .collect()
+}
+
+/// A docker-compose.override.yml file
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ComposeOverride {
+    pub services: BTreeMap<String, ServiceOverride>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub volumes: BTreeMap<String, ResourceDefinition>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub networks: BTreeMap<String, ResourceDefinition>,
+}
+
+/// Per-worktree settings layered over a compose service
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ServiceOverride {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
+}
+
+/// A top-level volume or network
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
 
 /// Generates docker-compose.override.yml files for worktrees
 pub struct ComposeGenerator<'a> {
     config: &'a DockerConfig,
     state_dir: &'a Path,
+    /// The worktree's `HNHN_<SERVICE>_*` variables for shared services
+    isolation_env: BTreeMap<String, String>,
+    /// Per shared service isolation value, for `{{isolation.<service>}}`
+    isolation_values: HashMap<String, String>,
 }
 
 impl<'a> ComposeGenerator<'a> {
     /// Create a new compose generator
     pub fn new(config: &'a DockerConfig, state_dir: &'a Path) -> Self {
-        Self { config, state_dir }
+        Self {
+            config,
+            state_dir,
+            isolation_env: BTreeMap::new(),
+            isolation_values: HashMap::new(),
+        }
     }
 
-    /// Generate docker-compose.override.yml content for a worktree
-    pub fn generate(
+    /// Pass the worktree's isolation inside the shared services to its containers
+    pub fn with_isolation(
+        mut self,
+        env: BTreeMap<String, String>,
+        values: HashMap<String, String>,
+    ) -> Self {
+        self.isolation_env = env;
+        self.isolation_values = values;
+        self
+    }
+
+    /// Build the override for a worktree
+    ///
+    /// Services get a port mapping for every allocated host port, `docker.env`
+    /// plus their own env and labels from `docker.services`, and their volume
+    /// mounts. Shared services are left out.
+    pub fn build(
         &self,
         worktree_name: &str,
-        _worktree_path: &Path,
+        worktree_path: &Path,
         ports: &HashMap<String, u16>,
-    ) -> Result<String> {
-        let mut output = String::new();
-
-        // Header
-        output.push_str("# Auto-generated by hn\n");
-        output.push_str(&format!("# Worktree: {}\n", worktree_name));
-        output.push_str("# Do not edit manually - regenerate with: hn docker regenerate\n\n");
-
-        // Services section
-        output.push_str("services:\n");
-
-        // Generate service overrides based on allocated ports
-        for (service_name, port) in ports {
-            output.push_str(&format!("  {}:\n", service_name));
-
-            // Port mappings
-            output.push_str("    ports:\n");
-            output.push_str(&format!(
-                "      - \"{}:{}\"",
-                port,
-                self.get_internal_port(service_name)
-            ));
-            output.push('\n');
-
-            // Environment variables with substitutions
-            if !self.config.env.is_empty() {
-                output.push_str("    environment:\n");
-                for (key, value) in &self.config.env {
-                    let substituted = self.substitute_variables(value, worktree_name, ports);
-                    output.push_str(&format!("      {}: \"{}\"\n", key, substituted));
-                }
-            }
+    ) -> Result<ComposeOverride> {
+        let strategy = DockerStrategy::from_config(self.config)?;
+        let compose = ComposeFile::discover(self.config, worktree_path).ok();
+
+        // Every service the config, the compose file or the allocations mention
+        let mut candidates: BTreeSet<&str> =
+            self.config.services.keys().map(|s| s.as_str()).collect();
+        candidates.extend(self.config.ports.base.keys().map(|s| s.as_str()));
+        if let Some(compose) = &compose {
+            candidates.extend(compose.services.keys().map(|s| s.as_str()));
         }
-
-        // Volumes section
-        if !self.config.shared.volumes.is_empty() || !self.config.isolated.volumes.is_empty() {
-            output.push_str("\nvolumes:\n");
-
-            // Shared volumes (external)
-            for volume in &self.config.shared.volumes {
-                output.push_str(&format!("  {}:\n", volume));
-                output.push_str("    external: true\n");
+        let known = candidates.clone();
+        candidates.extend(ports.keys().map(|s| s.as_str()).filter(|key| {
+            // Extra ports of a known service aren't services of their own
+            !known.iter().any(|service| {
+                key.strip_prefix(service)
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .is_some_and(|port| port.parse::<u16>().is_ok())
+            })
+        }));
+
+        let mut services = BTreeMap::new();
+        for service in candidates {
+            if strategy.is_shared(self.config, service) {
+                continue;
             }
-
-            // Isolated volumes (worktree-specific)
-            for volume in &self.config.isolated.volumes {
-                let volume_name = format!("{}-{}", worktree_name, volume);
-                output.push_str(&format!("  {}:\n", volume_name));
-                output.push_str(&format!("    name: {}\n", volume_name));
+            let settings = self.config.services.get(service);
+
+            let service_ports: Vec<String> =
+                container_ports(self.config, compose.as_ref(), service)
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, container)| {
+                        ports
+                            .get(&port_key(service, i, container))
+                            .map(|host| format!("{}:{}", host, container))
+                    })
+                    .collect();
+            if service_ports.is_empty() && settings.is_none() {
+                continue;
             }
+
+            let substitute =
+                |value: &String| self.substitute_variables(value, worktree_name, ports);
+            let mut environment: BTreeMap<String, String> = self
+                .config
+                .env
+                .iter()
+                .chain(settings.iter().flat_map(|s| s.env.iter()))
+                .map(|(key, value)| (key.clone(), substitute(value)))
+                .collect();
+            environment.extend(self.isolation_env.clone());
+
+            let mut labels: BTreeMap<String, String> = settings
+                .iter()
+                .flat_map(|s| s.labels.iter())
+                .map(|(key, value)| (key.clone(), substitute(value)))
+                .collect();
+            labels.insert(WORKTREE_LABEL.to_string(), worktree_name.to_string());
+
+            // Reach the shared services over the shared project's network
+            let networks = if strategy == DockerStrategy::Hybrid {
+                vec!["default".to_string(), SHARED_NETWORK.to_string()]
+            } else {
+                Vec::new()
+            };
+
+            services.insert(
+                service.to_string(),
+                ServiceOverride {
+                    ports: service_ports,
+                    environment,
+                    labels,
+                    volumes: settings
+                        .map(|s| s.volumes.iter().map(substitute).collect())
+                        .unwrap_or_default(),
+                    networks,
+                },
+            );
         }
 
-        // Networks section
-        if !self.config.shared.networks.is_empty() {
-            output.push_str("\nnetworks:\n");
-            for network in &self.config.shared.networks {
-                output.push_str(&format!("  {}:\n", network));
-                output.push_str("    external: true\n");
-            }
+        // Shared volumes are external; isolated ones get a per-worktree name
+        let mut volumes = BTreeMap::new();
+        for volume in &self.config.shared.volumes {
+            volumes.insert(
+                volume.clone(),
+                ResourceDefinition {
+                    external: Some(true),
+                    name: None,
+                },
+            );
+        }
+        for volume in &self.config.isolated.volumes {
+            volumes.insert(
+                volume.clone(),
+                ResourceDefinition {
+                    external: None,
+                    name: Some(isolated_volume_name(worktree_name, volume)),
+                },
+            );
         }
 
-        Ok(output)
+        let mut networks = BTreeMap::new();
+        for network in &self.config.shared.networks {
+            networks.insert(
+                network.clone(),
+                ResourceDefinition {
+                    external: Some(true),
+                    name: None,
+                },
+            );
+        }
+        if strategy == DockerStrategy::Hybrid {
+            let repo_root = self.state_dir.parent().unwrap_or(self.state_dir);
+            networks.insert(
+                SHARED_NETWORK.to_string(),
+                ResourceDefinition {
+                    external: Some(true),
+                    name: Some(format!(
+                        "{}_default",
+                        shared::shared_project_name(repo_root)
+                    )),
+                },
+            );
+        }
+
+        Ok(ComposeOverride {
+            services,
+            volumes,
+            networks,
+        })
+    }
+
+    /// Generate docker-compose.override.yml content for a worktree
+    pub fn generate(
+        &self,
+        worktree_name: &str,
+        worktree_path: &Path,
+        ports: &HashMap<String, u16>,
+    ) -> Result<String> {
+        let compose = self.build(worktree_name, worktree_path, ports)?;
+        let yaml = serde_yml::to_string(&compose).map_err(|e| {
+            HnError::DockerError(format!("Failed to serialize compose override: {}", e))
+        })?;
+
+        Ok(format!(
+            "# Auto-generated by hn\n# Worktree: {}\n# Do not edit manually - regenerate with: hn docker regenerate\n\n{}",
+            worktree_name, yaml
+        ))
     }
 
     /// Save docker-compose.override.yml to disk
+    #[allow(dead_code)] // Used in integration tests
     pub fn save(
         &self,
         worktree_name: &str,
@@ -97,24 +488,31 @@ impl<'a> ComposeGenerator<'a> {
         ports: &HashMap<String, u16>,
     ) -> Result<()> {
         let content = self.generate(worktree_name, worktree_path, ports)?;
+        self.write(worktree_name, &content)
+    }
+
+    /// Path of a worktree's override file
+    pub fn override_path(&self, worktree_name: &str) -> PathBuf {
+        self.state_dir
+            .join(worktree_name)
+            .join("docker-compose.override.yml")
+    }
 
+    /// Write generated override content for a worktree
+    pub fn write(&self, worktree_name: &str, content: &str) -> Result<()> {
         // Create worktree state directory
         let worktree_state = self.state_dir.join(worktree_name);
         fs::create_dir_all(&worktree_state)?;
 
         // Write override file
-        let override_path = worktree_state.join("docker-compose.override.yml");
-        fs::write(&override_path, content)?;
+        fs::write(self.override_path(worktree_name), content)?;
 
         Ok(())
     }
 
     /// Delete override file for a worktree
     pub fn delete(&self, worktree_name: &str) -> Result<()> {
-        let override_path = self
-            .state_dir
-            .join(worktree_name)
-            .join("docker-compose.override.yml");
+        let override_path = self.override_path(worktree_name);
 
         if override_path.exists() {
             fs::remove_file(&override_path)?;
@@ -141,25 +539,20 @@ impl<'a> ComposeGenerator<'a> {
             result = result.replace(&placeholder, &port.to_string());
         }
 
-        result
-    }
-
-    /// Get internal port for a service (default mappings)
-    fn get_internal_port(&self, service_name: &str) -> u16 {
-        match service_name {
-            "app" => 3000,
-            "postgres" => 5432,
-            "redis" => 6379,
-            "mysql" => 3306,
-            "mongodb" => 27017,
-            _ => 8080,
+        // Replace {{isolation.service}}
+        for (service, value) in &self.isolation_values {
+            let placeholder = format!("{{{{isolation.{}}}}}", service);
+            result = result.replace(&placeholder, value);
         }
+
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::DockerServiceConfig;
     use tempfile::TempDir;
 
     #[test]
@@ -179,14 +572,238 @@ mod tests {
     }
 
     #[test]
-    fn test_get_internal_port() {
+    fn test_hybrid_override_joins_shared_network() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_dir = temp_dir.path().join("my-app").join(".hn-state");
+        let mut config = DockerConfig {
+            strategy: "hybrid".to_string(),
+            ..Default::default()
+        };
+        config.shared.services = vec!["postgres".to_string()];
+        config.env.insert(
+            "DATABASE_URL".to_string(),
+            "postgres://postgres/{{isolation.postgres}}".to_string(),
+        );
+
+        let mut env = BTreeMap::new();
+        env.insert(
+            "HNHN_POSTGRES_DATABASE".to_string(),
+            "feature_x".to_string(),
+        );
+        let mut values = HashMap::new();
+        values.insert("postgres".to_string(), "feature_x".to_string());
+        let generator = ComposeGenerator::new(&config, &state_dir).with_isolation(env, values);
+
+        let mut ports = HashMap::new();
+        ports.insert("app".to_string(), 3001);
+        ports.insert("postgres".to_string(), 5433);
+        let compose = generator
+            .build("feature-x", temp_dir.path(), &ports)
+            .unwrap();
+
+        assert!(!compose.services.contains_key("postgres"));
+        let app = &compose.services["app"];
+        assert_eq!(
+            app.environment["DATABASE_URL"],
+            "postgres://postgres/feature_x"
+        );
+        assert_eq!(app.environment["HNHN_POSTGRES_DATABASE"], "feature_x");
+        assert_eq!(app.networks, vec!["default", "hn-shared"]);
+        assert_eq!(
+            compose.networks["hn-shared"].name.as_deref(),
+            Some("my-app-shared_default")
+        );
+    }
+
+    #[test]
+    fn test_service_names() {
         let temp_dir = TempDir::new().unwrap();
         let config = DockerConfig::default();
+        assert!(service_names(&config, temp_dir.path()).is_err());
+
+        fs::write(
+            temp_dir.path().join("docker-compose.yml"),
+            "services:\n  app:\n    image: app\n  postgres:\n    image: postgres\n",
+        )
+        .unwrap();
+        assert_eq!(
+            service_names(&config, temp_dir.path()).unwrap(),
+            vec!["app", "postgres"]
+        );
+    }
+
+    #[test]
+    fn test_discover_layers_overlays() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("docker-compose.yml"),
+            "services:\n  app:\n    ports: ['3000:3000']\n  worker:\n    image: worker\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("docker-compose.dev.yml"),
+            "services:\n  app:\n    ports: ['9229:9229', '3000:3000']\n  mailhog:\n    ports: [8025]\n",
+        )
+        .unwrap();
+        let mut config = DockerConfig {
+            compose_overlays: vec!["docker-compose.dev.yml".to_string()],
+            ..Default::default()
+        };
+        config.ports.base.insert("search".to_string(), 9200);
+
+        let compose = ComposeFile::discover(&config, temp_dir.path()).unwrap();
+        assert_eq!(compose.container_ports("app"), vec![3000, 9229]);
+        assert_eq!(
+            compose_files(&config).collect::<Vec<_>>(),
+            vec!["docker-compose.yml", "docker-compose.dev.yml"]
+        );
+
+        // Services without published ports get no host ports
+        assert_eq!(
+            port_services(&config, Some(&compose)),
+            vec!["app", "mailhog"]
+        );
+        assert_eq!(
+            port_services(&config, None),
+            vec!["app", "postgres", "redis", "search"]
+        );
+        assert_eq!(
+            undefined_services(&config, &compose),
+            vec![("docker.ports.base".to_string(), "search".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_compose_port_formats() {
+        let compose: ComposeFile = serde_yml::from_str(
+            r#"
+services:
+  web:
+    image: web
+    ports:
+      - 80
+      - "8443:443"
+      - "127.0.0.1:9000:9001/udp"
+      - "5000-5002:6000-6002"
+      - target: 7000
+        published: 7001
+  worker:
+    image: worker
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            compose.container_ports("web"),
+            vec![80, 443, 9001, 6000, 7000]
+        );
+        assert!(compose.container_ports("worker").is_empty());
+    }
+
+    #[test]
+    fn test_container_ports_precedence() {
+        let mut config = DockerConfig::default();
+        let compose: ComposeFile =
+            serde_yml::from_str("services:\n  app:\n    ports: ['8000:8000']\n").unwrap();
+
+        assert_eq!(container_ports(&config, None, "app"), vec![3000]);
+        assert_eq!(container_ports(&config, None, "postgres"), vec![5432]);
+        assert_eq!(container_ports(&config, None, "unknown"), vec![8080]);
+        assert_eq!(container_ports(&config, Some(&compose), "app"), vec![8000]);
+
+        config.services.insert(
+            "app".to_string(),
+            DockerServiceConfig {
+                ports: vec![3000, 9229],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            container_ports(&config, Some(&compose), "app"),
+            vec![3000, 9229]
+        );
+        assert_eq!(
+            port_plan(&config, Some(&compose), &["app"]),
+            vec![("app".to_string(), 3000), ("app-9229".to_string(), 9229)]
+        );
+    }
+
+    #[test]
+    fn test_multi_port_service_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DockerConfig::default();
+        config
+            .env
+            .insert("WORKTREE".to_string(), "{{worktree_name}}".to_string());
+        config.isolated.volumes = vec!["cache".to_string()];
+        let mut app = DockerServiceConfig {
+            ports: vec![3000, 9229],
+            volumes: vec!["cache:/app/cache".to_string()],
+            ..Default::default()
+        };
+        app.env.insert(
+            "DEBUG_URL".to_string(),
+            "http://localhost:{{port.app-9229}}".to_string(),
+        );
+        app.labels
+            .insert("traefik.enable".to_string(), "true".to_string());
+        config.services.insert("app".to_string(), app);
+        let generator = ComposeGenerator::new(&config, temp_dir.path());
+
+        let mut ports = HashMap::new();
+        ports.insert("app".to_string(), 3001);
+        ports.insert("app-9229".to_string(), 9230);
+        ports.insert("redis".to_string(), 6380);
+        let compose = generator
+            .build("feature-x", temp_dir.path(), &ports)
+            .unwrap();
+
+        assert_eq!(compose.services.len(), 2);
+        let app = &compose.services["app"];
+        assert_eq!(app.ports, vec!["3001:3000", "9230:9229"]);
+        assert_eq!(app.environment["WORKTREE"], "feature-x");
+        assert_eq!(app.environment["DEBUG_URL"], "http://localhost:9230");
+        assert_eq!(app.labels["traefik.enable"], "true");
+        assert_eq!(app.labels[WORKTREE_LABEL], "feature-x");
+        assert_eq!(app.volumes, vec!["cache:/app/cache"]);
+
+        // Per-service env stays with its service
+        let redis = &compose.services["redis"];
+        assert_eq!(redis.ports, vec!["6380:6379"]);
+        assert!(!redis.environment.contains_key("DEBUG_URL"));
+
+        assert_eq!(
+            compose.volumes["cache"].name.as_deref(),
+            Some("feature-x-cache")
+        );
+    }
+
+    #[test]
+    fn test_generated_yaml_round_trips() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = DockerConfig::default();
+        config.env.insert(
+            "TRICKY".to_string(),
+            "yes: \"quoted\" # not a comment".to_string(),
+        );
+        config.env.insert("NUMBER".to_string(), "5432".to_string());
         let generator = ComposeGenerator::new(&config, temp_dir.path());
 
-        assert_eq!(generator.get_internal_port("app"), 3000);
-        assert_eq!(generator.get_internal_port("postgres"), 5432);
-        assert_eq!(generator.get_internal_port("redis"), 6379);
-        assert_eq!(generator.get_internal_port("unknown"), 8080);
+        let mut ports = HashMap::new();
+        ports.insert("app".to_string(), 3001);
+        let output = generator
+            .generate("feature-x", temp_dir.path(), &ports)
+            .unwrap();
+
+        let parsed: ComposeOverride = serde_yml::from_str(&output).unwrap();
+        assert_eq!(
+            parsed,
+            generator
+                .build("feature-x", temp_dir.path(), &ports)
+                .unwrap()
+        );
+        let raw: serde_yml::Value = serde_yml::from_str(&output).unwrap();
+        assert!(raw["services"]["app"]["environment"]["NUMBER"].is_string());
+        assert!(raw["services"]["app"]["ports"][0].is_string());
     }
 }
@@ -1,7 +1,11 @@
 // Docker container lifecycle management
 // Start, stop, monitor containers for worktrees
 
-use crate::config::DockerConfig;
+use super::engine::{self, ContainerEngine, PROJECT_LABEL};
+use super::health::{self, ProbeTarget, ReadinessProbe};
+use super::ports::PortAllocator;
+use super::shared::{self, DockerStrategy};
+use crate::config::{DockerConfig, ProbeCheck};
 use crate::errors::{HnError, Result};
 use std::path::Path;
 use std::process::Command;
@@ -15,70 +19,66 @@ pub struct ContainerStatus {
     pub container_count: usize,
 }
 
-/// Docker Compose command variant
-#[derive(Debug, Clone, Copy)]
-enum DockerComposeVariant {
-    /// Legacy docker-compose (with hyphen)
-    Hyphenated,
-    /// Modern docker compose (no hyphen, subcommand of docker)
-    Subcommand,
-}
-
 /// Manages Docker container lifecycle for worktrees
 pub struct ContainerManager<'a> {
     config: &'a DockerConfig,
     state_dir: &'a Path,
-    compose_variant: DockerComposeVariant,
+    engine: ContainerEngine,
+}
+
+/// Sanitize a name for use as a Docker Compose project name
+/// Docker project names must be lowercase alphanumeric with hyphens only
+pub fn sanitize_project_name(name: &str) -> String {
+    // Sanitize name for Docker Compose project name requirements:
+    // - Lowercase only
+    // - Alphanumeric and hyphens
+    // - Cannot start/end with hyphen
+    let sanitized = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+
+    // Remove leading/trailing hyphens and collapse multiple hyphens
+    sanitized
+        .trim_matches('-')
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
 impl<'a> ContainerManager<'a> {
     /// Create a new container manager
     pub fn new(config: &'a DockerConfig, state_dir: &'a Path) -> Result<Self> {
-        let compose_variant = Self::detect_compose_variant();
+        let engine = ContainerEngine::from_config(config)?;
         Ok(Self {
             config,
             state_dir,
-            compose_variant,
+            engine,
         })
     }
 
-    /// Detect which docker-compose variant is available
-    fn detect_compose_variant() -> DockerComposeVariant {
-        // Try modern "docker compose" first
-        let modern = Command::new("docker")
-            .arg("compose")
-            .arg("version")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false);
-
-        if modern {
-            return DockerComposeVariant::Subcommand;
-        }
-
-        // Fall back to legacy "docker-compose"
-        DockerComposeVariant::Hyphenated
+    /// The container engine in use
+    pub fn engine(&self) -> ContainerEngine {
+        self.engine
     }
 
-    /// Get the docker-compose command and args based on detected variant
-    fn get_compose_command(&self, args: &[String]) -> (String, Vec<String>) {
-        match self.compose_variant {
-            DockerComposeVariant::Subcommand => {
-                let mut compose_args = vec!["compose".to_string()];
-                compose_args.extend_from_slice(args);
-                ("docker".to_string(), compose_args)
-            }
-            DockerComposeVariant::Hyphenated => ("docker-compose".to_string(), args.to_vec()),
-        }
+    /// Get the compose command and args for the engine in use
+    pub fn get_compose_command(&self, args: &[String]) -> (String, Vec<String>) {
+        self.engine.compose_command(args)
     }
 
-    /// Check if Docker is available on the system
+    /// Check if the container engine is available on the system
     pub fn is_docker_available(&self) -> bool {
-        Command::new("docker")
-            .arg("--version")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+        self.engine.is_available()
+    }
+
+    fn engine_unavailable(&self) -> HnError {
+        HnError::DockerError(format!(
+            "{} is not available. Please install it or set docker.engine.",
+            self.engine.runtime()
+        ))
     }
 
     /// Get container status for a worktree
@@ -103,18 +103,34 @@ impl<'a> ContainerManager<'a> {
         Self::validate_worktree_name(worktree_name)?;
 
         if !self.is_docker_available() {
-            return Err(HnError::DockerError(
-                "Docker is not available. Please install Docker.".to_string(),
-            ));
+            return Err(self.engine_unavailable());
+        }
+
+        let strategy = DockerStrategy::from_config(self.config)?;
+        self.start_shared()?;
+        if strategy == DockerStrategy::Shared {
+            // Nothing runs per worktree; it only uses the shared services
+            return Ok(());
         }
 
-        let args = self.build_start_command_args(worktree_name)?;
+        let mut args = self.build_start_command_args(worktree_name)?;
+        if strategy == DockerStrategy::Hybrid {
+            // Start only this worktree's own services; the shared ones it
+            // depends on are already running in the shared project
+            let services = super::compose::service_names(self.config, worktree_path)?;
+            args.push("--no-deps".to_string());
+            args.extend(
+                services
+                    .into_iter()
+                    .filter(|s| !strategy.is_shared(self.config, s)),
+            );
+        }
         let (program, full_args) = self.get_compose_command(&args);
         self.execute_command_safe(&program, &full_args, worktree_path)?;
 
         // Wait for health checks if enabled
         if self.config.healthcheck.enabled {
-            self.wait_for_healthy(worktree_name)?;
+            self.wait_until_ready(worktree_name)?;
         }
 
         Ok(())
@@ -163,25 +179,86 @@ impl<'a> ContainerManager<'a> {
     }
 
     /// Get Docker Compose project name for a worktree
-    /// Docker project names must be lowercase alphanumeric with hyphens only
     pub fn get_project_name(&self, worktree_name: &str) -> String {
-        // Sanitize name for Docker Compose project name requirements:
-        // - Lowercase only
-        // - Alphanumeric and hyphens
-        // - Cannot start/end with hyphen
-        let sanitized = worktree_name
-            .to_lowercase()
-            .chars()
-            .map(|c| if c.is_alphanumeric() { c } else { '-' })
-            .collect::<String>();
+        sanitize_project_name(worktree_name)
+    }
 
-        // Remove leading/trailing hyphens and collapse multiple hyphens
-        sanitized
-            .trim_matches('-')
-            .split('-')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join("-")
+    /// Repository root, where the shared services' compose project runs
+    fn repo_root(&self) -> &Path {
+        self.state_dir.parent().unwrap_or(self.state_dir)
+    }
+
+    /// Compose project name of the services shared by all worktrees
+    pub fn shared_project_name(&self) -> String {
+        shared::shared_project_name(self.repo_root())
+    }
+
+    /// Start the shared services, if the strategy has any
+    ///
+    /// `up -d` is a no-op for services that are already running, so this is
+    /// safe to call before every worktree start.
+    pub fn start_shared(&self) -> Result<()> {
+        let strategy = DockerStrategy::from_config(self.config)?;
+        if strategy == DockerStrategy::PerWorktree {
+            return Ok(());
+        }
+
+        if !self.is_docker_available() {
+            return Err(self.engine_unavailable());
+        }
+
+        let args = self.build_shared_start_command_args(strategy)?;
+        let (program, full_args) = self.get_compose_command(&args);
+        self.execute_command_safe(&program, &full_args, self.repo_root())
+    }
+
+    /// Stop the shared services
+    pub fn stop_shared(&self) -> Result<()> {
+        if !self.is_docker_available() {
+            return Ok(()); // Silent success if Docker not available
+        }
+
+        let args = vec![
+            "-p".to_string(),
+            self.shared_project_name(),
+            "down".to_string(),
+        ];
+        let (program, full_args) = self.get_compose_command(&args);
+        self.execute_command_safe(&program, &full_args, self.repo_root())
+    }
+
+    /// Number of running containers in the shared project
+    pub fn shared_container_count(&self) -> usize {
+        if !self.is_docker_available() {
+            return 0;
+        }
+        self.count_project_containers(&self.shared_project_name())
+    }
+
+    /// Add `-f` for the compose file and each overlay
+    fn push_compose_files(&self, args: &mut Vec<String>) {
+        for file in super::compose::compose_files(self.config) {
+            args.push("-f".to_string());
+            args.push(file.clone());
+        }
+    }
+
+    /// Build docker-compose up command arguments for the shared project
+    fn build_shared_start_command_args(&self, strategy: DockerStrategy) -> Result<Vec<String>> {
+        let mut args = vec!["-p".to_string(), self.shared_project_name()];
+        self.push_compose_files(&mut args);
+        args.push("up".to_string());
+        args.push("-d".to_string());
+
+        // With `shared` every service is shared; with `hybrid` only the listed ones
+        if strategy == DockerStrategy::Hybrid {
+            for service in &self.config.shared.services {
+                Self::validate_service_name(service)?;
+                args.push(service.clone());
+            }
+        }
+
+        Ok(args)
     }
 
     /// Build docker-compose up command arguments (safe from injection)
@@ -192,12 +269,8 @@ impl<'a> ContainerManager<'a> {
             .join(worktree_name)
             .join("docker-compose.override.yml");
 
-        let mut args = vec![
-            "-p".to_string(),
-            project_name,
-            "-f".to_string(),
-            self.config.compose_file.clone(),
-        ];
+        let mut args = vec!["-p".to_string(), project_name];
+        self.push_compose_files(&mut args);
 
         // Add override file if it exists
         if override_file.exists() {
@@ -310,13 +383,13 @@ impl<'a> ContainerManager<'a> {
 
         // Get list of all docker-compose projects by listing containers
         // and extracting their project labels
-        let output = Command::new("docker")
+        let output = Command::new(self.engine.runtime())
             .arg("ps")
             .arg("-a")
             .arg("--filter")
-            .arg("label=com.docker.compose.project")
+            .arg(format!("label={}", PROJECT_LABEL))
             .arg("--format")
-            .arg("{{.Label \"com.docker.compose.project\"}}")
+            .arg("{{.Labels}}")
             .output()?;
 
         if !output.status.success() {
@@ -328,7 +401,7 @@ impl<'a> ContainerManager<'a> {
         let projects_output = String::from_utf8_lossy(&output.stdout);
         let mut projects: std::collections::HashSet<String> = projects_output
             .lines()
-            .filter(|line| !line.is_empty())
+            .filter_map(|labels| engine::label_value(labels, PROJECT_LABEL))
             .map(|s| s.to_string())
             .collect();
 
@@ -338,8 +411,10 @@ impl<'a> ContainerManager<'a> {
             .map(|wt| self.get_project_name(wt))
             .collect();
 
-        // Find orphaned projects (those not in active worktrees)
-        projects.retain(|project| !active_projects.contains(project));
+        // Find orphaned projects (those not in active worktrees). The shared
+        // project belongs to the repository, not to a worktree.
+        let shared_project = self.shared_project_name();
+        projects.retain(|project| !active_projects.contains(project) && *project != shared_project);
 
         // Stop and remove each orphaned project
         for project in projects {
@@ -377,81 +452,95 @@ impl<'a> ContainerManager<'a> {
     /// Check if containers are running for a worktree
     /// Note: Currently not directly called but kept for potential future use
     #[allow(dead_code)]
-    fn check_containers_running(&self, worktree_name: &str, _worktree_path: &Path) -> bool {
-        let project_name = self.get_project_name(worktree_name);
-        let args = vec![
-            "-p".to_string(),
-            project_name,
-            "ps".to_string(),
-            "-q".to_string(),
-        ];
-        let (program, full_args) = self.get_compose_command(&args);
-
-        Command::new(program)
-            .args(full_args)
-            .output()
-            .map(|output| output.status.success() && !output.stdout.is_empty())
-            .unwrap_or(false)
+    fn check_containers_running(&self, worktree_name: &str, worktree_path: &Path) -> bool {
+        self.count_running_containers(worktree_name, worktree_path) > 0
     }
 
     /// Count the number of running containers for a worktree
     fn count_running_containers(&self, worktree_name: &str, _worktree_path: &Path) -> usize {
-        let project_name = self.get_project_name(worktree_name);
-        let args = vec![
-            "-p".to_string(),
-            project_name,
-            "ps".to_string(),
-            "-q".to_string(),
-        ];
-        let (program, full_args) = self.get_compose_command(&args);
+        self.count_project_containers(&self.get_project_name(worktree_name))
+    }
 
-        Command::new(program)
-            .args(full_args)
+    /// Count the number of running containers in a compose project
+    fn count_project_containers(&self, project_name: &str) -> usize {
+        self.project_containers(project_name).len()
+    }
+
+    /// Names of the running containers in a compose project
+    ///
+    /// Asks the engine rather than the compose front end, since
+    /// `podman-compose ps` doesn't take the same flags as `docker compose ps`.
+    pub fn project_containers(&self, project_name: &str) -> Vec<String> {
+        // Paused and restarting containers are listed too unless filtered out
+        let mut args = self.engine.project_ps_args(project_name, false);
+        args.push("--filter".to_string());
+        args.push("status=running".to_string());
+        args.push("--format".to_string());
+        args.push("{{.Names}}".to_string());
+
+        Command::new(self.engine.runtime())
+            .args(args)
             .output()
             .map(|output| {
                 if output.status.success() {
-                    // Count non-empty lines (each line is a container ID)
                     String::from_utf8_lossy(&output.stdout)
                         .lines()
-                        .filter(|line| !line.trim().is_empty())
-                        .count()
+                        .map(|line| line.trim())
+                        .filter(|line| !line.is_empty())
+                        .map(String::from)
+                        .collect()
                 } else {
-                    0
+                    Vec::new()
                 }
             })
-            .unwrap_or(0)
+            .unwrap_or_default()
+    }
+
+    /// Wait until a worktree's containers are up and its readiness probes
+    /// pass, naming the services that are holding things up on timeout
+    pub fn wait_until_ready(&self, worktree_name: &str) -> Result<()> {
+        let strategy = DockerStrategy::from_config(self.config)?;
+        if strategy == DockerStrategy::Shared {
+            // Nothing runs per worktree
+            return Ok(());
+        }
+
+        let healthcheck = &self.config.healthcheck;
+        let timeout = Duration::from_secs(self.parse_timeout(&healthcheck.timeout)?);
+        let interval = Duration::from_secs(self.parse_timeout(&healthcheck.interval)?);
+        self.wait_for_healthy(worktree_name, timeout, interval)?;
+
+        let probes = self.readiness_probes(worktree_name, strategy)?;
+        if !probes.is_empty() {
+            eprintln!("Waiting for services to become ready...");
+            health::wait_for_probes(&probes)?;
+        }
+        Ok(())
     }
 
-    /// Wait for containers to become healthy
-    fn wait_for_healthy(&self, worktree_name: &str) -> Result<()> {
-        let timeout = self.parse_timeout(&self.config.healthcheck.timeout)?;
+    /// Wait for the engine to report every container running, and healthy
+    /// if the compose file defines a healthcheck
+    fn wait_for_healthy(
+        &self,
+        worktree_name: &str,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<()> {
         let start = std::time::Instant::now();
         let project_name = self.get_project_name(worktree_name);
 
         eprintln!(
             "Waiting for containers to become healthy (timeout: {}s)...",
-            timeout
+            timeout.as_secs()
         );
 
         loop {
-            if start.elapsed().as_secs() > timeout {
-                return Err(HnError::DockerError(format!(
-                    "Health check timeout after {}s",
-                    timeout
-                )));
-            }
-
             // Check container health status
-            let args = vec![
-                "-p".to_string(),
-                project_name.clone(),
-                "ps".to_string(),
-                "--format".to_string(),
-                "{{.Service}},{{.Status}}".to_string(),
-            ];
-            let (program, full_args) = self.get_compose_command(&args);
+            let mut args = self.engine.project_ps_args(&project_name, true);
+            args.push("--format".to_string());
+            args.push("{{.Names}},{{.Status}}".to_string());
 
-            let output = Command::new(program).args(full_args).output()?;
+            let output = Command::new(self.engine.runtime()).args(args).output()?;
 
             if !output.status.success() {
                 return Err(HnError::DockerError(
@@ -460,7 +549,7 @@ impl<'a> ContainerManager<'a> {
             }
 
             let status_output = String::from_utf8_lossy(&output.stdout);
-            let mut all_healthy = true;
+            let mut blocking = Vec::new();
             let mut any_containers = false;
 
             for line in status_output.lines() {
@@ -468,13 +557,13 @@ impl<'a> ContainerManager<'a> {
                     continue;
                 }
                 any_containers = true;
-                let parts: Vec<&str> = line.split(',').collect();
-                if parts.len() >= 2 {
-                    let status = parts[1];
-                    // Check if container is running (not exited, not unhealthy)
-                    if status.contains("(unhealthy)") || status.contains("Exit") {
-                        all_healthy = false;
-                        break;
+                if let Some((name, status)) = line.split_once(',') {
+                    // Not ready while exited, unhealthy or still starting
+                    if status.contains("(unhealthy)")
+                        || status.contains("starting")
+                        || status.contains("Exit")
+                    {
+                        blocking.push(format!("{} ({})", name, status.trim()));
                     }
                 }
             }
@@ -485,35 +574,87 @@ impl<'a> ContainerManager<'a> {
                 ));
             }
 
-            if all_healthy {
+            if blocking.is_empty() {
                 eprintln!("✓ All containers are healthy");
                 return Ok(());
             }
 
+            if start.elapsed() + interval > timeout {
+                return Err(HnError::DockerError(format!(
+                    "Health check timeout after {}s, waiting for: {}",
+                    timeout.as_secs(),
+                    blocking.join(", ")
+                )));
+            }
+
             // Wait before next check
-            thread::sleep(Duration::from_secs(2));
+            thread::sleep(interval);
         }
     }
 
-    /// Parse timeout string (e.g., "30s", "1m") into seconds
-    pub fn parse_timeout(&self, timeout_str: &str) -> Result<u64> {
-        let timeout_str = timeout_str.trim();
-
-        if let Some(num_str) = timeout_str.strip_suffix('s') {
-            num_str.parse::<u64>().map_err(|_| {
-                HnError::ConfigError(format!("Invalid timeout value: {}", timeout_str))
+    /// The configured readiness probes of a worktree's own services, bound
+    /// to its allocated ports
+    fn readiness_probes(
+        &self,
+        worktree_name: &str,
+        strategy: DockerStrategy,
+    ) -> Result<Vec<ReadinessProbe>> {
+        let healthcheck = &self.config.healthcheck;
+        let mut services: Vec<&String> = healthcheck
+            .probes
+            .keys()
+            .filter(|service| !strategy.is_shared(self.config, service))
+            .collect();
+        services.sort();
+
+        let ports = PortAllocator::new(self.state_dir)?
+            .get_ports(worktree_name)
+            .unwrap_or_default();
+        let port_for = |service: &str, key: &Option<String>| {
+            let key = key.as_deref().unwrap_or(service);
+            ports.get(key).copied().ok_or_else(|| {
+                HnError::DockerError(format!(
+                    "Readiness probe for '{}': no port '{}' is allocated for '{}'",
+                    service, key, worktree_name
+                ))
             })
-        } else if let Some(num_str) = timeout_str.strip_suffix('m') {
-            let minutes = num_str.parse::<u64>().map_err(|_| {
-                HnError::ConfigError(format!("Invalid timeout value: {}", timeout_str))
-            })?;
-            Ok(minutes * 60)
-        } else {
-            // Default to seconds if no unit specified
-            timeout_str.parse::<u64>().map_err(|_| {
-                HnError::ConfigError(format!("Invalid timeout value: {}", timeout_str))
+        };
+
+        services
+            .into_iter()
+            .map(|service| {
+                let probe = &healthcheck.probes[service];
+                let target = match &probe.check {
+                    ProbeCheck::Tcp { port } => ProbeTarget::Tcp {
+                        port: port_for(service, port)?,
+                    },
+                    ProbeCheck::Http { path, status, port } => ProbeTarget::Http {
+                        port: port_for(service, port)?,
+                        path: path.clone(),
+                        status: *status,
+                    },
+                    ProbeCheck::Command { command } => ProbeTarget::Command {
+                        engine: self.engine,
+                        project: self.get_project_name(worktree_name),
+                        service: service.clone(),
+                        command: command.clone(),
+                    },
+                };
+                let timeout = probe.timeout.as_deref().unwrap_or(&healthcheck.timeout);
+                let interval = probe.interval.as_deref().unwrap_or(&healthcheck.interval);
+                Ok(ReadinessProbe {
+                    service: service.clone(),
+                    target,
+                    timeout: Duration::from_secs(self.parse_timeout(timeout)?),
+                    interval: Duration::from_secs(self.parse_timeout(interval)?),
+                })
             })
-        }
+            .collect()
+    }
+
+    /// Parse timeout string (e.g., "30s", "1m") into seconds
+    pub fn parse_timeout(&self, timeout_str: &str) -> Result<u64> {
+        health::parse_timeout(timeout_str)
     }
 
     /// Execute a command safely without shell injection
@@ -553,6 +694,31 @@ mod tests {
         assert_eq!(manager.get_project_name("my_feature"), "my-feature");
     }
 
+    #[test]
+    fn test_shared_start_command_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_dir = temp_dir.path().join("my-app").join(".hn-state");
+        let mut config = DockerConfig {
+            strategy: "hybrid".to_string(),
+            ..Default::default()
+        };
+        config.shared.services = vec!["postgres".to_string()];
+        let manager = ContainerManager::new(&config, &state_dir).unwrap();
+
+        assert_eq!(manager.shared_project_name(), "my-app-shared");
+        let args = manager
+            .build_shared_start_command_args(DockerStrategy::Hybrid)
+            .unwrap();
+        assert_eq!(&args[..2], &["-p".to_string(), "my-app-shared".to_string()]);
+        assert_eq!(args.last().unwrap(), "postgres");
+
+        // With `shared`, the whole compose file is brought up
+        let args = manager
+            .build_shared_start_command_args(DockerStrategy::Shared)
+            .unwrap();
+        assert_eq!(args.last().unwrap(), "-d");
+    }
+
     // ============================================================================
     // Unit Tests for Validation Functions
     // ============================================================================
@@ -776,26 +942,26 @@ mod tests {
     }
 
     // ============================================================================
-    // Unit Tests for Docker Compose Variant Detection
+    // Unit Tests for Container Engine Selection
     // ============================================================================
 
     #[test]
-    fn test_detect_compose_variant() {
-        // Test that variant detection doesn't crash
-        // Actual variant depends on system Docker installation
-        let result = ContainerManager::detect_compose_variant();
-
-        // Should return either Subcommand or Hyphenated
-        // We can't assert which one without knowing the system state,
-        // but we can verify the function executes
-        match result {
-            DockerComposeVariant::Subcommand => {
-                // Modern "docker compose" is available
-            }
-            DockerComposeVariant::Hyphenated => {
-                // Legacy "docker-compose" is available
-            }
-        }
+    fn test_configured_engine_is_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DockerConfig {
+            engine: "podman-compose".to_string(),
+            ..Default::default()
+        };
+        let manager = ContainerManager::new(&config, temp_dir.path()).unwrap();
+
+        assert_eq!(manager.engine(), ContainerEngine::PodmanCompose);
+        let (program, args) = manager.get_compose_command(&["ps".to_string()]);
+        assert_eq!(program, "podman-compose");
+        assert_eq!(args, vec!["ps"]);
+
+        // Detection depends on the system, but must not fail
+        let config = DockerConfig::default();
+        assert!(ContainerManager::new(&config, temp_dir.path()).is_ok());
     }
 
     #[test]
@@ -0,0 +1,239 @@
+// Container engines (docker.engine)
+//
+// hn drives containers through a compose front end and queries them through
+// the engine's own CLI. Docker, Podman and nerdctl share enough of the Docker
+// CLI (`ps --filter label=...`, Go templates in `--format`, `stats`) that only
+// the command names and a few output formats differ.
+
+use crate::config::DockerConfig;
+use crate::errors::{HnError, Result};
+use std::process::Command;
+
+/// Label compose front ends put on the containers of a project
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// Label compose front ends put on a container with its service name
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// A container engine and the compose front end used with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerEngine {
+    /// `docker compose`
+    Docker,
+    /// Legacy standalone `docker-compose`
+    DockerCompose,
+    /// `podman compose`
+    Podman,
+    /// Standalone `podman-compose`
+    PodmanCompose,
+    /// `nerdctl compose`
+    Nerdctl,
+}
+
+impl ContainerEngine {
+    /// Engines in the order auto-detection tries them
+    const ALL: [Self; 5] = [
+        Self::Docker,
+        Self::DockerCompose,
+        Self::Podman,
+        Self::PodmanCompose,
+        Self::Nerdctl,
+    ];
+
+    /// Parse a `docker.engine` value; `auto` means detect
+    pub fn parse(name: &str) -> Result<Option<Self>> {
+        if name == "auto" {
+            return Ok(None);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|engine| engine.config_name() == name)
+            .map(Some)
+            .ok_or_else(|| {
+                HnError::ConfigError(format!(
+                    "docker.engine: unknown engine '{}'. Use 'auto', 'docker', 'docker-compose', \
+                     'podman', 'podman-compose' or 'nerdctl'.",
+                    name
+                ))
+            })
+    }
+
+    /// The configured engine, or the first one found on this machine
+    pub fn from_config(config: &DockerConfig) -> Result<Self> {
+        Ok(Self::parse(&config.engine)?.unwrap_or_else(Self::detect))
+    }
+
+    /// First engine whose compose front end works, falling back to
+    /// `docker-compose` so errors name a familiar command
+    pub fn detect() -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|engine| engine.compose_available())
+            .unwrap_or(Self::DockerCompose)
+    }
+
+    /// Name of the engine in `docker.engine`
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::DockerCompose => "docker-compose",
+            Self::Podman => "podman",
+            Self::PodmanCompose => "podman-compose",
+            Self::Nerdctl => "nerdctl",
+        }
+    }
+
+    /// The engine's own CLI, used for `ps`, `stats` and `--version`
+    pub fn runtime(&self) -> &'static str {
+        match self {
+            Self::Docker | Self::DockerCompose => "docker",
+            Self::Podman | Self::PodmanCompose => "podman",
+            Self::Nerdctl => "nerdctl",
+        }
+    }
+
+    /// The compose command as users would type it
+    pub fn compose_name(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker compose",
+            Self::DockerCompose => "docker-compose",
+            Self::Podman => "podman compose",
+            Self::PodmanCompose => "podman-compose",
+            Self::Nerdctl => "nerdctl compose",
+        }
+    }
+
+    /// Program and arguments for a compose invocation
+    pub fn compose_command(&self, args: &[String]) -> (String, Vec<String>) {
+        match self {
+            Self::DockerCompose | Self::PodmanCompose => {
+                (self.compose_name().to_string(), args.to_vec())
+            }
+            Self::Docker | Self::Podman | Self::Nerdctl => {
+                let mut compose_args = vec!["compose".to_string()];
+                compose_args.extend_from_slice(args);
+                (self.runtime().to_string(), compose_args)
+            }
+        }
+    }
+
+    /// Whether the engine's CLI runs
+    pub fn is_available(&self) -> bool {
+        Command::new(self.runtime())
+            .arg("--version")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Whether the compose front end runs
+    fn compose_available(&self) -> bool {
+        let (program, args) = self.compose_command(&["version".to_string()]);
+        Command::new(program)
+            .args(args)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// `ps` arguments listing the containers of a compose project
+    pub fn project_ps_args(&self, project: &str, all: bool) -> Vec<String> {
+        let mut args = vec!["ps".to_string()];
+        if all {
+            args.push("-a".to_string());
+        }
+        args.push("--filter".to_string());
+        args.push(format!("label={}={}", PROJECT_LABEL, project));
+        args
+    }
+
+    /// Name of a running container of a compose project's service
+    pub fn service_container(&self, project: &str, service: &str) -> Option<String> {
+        let mut args = self.project_ps_args(project, false);
+        args.push("--filter".to_string());
+        args.push(format!("label={}={}", SERVICE_LABEL, service));
+        args.push("--format".to_string());
+        args.push("{{.Names}}".to_string());
+
+        let output = Command::new(self.runtime()).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(String::from)
+    }
+}
+
+/// Value of a label in the `{{.Labels}}` output of `ps`
+///
+/// Docker and nerdctl print `key=value,key=value`; Podman prints the Go map,
+/// `map[key:value key:value]`.
+pub fn label_value<'a>(labels: &'a str, key: &str) -> Option<&'a str> {
+    let labels = labels.trim();
+    if let Some(map) = labels
+        .strip_prefix("map[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return map
+            .split_whitespace()
+            .find_map(|pair| pair.strip_prefix(key)?.strip_prefix(':'));
+    }
+
+    labels
+        .split(',')
+        .find_map(|pair| pair.trim().strip_prefix(key)?.strip_prefix('='))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_engine() {
+        assert_eq!(ContainerEngine::parse("auto").unwrap(), None);
+        assert_eq!(
+            ContainerEngine::parse("podman-compose").unwrap(),
+            Some(ContainerEngine::PodmanCompose)
+        );
+        assert_eq!(
+            ContainerEngine::parse("nerdctl").unwrap(),
+            Some(ContainerEngine::Nerdctl)
+        );
+        assert!(ContainerEngine::parse("lxc").is_err());
+    }
+
+    #[test]
+    fn test_compose_command() {
+        let args = vec!["-p".to_string(), "feature-x".to_string(), "up".to_string()];
+
+        let (program, full) = ContainerEngine::Podman.compose_command(&args);
+        assert_eq!(program, "podman");
+        assert_eq!(full, vec!["compose", "-p", "feature-x", "up"]);
+
+        let (program, full) = ContainerEngine::PodmanCompose.compose_command(&args);
+        assert_eq!(program, "podman-compose");
+        assert_eq!(full, args);
+
+        let (program, full) = ContainerEngine::Nerdctl.compose_command(&args);
+        assert_eq!(program, "nerdctl");
+        assert_eq!(full[0], "compose");
+        assert_eq!(ContainerEngine::Nerdctl.runtime(), "nerdctl");
+    }
+
+    #[test]
+    fn test_label_value() {
+        // Docker / nerdctl
+        let docker = "com.docker.compose.service=app,com.docker.compose.project=feature-x";
+        assert_eq!(label_value(docker, PROJECT_LABEL), Some("feature-x"));
+
+        // Podman
+        let podman = "map[com.docker.compose.project:feature-x io.podman.compose.version:1.0.6]";
+        assert_eq!(label_value(podman, PROJECT_LABEL), Some("feature-x"));
+
+        assert_eq!(label_value("", PROJECT_LABEL), None);
+        assert_eq!(label_value("map[]", PROJECT_LABEL), None);
+    }
+}
@@ -0,0 +1,395 @@
+// Readiness probes (docker.healthcheck.probes)
+// Decide when a worktree's services are ready without relying on the
+// healthchecks of the compose file, which many services don't define
+
+use super::engine::ContainerEngine;
+use crate::config::{HealthCheckConfig, ProbeCheck};
+use crate::errors::{HnError, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Shortest time an attempt is given, so one near the deadline still runs
+const MIN_ATTEMPT: Duration = Duration::from_millis(200);
+
+/// Parse a duration such as "30s", "2m" or "45" (seconds) into seconds
+pub fn parse_timeout(timeout_str: &str) -> Result<u64> {
+    let timeout_str = timeout_str.trim();
+    let invalid = || HnError::ConfigError(format!("Invalid timeout value: {}", timeout_str));
+
+    if let Some(num_str) = timeout_str.strip_suffix('s') {
+        num_str.parse::<u64>().map_err(|_| invalid())
+    } else if let Some(num_str) = timeout_str.strip_suffix('m') {
+        let minutes = num_str.parse::<u64>().map_err(|_| invalid())?;
+        Ok(minutes * 60)
+    } else {
+        // Default to seconds if no unit specified
+        timeout_str.parse::<u64>().map_err(|_| invalid())
+    }
+}
+
+/// Check the durations and commands of `docker.healthcheck`
+pub fn validate(config: &HealthCheckConfig) -> Result<()> {
+    let field = |path: String, value: &str| {
+        parse_timeout(value).map_err(|_| {
+            HnError::ConfigError(format!(
+                "docker.healthcheck.{}: invalid duration '{}', e.g. '30s' or '2m'",
+                path, value
+            ))
+        })
+    };
+
+    field("timeout".to_string(), &config.timeout)?;
+    field("interval".to_string(), &config.interval)?;
+    for (service, probe) in &config.probes {
+        if let Some(ref timeout) = probe.timeout {
+            field(format!("probes.{}.timeout", service), timeout)?;
+        }
+        if let Some(ref interval) = probe.interval {
+            field(format!("probes.{}.interval", service), interval)?;
+        }
+        if let ProbeCheck::Command { command } = &probe.check {
+            if command.trim().is_empty() {
+                return Err(HnError::ConfigError(format!(
+                    "docker.healthcheck.probes.{}: command probe has an empty command",
+                    service
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What a probe checks, resolved for one worktree
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeTarget {
+    Tcp {
+        port: u16,
+    },
+    Http {
+        port: u16,
+        path: String,
+        status: u16,
+    },
+    Command {
+        engine: ContainerEngine,
+        project: String,
+        service: String,
+        command: String,
+    },
+}
+
+impl ProbeTarget {
+    /// Short description for progress and error messages
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Tcp { port } => format!("tcp :{}", port),
+            Self::Http { port, path, .. } => format!("GET :{}{}", port, path),
+            Self::Command { command, .. } => format!("`{}`", command),
+        }
+    }
+
+    /// Run the check once, giving up after `budget`
+    fn attempt(&self, budget: Duration) -> std::result::Result<(), String> {
+        match self {
+            Self::Tcp { port } => connect(*port, budget).map(|_| ()),
+            Self::Http { port, path, status } => {
+                let stream = connect(*port, budget)?;
+                let actual = http_status(stream, *port, path)?;
+                if actual == *status {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "GET {} returned {}, expected {}",
+                        path, actual, status
+                    ))
+                }
+            }
+            Self::Command {
+                engine,
+                project,
+                service,
+                command,
+            } => run_in_container(*engine, project, service, command, budget),
+        }
+    }
+}
+
+/// A service's readiness probe with its own deadline and retry interval
+#[derive(Debug, Clone)]
+pub struct ReadinessProbe {
+    pub service: String,
+    pub target: ProbeTarget,
+    pub timeout: Duration,
+    pub interval: Duration,
+}
+
+impl ReadinessProbe {
+    /// Retry until the check passes, returning how long it took, or the
+    /// last failure once the timeout runs out
+    pub fn wait(&self) -> std::result::Result<Duration, String> {
+        let start = Instant::now();
+        loop {
+            let remaining = self.timeout.saturating_sub(start.elapsed());
+            let error = match self.target.attempt(remaining.max(MIN_ATTEMPT)) {
+                Ok(()) => return Ok(start.elapsed()),
+                Err(error) => error,
+            };
+            if start.elapsed() + self.interval >= self.timeout {
+                return Err(error);
+            }
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+/// Run probes in parallel, reporting each service as it becomes ready;
+/// the error names every service that didn't
+pub fn wait_for_probes(probes: &[ReadinessProbe]) -> Result<()> {
+    let failures: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = probes
+            .iter()
+            .map(|probe| {
+                scope.spawn(move || match probe.wait() {
+                    Ok(elapsed) => {
+                        eprintln!(
+                            "  ✓ {} ready ({}, {:.1}s)",
+                            probe.service,
+                            probe.target.describe(),
+                            elapsed.as_secs_f64()
+                        );
+                        None
+                    }
+                    Err(error) => {
+                        eprintln!("  ✗ {} not ready: {}", probe.service, error);
+                        Some(format!(
+                            "'{}' ({}) after {}s: {}",
+                            probe.service,
+                            probe.target.describe(),
+                            probe.timeout.as_secs(),
+                            error
+                        ))
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap_or(None))
+            .collect()
+    });
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(HnError::DockerError(format!(
+            "Services not ready: {}",
+            failures.join("; ")
+        )))
+    }
+}
+
+fn connect(port: u16, budget: Duration) -> std::result::Result<TcpStream, String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let stream = TcpStream::connect_timeout(&addr, budget)
+        .map_err(|e| format!("connect to port {}: {}", port, e))?;
+    let _ = stream.set_read_timeout(Some(budget));
+    let _ = stream.set_write_timeout(Some(budget));
+    Ok(stream)
+}
+
+/// Send a GET and read the status code of the response
+fn http_status(mut stream: TcpStream, port: u16, path: &str) -> std::result::Result<u16, String> {
+    // One write, so servers answering early don't see a partial request
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost:{}\r\nUser-Agent: hn\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("GET {}: {}", path, e))?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut status_line)
+        .map_err(|e| format!("GET {}: {}", path, e))?;
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("GET {}: not an HTTP response", path))
+}
+
+/// Run `sh -c command` in the service's container through the engine CLI
+fn run_in_container(
+    engine: ContainerEngine,
+    project: &str,
+    service: &str,
+    command: &str,
+    budget: Duration,
+) -> std::result::Result<(), String> {
+    let container = engine
+        .service_container(project, service)
+        .ok_or_else(|| format!("no running container for service '{}'", service))?;
+
+    let mut child = Command::new(engine.runtime())
+        .args(["exec", &container, "sh", "-c", command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{} exec: {}", engine.runtime(), e))?;
+
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("`{}` exited with {}", command, status)),
+            Ok(None) if start.elapsed() >= budget => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("`{}` timed out", command));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("`{}`: {}", command, e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HealthProbeConfig;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn probe(target: ProbeTarget, timeout_ms: u64) -> ReadinessProbe {
+        ReadinessProbe {
+            service: "app".to_string(),
+            target,
+            timeout: Duration::from_millis(timeout_ms),
+            interval: Duration::from_millis(50),
+        }
+    }
+
+    /// Serve each connection with the next status, repeating the last one
+    fn http_server(statuses: Vec<u16>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let status = statuses[i.min(statuses.len() - 1)];
+                let _ = write!(stream, "HTTP/1.1 {} X\r\nContent-Length: 0\r\n\r\n", status);
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("30s").unwrap(), 30);
+        assert_eq!(parse_timeout("2m").unwrap(), 120);
+        assert_eq!(parse_timeout("45").unwrap(), 45);
+        assert!(parse_timeout("soon").is_err());
+    }
+
+    #[test]
+    fn test_tcp_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(probe(ProbeTarget::Tcp { port }, 500).wait().is_ok());
+
+        drop(listener);
+        let error = probe(ProbeTarget::Tcp { port }, 200).wait().unwrap_err();
+        assert!(error.contains(&port.to_string()), "{}", error);
+    }
+
+    #[test]
+    fn test_http_probe_retries_until_expected_status() {
+        let port = http_server(vec![503, 503, 204]);
+        let target = ProbeTarget::Http {
+            port,
+            path: "/health".to_string(),
+            status: 204,
+        };
+        assert!(probe(target, 2000).wait().is_ok());
+
+        let port = http_server(vec![500]);
+        let target = ProbeTarget::Http {
+            port,
+            path: "/health".to_string(),
+            status: 200,
+        };
+        let error = probe(target, 200).wait().unwrap_err();
+        assert_eq!(error, "GET /health returned 500, expected 200");
+    }
+
+    #[test]
+    fn test_wait_for_probes_names_blocking_service() {
+        let port = http_server(vec![200]);
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let mut db = probe(ProbeTarget::Tcp { port: closed }, 200);
+        db.service = "postgres".to_string();
+        let web = probe(
+            ProbeTarget::Http {
+                port,
+                path: "/".to_string(),
+                status: 200,
+            },
+            1000,
+        );
+
+        let error = wait_for_probes(&[web, db]).unwrap_err().to_string();
+        assert!(error.contains("'postgres'"), "{}", error);
+        assert!(!error.contains("'app'"), "{}", error);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = HealthCheckConfig::default();
+        assert!(validate(&config).is_ok());
+
+        config.probes.insert(
+            "worker".to_string(),
+            HealthProbeConfig {
+                check: ProbeCheck::Command {
+                    command: " ".to_string(),
+                },
+                timeout: None,
+                interval: None,
+            },
+        );
+        assert!(validate(&config).is_err());
+
+        config.probes.insert(
+            "worker".to_string(),
+            HealthProbeConfig {
+                check: ProbeCheck::Tcp { port: None },
+                timeout: Some("1h".to_string()),
+                interval: None,
+            },
+        );
+        let error = validate(&config).unwrap_err().to_string();
+        assert!(error.contains("probes.worker.timeout"), "{}", error);
+    }
+}
@@ -3,4 +3,8 @@
 
 pub mod compose;
 pub mod container;
+pub mod engine;
+pub mod health;
 pub mod ports;
+pub mod proxy;
+pub mod shared;
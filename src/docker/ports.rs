@@ -1,15 +1,44 @@
 // Port allocation system for Docker containers
 // Automatically assigns unique ports to each worktree
 
+use crate::config::PortsConfig;
 use crate::errors::{HnError, Result};
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::net::{SocketAddr, TcpListener};
 use std::path::{Path, PathBuf};
 
+/// Largest offset from a service's base port handed out by the hash strategy
+const HASH_WINDOW: u16 = 1000;
+
+/// How ports are picked for a new worktree (`docker.ports.strategy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortStrategy {
+    /// Lowest free port from the service's base port upwards
+    /// (`sequential`, or `auto-offset`, the old name and still the default)
+    Sequential,
+    /// Offset from the base port derived from the worktree name, so a
+    /// worktree gets the same ports back when it is recreated
+    Hash,
+}
+
+impl PortStrategy {
+    pub fn parse(strategy: &str) -> Result<Self> {
+        match strategy {
+            "sequential" | "auto-offset" => Ok(Self::Sequential),
+            "hash" => Ok(Self::Hash),
+            other => Err(HnError::ConfigError(format!(
+                "docker.ports.strategy: unknown strategy '{}'. Use 'sequential' or 'hash'.",
+                other
+            ))),
+        }
+    }
+}
+
 /// Port registry format persisted to disk
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct PortRegistry {
@@ -17,6 +46,151 @@ struct PortRegistry {
     allocations: HashMap<String, HashMap<String, u16>>,
     /// Next available port for each service type
     next_available: HashMap<String, u16>,
+    /// Map of worktree name -> service name -> base port the port was
+    /// allocated from, so a changed base port can be noticed
+    #[serde(default)]
+    bases: HashMap<String, HashMap<String, u16>>,
+}
+
+/// Owner of a port in the machine-wide registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobalPortOwner {
+    pub repo: PathBuf,
+    pub worktree: String,
+    pub service: String,
+}
+
+/// Machine-wide registry of the ports allocated by every repository
+/// (`docker.ports.global`), kept under the user data directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GlobalPortRegistry {
+    /// Map of port -> owner
+    pub ports: BTreeMap<u16, GlobalPortOwner>,
+}
+
+impl GlobalPortRegistry {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("hannahanna/port-registry.yaml"))
+    }
+
+    /// Load the registry with a shared lock, treating a missing file as empty
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = File::open(path)?;
+        FileExt::lock_shared(&file).map_err(|e| {
+            HnError::DockerError(format!(
+                "Failed to lock global port registry for reading: {}",
+                e
+            ))
+        })?;
+
+        let content = fs::read_to_string(path)?;
+        serde_yml::from_str(&content).map_err(|e| {
+            HnError::DockerError(format!("Failed to parse global port registry: {}", e))
+        })
+    }
+
+    /// Read, modify and write the registry under one exclusive lock, so
+    /// repositories updating it at the same time don't lose each other's
+    /// entries
+    ///
+    /// Nothing is written if `f` fails. Callers that also lock a
+    /// repository's own registry take this lock first.
+    fn update<T>(path: &Path, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        FileExt::lock_exclusive(&file).map_err(|e| {
+            HnError::DockerError(format!("Failed to lock global port registry: {}", e))
+        })?;
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let mut registry: Self = if content.trim().is_empty() {
+            Self::default()
+        } else {
+            serde_yml::from_str(&content).map_err(|e| {
+                HnError::DockerError(format!("Failed to parse global port registry: {}", e))
+            })?
+        };
+
+        let result = f(&mut registry)?;
+
+        let yaml = serde_yml::to_string(&registry).map_err(|e| {
+            HnError::DockerError(format!("Failed to serialize global port registry: {}", e))
+        })?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(yaml.as_bytes())?;
+        file.sync_all()?;
+        Ok(result)
+    }
+
+    /// Ports held by repositories other than `repo`
+    fn ports_of_others<'a>(&'a self, repo: &'a Path) -> impl Iterator<Item = u16> + 'a {
+        self.ports
+            .iter()
+            .filter(move |(_, owner)| owner.repo != repo)
+            .map(|(port, _)| *port)
+    }
+
+    /// Replace a repository's entries with its current allocations, leaving
+    /// ports another repository already holds with that repository
+    fn sync_repo(&mut self, repo: &Path, allocations: &HashMap<String, HashMap<String, u16>>) {
+        self.ports.retain(|_, owner| owner.repo != repo);
+        for (worktree, services) in allocations {
+            for (service, port) in services {
+                self.ports.entry(*port).or_insert_with(|| GlobalPortOwner {
+                    repo: repo.to_path_buf(),
+                    worktree: worktree.clone(),
+                    service: service.clone(),
+                });
+            }
+        }
+    }
+
+    /// Whether an entry's repository is gone or no longer holds the port,
+    /// e.g. because the repository or its `.hn-state` was deleted
+    pub fn is_stale(port: u16, owner: &GlobalPortOwner) -> bool {
+        if !owner.repo.is_dir() {
+            return true;
+        }
+        let local = PortAllocator::load_registry(&owner.repo.join(".hn-state")).unwrap_or_default();
+        local
+            .allocations
+            .get(&owner.worktree)
+            .and_then(|services| services.get(&owner.service))
+            != Some(&port)
+    }
+
+    /// Drop stale entries, returning what was reclaimed
+    pub fn reclaim_stale(path: &Path) -> Result<Vec<(u16, GlobalPortOwner)>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Self::update(path, |registry| {
+            let stale: Vec<(u16, GlobalPortOwner)> = registry
+                .ports
+                .iter()
+                .filter(|(port, owner)| Self::is_stale(**port, owner))
+                .map(|(port, owner)| (*port, owner.clone()))
+                .collect();
+            for (port, _) in &stale {
+                registry.ports.remove(port);
+            }
+            Ok(stale)
+        })
+    }
 }
 
 /// Manages port allocation for Docker services across worktrees
@@ -26,6 +200,9 @@ pub struct PortAllocator {
     base_ports: HashMap<String, u16>,
     port_range_start: u16,
     port_range_end: u16,
+    strategy: PortStrategy,
+    /// Machine-wide registry kept in sync with this one, if enabled
+    global_registry: Option<PathBuf>,
     /// Cached set of used ports for O(1) lookup
     used_ports: HashSet<u16>,
 }
@@ -53,10 +230,52 @@ impl PortAllocator {
             base_ports,
             port_range_start: 3000,
             port_range_end: 9999,
+            strategy: PortStrategy::Sequential,
+            global_registry: None,
             used_ports,
         })
     }
 
+    /// Create a port allocator using the strategy, base ports and range from
+    /// `docker.ports`
+    pub fn from_config(state_dir: &Path, config: &PortsConfig) -> Result<Self> {
+        let mut allocator = Self::new(state_dir)?;
+        allocator.strategy = PortStrategy::parse(&config.strategy)?;
+        allocator
+            .base_ports
+            .extend(config.base.iter().map(|(k, v)| (k.clone(), *v)));
+        if let Some([start, end]) = config.range {
+            allocator.port_range_start = start;
+            allocator.port_range_end = end;
+        }
+        if config.global {
+            let path = GlobalPortRegistry::default_path().ok_or_else(|| {
+                HnError::ConfigError(
+                    "Could not determine data directory for the global port registry".to_string(),
+                )
+            })?;
+            allocator.use_global_registry(&path)?;
+        }
+        Ok(allocator)
+    }
+
+    /// Avoid ports other repositories hold in the machine-wide registry at
+    /// `path`, and record this repository's allocations there
+    ///
+    /// The registry is read again on every allocation, so ports other
+    /// repositories took since are avoided too.
+    pub fn use_global_registry(&mut self, path: &Path) -> Result<()> {
+        GlobalPortRegistry::reclaim_stale(path)?;
+        self.global_registry = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Repository this registry belongs to, as recorded in the global registry
+    fn repo_key(&self) -> PathBuf {
+        let repo = self.state_dir.parent().unwrap_or(&self.state_dir);
+        fs::canonicalize(repo).unwrap_or_else(|_| repo.to_path_buf())
+    }
+
     /// Create a port allocator with custom port range
     /// Used primarily for testing port exhaustion scenarios
     #[allow(dead_code)] // Used in integration tests
@@ -69,22 +288,123 @@ impl PortAllocator {
 
     /// Allocate ports for a worktree's services
     /// Uses transaction-like semantics: all services get ports or none do
+    #[allow(dead_code)] // hn reconciles instead; used in tests
     pub fn allocate(
         &mut self,
         worktree_name: &str,
         services: &[&str],
     ) -> Result<HashMap<String, u16>> {
-        // Check if already allocated
-        if let Some(existing) = self.registry.allocations.get(worktree_name) {
-            return Ok(existing.clone());
-        }
+        self.update(|allocator| {
+            // Check if already allocated
+            if let Some(existing) = allocator.registry.allocations.get(worktree_name) {
+                return Ok(existing.clone());
+            }
+
+            let allocated_ports = allocator.allocate_services(worktree_name, services)?;
+            allocator.record(worktree_name, &allocated_ports);
+            Ok(allocated_ports)
+        })
+    }
 
+    /// Bring a worktree's allocation in line with the services it has now:
+    /// new services get ports, services that went away give theirs back and
+    /// services whose base port changed are moved; the rest keep their ports
+    pub fn reconcile(
+        &mut self,
+        worktree_name: &str,
+        services: &[&str],
+    ) -> Result<HashMap<String, u16>> {
+        self.update(|allocator| {
+            let mut ports = allocator
+                .registry
+                .allocations
+                .get(worktree_name)
+                .cloned()
+                .unwrap_or_default();
+            let bases = allocator
+                .registry
+                .bases
+                .get(worktree_name)
+                .cloned()
+                .unwrap_or_default();
+
+            // Allocations from before bases were recorded are kept as they are
+            let outdated: Vec<String> = ports
+                .keys()
+                .filter(|service| {
+                    !services.contains(&service.as_str())
+                        || bases
+                            .get(*service)
+                            .is_some_and(|base| *base != allocator.base_port(service))
+                })
+                .cloned()
+                .collect();
+            for service in &outdated {
+                if let Some(port) = ports.remove(service) {
+                    allocator.used_ports.remove(&port);
+                }
+            }
+
+            let missing: Vec<&str> = services
+                .iter()
+                .copied()
+                .filter(|service| !ports.contains_key(*service))
+                .collect();
+            ports.extend(allocator.allocate_services(worktree_name, &missing)?);
+
+            allocator.record(worktree_name, &ports);
+            Ok(ports)
+        })
+    }
+
+    /// Give a worktree new ports for the services it has, each different
+    /// from its current one
+    ///
+    /// The old ports stay claimed while the new ones are picked, so the hash
+    /// strategy probes past the worktree's slot instead of handing it back.
+    pub fn reassign(&mut self, worktree_name: &str) -> Result<HashMap<String, u16>> {
+        self.update(|allocator| {
+            let current = allocator.get_ports(worktree_name)?;
+            let services: Vec<&str> = current.keys().map(String::as_str).collect();
+            let new_ports = allocator.allocate_services(worktree_name, &services)?;
+
+            for port in current.values() {
+                allocator.used_ports.remove(port);
+            }
+            allocator.record(worktree_name, &new_ports);
+            Ok(new_ports)
+        })
+    }
+
+    /// Store a worktree's ports along with the base ports they came from
+    fn record(&mut self, worktree_name: &str, ports: &HashMap<String, u16>) {
+        let bases = ports
+            .keys()
+            .map(|service| (service.clone(), self.base_port(service)))
+            .collect();
+        self.registry
+            .allocations
+            .insert(worktree_name.to_string(), ports.clone());
+        self.registry.bases.insert(worktree_name.to_string(), bases);
+    }
+
+    /// Port a service's allocations start from
+    fn base_port(&self, service: &str) -> u16 {
+        self.base_ports.get(service).copied().unwrap_or(3000)
+    }
+
+    /// Pick a port for each service, releasing the ones already picked if
+    /// any service can't get one
+    fn allocate_services(
+        &mut self,
+        worktree_name: &str,
+        services: &[&str],
+    ) -> Result<HashMap<String, u16>> {
         let mut allocated_ports = HashMap::new();
         let mut temp_used_ports = Vec::new();
 
-        // Allocate all ports first (transaction phase)
         for service in services {
-            match self.allocate_port_for_service(service) {
+            match self.allocate_port_for_service(worktree_name, service) {
                 Ok(port) => {
                     allocated_ports.insert(service.to_string(), port);
                     temp_used_ports.push(port);
@@ -99,14 +419,6 @@ impl PortAllocator {
             }
         }
 
-        // All allocations successful - commit the transaction
-        self.registry
-            .allocations
-            .insert(worktree_name.to_string(), allocated_ports.clone());
-
-        // Auto-save after allocation
-        self.save()?;
-
         Ok(allocated_ports)
     }
 
@@ -123,14 +435,32 @@ impl PortAllocator {
 
     /// Release ports when a worktree is removed
     pub fn release(&mut self, worktree_name: &str) -> Result<()> {
-        if let Some(ports) = self.registry.allocations.remove(worktree_name) {
-            // Remove ports from used_ports cache
-            for port in ports.values() {
-                self.used_ports.remove(port);
+        self.update(|allocator| {
+            if let Some(ports) = allocator.registry.allocations.remove(worktree_name) {
+                // Remove ports from used_ports cache
+                for port in ports.values() {
+                    allocator.used_ports.remove(port);
+                }
             }
-            self.save()?;
-        }
-        Ok(())
+            allocator.registry.bases.remove(worktree_name);
+            Ok(())
+        })
+    }
+
+    /// Move a worktree's ports over to its new name
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        self.update(|allocator| {
+            if let Some(ports) = allocator.registry.allocations.remove(old_name) {
+                allocator
+                    .registry
+                    .allocations
+                    .insert(new_name.to_string(), ports);
+            }
+            if let Some(bases) = allocator.registry.bases.remove(old_name) {
+                allocator.registry.bases.insert(new_name.to_string(), bases);
+            }
+            Ok(())
+        })
     }
 
     /// List all port allocations
@@ -143,14 +473,44 @@ impl PortAllocator {
     }
 
     /// Save registry to disk with file locking to prevent concurrent corruption
-    pub fn save(&self) -> Result<()> {
+    #[allow(dead_code)] // Allocations save themselves; used in tests
+    pub fn save(&mut self) -> Result<()> {
+        self.update(|_| Ok(()))
+    }
+
+    /// Read, modify and write the registry under one exclusive lock, so hn
+    /// processes allocating at the same time don't lose each other's
+    /// entries
+    ///
+    /// With a global registry, that one is locked and read first, and `f`
+    /// runs with the ports other repositories hold there marked as used.
+    /// Nothing is written if `f` fails.
+    fn update<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let Some(global) = self.global_registry.clone() else {
+            return self.update_local(f);
+        };
+
+        let repo = self.repo_key();
+        GlobalPortRegistry::update(&global, |registry| {
+            let result = self.update_local(|allocator| {
+                allocator.used_ports.extend(registry.ports_of_others(&repo));
+                f(allocator)
+            })?;
+            registry.sync_repo(&repo, &self.registry.allocations);
+            Ok(result)
+        })
+    }
+
+    /// `update` for this repository's own registry
+    fn update_local<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
         let registry_path = self.state_dir.join("port-registry.yaml");
 
         // Ensure directory exists
         fs::create_dir_all(&self.state_dir)?;
 
         // Open file WITHOUT truncate first (we'll truncate after acquiring lock)
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
+            .read(true)
             .write(true)
             .create(true)
             .truncate(false)
@@ -160,19 +520,32 @@ impl PortAllocator {
         FileExt::lock_exclusive(&file)
             .map_err(|e| HnError::DockerError(format!("Failed to lock registry file: {}", e)))?;
 
-        // Now that we have the lock, truncate the file
-        file.set_len(0)?;
+        // Pick up what others wrote since this allocator was created
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        if !content.trim().is_empty() {
+            self.registry = serde_yml::from_str(&content)
+                .map_err(|e| HnError::DockerError(format!("Failed to parse registry: {}", e)))?;
+            self.used_ports.extend(
+                self.registry
+                    .allocations
+                    .values()
+                    .flat_map(|services| services.values().copied()),
+            );
+        }
+
+        let result = f(self)?;
 
         let yaml = serde_yml::to_string(&self.registry)
             .map_err(|e| HnError::DockerError(format!("Failed to serialize registry: {}", e)))?;
 
-        // Write to file
-        let mut file_mut = file;
-        file_mut.write_all(yaml.as_bytes())?;
-        file_mut.sync_all()?;
+        // Now that we have the lock, truncate the file and write
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(yaml.as_bytes())?;
+        file.sync_all()?;
 
-        // Lock is automatically released when file goes out of scope
-        Ok(())
+        Ok(result)
     }
 
     /// Load registry from disk with file locking to prevent reading during writes
@@ -215,10 +588,77 @@ impl PortAllocator {
         ipv4_available || ipv6_available
     }
 
+    /// Allocate a port for a service type using the configured strategy
+    fn allocate_port_for_service(&mut self, worktree_name: &str, service: &str) -> Result<u16> {
+        match self.strategy {
+            PortStrategy::Sequential => self.allocate_sequential(service),
+            PortStrategy::Hash => self.allocate_hashed(worktree_name, service),
+        }
+    }
+
+    /// Check a candidate port and claim it if it's free in the registry and
+    /// on the system
+    fn try_claim(&mut self, port: u16) -> bool {
+        // O(1) lookup using HashSet instead of O(n) iteration
+        if self.used_ports.contains(&port) {
+            return false;
+        }
+        if !self.is_port_available_on_system(port) {
+            eprintln!(
+                "Warning: Port {} is in use by another process, trying next port",
+                port
+            );
+            return false;
+        }
+        self.used_ports.insert(port);
+        true
+    }
+
+    /// Offset from the base port for a worktree, stable across runs and
+    /// platforms: 1..=window, so the base port itself stays free for the
+    /// main checkout
+    fn hash_offset(worktree_name: &str, window: u16) -> u16 {
+        let digest = Sha256::digest(worktree_name.as_bytes());
+        let value = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"));
+        (value % window as u64) as u16 + 1
+    }
+
+    /// Allocate the worktree's hashed port for a service, probing the
+    /// following ports (wrapping around the window) on collisions
+    fn allocate_hashed(&mut self, worktree_name: &str, service: &str) -> Result<u16> {
+        let base_port = self.base_port(service);
+        let window = self
+            .port_range_end
+            .saturating_sub(base_port)
+            .min(HASH_WINDOW);
+        if window == 0 {
+            return Err(HnError::PortAllocationError(format!(
+                "Base port {} of service '{}' leaves no room in range {}-{}",
+                base_port, service, self.port_range_start, self.port_range_end
+            )));
+        }
+
+        let offset = Self::hash_offset(worktree_name, window);
+        for probe in 0..window {
+            // Offsets run 1..=window
+            let port = base_port + (offset - 1 + probe) % window + 1;
+            if self.try_claim(port) {
+                return Ok(port);
+            }
+        }
+
+        Err(HnError::PortAllocationError(format!(
+            "Port exhausted for service '{}': no available ports in {}-{}",
+            service,
+            base_port + 1,
+            base_port + window
+        )))
+    }
+
     /// Allocate next available port for a service type
-    fn allocate_port_for_service(&mut self, service: &str) -> Result<u16> {
+    fn allocate_sequential(&mut self, service: &str) -> Result<u16> {
         // Get base port for this service
-        let base_port = self.base_ports.get(service).copied().unwrap_or(3000);
+        let base_port = self.base_port(service);
 
         // Always start from base port to fill gaps (released ports)
         // The HashSet lookup is O(1) so this is still efficient
@@ -234,23 +674,12 @@ impl PortAllocator {
                 )));
             }
 
-            // O(1) lookup using HashSet instead of O(n) iteration
-            if !self.used_ports.contains(&port) {
-                // Check if port is actually available on the system
-                if self.is_port_available_on_system(port) {
-                    // Found an available port - add to cache and update next_available
-                    self.used_ports.insert(port);
-                    self.registry
-                        .next_available
-                        .insert(service.to_string(), port + 1);
-                    return Ok(port);
-                } else {
-                    // Port is in use by another process, skip it
-                    eprintln!(
-                        "Warning: Port {} is in use by another process, trying next port",
-                        port
-                    );
-                }
+            if self.try_claim(port) {
+                // Found an available port - update next_available
+                self.registry
+                    .next_available
+                    .insert(service.to_string(), port + 1);
+                return Ok(port);
             }
 
             port += 1;
@@ -291,16 +720,264 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_keeps_ports() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut allocator = PortAllocator::new(temp_dir.path()).unwrap();
+        let ports = allocator.allocate("old", &["app"]).unwrap();
+
+        allocator.rename("old", "new").unwrap();
+
+        let loaded = PortAllocator::new(temp_dir.path()).unwrap();
+        assert!(loaded.get_ports("old").is_err());
+        assert_eq!(loaded.get_ports("new").unwrap(), ports);
+    }
+
+    fn hash_config() -> PortsConfig {
+        PortsConfig {
+            strategy: "hash".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_port_strategy_parse() {
+        assert_eq!(
+            PortStrategy::parse("auto-offset").unwrap(),
+            PortStrategy::Sequential
+        );
+        assert_eq!(
+            PortStrategy::parse("sequential").unwrap(),
+            PortStrategy::Sequential
+        );
+        assert_eq!(PortStrategy::parse("hash").unwrap(), PortStrategy::Hash);
+        assert!(PortStrategy::parse("random").is_err());
+    }
+
+    #[test]
+    fn test_hash_allocation_is_stable() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut allocator = PortAllocator::from_config(temp_dir.path(), &hash_config()).unwrap();
+
+        let first = allocator
+            .allocate("feature-x", &["app", "postgres"])
+            .unwrap();
+        assert!((3001..=3000 + HASH_WINDOW).contains(&first["app"]));
+        assert!((5433..=5432 + HASH_WINDOW).contains(&first["postgres"]));
+
+        // Recreating the worktree gets the same ports back
+        allocator.release("feature-x").unwrap();
+        let mut allocator = PortAllocator::from_config(temp_dir.path(), &hash_config()).unwrap();
+        let again = allocator
+            .allocate("feature-x", &["app", "postgres"])
+            .unwrap();
+        assert_eq!(again, first);
+
+        // ...and so does the same worktree in another registry
+        let other_dir = TempDir::new().unwrap();
+        let mut allocator = PortAllocator::from_config(other_dir.path(), &hash_config()).unwrap();
+        assert_eq!(
+            allocator
+                .allocate("feature-x", &["app", "postgres"])
+                .unwrap(),
+            first
+        );
+    }
+
+    #[test]
+    fn test_hash_allocation_probes_on_collision() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = PortsConfig {
+            strategy: "hash".to_string(),
+            range: Some([3000, 3003]),
+            ..Default::default()
+        };
+        let mut allocator = PortAllocator::from_config(temp_dir.path(), &config).unwrap();
+
+        let mut ports: Vec<u16> = ["a", "b", "c"]
+            .iter()
+            .filter_map(|wt| allocator.allocate(wt, &["app"]).ok())
+            .map(|p| p["app"])
+            .collect();
+        ports.sort();
+        ports.dedup();
+        assert!(ports.iter().all(|p| (3001..=3003).contains(p)));
+        assert_eq!(ports.len(), allocator.list_all().len());
+
+        // The window is full (or blocked by the system), so nothing is left
+        assert!(allocator.allocate("d", &["app"]).is_err());
+        assert!(allocator.get_ports("d").is_err());
+    }
+
+    #[test]
+    fn test_reconcile_follows_services_and_base_ports() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut allocator = PortAllocator::new(temp_dir.path()).unwrap();
+        let before = allocator.allocate("wt", &["app", "redis"]).unwrap();
+
+        // Unchanged services keep their ports
+        assert_eq!(
+            allocator.reconcile("wt", &["app", "redis"]).unwrap(),
+            before
+        );
+
+        // A new service gets a port and a removed one gives its port back
+        let ports = allocator.reconcile("wt", &["app", "postgres"]).unwrap();
+        assert_eq!(ports["app"], before["app"]);
+        assert!(ports.contains_key("postgres"));
+        assert!(!ports.contains_key("redis"));
+
+        // A changed base port moves the service
+        let config = PortsConfig {
+            base: HashMap::from([("app".to_string(), 4000)]),
+            ..Default::default()
+        };
+        let mut allocator = PortAllocator::from_config(temp_dir.path(), &config).unwrap();
+        let moved = allocator.reconcile("wt", &["app", "postgres"]).unwrap();
+        assert!(moved["app"] >= 4000);
+        assert_eq!(moved["postgres"], ports["postgres"]);
+        assert_eq!(
+            PortAllocator::new(temp_dir.path())
+                .unwrap()
+                .get_ports("wt")
+                .unwrap(),
+            moved
+        );
+    }
+
+    #[test]
+    fn test_reassign_moves_hash_ports_off_current_slot() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut allocator = PortAllocator::from_config(temp_dir.path(), &hash_config()).unwrap();
+        let before = allocator
+            .allocate("feature-x", &["app", "postgres"])
+            .unwrap();
+
+        let after = allocator.reassign("feature-x").unwrap();
+        assert_ne!(after["app"], before["app"]);
+        assert_ne!(after["postgres"], before["postgres"]);
+
+        let loaded = PortAllocator::new(temp_dir.path()).unwrap();
+        assert_eq!(loaded.get_ports("feature-x").unwrap(), after);
+        assert!(allocator.reassign("missing").is_err());
+    }
+
+    #[test]
+    fn test_global_registry_is_shared_between_repos() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = temp_dir.path().join("global/port-registry.yaml");
+        let repo_a = temp_dir.path().join("a/.hn-state");
+        let repo_b = temp_dir.path().join("b/.hn-state");
+        fs::create_dir_all(&repo_a).unwrap();
+        fs::create_dir_all(&repo_b).unwrap();
+
+        let mut allocator = PortAllocator::new(&repo_a).unwrap();
+        allocator.use_global_registry(&global).unwrap();
+        let a = allocator.allocate("feature", &["app"]).unwrap()["app"];
+
+        let mut allocator = PortAllocator::new(&repo_b).unwrap();
+        allocator.use_global_registry(&global).unwrap();
+        let b = allocator.allocate("feature", &["app"]).unwrap()["app"];
+        assert_ne!(a, b);
+
+        let registry = GlobalPortRegistry::load(&global).unwrap();
+        let owner = &registry.ports[&b];
+        assert_eq!(
+            owner.repo,
+            fs::canonicalize(temp_dir.path().join("b")).unwrap()
+        );
+        assert_eq!(owner.worktree, "feature");
+
+        // Releasing removes the entry
+        allocator.release("feature").unwrap();
+        assert!(!GlobalPortRegistry::load(&global)
+            .unwrap()
+            .ports
+            .contains_key(&b));
+    }
+
+    #[test]
+    fn test_global_registry_is_reread_on_allocation() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = temp_dir.path().join("global/port-registry.yaml");
+        let repo_a = temp_dir.path().join("a/.hn-state");
+        let repo_b = temp_dir.path().join("b/.hn-state");
+        fs::create_dir_all(&repo_a).unwrap();
+        fs::create_dir_all(&repo_b).unwrap();
+
+        // Both repositories are set up before either allocates
+        let mut a = PortAllocator::new(&repo_a).unwrap();
+        a.use_global_registry(&global).unwrap();
+        let mut b = PortAllocator::new(&repo_b).unwrap();
+        b.use_global_registry(&global).unwrap();
+
+        let port_a = a.allocate("feature", &["app"]).unwrap()["app"];
+        let port_b = b.allocate("feature", &["app"]).unwrap()["app"];
+        assert_ne!(port_a, port_b);
+
+        let registry = GlobalPortRegistry::load(&global).unwrap();
+        assert_eq!(registry.ports.len(), 2);
+        assert_eq!(
+            registry.ports[&port_a].repo,
+            fs::canonicalize(temp_dir.path().join("a")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_global_registry_keeps_other_repos_owners() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = temp_dir.path().join("global/port-registry.yaml");
+        let repo_a = temp_dir.path().join("a/.hn-state");
+        let repo_b = temp_dir.path().join("b/.hn-state");
+        fs::create_dir_all(&repo_a).unwrap();
+        fs::create_dir_all(&repo_b).unwrap();
+
+        let mut a = PortAllocator::new(&repo_a).unwrap();
+        a.use_global_registry(&global).unwrap();
+        let port = a.allocate("feature", &["app"]).unwrap()["app"];
+
+        // B picked the same port before it used the global registry
+        let mut b = PortAllocator::new(&repo_b).unwrap();
+        b.allocate("old", &["app"]).unwrap();
+        assert_eq!(b.get_ports("old").unwrap()["app"], port);
+        b.use_global_registry(&global).unwrap();
+        b.allocate("new", &["app"]).unwrap();
+
+        let registry = GlobalPortRegistry::load(&global).unwrap();
+        assert_eq!(
+            registry.ports[&port].repo,
+            fs::canonicalize(temp_dir.path().join("a")).unwrap()
+        );
+        assert_eq!(registry.ports[&port].worktree, "feature");
+    }
+
+    #[test]
+    fn test_global_registry_reclaims_deleted_repos() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = temp_dir.path().join("global/port-registry.yaml");
+        let repo = temp_dir.path().join("gone");
+        fs::create_dir_all(repo.join(".hn-state")).unwrap();
+
+        let mut allocator = PortAllocator::new(&repo.join(".hn-state")).unwrap();
+        allocator.use_global_registry(&global).unwrap();
+        let port = allocator.allocate("feature", &["app"]).unwrap()["app"];
+        fs::remove_dir_all(&repo).unwrap();
+
+        let reclaimed = GlobalPortRegistry::reclaim_stale(&global).unwrap();
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].0, port);
+        assert!(GlobalPortRegistry::load(&global).unwrap().ports.is_empty());
+    }
+
     // ============================================================================
     // File Locking Tests for Concurrent Access
     // ============================================================================
 
     #[test]
     fn test_concurrent_port_allocation() {
-        // Test that multiple threads allocating ports concurrently don't corrupt the registry
-        // Note: Due to how PortAllocator works (each instance loads from disk), concurrent
-        // allocations may overwrite each other. The file locking prevents corruption, not
-        // conflicts. In real usage, the caller would serialize worktree operations.
+        // Test that multiple threads allocating ports concurrently neither corrupt the
+        // registry nor lose each other's entries: each allocation re-reads the registry
+        // under the write lock
         let temp_dir = TempDir::new().unwrap();
         let state_dir = Arc::new(temp_dir.path().to_path_buf());
 
@@ -338,12 +1015,13 @@ mod tests {
         let final_allocator = PortAllocator::new(&state_dir).unwrap();
         let allocations = final_allocator.list_all();
 
-        // At least one allocation should be preserved
-        // (The exact number depends on timing - last write wins)
-        assert!(
-            !allocations.is_empty(),
-            "Registry should have at least one allocation"
-        );
+        // Every allocation is kept, each on its own port
+        assert_eq!(allocations.len(), 5, "No allocation should be lost");
+        let ports: HashSet<u16> = allocations
+            .iter()
+            .flat_map(|(_, services)| services.values().copied())
+            .collect();
+        assert_eq!(ports.len(), 5, "Allocations should not share ports");
 
         // Verify the registry file is valid YAML (no corruption)
         let registry_path = state_dir.join("port-registry.yaml");
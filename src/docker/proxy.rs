@@ -0,0 +1,295 @@
+// Local reverse proxy (hn proxy)
+// Routes <worktree>.<domain> to the worktree's allocated port, so each
+// worktree gets a stable URL however its ports are assigned
+
+use super::container::sanitize_project_name;
+use super::ports::PortAllocator;
+use crate::config::ProxyConfig;
+use crate::errors::{HnError, Result};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Largest request head accepted before the Host header must have arrived
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// A hostname and the port it is proxied to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub host: String,
+    pub worktree: String,
+    pub port: u16,
+}
+
+/// Hostname of a worktree under the proxy domain
+pub fn host_for(worktree: &str, domain: &str) -> String {
+    format!("{}.{}", sanitize_project_name(worktree), domain)
+}
+
+/// Check `docker.proxy.domain` is a usable hostname suffix
+pub fn validate_domain(domain: &str) -> Result<()> {
+    let valid = !domain.is_empty()
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(HnError::ConfigError(format!(
+            "docker.proxy.domain: '{}' is not a valid domain, e.g. 'localhost' or 'dev.test'",
+            domain
+        )))
+    }
+}
+
+/// Routes for every worktree with a port allocated for the proxied service,
+/// sorted by hostname
+pub fn routes(state_dir: &Path, config: &ProxyConfig) -> Result<Vec<Route>> {
+    let allocator = PortAllocator::new(state_dir)?;
+    let mut routes: Vec<Route> = allocator
+        .list_all()
+        .into_iter()
+        .filter_map(|(worktree, ports)| {
+            let port = *ports.get(&config.service)?;
+            Some(Route {
+                host: host_for(&worktree, &config.domain),
+                worktree,
+                port,
+            })
+        })
+        .collect();
+    routes.sort_by(|a, b| a.host.cmp(&b.host));
+    Ok(routes)
+}
+
+/// HTTP reverse proxy forwarding each connection to the route of its Host
+///
+/// Routes are read from the port registry for every new connection, so
+/// allocations made or changed while the proxy runs are picked up.
+pub struct ReverseProxy {
+    state_dir: PathBuf,
+    config: ProxyConfig,
+}
+
+impl ReverseProxy {
+    pub fn new(state_dir: &Path, config: &ProxyConfig) -> Self {
+        Self {
+            state_dir: state_dir.to_path_buf(),
+            config: config.clone(),
+        }
+    }
+
+    /// Accept connections until the listener fails, one thread each
+    pub fn serve(&self, listener: TcpListener) -> Result<()> {
+        for client in listener.incoming() {
+            let client = client?;
+            let state_dir = self.state_dir.clone();
+            let config = self.config.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(&state_dir, &config, client) {
+                    eprintln!("proxy: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+fn handle_connection(state_dir: &Path, config: &ProxyConfig, mut client: TcpStream) -> Result<()> {
+    let Some((head, body_start)) = read_head(&mut client)? else {
+        return Ok(());
+    };
+
+    let host = match host_header(&head) {
+        Some(host) => host,
+        None => {
+            return respond(
+                &mut client,
+                "400 Bad Request",
+                "hn proxy: request has no Host header\n",
+            )
+        }
+    };
+
+    let routes = routes(state_dir, config)?;
+    let Some(route) = routes.iter().find(|route| route.host == host) else {
+        let mut body = format!("hn proxy: no worktree is routed at {}\n", host);
+        if !routes.is_empty() {
+            body.push_str("\nKnown hosts:\n");
+            for route in &routes {
+                body.push_str(&format!("  {}\n", route.host));
+            }
+        }
+        return respond(&mut client, "404 Not Found", &body);
+    };
+
+    let mut upstream = match TcpStream::connect(("127.0.0.1", route.port)) {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let body = format!(
+                "hn proxy: {} ('{}' on port {}) is not reachable: {}\n",
+                route.worktree, config.service, route.port, e
+            );
+            return respond(&mut client, "502 Bad Gateway", &body);
+        }
+    };
+
+    let peer = client
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default();
+    upstream.write_all(forwarded_head(&head, &host, &peer).as_bytes())?;
+    upstream.write_all(&body_start)?;
+
+    // Pipe both directions until either side closes
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let uploader = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    let _ = io::copy(&mut upstream, &mut client);
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = uploader.join();
+    Ok(())
+}
+
+/// Read up to the end of the request head, returning it and whatever part
+/// of the body arrived with it; `None` if the client closed first
+fn read_head(client: &mut TcpStream) -> Result<Option<(String, Vec<u8>)>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = client.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let body = buffer.split_off(end + 4);
+            return Ok(Some((String::from_utf8_lossy(&buffer).into_owned(), body)));
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err(HnError::DockerError(
+                "proxy: request head too large".to_string(),
+            ));
+        }
+    }
+}
+
+/// Lowercased Host header of a request head, without its port
+fn host_header(head: &str) -> Option<String> {
+    let value = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then(|| value.trim())
+    })?;
+    let host = value.rsplit_once(':').map_or(value, |(host, _)| host);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Request head with the X-Forwarded-* headers added, so apps can build
+/// callback URLs for the hostname rather than their own port
+fn forwarded_head(head: &str, host: &str, peer: &str) -> String {
+    let mut forwarded = head.trim_end_matches("\r\n").to_string();
+    forwarded.push_str(&format!(
+        "\r\nX-Forwarded-Host: {}\r\nX-Forwarded-Proto: http\r\nX-Forwarded-For: {}\r\n\r\n",
+        host, peer
+    ));
+    forwarded
+}
+
+fn respond(client: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write!(
+        client,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_host_for() {
+        assert_eq!(
+            host_for("feature-auth", "localhost"),
+            "feature-auth.localhost"
+        );
+        assert_eq!(
+            host_for("Feature_Auth", "dev.test"),
+            "feature-auth.dev.test"
+        );
+    }
+
+    #[test]
+    fn test_validate_domain() {
+        assert!(validate_domain("localhost").is_ok());
+        assert!(validate_domain("dev.test").is_ok());
+        assert!(validate_domain("").is_err());
+        assert!(validate_domain(".localhost").is_err());
+        assert!(validate_domain("local_host").is_err());
+    }
+
+    #[test]
+    fn test_host_header() {
+        let head = "GET / HTTP/1.1\r\nhost: Feature-Auth.localhost:8080\r\nAccept: */*\r\n\r\n";
+        assert_eq!(
+            host_header(head),
+            Some("feature-auth.localhost".to_string())
+        );
+
+        let head = "GET / HTTP/1.1\r\nHost: feature-auth.localhost\r\n\r\n";
+        assert_eq!(
+            host_header(head),
+            Some("feature-auth.localhost".to_string())
+        );
+
+        assert_eq!(host_header("GET / HTTP/1.1\r\nAccept: */*\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn test_forwarded_head() {
+        let head = "GET / HTTP/1.1\r\nHost: a.localhost:8080\r\n\r\n";
+        let forwarded = forwarded_head(head, "a.localhost", "127.0.0.1");
+        assert!(forwarded.starts_with("GET / HTTP/1.1\r\nHost: a.localhost:8080\r\n"));
+        assert!(forwarded.contains("X-Forwarded-Host: a.localhost\r\n"));
+        assert!(forwarded.ends_with("X-Forwarded-For: 127.0.0.1\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_routes_follow_allocations() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ProxyConfig::default();
+
+        let mut allocator = PortAllocator::new(temp_dir.path()).unwrap();
+        let ports = allocator
+            .allocate("feature-auth", &["app", "redis"])
+            .unwrap();
+        allocator.allocate("db-only", &["postgres"]).unwrap();
+
+        let routes = routes(temp_dir.path(), &config).unwrap();
+        assert_eq!(
+            routes,
+            vec![Route {
+                host: "feature-auth.localhost".to_string(),
+                worktree: "feature-auth".to_string(),
+                port: ports["app"],
+            }]
+        );
+
+        allocator.release("feature-auth").unwrap();
+        assert!(super::routes(temp_dir.path(), &config).unwrap().is_empty());
+    }
+}
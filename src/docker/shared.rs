@@ -0,0 +1,439 @@
+// Shared Docker services (docker.strategy: shared / hybrid)
+//
+// With `shared`, every compose service runs once in a repo-level project;
+// with `hybrid`, only the services listed in `docker.shared.services` do and
+// the rest still run per worktree. Each worktree gets its own slice of the
+// shared services - a database name, a Redis DB index or a key prefix -
+// handed to it through `HNHN_<SERVICE>_*` environment variables.
+
+use crate::config::{DockerConfig, SharedIsolation};
+use crate::errors::{HnError, Result};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+const REGISTRY_FILE: &str = "shared-registry.yaml";
+
+/// File in a worktree's state directory holding its isolation variables
+pub const ENV_FILE: &str = "docker.env";
+
+/// Highest DB index handed out (Redis has databases 0-15; 0 is left alone)
+const MAX_DB_INDEX: u32 = 15;
+
+/// Which services run per worktree and which run once for the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockerStrategy {
+    /// Every worktree runs its own copy of every service
+    PerWorktree,
+    /// Every service runs once for the repository
+    Shared,
+    /// Services in `docker.shared.services` are shared, the rest per worktree
+    Hybrid,
+}
+
+impl DockerStrategy {
+    /// Read and check `docker.strategy`
+    pub fn from_config(config: &DockerConfig) -> Result<Self> {
+        let strategy = match config.strategy.as_str() {
+            "per-worktree" => Self::PerWorktree,
+            "shared" => Self::Shared,
+            "hybrid" => Self::Hybrid,
+            other => {
+                return Err(HnError::ConfigError(format!(
+                "docker.strategy: unknown strategy '{}'. Use 'per-worktree', 'shared' or 'hybrid'.",
+                other
+            )))
+            }
+        };
+
+        if strategy == Self::Hybrid && config.shared.services.is_empty() {
+            return Err(HnError::ConfigError(
+                "docker.strategy: 'hybrid' needs the shared services listed in docker.shared.services"
+                    .to_string(),
+            ));
+        }
+
+        Ok(strategy)
+    }
+
+    /// Whether a service runs in the repo-level project
+    pub fn is_shared(&self, config: &DockerConfig, service: &str) -> bool {
+        match self {
+            Self::PerWorktree => false,
+            Self::Shared => true,
+            Self::Hybrid => config.shared.services.iter().any(|s| s == service),
+        }
+    }
+
+    /// The shared services out of all compose services, in compose order
+    pub fn shared_services(&self, config: &DockerConfig, services: &[String]) -> Vec<String> {
+        services
+            .iter()
+            .filter(|s| self.is_shared(config, s))
+            .cloned()
+            .collect()
+    }
+
+    /// The shared services of the compose files in `dir`
+    ///
+    /// Falls back to the services with base ports when the compose file
+    /// can't be read, so isolation still works before it exists.
+    pub fn shared_services_in(&self, config: &DockerConfig, dir: &Path) -> Vec<String> {
+        match self {
+            Self::PerWorktree => Vec::new(),
+            Self::Hybrid => config.shared.services.clone(),
+            Self::Shared => {
+                let services = super::compose::service_names(config, dir).unwrap_or_else(|_| {
+                    let mut services: Vec<String> = config.ports.base.keys().cloned().collect();
+                    services.sort();
+                    services
+                });
+                self.shared_services(config, &services)
+            }
+        }
+    }
+}
+
+/// Compose project name of the shared services for the repository at `repo_root`
+pub fn shared_project_name(repo_root: &Path) -> String {
+    let repo = repo_root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "hn".to_string());
+    format!("{}-shared", super::container::sanitize_project_name(&repo))
+}
+
+/// Isolation for a shared service, guessed from its name unless configured
+pub fn isolation_for(config: &DockerConfig, service: &str) -> SharedIsolation {
+    if let Some(isolation) = config.shared.isolation.get(service) {
+        return *isolation;
+    }
+
+    let service = service.to_lowercase();
+    let is = |names: &[&str]| names.iter().any(|n| service.contains(n));
+    if is(&["postgres", "mysql", "mariadb", "mongo"]) {
+        SharedIsolation::Database
+    } else if is(&["redis", "valkey"]) {
+        SharedIsolation::DbIndex
+    } else {
+        SharedIsolation::None
+    }
+}
+
+/// DB indexes handed out to worktrees, persisted in the state directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SharedRegistry {
+    /// Map of service name -> worktree name -> DB index
+    db_indexes: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl SharedRegistry {
+    pub fn load(state_dir: &Path) -> Result<Self> {
+        let path = state_dir.join(REGISTRY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = File::open(&path)?;
+        FileExt::lock_shared(&file).map_err(|e| {
+            HnError::DockerError(format!("Failed to lock shared registry for reading: {}", e))
+        })?;
+        let content = fs::read_to_string(&path)?;
+        serde_yml::from_str(&content)
+            .map_err(|e| HnError::DockerError(format!("Failed to parse shared registry: {}", e)))
+    }
+
+    pub fn save(&self, state_dir: &Path) -> Result<()> {
+        fs::create_dir_all(state_dir)?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(state_dir.join(REGISTRY_FILE))?;
+        FileExt::lock_exclusive(&file)
+            .map_err(|e| HnError::DockerError(format!("Failed to lock shared registry: {}", e)))?;
+        file.set_len(0)?;
+
+        let yaml = serde_yml::to_string(self).map_err(|e| {
+            HnError::DockerError(format!("Failed to serialize shared registry: {}", e))
+        })?;
+        file.write_all(yaml.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// The worktree's DB index for a service, handing out the lowest free one
+    pub fn db_index(&mut self, service: &str, worktree: &str) -> Result<u32> {
+        let indexes = self.db_indexes.entry(service.to_string()).or_default();
+        if let Some(index) = indexes.get(worktree) {
+            return Ok(*index);
+        }
+
+        let index = (1..=MAX_DB_INDEX)
+            .find(|i| !indexes.values().any(|used| used == i))
+            .ok_or_else(|| {
+                HnError::DockerError(format!(
+                    "No free DB index left in shared service '{}' ({} worktrees use it)",
+                    service,
+                    indexes.len()
+                ))
+            })?;
+        indexes.insert(worktree.to_string(), index);
+        Ok(index)
+    }
+
+    /// Move a worktree's DB indexes over to its new name
+    ///
+    /// Returns whether the worktree had any.
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> bool {
+        let mut renamed = false;
+        for indexes in self.db_indexes.values_mut() {
+            if let Some(index) = indexes.remove(old_name) {
+                indexes.insert(new_name.to_string(), index);
+                renamed = true;
+            }
+        }
+        renamed
+    }
+
+    /// Give back everything a worktree was handed
+    ///
+    /// Returns whether the worktree had anything to give back.
+    pub fn release(&mut self, worktree: &str) -> bool {
+        let mut released = false;
+        for indexes in self.db_indexes.values_mut() {
+            released |= indexes.remove(worktree).is_some();
+        }
+        self.db_indexes.retain(|_, indexes| !indexes.is_empty());
+        released
+    }
+}
+
+/// Prefix of a shared service's isolation variables, e.g. `HNHN_POSTGRES`
+fn env_prefix(service: &str) -> String {
+    let service: String = service
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("HNHN_{}", service)
+}
+
+/// Database name for a worktree: lowercase alphanumerics and underscores
+fn database_name(worktree: &str) -> String {
+    worktree
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Data a worktree keeps in the shared services under its name: a database
+/// or a key prefix per service
+///
+/// Unlike DB indexes, these can't follow the worktree when it's renamed.
+pub fn named_data(
+    config: &DockerConfig,
+    shared_services: &[String],
+    worktree: &str,
+) -> Vec<String> {
+    shared_services
+        .iter()
+        .filter_map(|service| match isolation_for(config, service) {
+            SharedIsolation::Database => Some(format!(
+                "{} database '{}'",
+                service,
+                database_name(worktree)
+            )),
+            SharedIsolation::Prefix => Some(format!("{} keys prefixed '{}:'", service, worktree)),
+            SharedIsolation::DbIndex | SharedIsolation::None => None,
+        })
+        .collect()
+}
+
+/// A worktree's slice of the shared services
+///
+/// Returns the `HNHN_<SERVICE>_*` variables, plus a `{{isolation.<service>}}`
+/// value per service for substitution in `docker.env`.
+pub fn isolation_env(
+    config: &DockerConfig,
+    shared_services: &[String],
+    worktree: &str,
+    registry: &mut SharedRegistry,
+) -> Result<(BTreeMap<String, String>, HashMap<String, String>)> {
+    let mut env = BTreeMap::new();
+    let mut values = HashMap::new();
+
+    for service in shared_services {
+        let prefix = env_prefix(service);
+        let (key, value) = match isolation_for(config, service) {
+            SharedIsolation::Database => ("DATABASE", database_name(worktree)),
+            SharedIsolation::DbIndex => (
+                "DB_INDEX",
+                registry.db_index(service, worktree)?.to_string(),
+            ),
+            SharedIsolation::Prefix => ("PREFIX", format!("{}:", worktree)),
+            SharedIsolation::None => continue,
+        };
+        env.insert(format!("{}_{}", prefix, key), value.clone());
+        values.insert(service.clone(), value);
+    }
+
+    Ok((env, values))
+}
+
+/// Write a worktree's isolation variables to `<state_dir>/<worktree>/docker.env`
+/// for processes running outside Docker
+pub fn write_env_file(
+    state_dir: &Path,
+    worktree: &str,
+    env: &BTreeMap<String, String>,
+) -> Result<()> {
+    let dir = state_dir.join(worktree);
+    fs::create_dir_all(&dir)?;
+    let content: String = env
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect();
+    fs::write(dir.join(ENV_FILE), content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn hybrid_config() -> DockerConfig {
+        let mut config = DockerConfig {
+            strategy: "hybrid".to_string(),
+            ..Default::default()
+        };
+        config.shared.services = vec!["postgres".to_string(), "redis".to_string()];
+        config
+    }
+
+    #[test]
+    fn test_strategy_from_config() {
+        let mut config = DockerConfig::default();
+        assert_eq!(
+            DockerStrategy::from_config(&config).unwrap(),
+            DockerStrategy::PerWorktree
+        );
+
+        config.strategy = "shared".to_string();
+        assert_eq!(
+            DockerStrategy::from_config(&config).unwrap(),
+            DockerStrategy::Shared
+        );
+
+        config.strategy = "hybrid".to_string();
+        assert!(DockerStrategy::from_config(&config).is_err());
+        assert_eq!(
+            DockerStrategy::from_config(&hybrid_config()).unwrap(),
+            DockerStrategy::Hybrid
+        );
+
+        config.strategy = "pooled".to_string();
+        assert!(DockerStrategy::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_shared_services_per_strategy() {
+        let services: Vec<String> = ["app", "postgres", "redis"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let config = hybrid_config();
+
+        assert!(DockerStrategy::PerWorktree
+            .shared_services(&config, &services)
+            .is_empty());
+        assert_eq!(
+            DockerStrategy::Shared.shared_services(&config, &services),
+            services
+        );
+        assert_eq!(
+            DockerStrategy::Hybrid.shared_services(&config, &services),
+            vec!["postgres".to_string(), "redis".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_isolation_env() {
+        let mut config = hybrid_config();
+        config.shared.services.push("search".to_string());
+        config
+            .shared
+            .isolation
+            .insert("search".to_string(), SharedIsolation::Prefix);
+        let mut registry = SharedRegistry::default();
+
+        let (env, values) =
+            isolation_env(&config, &config.shared.services, "feature-x", &mut registry).unwrap();
+        assert_eq!(env["HNHN_POSTGRES_DATABASE"], "feature_x");
+        assert_eq!(env["HNHN_REDIS_DB_INDEX"], "1");
+        assert_eq!(env["HNHN_SEARCH_PREFIX"], "feature-x:");
+        assert_eq!(values["postgres"], "feature_x");
+
+        let (env, _) =
+            isolation_env(&config, &config.shared.services, "other", &mut registry).unwrap();
+        assert_eq!(env["HNHN_REDIS_DB_INDEX"], "2");
+
+        assert_eq!(
+            named_data(&config, &config.shared.services, "feature-x"),
+            vec![
+                "postgres database 'feature_x'".to_string(),
+                "search keys prefixed 'feature-x:'".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_db_indexes_are_reused_after_release() {
+        let temp = TempDir::new().unwrap();
+        let mut registry = SharedRegistry::default();
+        assert_eq!(registry.db_index("redis", "a").unwrap(), 1);
+        assert_eq!(registry.db_index("redis", "b").unwrap(), 2);
+        assert_eq!(registry.db_index("redis", "a").unwrap(), 1);
+        registry.save(temp.path()).unwrap();
+
+        let mut registry = SharedRegistry::load(temp.path()).unwrap();
+        assert!(registry.release("a"));
+        assert!(!registry.release("a"));
+        assert_eq!(registry.db_index("redis", "c").unwrap(), 1);
+
+        for i in 0..13 {
+            registry.db_index("redis", &format!("wt{}", i)).unwrap();
+        }
+        assert!(registry.db_index("redis", "one-too-many").is_err());
+    }
+
+    #[test]
+    fn test_rename_keeps_db_indexes() {
+        let mut registry = SharedRegistry::default();
+        registry.db_index("redis", "a").unwrap();
+        registry.db_index("redis", "b").unwrap();
+
+        assert!(registry.rename("b", "c"));
+        assert!(!registry.rename("b", "d"));
+        assert_eq!(registry.db_index("redis", "c").unwrap(), 2);
+        assert_eq!(registry.db_index("redis", "b").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_shared_project_name() {
+        assert_eq!(
+            shared_project_name(Path::new("/src/My_Repo")),
+            "my-repo-shared"
+        );
+    }
+}
@@ -0,0 +1,716 @@
+// Condition expressions for conditional hooks
+//
+// Grammar (lowest to highest precedence):
+//
+//   expr       := and ( "||" and )*
+//   and        := unary ( "&&" unary )*
+//   unary      := "!" unary | comparison
+//   comparison := postfix ( ("==" | "!=") postfix | ("=~" | "!~") STRING )?
+//   postfix    := primary ( "." IDENT "(" STRING ")" )*
+//   primary    := STRING | "true" | "false" | IDENT | IDENT "(" STRING ")" | "(" expr ")"
+//
+// Variables: branch, name, template, profile, vcs, path (strings) and tags
+// (list). Methods: startsWith/endsWith/contains on strings, contains on tags.
+// `exists('file')` checks for a file relative to the worktree. Strings and
+// lists used as booleans are true when non-empty, so `template && ...` works.
+//
+// Expressions are parsed and type-checked up front so that `hn config
+// validate` can report mistakes before a hook ever runs.
+
+use crate::errors::{HnError, Result};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// Values a condition can refer to
+#[derive(Debug, Clone, Default)]
+pub struct ConditionContext {
+    pub branch: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub template: Option<String>,
+    pub profile: Option<String>,
+    pub vcs: String,
+    pub path: PathBuf,
+}
+
+/// A parsed, type-checked hook condition
+#[derive(Debug, Clone)]
+pub struct Condition {
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse a condition, reporting the column of the first error
+    pub fn parse(source: &str) -> Result<Self> {
+        let error = |message: String, offset: usize| {
+            let column = source[..offset.min(source.len())].chars().count() + 1;
+            HnError::ConfigError(format!(
+                "Invalid hook condition '{}': {} at column {}",
+                source, message, column
+            ))
+        };
+
+        let tokens = tokenize(source).map_err(|e| error(e.message, e.offset))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: source.len(),
+        };
+        let expr = parser.parse().map_err(|e| error(e.message, e.offset))?;
+
+        Ok(Self { expr })
+    }
+
+    /// Evaluate the condition against a worktree
+    pub fn evaluate(&self, context: &ConditionContext) -> bool {
+        self.expr.evaluate(context).is_truthy()
+    }
+}
+
+#[derive(Debug)]
+struct ParseError {
+    message: String,
+    offset: usize,
+}
+
+type ParseResult<T> = std::result::Result<T, ParseError>;
+
+fn parse_error<T>(message: impl Into<String>, offset: usize) -> ParseResult<T> {
+    Err(ParseError {
+        message: message.into(),
+        offset,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Ident(String),
+    And,
+    Or,
+    Not,
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    LParen,
+    RParen,
+    Dot,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Str(s) => format!("string '{}'", s),
+            Token::Ident(s) => format!("'{}'", s),
+            Token::And => "'&&'".to_string(),
+            Token::Or => "'||'".to_string(),
+            Token::Not => "'!'".to_string(),
+            Token::Eq => "'=='".to_string(),
+            Token::Ne => "'!='".to_string(),
+            Token::Match => "'=~'".to_string(),
+            Token::NotMatch => "'!~'".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::Dot => "'.'".to_string(),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> ParseResult<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '.' => Token::Dot,
+            '&' | '|' => {
+                if chars.next_if(|&(_, next)| next == c).is_none() {
+                    return parse_error(format!("expected '{}{}'", c, c), offset);
+                }
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '!' => match chars.next_if(|&(_, next)| next == '=' || next == '~') {
+                Some((_, '=')) => Token::Ne,
+                Some(_) => Token::NotMatch,
+                None => Token::Not,
+            },
+            '=' => match chars.next_if(|&(_, next)| next == '=' || next == '~') {
+                Some((_, '=')) => Token::Eq,
+                Some(_) => Token::Match,
+                None => return parse_error("expected '==' or '=~'", offset),
+            },
+            '\'' | '"' => {
+                // A backslash only escapes the quote character, so regex
+                // escapes like \d pass through untouched
+                let mut value = String::new();
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' && chars.next_if(|&(_, q)| q == c).is_some() {
+                        value.push(c);
+                    } else if next == c {
+                        closed = true;
+                        break;
+                    } else {
+                        value.push(next);
+                    }
+                }
+                if !closed {
+                    return parse_error("unterminated string", offset);
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, next)) =
+                    chars.next_if(|&(_, next)| next.is_ascii_alphanumeric() || next == '_')
+                {
+                    ident.push(next);
+                }
+                Token::Ident(ident)
+            }
+            other => return parse_error(format!("unexpected character '{}'", other), offset),
+        };
+        tokens.push((token, offset));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Bool,
+    Str,
+    List,
+}
+
+impl Type {
+    fn name(self) -> &'static str {
+        match self {
+            Type::Bool => "boolean",
+            Type::Str => "string",
+            Type::List => "list",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Variable {
+    Branch,
+    Name,
+    Template,
+    Profile,
+    Vcs,
+    Path,
+    Tags,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "branch" => Some(Variable::Branch),
+            "name" => Some(Variable::Name),
+            "template" => Some(Variable::Template),
+            "profile" => Some(Variable::Profile),
+            "vcs" => Some(Variable::Vcs),
+            "path" => Some(Variable::Path),
+            "tags" => Some(Variable::Tags),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Method {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Bool(bool),
+    Str(String),
+    Var(Variable),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Equals {
+        left: Box<Expr>,
+        right: Box<Expr>,
+        negate: bool,
+    },
+    Matches {
+        value: Box<Expr>,
+        regex: Regex,
+        negate: bool,
+    },
+    Call {
+        receiver: Box<Expr>,
+        method: Method,
+        argument: String,
+    },
+    Exists(String),
+}
+
+impl Expr {
+    fn ty(&self) -> Type {
+        match self {
+            Expr::Str(_) => Type::Str,
+            Expr::Var(Variable::Tags) => Type::List,
+            Expr::Var(_) => Type::Str,
+            _ => Type::Bool,
+        }
+    }
+
+    fn evaluate(&self, context: &ConditionContext) -> Value {
+        match self {
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Str(s) => Value::Str(s.clone()),
+            Expr::Var(variable) => match variable {
+                Variable::Branch => Value::Str(context.branch.clone()),
+                Variable::Name => Value::Str(context.name.clone()),
+                Variable::Template => Value::Str(context.template.clone().unwrap_or_default()),
+                Variable::Profile => Value::Str(context.profile.clone().unwrap_or_default()),
+                Variable::Vcs => Value::Str(context.vcs.clone()),
+                Variable::Path => Value::Str(context.path.to_string_lossy().into_owned()),
+                Variable::Tags => Value::List(context.tags.clone()),
+            },
+            Expr::Not(inner) => Value::Bool(!inner.evaluate(context).is_truthy()),
+            Expr::And(left, right) => Value::Bool(
+                left.evaluate(context).is_truthy() && right.evaluate(context).is_truthy(),
+            ),
+            Expr::Or(left, right) => Value::Bool(
+                left.evaluate(context).is_truthy() || right.evaluate(context).is_truthy(),
+            ),
+            Expr::Equals {
+                left,
+                right,
+                negate,
+            } => Value::Bool((left.evaluate(context) == right.evaluate(context)) != *negate),
+            Expr::Matches {
+                value,
+                regex,
+                negate,
+            } => {
+                let matched = match value.evaluate(context) {
+                    Value::Str(s) => regex.is_match(&s),
+                    _ => false,
+                };
+                Value::Bool(matched != *negate)
+            }
+            Expr::Call {
+                receiver,
+                method,
+                argument,
+            } => {
+                let result = match (receiver.evaluate(context), method) {
+                    (Value::Str(s), Method::StartsWith) => s.starts_with(argument.as_str()),
+                    (Value::Str(s), Method::EndsWith) => s.ends_with(argument.as_str()),
+                    (Value::Str(s), Method::Contains) => s.contains(argument.as_str()),
+                    (Value::List(items), Method::Contains) => items.contains(argument),
+                    _ => false,
+                };
+                Value::Bool(result)
+            }
+            Expr::Exists(file) => {
+                let file = Path::new(file);
+                let path = if file.is_absolute() {
+                    file.to_path_buf()
+                } else {
+                    context.path.join(file)
+                };
+                Value::Bool(path.exists())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    Str(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Offset reported for errors at the end of the input
+    end: usize,
+}
+
+impl Parser {
+    fn parse(&mut self) -> ParseResult<Expr> {
+        if self.tokens.is_empty() {
+            return parse_error("empty condition", 0);
+        }
+
+        let expr = self.parse_or()?;
+        if let Some((token, offset)) = self.tokens.get(self.position) {
+            return parse_error(format!("unexpected {}", token.describe()), *offset);
+        }
+        Ok(expr)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(_, offset)| *offset)
+            .unwrap_or(self.end)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> ParseResult<()> {
+        let offset = self.offset();
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => parse_error(
+                format!(
+                    "expected {}, found {}",
+                    expected.describe(),
+                    token.describe()
+                ),
+                offset,
+            ),
+            None => parse_error(format!("expected {}", expected.describe()), offset),
+        }
+    }
+
+    fn expect_string(&mut self, what: &str) -> ParseResult<String> {
+        let offset = self.offset();
+        match self.advance() {
+            Some(Token::Str(s)) => Ok(s),
+            Some(token) => parse_error(
+                format!(
+                    "{} must be a quoted string, found {}",
+                    what,
+                    token.describe()
+                ),
+                offset,
+            ),
+            None => parse_error(format!("expected {}", what), offset),
+        }
+    }
+
+    fn parse_or(&mut self) -> ParseResult<Expr> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ParseResult<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.advance();
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.advance();
+            let inner = self.parse_unary()?;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult<Expr> {
+        let left_offset = self.offset();
+        let left = self.parse_postfix()?;
+
+        match self.peek() {
+            Some(Token::Eq) | Some(Token::Ne) => {
+                let negate = self.advance() == Some(Token::Ne);
+                let right_offset = self.offset();
+                let right = self.parse_postfix()?;
+                if left.ty() == Type::List || left.ty() != right.ty() {
+                    return parse_error(
+                        format!(
+                            "cannot compare {} with {}",
+                            left.ty().name(),
+                            right.ty().name()
+                        ),
+                        right_offset,
+                    );
+                }
+                Ok(Expr::Equals {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    negate,
+                })
+            }
+            Some(Token::Match) | Some(Token::NotMatch) => {
+                let negate = self.advance() == Some(Token::NotMatch);
+                if left.ty() != Type::Str {
+                    return parse_error(
+                        format!("'=~' needs a string, found {}", left.ty().name()),
+                        left_offset,
+                    );
+                }
+                let pattern_offset = self.offset();
+                let pattern = self.expect_string("regex pattern")?;
+                let regex = Regex::new(&pattern).map_err(|e| ParseError {
+                    message: format!("invalid regex '{}': {}", pattern, e),
+                    offset: pattern_offset,
+                })?;
+                Ok(Expr::Matches {
+                    value: Box::new(left),
+                    regex,
+                    negate,
+                })
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.parse_primary()?;
+
+        while self.peek() == Some(&Token::Dot) {
+            self.advance();
+            let offset = self.offset();
+            let name = match self.advance() {
+                Some(Token::Ident(name)) => name,
+                _ => return parse_error("expected method name after '.'", offset),
+            };
+
+            let method = match (expr.ty(), name.as_str()) {
+                (Type::Str, "startsWith") => Method::StartsWith,
+                (Type::Str, "endsWith") => Method::EndsWith,
+                (Type::Str | Type::List, "contains") => Method::Contains,
+                (ty, _) => {
+                    return parse_error(
+                        format!("unknown method '{}' on {}", name, ty.name()),
+                        offset,
+                    )
+                }
+            };
+
+            self.expect(Token::LParen)?;
+            let argument = self.expect_string(&format!("argument of {}()", name))?;
+            self.expect(Token::RParen)?;
+
+            expr = Expr::Call {
+                receiver: Box::new(expr),
+                method,
+                argument,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expr> {
+        let offset = self.offset();
+        match self.advance() {
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                "exists" => {
+                    self.expect(Token::LParen)?;
+                    let file = self.expect_string("argument of exists()")?;
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Exists(file))
+                }
+                name => match Variable::from_name(name) {
+                    Some(variable) => Ok(Expr::Var(variable)),
+                    None => parse_error(
+                        format!(
+                            "unknown variable '{}' (expected branch, name, tags, template, profile, vcs, path or exists())",
+                            name
+                        ),
+                        offset,
+                    ),
+                },
+            },
+            Some(token) => parse_error(format!("unexpected {}", token.describe()), offset),
+            None => parse_error("unexpected end of condition", offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context() -> ConditionContext {
+        ConditionContext {
+            branch: "release/1.2".to_string(),
+            name: "rel-12".to_string(),
+            tags: vec!["ci".to_string(), "backend".to_string()],
+            template: Some("microservice".to_string()),
+            profile: None,
+            vcs: "git".to_string(),
+            path: PathBuf::from("/nonexistent/rel-12"),
+        }
+    }
+
+    fn eval(source: &str) -> bool {
+        Condition::parse(source).unwrap().evaluate(&context())
+    }
+
+    fn parse_err(source: &str) -> String {
+        Condition::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_legacy_branch_methods() {
+        assert!(eval("branch.startsWith('release/')"));
+        assert!(eval("branch.endsWith(\".2\")"));
+        assert!(eval("branch.contains('ease')"));
+        assert!(!eval("branch.startsWith('feature/')"));
+    }
+
+    #[test]
+    fn test_regex_match() {
+        assert!(eval("branch =~ '^release/\\d+\\.\\d+$'"));
+        assert!(!eval("branch =~ '^feature/'"));
+        assert!(eval("branch !~ '^feature/'"));
+        assert!(eval("name =~ 'rel'"));
+    }
+
+    #[test]
+    fn test_boolean_operators_and_precedence() {
+        assert!(eval("branch =~ '^release/' && tags.contains('ci')"));
+        assert!(!eval("branch =~ '^release/' && tags.contains('frontend')"));
+        assert!(eval("tags.contains('frontend') || vcs == 'git'"));
+        assert!(eval("!tags.contains('frontend')"));
+        assert!(eval("!!true"));
+        // && binds tighter than ||
+        assert!(eval("true || false && false"));
+        assert!(!eval("(true || false) && false"));
+    }
+
+    #[test]
+    fn test_equality() {
+        assert!(eval("vcs == 'git'"));
+        assert!(eval("vcs != 'hg'"));
+        assert!(eval("template == 'microservice'"));
+        assert!(eval("profile == ''"));
+        assert!(eval("(vcs == 'git') == true"));
+    }
+
+    #[test]
+    fn test_truthiness_of_optional_values() {
+        assert!(eval("template"));
+        assert!(!eval("profile"));
+        assert!(eval("tags && !profile"));
+
+        let mut ctx = context();
+        ctx.tags.clear();
+        assert!(!Condition::parse("tags").unwrap().evaluate(&ctx));
+    }
+
+    #[test]
+    fn test_exists_is_relative_to_worktree() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("package.json"), "{}").unwrap();
+
+        let mut ctx = context();
+        ctx.path = temp.path().to_path_buf();
+
+        let check = |source: &str| Condition::parse(source).unwrap().evaluate(&ctx);
+        assert!(check("exists('package.json')"));
+        assert!(!check("exists('Cargo.toml')"));
+        assert!(check(&format!(
+            "exists('{}')",
+            temp.path().join("package.json").display()
+        )));
+    }
+
+    #[test]
+    fn test_escaped_quotes() {
+        let mut ctx = context();
+        ctx.branch = "it's".to_string();
+        assert!(Condition::parse(r"branch == 'it\'s'")
+            .unwrap()
+            .evaluate(&ctx));
+    }
+
+    #[test]
+    fn test_parse_errors_report_column() {
+        let err = parse_err("branch == 'x' &&");
+        assert!(err.contains("unexpected end of condition"), "{}", err);
+        assert!(err.contains("column 17"), "{}", err);
+
+        let err = parse_err("(branch == 'x'");
+        assert!(err.contains("expected ')'"), "{}", err);
+
+        let err = parse_err("branch = 'x'");
+        assert!(err.contains("expected '==' or '=~'"), "{}", err);
+        assert!(err.contains("column 8"), "{}", err);
+
+        let err = parse_err("branch == 'x");
+        assert!(err.contains("unterminated string"), "{}", err);
+
+        let err = parse_err("branch == 'x' 'y'");
+        assert!(err.contains("unexpected string 'y'"), "{}", err);
+    }
+
+    #[test]
+    fn test_rejects_unknown_names() {
+        assert!(parse_err("invalid.condition()").contains("unknown variable 'invalid'"));
+        assert!(parse_err("branch.invalid('test')").contains("unknown method 'invalid'"));
+        assert!(parse_err("tags.startsWith('c')").contains("unknown method 'startsWith' on list"));
+        assert!(parse_err("").contains("empty condition"));
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert!(parse_err("tags == 'ci'").contains("cannot compare list"));
+        assert!(parse_err("branch == true").contains("cannot compare string with boolean"));
+        assert!(parse_err("tags =~ 'ci'").contains("needs a string"));
+        assert!(parse_err("branch.contains(name)").contains("must be a quoted string"));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let err = parse_err("branch =~ '(unclosed'");
+        assert!(err.contains("invalid regex"), "{}", err);
+        assert!(err.contains("column 11"), "{}", err);
+    }
+}
@@ -0,0 +1,389 @@
+// Hook output logs
+//
+// Every hook run writes its combined stdout/stderr to a file under
+// `<state_dir>/hook-logs/`, so a slow or failing hook can be inspected after
+// the fact with `hn hooks logs`. Files are named
+// `<timestamp>-<hook>[.<step>].log` and rotated by count per worktree.
+//
+// When the worktree's state directory doesn't exist (pre_create, post_remove)
+// the output goes to a temp file instead and is only shown on failure.
+//
+// With `hooks.stream_output` the output is also echoed to the terminal line by
+// line, prefixed with the hook name, while still being written to the log.
+
+use crate::errors::{HnError, Result};
+use colored::*;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Directory inside a worktree's state dir that holds hook logs
+pub const LOG_DIR: &str = "hook-logs";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%9f";
+
+/// A log file found in a worktree's log directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub path: PathBuf,
+    /// Hook type, e.g. `post_create`
+    pub hook: String,
+    /// Step name for multi-step hooks
+    pub step: Option<String>,
+}
+
+impl LogEntry {
+    /// `post_create` or `post_create:<step>`, as recorded in the activity log
+    pub fn label(&self) -> String {
+        match &self.step {
+            Some(step) => format!("{}:{}", self.hook, step),
+            None => self.hook.clone(),
+        }
+    }
+
+    /// Whether this entry matches a `--hook` filter
+    ///
+    /// `post_create` matches the hook and all of its steps;
+    /// `post_create:install` matches a single step.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.hook == filter || self.label() == filter
+    }
+
+    fn parse(path: &Path) -> Option<Self> {
+        let stem = path.file_name()?.to_str()?.strip_suffix(".log")?;
+        // Drop the de-duplication suffix added on name collisions
+        let stem = stem.rsplit_once('~').map_or(stem, |(stem, _)| stem);
+        let (_timestamp, rest) = stem.split_once('-')?;
+        let (hook, step) = match rest.split_once('.') {
+            Some((hook, step)) => (hook, Some(step.to_string())),
+            None => (rest, None),
+        };
+
+        Some(Self {
+            path: path.to_path_buf(),
+            hook: hook.to_string(),
+            step,
+        })
+    }
+}
+
+/// Path of the log directory for a worktree state dir
+pub fn log_dir(state_dir: &Path) -> PathBuf {
+    state_dir.join(LOG_DIR)
+}
+
+/// List logs for a worktree, oldest first, optionally filtered by hook
+pub fn list_logs(state_dir: &Path, hook: Option<&str>) -> Result<Vec<LogEntry>> {
+    let dir = log_dir(state_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<LogEntry> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| LogEntry::parse(&entry.path()))
+        .filter(|entry| hook.is_none_or(|filter| entry.matches(filter)))
+        .collect();
+
+    // Timestamps are fixed-width, so name order is chronological
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Delete the oldest logs so that at most `keep` remain
+pub fn rotate(state_dir: &Path, keep: usize) -> Result<()> {
+    let entries = list_logs(state_dir, None)?;
+    let excess = entries.len().saturating_sub(keep);
+    for entry in &entries[..excess] {
+        // Another hook may have rotated the same file concurrently
+        match fs::remove_file(&entry.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Where a running hook command writes its output
+pub struct HookOutput {
+    /// `post_create` or `post_create:<step>`
+    label: String,
+    path: PathBuf,
+    file: File,
+    /// Offset where the command's own output starts (after the header)
+    start: u64,
+    persistent: bool,
+    // Keeps the temp file alive until the output has been read
+    _temp: Option<tempfile::NamedTempFile>,
+}
+
+impl HookOutput {
+    /// Open the output file for one hook (or step) run
+    ///
+    /// Writes a persistent log when `state_dir` exists and `retention` is
+    /// non-zero, rotating older logs; otherwise falls back to a temp file.
+    pub fn create(
+        state_dir: &Path,
+        hook: &str,
+        step: Option<&str>,
+        command: &str,
+        retention: usize,
+    ) -> Result<Self> {
+        let label = match step {
+            Some(step) => format!("{}:{}", hook, step),
+            None => hook.to_string(),
+        };
+        if retention == 0 || !state_dir.is_dir() {
+            return Self::temporary(label);
+        }
+
+        let dir = log_dir(state_dir);
+        fs::create_dir_all(&dir)?;
+
+        let timestamp = chrono::Utc::now();
+        let mut base = format!("{}-{}", timestamp.format(TIMESTAMP_FORMAT), hook);
+        if let Some(step) = step {
+            base.push('.');
+            base.push_str(&sanitize(step));
+        }
+
+        // Parallel runs of the same hook could still share a timestamp
+        let mut attempt = 1;
+        let (path, mut file) = loop {
+            let name = if attempt == 1 {
+                format!("{}.log", base)
+            } else {
+                format!("{}~{}.log", base, attempt)
+            };
+            let path = dir.join(name);
+            match OpenOptions::new().append(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        writeln!(file, "# hook: {}", label)?;
+        writeln!(file, "# started: {}", timestamp.to_rfc3339())?;
+        for line in command.lines() {
+            writeln!(file, "# $ {}", line)?;
+        }
+        let start = file.seek(SeekFrom::End(0))?;
+
+        rotate(state_dir, retention)?;
+
+        Ok(Self {
+            label,
+            path,
+            file,
+            start,
+            persistent: true,
+            _temp: None,
+        })
+    }
+
+    fn temporary(label: String) -> Result<Self> {
+        let temp = tempfile::NamedTempFile::new().map_err(|e| {
+            HnError::HookError(format!("Failed to create temp file for hook output: {}", e))
+        })?;
+        let file = temp.reopen()?;
+
+        Ok(Self {
+            label,
+            path: temp.path().to_path_buf(),
+            file,
+            start: 0,
+            persistent: false,
+            _temp: Some(temp),
+        })
+    }
+
+    /// Path of the persistent log, if one is being written
+    pub fn log_path(&self) -> Option<&Path> {
+        self.persistent.then_some(self.path.as_path())
+    }
+
+    /// Handles for the child's stdout and stderr
+    ///
+    /// Writing to a file rather than a pipe avoids deadlocking on hooks that
+    /// produce more output than a pipe buffer holds.
+    pub fn stdio(&self) -> Result<(Stdio, Stdio)> {
+        Ok((
+            Stdio::from(self.file.try_clone()?),
+            Stdio::from(self.file.try_clone()?),
+        ))
+    }
+
+    /// Copy a child's piped stdout/stderr to the log and, prefixed with the
+    /// hook label, to the terminal as lines arrive
+    pub fn stream(&self, child: &mut Child) -> Result<OutputStream> {
+        let (sender, receiver) = mpsc::channel();
+        let prefix = format!("[{}]", self.label).dimmed().to_string();
+
+        let mut readers = 0;
+        let pipes: [Option<Box<dyn Read + Send>>; 2] = [
+            child
+                .stdout
+                .take()
+                .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+            child
+                .stderr
+                .take()
+                .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+        ];
+        for pipe in pipes.into_iter().flatten() {
+            let mut log = self.file.try_clone()?;
+            let prefix = prefix.clone();
+            let sender = sender.clone();
+            readers += 1;
+
+            thread::spawn(move || {
+                let mut reader = BufReader::new(pipe);
+                let mut line = Vec::new();
+                while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                    // One write per line keeps stdout and stderr lines whole
+                    let _ = log.write_all(&line);
+                    let text = String::from_utf8_lossy(&line);
+                    eprintln!("{} {}", prefix, text.trim_end_matches(['\r', '\n']));
+                    line.clear();
+                }
+                let _ = sender.send(());
+            });
+        }
+
+        Ok(OutputStream { receiver, readers })
+    }
+
+    /// Everything the command has written so far
+    pub fn read(&self) -> String {
+        let mut bytes = Vec::new();
+        if let Ok(mut file) = File::open(&self.path) {
+            if file.seek(SeekFrom::Start(self.start)).is_ok() {
+                let _ = file.read_to_end(&mut bytes);
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Append a closing line (exit status, duration) to a persistent log
+    pub fn finish(&mut self, summary: &str) {
+        if self.persistent {
+            let _ = writeln!(self.file, "# {}", summary);
+        }
+    }
+}
+
+/// Reader threads started by `HookOutput::stream`
+pub struct OutputStream {
+    receiver: mpsc::Receiver<()>,
+    readers: usize,
+}
+
+impl OutputStream {
+    /// Wait for the readers to drain the pipes once the child has exited
+    ///
+    /// A background process started by the hook can keep a pipe open
+    /// indefinitely, so give up after `grace` and leave the readers behind.
+    pub fn finish(self, grace: Duration) {
+        for _ in 0..self.readers {
+            if self.receiver.recv_timeout(grace).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Keep step names safe to use in a file name
+fn sanitize(step: &str) -> String {
+    step.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_output_is_persisted_with_header() {
+        let temp = TempDir::new().unwrap();
+        let mut output =
+            HookOutput::create(temp.path(), "post_create", None, "echo hi", 20).unwrap();
+        writeln!(output.file, "hi").unwrap();
+        output.finish("exit code 0");
+
+        assert_eq!(output.read(), "hi\n# exit code 0\n");
+
+        let logs = list_logs(temp.path(), None).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].label(), "post_create");
+        let content = fs::read_to_string(&logs[0].path).unwrap();
+        assert!(content.starts_with("# hook: post_create\n"));
+        assert!(content.contains("# $ echo hi\n"));
+    }
+
+    #[test]
+    fn test_missing_state_dir_uses_temp_file() {
+        let temp = TempDir::new().unwrap();
+        let state_dir = temp.path().join("gone");
+        let output = HookOutput::create(&state_dir, "post_remove", None, "true", 20).unwrap();
+
+        assert!(output.log_path().is_none());
+        assert!(!state_dir.exists());
+    }
+
+    #[test]
+    fn test_steps_are_filtered_by_hook() {
+        let temp = TempDir::new().unwrap();
+        for (hook, step) in [
+            ("post_create", Some("npm install")),
+            ("post_create", None),
+            ("pre_remove", None),
+        ] {
+            HookOutput::create(temp.path(), hook, step, "true", 20).unwrap();
+        }
+
+        let post_create = list_logs(temp.path(), Some("post_create")).unwrap();
+        assert_eq!(post_create.len(), 2);
+        let step = list_logs(temp.path(), Some("post_create:npm_install")).unwrap();
+        assert_eq!(step.len(), 1);
+        assert_eq!(step[0].step.as_deref(), Some("npm_install"));
+        assert_eq!(list_logs(temp.path(), Some("pre_remove")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest() {
+        let temp = TempDir::new().unwrap();
+        let mut created = Vec::new();
+        for _ in 0..5 {
+            let output = HookOutput::create(temp.path(), "post_create", None, "true", 3).unwrap();
+            created.push(output.log_path().unwrap().to_path_buf());
+        }
+
+        let remaining: Vec<PathBuf> = list_logs(temp.path(), None)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(remaining, created[2..].to_vec());
+    }
+
+    #[test]
+    fn test_zero_retention_disables_logs() {
+        let temp = TempDir::new().unwrap();
+        let output = HookOutput::create(temp.path(), "post_create", None, "true", 0).unwrap();
+        assert!(output.log_path().is_none());
+        assert!(!log_dir(temp.path()).exists());
+    }
+}
@@ -0,0 +1,1172 @@
+pub mod condition;
+pub mod logs;
+pub mod steps;
+
+pub use condition::{Condition, ConditionContext};
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{ConditionalHook, HookScript, HookStep, HooksConfig};
+use crate::errors::{HnError, Result};
+use crate::monitoring::{self, ActivityEvent, ActivityLog};
+use crate::vcs::Worktree;
+use logs::HookOutput;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use steps::StepStatus;
+
+/// How long to wait for streamed output after a hook exits
+const STREAM_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookType {
+    PreCreate,
+    PostCreate,
+    PreRemove,
+    PostRemove,
+    PostSwitch,
+    PreIntegrate,
+    PostIntegrate,
+    PreSync,
+    PostSync,
+    PreReturn,
+    PostSnapshotRestore,
+    PreDockerStart,
+    PostDockerStart,
+    PostDockerStop,
+}
+
+impl HookType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookType::PreCreate => "pre_create",
+            HookType::PostCreate => "post_create",
+            HookType::PreRemove => "pre_remove",
+            HookType::PostRemove => "post_remove",
+            HookType::PostSwitch => "post_switch",
+            HookType::PreIntegrate => "pre_integrate",
+            HookType::PostIntegrate => "post_integrate",
+            HookType::PreSync => "pre_sync",
+            HookType::PostSync => "post_sync",
+            HookType::PreReturn => "pre_return",
+            HookType::PostSnapshotRestore => "post_snapshot_restore",
+            HookType::PreDockerStart => "pre_docker_start",
+            HookType::PostDockerStart => "post_docker_start",
+            HookType::PostDockerStop => "post_docker_stop",
+        }
+    }
+
+    /// The unconditional script and conditional hooks configured for this type
+    pub fn configured_in(self, config: &HooksConfig) -> (Option<&HookScript>, &[ConditionalHook]) {
+        let (script, conditions) = match self {
+            HookType::PreCreate => (&config.pre_create, &config.pre_create_conditions),
+            HookType::PostCreate => (&config.post_create, &config.post_create_conditions),
+            HookType::PreRemove => (&config.pre_remove, &config.pre_remove_conditions),
+            HookType::PostRemove => (&config.post_remove, &config.post_remove_conditions),
+            HookType::PostSwitch => (&config.post_switch, &config.post_switch_conditions),
+            HookType::PreIntegrate => (&config.pre_integrate, &config.pre_integrate_conditions),
+            HookType::PostIntegrate => (&config.post_integrate, &config.post_integrate_conditions),
+            HookType::PreSync => (&config.pre_sync, &config.pre_sync_conditions),
+            HookType::PostSync => (&config.post_sync, &config.post_sync_conditions),
+            HookType::PreReturn => (&config.pre_return, &config.pre_return_conditions),
+            HookType::PostSnapshotRestore => (
+                &config.post_snapshot_restore,
+                &config.post_snapshot_restore_conditions,
+            ),
+            HookType::PreDockerStart => (
+                &config.pre_docker_start,
+                &config.pre_docker_start_conditions,
+            ),
+            HookType::PostDockerStart => (
+                &config.post_docker_start,
+                &config.post_docker_start_conditions,
+            ),
+            HookType::PostDockerStop => (
+                &config.post_docker_stop,
+                &config.post_docker_stop_conditions,
+            ),
+        };
+        (script.as_ref(), conditions.as_slice())
+    }
+
+    /// Whether anything would run for this type under `config`
+    pub fn is_configured(self, config: &HooksConfig) -> bool {
+        let (script, conditions) = self.configured_in(config);
+        script.is_some() || !conditions.is_empty()
+    }
+}
+
+pub struct HookExecutor {
+    config: HooksConfig,
+    skip_hooks: bool,
+    clock: Arc<dyn Clock>,
+    template: Option<String>,
+    profile: Option<String>,
+    /// Repository whose hooks must be trusted before they run
+    repo_root: PathBuf,
+    trusted: AtomicBool,
+    /// Operation-specific variables, e.g. the sync source branch
+    extra_env: Vec<(String, String)>,
+}
+
+impl HookExecutor {
+    /// Create an executor for the hooks of the repository at `repo_root`
+    ///
+    /// The repository's own hooks only run once they're trusted (see
+    /// `crate::trust`); hooks from system and user config always run.
+    pub fn new(config: HooksConfig, skip_hooks: bool, repo_root: &Path) -> Self {
+        Self::new_with_clock(config, skip_hooks, repo_root, Arc::new(SystemClock))
+    }
+
+    pub fn new_with_clock(
+        config: HooksConfig,
+        skip_hooks: bool,
+        repo_root: &Path,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            config,
+            skip_hooks,
+            clock,
+            template: None,
+            profile: None,
+            repo_root: repo_root.to_path_buf(),
+            trusted: AtomicBool::new(false),
+            extra_env: Vec::new(),
+        }
+    }
+
+    /// Set the template the worktree is created from, for `template` conditions
+    ///
+    /// When unset, the template recorded in the worktree's activity log is used.
+    pub fn with_template(mut self, template: Option<String>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Set the config profile in effect, for `profile` conditions
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Pass an extra environment variable to every hook this executor runs
+    pub fn with_env(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.extra_env.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Execute a hook if it's configured
+    pub fn run_hook(
+        &self,
+        hook_type: HookType,
+        worktree: &Worktree,
+        state_dir: &Path,
+    ) -> Result<()> {
+        // Skip hook execution if --no-hooks flag is set
+        if self.skip_hooks {
+            return Ok(());
+        }
+
+        // First, run the regular (unconditional) hook if configured
+        let (script, conditional_hooks) = hook_type.configured_in(&self.config);
+
+        if script.is_some() || !conditional_hooks.is_empty() {
+            self.ensure_trusted()?;
+        }
+
+        if let Some(script) = script {
+            self.execute_hook(hook_type, script, worktree, state_dir)?;
+        }
+
+        // Then, evaluate and run any conditional hooks that match
+        if !conditional_hooks.is_empty() {
+            let context = self.condition_context(worktree, state_dir);
+            for conditional_hook in conditional_hooks {
+                if self.evaluate_condition(&conditional_hook.condition, &context)? {
+                    self.execute_hook(hook_type, &conditional_hook.command, worktree, state_dir)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check (and if needed ask for) trust once per executor
+    fn ensure_trusted(&self) -> Result<()> {
+        if !self.trusted.load(Ordering::Relaxed) {
+            crate::trust::ensure_trusted(&self.repo_root)?;
+            self.trusted.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Evaluate a condition expression (see `condition` for the syntax)
+    fn evaluate_condition(&self, condition: &str, context: &ConditionContext) -> Result<bool> {
+        Ok(Condition::parse(condition)?.evaluate(context))
+    }
+
+    /// Gather the values conditions can refer to
+    fn condition_context(&self, worktree: &Worktree, state_dir: &Path) -> ConditionContext {
+        // state_dir is the worktree's own state directory; tags are indexed
+        // one level up
+        let tags = state_dir
+            .parent()
+            .and_then(|root| crate::tags::get_worktree_tags(root, &worktree.name).ok())
+            .unwrap_or_default();
+
+        let template = self.template.clone().or_else(|| {
+            let log = ActivityLog::load(&state_dir.join("activity.json")).ok()?;
+            log.events.iter().find_map(|event| match event {
+                ActivityEvent::WorktreeCreated { template, .. } => template.clone(),
+                _ => None,
+            })
+        });
+
+        // The worktree may not exist yet (pre_create), so look upwards
+        let vcs = worktree
+            .path
+            .ancestors()
+            .find_map(crate::vcs::traits::detect_vcs_type)
+            .map(|vcs| vcs.as_str().to_string())
+            .unwrap_or_default();
+
+        ConditionContext {
+            branch: worktree.branch.clone(),
+            name: worktree.name.clone(),
+            tags,
+            template,
+            profile: self.profile.clone(),
+            vcs,
+            path: worktree.path.clone(),
+        }
+    }
+
+    /// Execute a hook body: a single command or a graph of steps
+    fn execute_hook(
+        &self,
+        hook_type: HookType,
+        script: &HookScript,
+        worktree: &Worktree,
+        state_dir: &Path,
+    ) -> Result<()> {
+        // Build environment variables
+        let env = self.build_env(worktree, state_dir);
+
+        // Determine working directory based on hook type
+        // Pre-create runs in parent of worktree since worktree doesn't exist yet
+        // Post-remove runs in parent since worktree was just deleted
+        // With a custom worktrees.root the parent may not exist yet, so use the
+        // nearest existing ancestor
+        let working_dir = match hook_type {
+            HookType::PreCreate | HookType::PostRemove => worktree
+                .path
+                .ancestors()
+                .skip(1)
+                .find(|dir| dir.is_dir())
+                .unwrap_or(&worktree.path),
+            _ => &worktree.path,
+        };
+
+        match script {
+            HookScript::Command(command) => {
+                let start = self.clock.now();
+                let mut output = self.open_output(state_dir, hook_type, None, command)?;
+                let result = self.run_command(
+                    &format!("{} hook", hook_type.as_str()),
+                    command,
+                    working_dir,
+                    &env,
+                    self.config.timeout_seconds,
+                    &mut output,
+                );
+                self.record_execution(
+                    worktree,
+                    state_dir,
+                    hook_type.as_str().to_string(),
+                    self.clock.now().duration_since(start),
+                    result.is_ok(),
+                );
+                result
+            }
+            HookScript::Steps(steps) => {
+                self.execute_steps(hook_type, steps, working_dir, &env, worktree, state_dir)
+            }
+        }
+    }
+
+    /// Run hook steps in dependency order, independent steps in parallel
+    fn execute_steps(
+        &self,
+        hook_type: HookType,
+        steps: &[HookStep],
+        working_dir: &Path,
+        env: &HashMap<String, String>,
+        worktree: &Worktree,
+        state_dir: &Path,
+    ) -> Result<()> {
+        // Cycles would otherwise leave steps waiting forever
+        steps::validate_steps(steps).map_err(|e| {
+            HnError::HookError(format!("{} hook is invalid: {}", hook_type.as_str(), e))
+        })?;
+
+        let outcomes = steps::run_steps(steps, self.clock.as_ref(), |step| {
+            let mut step_env = env.clone();
+            step_env.extend(step.env.clone());
+
+            // Joining an absolute path replaces the base
+            let step_dir = match step.working_dir {
+                Some(ref dir) => working_dir.join(dir),
+                None => working_dir.to_path_buf(),
+            };
+
+            let mut output = self.open_output(state_dir, hook_type, Some(&step.name), &step.run)?;
+            self.run_command(
+                &format!("{} hook step '{}'", hook_type.as_str(), step.name),
+                &step.run,
+                &step_dir,
+                &step_env,
+                step.timeout_seconds.unwrap_or(self.config.timeout_seconds),
+                &mut output,
+            )
+        });
+
+        let mut first_error = None;
+        for (outcome, step) in outcomes.into_iter().zip(steps) {
+            let hook = format!("{}:{}", hook_type.as_str(), outcome.name);
+            match outcome.status {
+                StepStatus::Succeeded => {
+                    self.record_execution(worktree, state_dir, hook, outcome.duration, true);
+                }
+                StepStatus::Failed(e) => {
+                    self.record_execution(worktree, state_dir, hook, outcome.duration, false);
+                    if step.continue_on_error {
+                        eprintln!(
+                            "⚠ {} hook step '{}' failed (continue_on_error)",
+                            hook_type.as_str(),
+                            step.name
+                        );
+                    } else if first_error.is_none() {
+                        first_error = Some(e);
+                    }
+                }
+                StepStatus::Skipped => {}
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a HookExecuted activity event for the worktree
+    ///
+    /// Skipped when the state directory doesn't exist (before creation or
+    /// after removal) so that logging doesn't resurrect it.
+    fn record_execution(
+        &self,
+        worktree: &Worktree,
+        state_dir: &Path,
+        hook: String,
+        duration: Duration,
+        success: bool,
+    ) {
+        let Some(state_root) = state_dir.parent() else {
+            return;
+        };
+        if !state_dir.is_dir() {
+            return;
+        }
+
+        let _ = monitoring::log_activity(
+            state_root,
+            &worktree.name,
+            ActivityEvent::HookExecuted {
+                timestamp: monitoring::now(),
+                hook,
+                duration_ms: duration.as_millis() as u64,
+                success,
+            },
+        );
+    }
+
+    /// Open the output log for one hook (or step) run
+    fn open_output(
+        &self,
+        state_dir: &Path,
+        hook_type: HookType,
+        step: Option<&str>,
+        command: &str,
+    ) -> Result<HookOutput> {
+        HookOutput::create(
+            state_dir,
+            hook_type.as_str(),
+            step,
+            command,
+            self.config.log_retention,
+        )
+    }
+
+    /// Run a shell command with a timeout, writing its output to `output`
+    fn run_command(
+        &self,
+        label: &str,
+        script: &str,
+        working_dir: &Path,
+        env: &HashMap<String, String>,
+        timeout_seconds: u64,
+        output: &mut HookOutput,
+    ) -> Result<()> {
+        let start = self.clock.now();
+
+        // Output goes straight to the log file, or through pipes that are
+        // copied to both the log and the terminal when streaming
+        let (stdout, stderr) = if self.config.stream_output {
+            (Stdio::piped(), Stdio::piped())
+        } else {
+            output.stdio()?
+        };
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .current_dir(working_dir)
+            .envs(env)
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()?;
+
+        let mut stream = if self.config.stream_output {
+            Some(output.stream(&mut child)?)
+        } else {
+            None
+        };
+
+        // Wait with timeout
+        let timeout = Duration::from_secs(timeout_seconds);
+
+        // Use platform-specific wait_timeout if available (Unix/Windows)
+        #[cfg(unix)]
+        let status = match wait_with_timeout(&mut child, timeout, self.clock.clone())? {
+            Some(status) => status,
+            None => {
+                // Timeout occurred - kill process and read partial output
+                let kill_result = child.kill();
+                let wait_result = child.wait();
+
+                // Check if process was already dead (race condition)
+                match (kill_result, wait_result) {
+                    (Err(e), Ok(status)) if e.kind() == std::io::ErrorKind::InvalidInput => {
+                        // Process completed just before timeout
+                        status
+                    }
+                    _ => {
+                        if let Some(stream) = stream.take() {
+                            stream.finish(STREAM_DRAIN_TIMEOUT);
+                        }
+                        let partial = output.read();
+                        output.finish(&format!("timed out after {} seconds", timeout_seconds));
+                        return Err(HnError::HookError(format!(
+                            "{} timed out after {} seconds\nPartial output: {}{}",
+                            label,
+                            timeout_seconds,
+                            truncate(&partial, 500),
+                            log_hint(output)
+                        )));
+                    }
+                }
+            }
+        };
+
+        #[cfg(not(unix))]
+        let status = {
+            // For non-Unix systems, use a simple wait (no timeout for now)
+            // TODO: Implement timeout for Windows using WaitForSingleObject
+            let _ = timeout;
+            child.wait()?
+        };
+
+        let duration = self.clock.now().duration_since(start);
+        if let Some(stream) = stream.take() {
+            stream.finish(STREAM_DRAIN_TIMEOUT);
+        }
+        let text = output.read();
+        output.finish(&format!(
+            "exit code {} after {:.1}s",
+            status.code().unwrap_or(-1),
+            duration.as_secs_f64()
+        ));
+
+        if !status.success() {
+            return Err(HnError::HookError(format!(
+                "{} failed with exit code {}\nOutput: {}{}",
+                label,
+                status.code().unwrap_or(-1),
+                text,
+                log_hint(output)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Build environment variables for hook execution
+    /// v0.5: Changed from WT_* to HNHN_* prefix to avoid collision with Hacker News CLI tools
+    fn build_env(&self, worktree: &Worktree, state_dir: &Path) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+
+        env.insert("HNHN_NAME".to_string(), worktree.name.clone());
+        env.insert(
+            "HNHN_PATH".to_string(),
+            worktree.path.to_string_lossy().to_string(),
+        );
+        env.insert("HNHN_BRANCH".to_string(), worktree.branch.clone());
+        env.insert("HNHN_COMMIT".to_string(), worktree.commit.clone());
+        env.insert(
+            "HNHN_STATE_DIR".to_string(),
+            state_dir.to_string_lossy().to_string(),
+        );
+        for (key, value) in &self.extra_env {
+            env.insert(key.clone(), value.clone());
+        }
+
+        env
+    }
+}
+
+/// Shorten hook output for error messages, respecting char boundaries
+fn truncate(text: &str, max_len: usize) -> &str {
+    match text.char_indices().nth(max_len) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+/// Point at the persistent log in error messages, when there is one
+fn log_hint(output: &HookOutput) -> String {
+    match output.log_path() {
+        Some(path) => format!("\nLog: {}", path.display()),
+        None => String::new(),
+    }
+}
+
+/// Helper function to wait for a child process with timeout
+/// Uses simple polling with try_wait()
+#[cfg(unix)]
+fn wait_with_timeout(
+    child: &mut std::process::Child,
+    timeout: Duration,
+    clock: Arc<dyn Clock>,
+) -> Result<Option<std::process::ExitStatus>> {
+    let start = clock.now();
+    let poll_interval = Duration::from_millis(100);
+
+    loop {
+        // Check if process has completed
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Process completed
+                return Ok(Some(status));
+            }
+            Ok(None) => {
+                // Process still running, check timeout
+                if clock.now().duration_since(start) >= timeout {
+                    // Timeout exceeded
+                    return Ok(None);
+                }
+
+                // Sleep before next poll
+                clock.sleep(poll_interval);
+            }
+            Err(e) => {
+                // Error in try_wait() - try to clean up anyway to prevent orphan
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(HnError::HookError(format!(
+                    "Failed to monitor child process: {}",
+                    e
+                )));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HooksConfig;
+    use crate::vcs::Worktree;
+    use tempfile::TempDir;
+
+    fn create_test_worktree(temp: &TempDir) -> Worktree {
+        let wt_path = temp.path().join("test-worktree");
+        std::fs::create_dir_all(&wt_path).unwrap();
+
+        Worktree {
+            name: "test-worktree".to_string(),
+            path: wt_path,
+            branch: "main".to_string(),
+            commit: "abc123".to_string(),
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_execute_successful_hook() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let config = HooksConfig {
+            post_create: Some("echo 'Hello from hook'".into()),
+            timeout_seconds: 30,
+            ..Default::default()
+        };
+
+        let executor = HookExecutor::new(config, false, temp.path());
+        let result = executor.run_hook(HookType::PostCreate, &worktree, &state_dir);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_execute_failing_hook() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let config = HooksConfig {
+            post_create: Some("exit 1".into()),
+            timeout_seconds: 30,
+            ..Default::default()
+        };
+
+        let executor = HookExecutor::new(config, false, temp.path());
+        let result = executor.run_hook(HookType::PostCreate, &worktree, &state_dir);
+
+        assert!(result.is_err());
+        if let Err(HnError::HookError(msg)) = result {
+            assert!(msg.contains("failed"));
+        } else {
+            panic!("Expected HookError");
+        }
+    }
+
+    #[test]
+    fn test_missing_hook_is_noop() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let config = HooksConfig::default();
+
+        let executor = HookExecutor::new(config, false, temp.path());
+        let result = executor.run_hook(HookType::PostCreate, &worktree, &state_dir);
+
+        // Should succeed without doing anything
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_environment_variables_hnhn_prefix() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        // Create a hook that writes environment variables to a file
+        let output_file = temp.path().join("env_output.txt");
+        let hook_script = format!(
+            r#"echo "HNHN_NAME=$HNHN_NAME" > {}
+echo "HNHN_PATH=$HNHN_PATH" >> {}
+echo "HNHN_BRANCH=$HNHN_BRANCH" >> {}
+echo "HNHN_COMMIT=$HNHN_COMMIT" >> {}
+echo "HNHN_STATE_DIR=$HNHN_STATE_DIR" >> {}"#,
+            output_file.display(),
+            output_file.display(),
+            output_file.display(),
+            output_file.display(),
+            output_file.display()
+        );
+
+        let config = HooksConfig {
+            post_create: Some(hook_script.into()),
+            timeout_seconds: 30,
+            ..Default::default()
+        };
+
+        let executor = HookExecutor::new(config, false, temp.path());
+        executor
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap();
+
+        // Verify NEW HNHN_* environment variables are passed
+        let content = std::fs::read_to_string(&output_file).unwrap();
+        assert!(
+            content.contains("HNHN_NAME=test-worktree"),
+            "HNHN_NAME should be set"
+        );
+        assert!(
+            content.contains("HNHN_BRANCH=main"),
+            "HNHN_BRANCH should be set"
+        );
+        assert!(
+            content.contains("HNHN_COMMIT=abc123"),
+            "HNHN_COMMIT should be set"
+        );
+        assert!(content.contains("HNHN_PATH="), "HNHN_PATH should be set");
+        assert!(
+            content.contains("HNHN_STATE_DIR="),
+            "HNHN_STATE_DIR should be set"
+        );
+    }
+
+    #[test]
+    fn test_old_wt_variables_not_set() {
+        // v0.5 BREAKING CHANGE: Old WT_* variables are no longer set
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        // Create a hook that checks for absence of old variables
+        let output_file = temp.path().join("env_output.txt");
+        let hook_script = format!(
+            r#"echo "WT_NAME=${{WT_NAME:-NOT_SET}}" > {}
+echo "WT_BRANCH=${{WT_BRANCH:-NOT_SET}}" >> {}"#,
+            output_file.display(),
+            output_file.display()
+        );
+
+        let config = HooksConfig {
+            post_create: Some(hook_script.into()),
+            timeout_seconds: 30,
+            ..Default::default()
+        };
+
+        let executor = HookExecutor::new(config, false, temp.path());
+        executor
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap();
+
+        // Verify OLD WT_* variables are NOT set (breaking change in v0.5)
+        let content = std::fs::read_to_string(&output_file).unwrap();
+        assert!(
+            content.contains("WT_NAME=NOT_SET"),
+            "Old WT_NAME should not be set"
+        );
+        assert!(
+            content.contains("WT_BRANCH=NOT_SET"),
+            "Old WT_BRANCH should not be set"
+        );
+    }
+
+    #[test]
+    fn test_all_hook_types_use_hnhn_variables() {
+        // Verify all hook types receive HNHN_* variables
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let hook_types = vec![
+            (HookType::PreCreate, "pre_create"),
+            (HookType::PostCreate, "post_create"),
+            (HookType::PreRemove, "pre_remove"),
+            (HookType::PostRemove, "post_remove"),
+            (HookType::PostSwitch, "post_switch"),
+            (HookType::PreIntegrate, "pre_integrate"),
+            (HookType::PostIntegrate, "post_integrate"),
+            (HookType::PreSync, "pre_sync"),
+            (HookType::PostSync, "post_sync"),
+            (HookType::PreReturn, "pre_return"),
+            (HookType::PostSnapshotRestore, "post_snapshot_restore"),
+            (HookType::PreDockerStart, "pre_docker_start"),
+            (HookType::PostDockerStart, "post_docker_start"),
+            (HookType::PostDockerStop, "post_docker_stop"),
+        ];
+
+        for (hook_type, hook_name) in hook_types {
+            let output_file = temp.path().join(format!("env_{}.txt", hook_name));
+            let hook_script = format!(r#"echo "HNHN_NAME=$HNHN_NAME" > {}"#, output_file.display());
+
+            let mut config = HooksConfig::default();
+            match hook_type {
+                HookType::PreCreate => config.pre_create = Some(hook_script.into()),
+                HookType::PostCreate => config.post_create = Some(hook_script.into()),
+                HookType::PreRemove => config.pre_remove = Some(hook_script.into()),
+                HookType::PostRemove => config.post_remove = Some(hook_script.into()),
+                HookType::PostSwitch => config.post_switch = Some(hook_script.into()),
+                HookType::PreIntegrate => config.pre_integrate = Some(hook_script.into()),
+                HookType::PostIntegrate => config.post_integrate = Some(hook_script.into()),
+                HookType::PreSync => config.pre_sync = Some(hook_script.into()),
+                HookType::PostSync => config.post_sync = Some(hook_script.into()),
+                HookType::PreReturn => config.pre_return = Some(hook_script.into()),
+                HookType::PostSnapshotRestore => {
+                    config.post_snapshot_restore = Some(hook_script.into())
+                }
+                HookType::PreDockerStart => config.pre_docker_start = Some(hook_script.into()),
+                HookType::PostDockerStart => config.post_docker_start = Some(hook_script.into()),
+                HookType::PostDockerStop => config.post_docker_stop = Some(hook_script.into()),
+            }
+            assert_eq!(hook_type.as_str(), hook_name);
+            assert!(hook_type.is_configured(&config));
+
+            let executor = HookExecutor::new(config, false, temp.path());
+            executor.run_hook(hook_type, &worktree, &state_dir).unwrap();
+
+            // Verify HNHN_NAME is set for this hook type
+            let content = std::fs::read_to_string(&output_file).unwrap();
+            assert!(
+                content.contains("HNHN_NAME=test-worktree"),
+                "Hook {} should receive HNHN_NAME",
+                hook_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_extra_env_is_passed_to_hooks() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+        let output_file = temp.path().join("sync_env.txt");
+
+        let config = HooksConfig {
+            post_sync: Some(
+                format!(
+                    "echo $HNHN_NAME:$HNHN_SYNC_RESULT > {}",
+                    output_file.display()
+                )
+                .into(),
+            ),
+            ..Default::default()
+        };
+
+        HookExecutor::new(config, false, temp.path())
+            .with_env("HNHN_SYNC_RESULT", "conflict")
+            .run_hook(HookType::PostSync, &worktree, &state_dir)
+            .unwrap();
+
+        let content = std::fs::read_to_string(&output_file).unwrap();
+        assert_eq!(content.trim(), "test-worktree:conflict");
+    }
+
+    #[test]
+    fn test_skip_hooks_flag() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        // Create a hook that would fail
+        let config = HooksConfig {
+            post_create: Some("exit 1".into()),
+            timeout_seconds: 30,
+            ..Default::default()
+        };
+
+        // With skip_hooks=true, should succeed even though hook would fail
+        let executor = HookExecutor::new(config, true, temp.path());
+        let result = executor.run_hook(HookType::PostCreate, &worktree, &state_dir);
+
+        assert!(result.is_ok(), "Hook should be skipped and not fail");
+    }
+
+    // Note: Timeout behavior is manually tested but not included in automated tests.
+    // While we have a Clock abstraction for time operations, testing actual process
+    // timeouts requires spawning real processes that take real time to complete.
+    // To properly test timeouts without real-time delays would require mocking
+    // process execution itself, which adds significant complexity.
+    // The timeout implementation has been verified through manual testing.
+
+    fn branch_context(branch: &str) -> ConditionContext {
+        ConditionContext {
+            branch: branch.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_condition_starts_with_match() {
+        let config = HooksConfig::default();
+        let executor = HookExecutor::new(config, false, &std::env::temp_dir());
+
+        let result = executor
+            .evaluate_condition(
+                "branch.startsWith('feature/')",
+                &branch_context("feature/new-api"),
+            )
+            .unwrap();
+        assert!(result);
+    }
+
+    #[test]
+    fn test_evaluate_condition_starts_with_no_match() {
+        let config = HooksConfig::default();
+        let executor = HookExecutor::new(config, false, &std::env::temp_dir());
+
+        let result = executor
+            .evaluate_condition(
+                "branch.startsWith('feature/')",
+                &branch_context("hotfix/bug-123"),
+            )
+            .unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_evaluate_condition_ends_with_match() {
+        let config = HooksConfig::default();
+        let executor = HookExecutor::new(config, false, &std::env::temp_dir());
+
+        let result = executor
+            .evaluate_condition("branch.endsWith('-prod')", &branch_context("release-prod"))
+            .unwrap();
+        assert!(result);
+    }
+
+    #[test]
+    fn test_evaluate_condition_contains_match() {
+        let config = HooksConfig::default();
+        let executor = HookExecutor::new(config, false, &std::env::temp_dir());
+
+        let result = executor
+            .evaluate_condition(
+                "branch.contains('bugfix')",
+                &branch_context("feature/bugfix-auth"),
+            )
+            .unwrap();
+        assert!(result);
+    }
+
+    #[test]
+    fn test_evaluate_condition_invalid() {
+        let config = HooksConfig::default();
+        let executor = HookExecutor::new(config, false, &std::env::temp_dir());
+
+        let result = executor.evaluate_condition("invalid.condition()", &branch_context("main"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_condition_context_uses_tags_and_template() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_root = temp.path().join("state");
+        let state_dir = state_root.join(&worktree.name);
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        crate::tags::add_tags(&state_root, &worktree.name, &["ci".to_string()]).unwrap();
+        crate::monitoring::log_activity(
+            &state_root,
+            &worktree.name,
+            ActivityEvent::WorktreeCreated {
+                timestamp: 0,
+                branch: "main".to_string(),
+                template: Some("microservice".to_string()),
+            },
+        )
+        .unwrap();
+
+        let executor = HookExecutor::new(HooksConfig::default(), false, temp.path())
+            .with_profile(Some("dev".into()));
+        let context = executor.condition_context(&worktree, &state_dir);
+
+        assert_eq!(context.tags, vec!["ci".to_string()]);
+        assert_eq!(context.template.as_deref(), Some("microservice"));
+        assert_eq!(context.profile.as_deref(), Some("dev"));
+        assert!(executor
+            .evaluate_condition(
+                "tags.contains('ci') && template == 'microservice' && profile == 'dev'",
+                &context
+            )
+            .unwrap());
+    }
+
+    fn test_step(name: &str, run: &str, needs: &[&str]) -> HookStep {
+        HookStep {
+            name: name.to_string(),
+            run: run.to_string(),
+            needs: needs.iter().map(|n| n.to_string()).collect(),
+            timeout_seconds: None,
+            working_dir: None,
+            env: HashMap::new(),
+            continue_on_error: false,
+        }
+    }
+
+    #[test]
+    fn test_steps_use_env_working_dir_and_dependencies() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state").join(&worktree.name);
+        std::fs::create_dir_all(worktree.path.join("sub")).unwrap();
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let mut install = test_step("install", "echo \"$GREETING\" > installed.txt", &[]);
+        install
+            .env
+            .insert("GREETING".to_string(), "hello".to_string());
+        let mut codegen = test_step(
+            "codegen",
+            "cat ../installed.txt > generated.txt",
+            &["install"],
+        );
+        codegen.working_dir = Some("sub".to_string());
+
+        let config = HooksConfig {
+            post_create: Some(HookScript::Steps(vec![codegen, install])),
+            ..Default::default()
+        };
+
+        HookExecutor::new(config, false, temp.path())
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap();
+
+        let generated = std::fs::read_to_string(worktree.path.join("sub/generated.txt")).unwrap();
+        assert_eq!(generated.trim(), "hello");
+
+        // One activity event per step
+        let log = ActivityLog::load(&state_dir.join("activity.json")).unwrap();
+        let hooks: Vec<String> = log
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ActivityEvent::HookExecuted { hook, success, .. } if *success => Some(hook.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hooks.len(), 2);
+        assert!(hooks.contains(&"post_create:install".to_string()));
+        assert!(hooks.contains(&"post_create:codegen".to_string()));
+    }
+
+    #[test]
+    fn test_failing_step_reports_step_name() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state").join(&worktree.name);
+
+        let config = HooksConfig {
+            post_create: Some(HookScript::Steps(vec![
+                test_step("migrate", "exit 3", &[]),
+                test_step("seed", "touch seeded", &["migrate"]),
+            ])),
+            ..Default::default()
+        };
+
+        let result = HookExecutor::new(config, false, temp.path()).run_hook(
+            HookType::PostCreate,
+            &worktree,
+            &state_dir,
+        );
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("post_create hook step 'migrate' failed with exit code 3"));
+        assert!(!worktree.path.join("seeded").exists());
+        // State dir didn't exist, so no activity log was created
+        assert!(!state_dir.exists());
+    }
+
+    #[test]
+    fn test_continue_on_error_step() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+
+        let mut lint = test_step("lint", "exit 1", &[]);
+        lint.continue_on_error = true;
+
+        let config = HooksConfig {
+            post_create: Some(HookScript::Steps(vec![
+                lint,
+                test_step("build", "touch built", &["lint"]),
+            ])),
+            ..Default::default()
+        };
+
+        HookExecutor::new(config, false, temp.path())
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap();
+        assert!(worktree.path.join("built").exists());
+    }
+
+    #[test]
+    fn test_step_cycle_is_rejected_at_runtime() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+
+        let config = HooksConfig {
+            post_create: Some(HookScript::Steps(vec![
+                test_step("a", "true", &["b"]),
+                test_step("b", "true", &["a"]),
+            ])),
+            ..Default::default()
+        };
+
+        let err = HookExecutor::new(config, false, temp.path())
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dependency cycle"), "{}", err);
+    }
+
+    #[test]
+    fn test_streamed_output_is_still_captured() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let config = HooksConfig {
+            post_create: Some("echo to-stdout && echo to-stderr >&2 && exit 2".into()),
+            stream_output: true,
+            ..Default::default()
+        };
+
+        let err = HookExecutor::new(config, false, temp.path())
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exit code 2"), "{}", err);
+        assert!(err.contains("to-stdout"), "{}", err);
+        assert!(err.contains("to-stderr"), "{}", err);
+
+        let logs = logs::list_logs(&state_dir, Some("post_create")).unwrap();
+        let content = std::fs::read_to_string(&logs[0].path).unwrap();
+        assert!(content.contains("to-stdout\n"));
+        assert!(content.contains("to-stderr\n"));
+    }
+
+    #[test]
+    fn test_streamed_output_survives_timeout() {
+        let temp = TempDir::new().unwrap();
+        let worktree = create_test_worktree(&temp);
+        let state_dir = temp.path().join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let config = HooksConfig {
+            post_create: Some("echo started && sleep 30".into()),
+            timeout_seconds: 1,
+            stream_output: true,
+            ..Default::default()
+        };
+
+        let err = HookExecutor::new(config, false, temp.path())
+            .run_hook(HookType::PostCreate, &worktree, &state_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("timed out after 1 seconds"), "{}", err);
+        assert!(err.contains("started"), "{}", err);
+    }
+}
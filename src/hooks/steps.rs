@@ -0,0 +1,367 @@
+// Multi-step hooks
+//
+// A hook can be a list of named steps with `needs:` dependencies. Steps are
+// started as soon as everything they need has finished, so independent steps
+// run in parallel. A failing step stops new steps from being scheduled unless
+// it is marked `continue_on_error`, in which case its dependents still run.
+
+use crate::clock::Clock;
+use crate::config::HookStep;
+use crate::errors::{HnError, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How a step ended
+#[derive(Debug)]
+pub enum StepStatus {
+    Succeeded,
+    Failed(HnError),
+    /// Not run because a dependency failed or the hook was aborted
+    Skipped,
+}
+
+/// Result of one step
+#[derive(Debug)]
+pub struct StepOutcome {
+    pub name: String,
+    pub status: StepStatus,
+    pub duration: Duration,
+}
+
+/// Check step names and that `needs` form a valid dependency graph
+pub fn validate_steps(steps: &[HookStep]) -> Result<()> {
+    if steps.is_empty() {
+        return Err(HnError::ConfigError(
+            "hook must have at least one step".to_string(),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    for step in steps {
+        if step.name.trim().is_empty() {
+            return Err(HnError::ConfigError(
+                "hook step name cannot be empty".to_string(),
+            ));
+        }
+        if !names.insert(step.name.as_str()) {
+            return Err(HnError::ConfigError(format!(
+                "duplicate hook step '{}'",
+                step.name
+            )));
+        }
+    }
+
+    for step in steps {
+        for need in &step.needs {
+            if need == &step.name {
+                return Err(HnError::ConfigError(format!(
+                    "hook step '{}' needs itself",
+                    step.name
+                )));
+            }
+            if !names.contains(need.as_str()) {
+                return Err(HnError::ConfigError(format!(
+                    "hook step '{}' needs unknown step '{}'",
+                    step.name, need
+                )));
+            }
+        }
+    }
+
+    // Kahn's algorithm: whatever can't be ordered is part of a cycle
+    let mut remaining: HashMap<&str, usize> = steps
+        .iter()
+        .map(|step| (step.name.as_str(), step.needs.len()))
+        .collect();
+    let mut ready: Vec<&str> = remaining
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&name, _)| name)
+        .collect();
+
+    while let Some(done) = ready.pop() {
+        remaining.remove(done);
+        for step in steps {
+            if step.needs.iter().any(|need| need == done) {
+                if let Some(count) = remaining.get_mut(step.name.as_str()) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(step.name.as_str());
+                    }
+                }
+            }
+        }
+    }
+
+    if !remaining.is_empty() {
+        let mut cycle: Vec<&str> = remaining.into_keys().collect();
+        cycle.sort_unstable();
+        return Err(HnError::ConfigError(format!(
+            "hook steps have a dependency cycle: {}",
+            cycle.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Run steps in dependency order, in parallel where possible
+///
+/// `run` executes a single step. Outcomes are returned in the order the
+/// steps are declared. The steps must have passed `validate_steps`.
+pub fn run_steps<F>(steps: &[HookStep], clock: &dyn Clock, run: F) -> Vec<StepOutcome>
+where
+    F: Fn(&HookStep) -> Result<()> + Sync,
+{
+    let index_of: HashMap<&str, usize> = steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| (step.name.as_str(), index))
+        .collect();
+
+    let mut started = vec![false; steps.len()];
+    let mut outcomes: Vec<Option<StepOutcome>> = steps.iter().map(|_| None).collect();
+    let mut aborted = false;
+
+    // A finished dependency lets dependents run if it succeeded or may fail
+    let satisfied = |outcome: &StepOutcome, step: &HookStep| match outcome.status {
+        StepStatus::Succeeded => true,
+        StepStatus::Failed(_) => step.continue_on_error,
+        StepStatus::Skipped => false,
+    };
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let mut running = 0;
+
+        loop {
+            // Start everything that's ready; skipping a step can unblock
+            // decisions about others, so repeat until nothing changes
+            let mut progress = !aborted;
+            while progress {
+                progress = false;
+                for index in 0..steps.len() {
+                    if started[index] {
+                        continue;
+                    }
+
+                    let mut ready = true;
+                    let mut blocked = false;
+                    for need in &steps[index].needs {
+                        let need_index = index_of[need.as_str()];
+                        match &outcomes[need_index] {
+                            Some(outcome) if satisfied(outcome, &steps[need_index]) => {}
+                            Some(_) => blocked = true,
+                            None => ready = false,
+                        }
+                    }
+
+                    if blocked {
+                        started[index] = true;
+                        outcomes[index] = Some(StepOutcome {
+                            name: steps[index].name.clone(),
+                            status: StepStatus::Skipped,
+                            duration: Duration::ZERO,
+                        });
+                        progress = true;
+                    } else if ready {
+                        started[index] = true;
+                        running += 1;
+
+                        let sender = sender.clone();
+                        let step = &steps[index];
+                        let run = &run;
+                        scope.spawn(move || {
+                            let start = clock.now();
+                            let result = run(step);
+                            let duration = clock.now().duration_since(start);
+                            let _ = sender.send((index, result, duration));
+                        });
+                    }
+                }
+            }
+
+            if running == 0 {
+                break;
+            }
+
+            let Ok((index, result, duration)) = receiver.recv() else {
+                break;
+            };
+            running -= 1;
+
+            let status = match result {
+                Ok(()) => StepStatus::Succeeded,
+                Err(e) => {
+                    if !steps[index].continue_on_error {
+                        aborted = true;
+                    }
+                    StepStatus::Failed(e)
+                }
+            };
+            outcomes[index] = Some(StepOutcome {
+                name: steps[index].name.clone(),
+                status,
+                duration,
+            });
+        }
+    });
+
+    outcomes
+        .into_iter()
+        .zip(steps)
+        .map(|(outcome, step)| {
+            outcome.unwrap_or_else(|| StepOutcome {
+                name: step.name.clone(),
+                status: StepStatus::Skipped,
+                duration: Duration::ZERO,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use std::sync::Mutex;
+
+    fn step(name: &str, needs: &[&str]) -> HookStep {
+        HookStep {
+            name: name.to_string(),
+            run: String::new(),
+            needs: needs.iter().map(|n| n.to_string()).collect(),
+            timeout_seconds: None,
+            working_dir: None,
+            env: HashMap::new(),
+            continue_on_error: false,
+        }
+    }
+
+    fn statuses(outcomes: &[StepOutcome]) -> Vec<(&str, &'static str)> {
+        outcomes
+            .iter()
+            .map(|o| {
+                let status = match o.status {
+                    StepStatus::Succeeded => "ok",
+                    StepStatus::Failed(_) => "failed",
+                    StepStatus::Skipped => "skipped",
+                };
+                (o.name.as_str(), status)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_accepts_dag() {
+        let steps = vec![
+            step("install", &[]),
+            step("codegen", &["install"]),
+            step("db", &[]),
+            step("seed", &["db", "codegen"]),
+        ];
+        assert!(validate_steps(&steps).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_graphs() {
+        let err = |steps: Vec<HookStep>| validate_steps(&steps).unwrap_err().to_string();
+
+        assert!(err(vec![]).contains("at least one step"));
+        assert!(err(vec![step("a", &[]), step("a", &[])]).contains("duplicate"));
+        assert!(err(vec![step("a", &["a"])]).contains("needs itself"));
+        assert!(err(vec![step("a", &["missing"])]).contains("unknown step 'missing'"));
+        assert!(err(vec![
+            step("a", &["c"]),
+            step("b", &["a"]),
+            step("c", &["b"]),
+            step("d", &[]),
+        ])
+        .contains("cycle: a, b, c"));
+    }
+
+    #[test]
+    fn test_dependencies_run_first() {
+        let steps = vec![
+            step("seed", &["migrate"]),
+            step("migrate", &["install"]),
+            step("install", &[]),
+        ];
+        let order = Mutex::new(Vec::new());
+
+        let outcomes = run_steps(&steps, &SystemClock, |step| {
+            order.lock().unwrap().push(step.name.clone());
+            Ok(())
+        });
+
+        assert_eq!(
+            order.into_inner().unwrap(),
+            vec!["install", "migrate", "seed"]
+        );
+        assert!(outcomes
+            .iter()
+            .all(|o| matches!(o.status, StepStatus::Succeeded)));
+    }
+
+    #[test]
+    fn test_independent_steps_run_in_parallel() {
+        let steps = vec![step("a", &[]), step("b", &[])];
+        let barrier = std::sync::Barrier::new(2);
+
+        // Each step waits for the other to start; this deadlocks if serial
+        let outcomes = run_steps(&steps, &SystemClock, |_| {
+            barrier.wait();
+            Ok(())
+        });
+
+        assert_eq!(statuses(&outcomes), vec![("a", "ok"), ("b", "ok")]);
+    }
+
+    #[test]
+    fn test_failure_skips_dependents_and_stops_scheduling() {
+        let steps = vec![
+            step("install", &[]),
+            step("build", &["install"]),
+            step("test", &["build"]),
+        ];
+
+        let outcomes = run_steps(&steps, &SystemClock, |step| {
+            if step.name == "install" {
+                Err(HnError::HookError("boom".to_string()))
+            } else {
+                Ok(())
+            }
+        });
+
+        assert_eq!(
+            statuses(&outcomes),
+            vec![
+                ("install", "failed"),
+                ("build", "skipped"),
+                ("test", "skipped")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_continue_on_error_lets_dependents_run() {
+        let mut lint = step("lint", &[]);
+        lint.continue_on_error = true;
+        let steps = vec![lint, step("build", &["lint"])];
+
+        let outcomes = run_steps(&steps, &SystemClock, |step| {
+            if step.name == "lint" {
+                Err(HnError::HookError("lint failed".to_string()))
+            } else {
+                Ok(())
+            }
+        });
+
+        assert_eq!(
+            statuses(&outcomes),
+            vec![("lint", "failed"), ("build", "ok")]
+        );
+    }
+}
@@ -4,15 +4,19 @@
 pub mod cli;
 pub mod clock;
 pub mod config;
+pub mod conflict;
+pub mod diff;
 pub mod docker;
 pub mod env;
 pub mod errors;
 pub mod fuzzy;
 pub mod hooks;
 pub mod monitoring;
+pub mod restack;
 pub mod snapshot;
 pub mod state;
 pub mod suggestions;
 pub mod tags;
 pub mod templates;
+pub mod trust;
 pub mod vcs;
@@ -4,17 +4,21 @@ use clap_complete::Shell;
 mod cli;
 mod clock;
 mod config;
+mod conflict;
+mod diff;
 mod docker;
 mod env;
 mod errors;
 mod fuzzy;
 mod hooks;
 mod monitoring;
+mod restack;
 mod snapshot;
 mod state;
 mod suggestions;
 mod tags;
 mod templates;
+mod trust;
 mod vcs;
 
 #[derive(Parser)]
@@ -30,6 +34,10 @@ struct Cli {
     #[arg(long, global = true, value_name = "TYPE")]
     vcs: Option<String>,
 
+    /// Output format for list, info, stats and templates list
+    #[arg(long, global = true, value_enum, default_value_t = cli::output::OutputFormat::Table)]
+    format: cli::output::OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -70,6 +78,9 @@ enum Commands {
         /// Filter by tag
         #[arg(long)]
         tag: Option<String>,
+        /// Only show worktrees behind the default branch by more than N commits
+        #[arg(long, value_name = "N")]
+        stale: Option<usize>,
     },
     /// Remove a worktree
     Remove {
@@ -79,6 +90,28 @@ enum Commands {
         #[arg(short, long)]
         force: bool,
     },
+    /// Rename a worktree, moving its directory, state, tags, snapshots and ports
+    Rename {
+        /// Current name of the worktree
+        old_name: String,
+        /// New name for the worktree
+        new_name: String,
+        /// Rename the worktree's branch to the new name too
+        #[arg(long)]
+        branch: bool,
+    },
+    /// Register worktrees created outside hn (e.g. with plain `git worktree add`)
+    Adopt {
+        /// Path of the worktree to adopt
+        #[arg(required_unless_present = "all")]
+        path: Option<std::path::PathBuf>,
+        /// Adopt every worktree hn doesn't manage yet
+        #[arg(long, conflicts_with = "path")]
+        all: bool,
+        /// Run the post_create hook in adopted worktrees
+        #[arg(long)]
+        run_hooks: bool,
+    },
     /// Switch to a worktree (outputs path for shell wrapper)
     Switch {
         /// Name of the worktree to switch to
@@ -138,6 +171,9 @@ enum Commands {
         /// Merge strategy (e.g., 'recursive', 'ours', 'theirs')
         #[arg(long)]
         strategy: Option<String>,
+        /// Abort the merge instead of stopping on conflicts
+        #[arg(long)]
+        abort_on_conflict: bool,
     },
     /// Sync current worktree with another branch
     Sync {
@@ -152,6 +188,30 @@ enum Commands {
         /// Don't automatically commit after merge
         #[arg(long)]
         no_commit: bool,
+        /// Abort the merge/rebase (restoring the autostash) instead of stopping on conflicts
+        #[arg(long)]
+        abort_on_conflict: bool,
+        /// Finish a sync that stopped on conflicts, once they're resolved
+        #[arg(long = "continue", conflicts_with_all = ["source_branch", "strategy", "autostash", "no_commit", "abort_on_conflict", "abort"])]
+        continue_sync: bool,
+        /// Give up on a sync that stopped on conflicts and restore the autostash
+        #[arg(long, conflicts_with_all = ["source_branch", "strategy", "autostash", "no_commit", "abort_on_conflict"])]
+        abort: bool,
+    },
+    /// Rebase child worktrees onto their parents after the parents move
+    Restack {
+        /// Restack every parent tree, not just the current worktree's descendants
+        #[arg(long)]
+        all: bool,
+        /// Print the rebases that would run without running them
+        #[arg(long)]
+        dry_run: bool,
+        /// Carry on with a restack that stopped on conflicts, once they're resolved
+        #[arg(long = "continue", conflicts_with_all = ["all", "dry_run", "abort"])]
+        continue_restack: bool,
+        /// Abort the rebase a restack stopped on and skip the rest
+        #[arg(long, conflicts_with_all = ["all", "dry_run"])]
+        abort: bool,
     },
     /// Output shell integration code for ~/.bashrc or ~/.zshrc
     InitShell,
@@ -183,6 +243,17 @@ enum Commands {
         #[command(subcommand)]
         command: PortsCommands,
     },
+    /// Run a local reverse proxy serving each worktree at <name>.<domain>
+    Proxy {
+        /// Port to listen on (defaults to docker.proxy.port, 8080)
+        #[arg(long)]
+        port: Option<u16>,
+        /// Domain worktree hostnames live under (defaults to docker.proxy.domain, localhost)
+        #[arg(long)]
+        domain: Option<String>,
+        #[command(subcommand)]
+        command: Option<ProxyCommands>,
+    },
     /// Manage Docker containers
     Docker {
         #[command(subcommand)]
@@ -252,6 +323,22 @@ enum Commands {
         #[arg(long)]
         limit: Option<usize>,
     },
+    /// Inspect hook runs
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+    /// Trust this repository's hooks (or manage trusted repositories)
+    Trust {
+        /// Stop trusting this repository's hooks
+        #[arg(long)]
+        revoke: bool,
+        /// Trust the hooks without asking for confirmation
+        #[arg(short, long, conflicts_with = "revoke")]
+        yes: bool,
+        #[command(subcommand)]
+        command: Option<TrustCommands>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -295,7 +382,11 @@ enum ConfigCommands {
 #[derive(Subcommand)]
 enum PortsCommands {
     /// List all port allocations
-    List,
+    List {
+        /// Show the machine-wide registry: ports of every repository
+        #[arg(long)]
+        global: bool,
+    },
     /// Show port allocations for a specific worktree
     Show {
         /// Name of the worktree
@@ -313,6 +404,12 @@ enum PortsCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum ProxyCommands {
+    /// List the hostnames the proxy routes and their ports
+    Routes,
+}
+
 #[derive(Subcommand)]
 enum DockerCommands {
     /// Show container status for all worktrees
@@ -320,12 +417,23 @@ enum DockerCommands {
     /// Start containers for a worktree
     Start {
         /// Name of the worktree
-        name: String,
+        #[arg(required_unless_present = "shared")]
+        name: Option<String>,
+        /// Start only the services shared by all worktrees
+        #[arg(long, conflicts_with = "name")]
+        shared: bool,
+        /// Wait until containers are healthy and readiness probes pass
+        #[arg(long, conflicts_with = "shared")]
+        wait: bool,
     },
     /// Stop containers for a worktree
     Stop {
         /// Name of the worktree
-        name: String,
+        #[arg(required_unless_present = "shared")]
+        name: Option<String>,
+        /// Stop the services shared by all worktrees
+        #[arg(long, conflicts_with = "name")]
+        shared: bool,
     },
     /// Restart containers for a worktree
     Restart {
@@ -351,13 +459,40 @@ enum DockerCommands {
     },
     /// Clean up orphaned containers
     Prune,
+    /// Stop containers of worktrees that haven't been used for a while
+    Reap {
+        /// Idle time after which containers are stopped, e.g. "2h"
+        /// (defaults to docker.idle_stop_after)
+        #[arg(long)]
+        after: Option<String>,
+        /// List what would be stopped without stopping it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the services and ports discovered from the compose files
+    Services {
+        /// Show this worktree's host ports too
+        name: Option<String>,
+    },
+    /// Rebuild override files after config or port changes
+    Regenerate {
+        /// Name of the worktree (defaults to current)
+        #[arg(conflicts_with = "all")]
+        name: Option<String>,
+        /// Regenerate every worktree
+        #[arg(long)]
+        all: bool,
+        /// Restart running containers whose override changed
+        #[arg(long)]
+        restart: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum TemplatesCommands {
     /// List all available templates
     List {
-        /// Output format (json or table)
+        /// Output as JSON (shorthand for --format json)
         #[arg(long)]
         json: bool,
     },
@@ -492,6 +627,27 @@ enum SnapshotCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum HooksCommands {
+    /// Show output from past hook runs
+    Logs {
+        /// Worktree name
+        worktree: String,
+        /// Only show this hook (e.g. post_create, or post_create:<step>)
+        #[arg(long)]
+        hook: Option<String>,
+        /// Keep printing new output as hooks run
+        #[arg(long, short)]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrustCommands {
+    /// List repositories with trusted hooks
+    List,
+}
+
 /// Resolve command aliases before parsing
 fn resolve_aliases() -> Vec<String> {
     let args: Vec<String> = std::env::args().collect();
@@ -527,14 +683,18 @@ fn resolve_aliases() -> Vec<String> {
     while command_idx < args.len() {
         let arg = &args[command_idx];
         // Skip global flags
-        if arg == "--no-hooks" || arg == "--vcs" {
+        if arg == "--no-hooks" || arg == "--vcs" || arg == "--format" {
             command_idx += 1;
-            // Skip --vcs value
-            if arg == "--vcs" && command_idx < args.len() {
+            // Skip --vcs/--format value
+            if arg != "--no-hooks" && command_idx < args.len() {
                 command_idx += 1;
             }
             continue;
         }
+        if arg.starts_with("--vcs=") || arg.starts_with("--format=") {
+            command_idx += 1;
+            continue;
+        }
         // Found the command
         break;
     }
@@ -647,15 +807,27 @@ fn main() {
             cli.no_hooks,
             vcs_type,
         ),
-        Commands::List { tree, tag } => cli::list::run(tree, tag, vcs_type),
+        Commands::List { tree, tag, stale } => {
+            cli::list::run(tree, tag, stale, cli.format, vcs_type)
+        }
         Commands::Remove { name, force } => cli::remove::run(name, force, cli.no_hooks, vcs_type),
+        Commands::Rename {
+            old_name,
+            new_name,
+            branch,
+        } => cli::rename::run(old_name, new_name, branch, vcs_type),
+        Commands::Adopt {
+            path,
+            all,
+            run_hooks,
+        } => cli::adopt::run(path, all, run_hooks, cli.no_hooks, vcs_type),
         Commands::Switch { name } => cli::switch::run(name, vcs_type),
         Commands::Return {
             merge,
             delete,
             no_ff,
         } => cli::return_cmd::run(merge, delete, no_ff, cli.no_hooks, vcs_type),
-        Commands::Info { name } => cli::info::run(name, vcs_type),
+        Commands::Info { name } => cli::info::run(name, cli.format, vcs_type),
         Commands::Each {
             command,
             parallel,
@@ -677,13 +849,55 @@ fn main() {
             no_ff,
             squash,
             strategy,
-        } => cli::integrate::run(source, into, no_ff, squash, strategy, vcs_type),
+            abort_on_conflict,
+        } => cli::integrate::run(
+            source,
+            into,
+            no_ff,
+            squash,
+            strategy,
+            abort_on_conflict,
+            vcs_type,
+        ),
         Commands::Sync {
             source_branch,
             strategy,
             autostash,
             no_commit,
-        } => cli::sync::run(source_branch, strategy, autostash, no_commit, vcs_type),
+            abort_on_conflict,
+            continue_sync,
+            abort,
+        } => {
+            if continue_sync {
+                cli::sync::continue_sync(cli.no_hooks, vcs_type)
+            } else if abort {
+                cli::sync::abort(vcs_type)
+            } else {
+                cli::sync::run(
+                    source_branch,
+                    strategy,
+                    autostash,
+                    no_commit,
+                    abort_on_conflict,
+                    cli.no_hooks,
+                    vcs_type,
+                )
+            }
+        }
+        Commands::Restack {
+            all,
+            dry_run,
+            continue_restack,
+            abort,
+        } => {
+            if continue_restack {
+                cli::restack::continue_restack(vcs_type)
+            } else if abort {
+                cli::restack::abort(vcs_type)
+            } else {
+                cli::restack::run(all, dry_run, vcs_type)
+            }
+        }
         Commands::InitShell => cli::init_shell::run(),
         Commands::Prune => cli::prune::run(),
         Commands::Completions { shell } => {
@@ -707,16 +921,36 @@ fn main() {
             ConfigCommands::Edit => cli::config_cmd::edit(),
         },
         Commands::Ports { command } => match command {
-            PortsCommands::List => cli::ports::list(),
+            PortsCommands::List { global } => {
+                if global {
+                    cli::ports::list_global()
+                } else {
+                    cli::ports::list()
+                }
+            }
             PortsCommands::Show { name } => cli::ports::show(name),
             PortsCommands::Release { name } => cli::ports::release(name),
             PortsCommands::Reassign { name } => cli::ports::reassign(name),
         },
+        Commands::Proxy {
+            port,
+            domain,
+            command,
+        } => match command {
+            Some(ProxyCommands::Routes) => cli::proxy::routes(),
+            None => cli::proxy::run(port, domain),
+        },
         Commands::Docker { command } => match command {
             DockerCommands::Ps => cli::docker::ps(),
-            DockerCommands::Start { name } => cli::docker::start(name),
-            DockerCommands::Stop { name } => cli::docker::stop(name),
-            DockerCommands::Restart { name } => cli::docker::restart(name),
+            DockerCommands::Start { name, shared, wait } => match name {
+                Some(name) if !shared => cli::docker::start(name, wait, cli.no_hooks),
+                _ => cli::docker::start_shared(),
+            },
+            DockerCommands::Stop { name, shared } => match name {
+                Some(name) if !shared => cli::docker::stop(name, cli.no_hooks),
+                _ => cli::docker::stop_shared(),
+            },
+            DockerCommands::Restart { name } => cli::docker::restart(name, cli.no_hooks),
             DockerCommands::Logs { name, service } => cli::docker::logs(name, service),
             DockerCommands::Exec {
                 name,
@@ -724,9 +958,23 @@ fn main() {
                 command,
             } => cli::docker::exec(name, service, command),
             DockerCommands::Prune => cli::docker::prune(),
+            DockerCommands::Reap { after, dry_run } => {
+                cli::docker::reap(after, dry_run, cli.no_hooks)
+            }
+            DockerCommands::Services { name } => cli::docker::services(name),
+            DockerCommands::Regenerate { name, all, restart } => {
+                cli::docker::regenerate(name, all, restart, cli.no_hooks)
+            }
         },
         Commands::Templates { command } => match command {
-            TemplatesCommands::List { json } => cli::templates::list(json),
+            TemplatesCommands::List { json } => {
+                let format = if json {
+                    cli::output::OutputFormat::Json
+                } else {
+                    cli.format
+                };
+                cli::templates::list(format)
+            }
             TemplatesCommands::Show { name } => cli::templates::show(&name),
             TemplatesCommands::Create {
                 name,
@@ -768,10 +1016,10 @@ fn main() {
             }
             SnapshotCommands::List { worktree } => cli::snapshot::list(worktree.as_deref()),
             SnapshotCommands::Restore { worktree, snapshot } => {
-                cli::snapshot::restore(&worktree, &snapshot, vcs_type)
+                cli::snapshot::restore(&worktree, &snapshot, cli.no_hooks, vcs_type)
             }
             SnapshotCommands::Delete { worktree, snapshot } => {
-                cli::snapshot::delete(&worktree, &snapshot)
+                cli::snapshot::delete(&worktree, &snapshot, vcs_type)
             }
         },
         Commands::Stats {
@@ -780,13 +1028,30 @@ fn main() {
             disk,
             history,
             days,
-        } => cli::stats::run(name, all, disk, history, days, vcs_type),
+        } => cli::stats::run(name, all, disk, history, days, cli.format, vcs_type),
         Commands::Tag { worktree, tags } => cli::tag::add(&worktree, &tags),
         Commands::Tags { worktree } => cli::tag::list(worktree.as_deref()),
-        Commands::Monitor { live, refresh } => cli::monitor::run(live, refresh, vcs_type),
+        Commands::Monitor { live, refresh } => {
+            cli::monitor::run(live, refresh, cli.no_hooks, vcs_type)
+        }
         Commands::Activity { name, since, limit } => {
             cli::activity::run(name, since, limit, vcs_type)
         }
+        Commands::Hooks { command } => match command {
+            HooksCommands::Logs {
+                worktree,
+                hook,
+                follow,
+            } => cli::hooks::logs(worktree, hook, follow, vcs_type),
+        },
+        Commands::Trust {
+            revoke,
+            yes,
+            command,
+        } => match command {
+            Some(TrustCommands::List) => cli::trust::list(),
+            None => cli::trust::run(revoke, yes),
+        },
     };
 
     // Handle errors with suggestions
@@ -5,7 +5,7 @@
 // - Historical metrics tracking
 // - Resource usage monitoring
 
-use crate::errors::Result;
+use crate::errors::{HnError, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -22,6 +22,14 @@ pub enum ActivityEvent {
     WorktreeRemoved {
         timestamp: u64,
     },
+    WorktreeRenamed {
+        timestamp: u64,
+        from: String,
+    },
+    WorktreeAdopted {
+        timestamp: u64,
+        branch: String,
+    },
     WorktreeSwitched {
         timestamp: u64,
         from: Option<String>,
@@ -104,7 +112,6 @@ impl ActivityLog {
     }
 
     /// Get last N events
-    #[allow(dead_code)] // Reserved for future `hn activity` command
     pub fn last_events(&self, count: usize) -> Vec<&ActivityEvent> {
         let start = if self.events.len() > count {
             self.events.len() - count
@@ -114,11 +121,35 @@ impl ActivityLog {
         self.events[start..].iter().collect()
     }
 
+    /// When the worktree was last used: switched to, or had its containers
+    /// started. Falls back to its creation (or adoption) for worktrees never
+    /// used since.
+    pub fn last_used(&self) -> Option<u64> {
+        let used = self
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ActivityEvent::WorktreeSwitched { timestamp, .. }
+                | ActivityEvent::DockerStarted { timestamp, .. } => Some(*timestamp),
+                _ => None,
+            })
+            .max();
+        used.or_else(|| {
+            self.events.iter().rev().find_map(|event| match event {
+                ActivityEvent::WorktreeCreated { timestamp, .. }
+                | ActivityEvent::WorktreeAdopted { timestamp, .. } => Some(*timestamp),
+                _ => None,
+            })
+        })
+    }
+
     #[allow(dead_code)] // Helper for public methods
     fn event_timestamp(&self, event: &ActivityEvent) -> u64 {
         match event {
             ActivityEvent::WorktreeCreated { timestamp, .. } => *timestamp,
             ActivityEvent::WorktreeRemoved { timestamp } => *timestamp,
+            ActivityEvent::WorktreeRenamed { timestamp, .. } => *timestamp,
+            ActivityEvent::WorktreeAdopted { timestamp, .. } => *timestamp,
             ActivityEvent::WorktreeSwitched { timestamp, .. } => *timestamp,
             ActivityEvent::DockerStarted { timestamp, .. } => *timestamp,
             ActivityEvent::DockerStopped { timestamp } => *timestamp,
@@ -235,6 +266,29 @@ pub fn log_activity(state_dir: &Path, worktree: &str, event: ActivityEvent) -> R
     Ok(())
 }
 
+/// Point a renamed worktree's activity log and metrics history, already in
+/// its new state directory, at its new name, and log the rename
+pub fn record_rename(state_dir: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    let metrics_path = get_metrics_path(state_dir, new_name);
+    if metrics_path.exists() {
+        let mut history = MetricsHistory::load(&metrics_path)?;
+        history.worktree = new_name.to_string();
+        history.save(&metrics_path)?;
+    }
+
+    let log_path = get_activity_log_path(state_dir, new_name);
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut log = ActivityLog::load(&log_path)?;
+    log.worktree = new_name.to_string();
+    log.add_event(ActivityEvent::WorktreeRenamed {
+        timestamp: now(),
+        from: old_name.to_string(),
+    });
+    log.save(&log_path)
+}
+
 /// Record a metrics snapshot for a worktree
 pub fn record_metrics(state_dir: &Path, worktree: &str, snapshot: MetricsSnapshot) -> Result<()> {
     let metrics_path = get_metrics_path(state_dir, worktree);
@@ -251,6 +305,31 @@ pub fn record_metrics(state_dir: &Path, worktree: &str, snapshot: MetricsSnapsho
     Ok(())
 }
 
+/// Parse a duration such as "90s", "30m", "2h" or "7d" into seconds
+pub fn parse_duration(duration: &str) -> Result<u64> {
+    let duration = duration.trim();
+    let invalid = || {
+        HnError::ConfigError(format!(
+            "Invalid duration '{}': use a number with s, m, h or d, e.g. '2h'",
+            duration
+        ))
+    };
+
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(split);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let factor = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+    Ok(value * factor)
+}
+
 /// Get current timestamp in seconds
 pub fn now() -> u64 {
     std::time::SystemTime::now()
@@ -282,6 +361,41 @@ mod tests {
         assert_eq!(loaded.events.len(), 1);
     }
 
+    #[test]
+    fn test_last_used() {
+        let mut log = ActivityLog::new("test".to_string());
+        assert_eq!(log.last_used(), None);
+
+        log.add_event(ActivityEvent::WorktreeCreated {
+            timestamp: 1000,
+            branch: "main".to_string(),
+            template: None,
+        });
+        assert_eq!(log.last_used(), Some(1000));
+
+        log.add_event(ActivityEvent::DockerStarted {
+            timestamp: 3000,
+            services: vec![],
+        });
+        log.add_event(ActivityEvent::WorktreeSwitched {
+            timestamp: 2000,
+            from: None,
+        });
+        log.add_event(ActivityEvent::DockerStopped { timestamp: 4000 });
+        assert_eq!(log.last_used(), Some(3000));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("30m").unwrap(), 1800);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("7d").unwrap(), 604800);
+        assert!(parse_duration("2").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("2w").is_err());
+    }
+
     #[test]
     fn test_metrics_history() {
         let temp = TempDir::new().unwrap();
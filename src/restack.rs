@@ -0,0 +1,232 @@
+// Restacking child worktrees onto their parents
+//
+// Worktrees created from inside another worktree record it as their parent.
+// When a parent gains commits or is rebased, `hn restack` rebases its
+// descendants onto it, parents before children. While a rebase waits on
+// conflicts, the steps still to do are kept in `.hn-state/restack.json` so
+// `hn restack --continue` can pick up where it stopped.
+//
+// Each child also remembers the parent commit it was last based on, in
+// `.hn-state/<name>/restack-base`. Only the child's commits since then are
+// rebased, so a parent that was amended or rebased doesn't get its old
+// commits replayed onto the new ones.
+
+use crate::errors::{HnError, Result};
+use crate::vcs::traits::VcsBackend;
+use crate::vcs::Worktree;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const PLAN_FILE: &str = "restack.json";
+const BASE_FILE: &str = "restack-base";
+
+/// Rebase of one worktree onto the branch of its parent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestackStep {
+    pub worktree: String,
+    pub parent: String,
+    /// Branch of the parent worktree
+    pub onto: String,
+}
+
+/// A restack that stopped on conflicts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestackPlan {
+    /// Steps still to do, starting with the one that stopped
+    pub steps: Vec<RestackStep>,
+    /// Unix timestamp of when the restack started
+    pub started_at: u64,
+    /// Worktrees rebased before the restack stopped
+    #[serde(default)]
+    pub restacked: usize,
+}
+
+impl RestackPlan {
+    /// Load the stopped restack from the `.hn-state` directory, if any
+    pub fn load(state_root: &Path) -> Result<Option<Self>> {
+        let path = state_root.join(PLAN_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let plan = serde_json::from_str(&content)
+            .map_err(|e| HnError::StateError(format!("Failed to parse restack plan: {}", e)))?;
+        Ok(Some(plan))
+    }
+
+    pub fn save(&self, state_root: &Path) -> Result<()> {
+        fs::create_dir_all(state_root)?;
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(state_root.join(PLAN_FILE), content)?;
+        Ok(())
+    }
+
+    /// Forget the stopped restack, if any
+    pub fn clear(state_root: &Path) -> Result<()> {
+        let path = state_root.join(PLAN_FILE);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// The parent commit a worktree was last based on, from its state directory
+pub fn load_base(state_dir: &Path) -> Result<Option<String>> {
+    let path = state_dir.join(BASE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let base = fs::read_to_string(path)?.trim().to_string();
+    Ok(Some(base).filter(|base| !base.is_empty()))
+}
+
+/// Remember `parent_commit` as the base of `worktree` if the worktree
+/// contains it, and forget any earlier base otherwise
+pub fn record_base(
+    backend: &dyn VcsBackend,
+    state_dir: &Path,
+    worktree: &Worktree,
+    parent_commit: &str,
+) -> Result<()> {
+    let path = state_dir.join(BASE_FILE);
+    let contains = backend
+        .ahead_behind(worktree, parent_commit)?
+        .is_some_and(|counts| counts.behind == 0);
+    if contains {
+        fs::create_dir_all(state_dir)?;
+        fs::write(path, parent_commit)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Worktrees at the top of a parent tree: no parent, or one that's gone
+pub fn roots(worktrees: &[Worktree]) -> Vec<&str> {
+    let names: HashSet<&str> = worktrees.iter().map(|wt| wt.name.as_str()).collect();
+    worktrees
+        .iter()
+        .filter(|wt| {
+            wt.parent
+                .as_deref()
+                .is_none_or(|parent| !names.contains(parent))
+        })
+        .map(|wt| wt.name.as_str())
+        .collect()
+}
+
+/// Rebase steps for the descendants of `roots`, each parent before its
+/// children
+///
+/// The roots themselves are not rebased.
+pub fn plan(worktrees: &[Worktree], roots: &[&str]) -> Vec<RestackStep> {
+    fn visit(
+        parent: &Worktree,
+        worktrees: &[Worktree],
+        seen: &mut HashSet<String>,
+        steps: &mut Vec<RestackStep>,
+    ) {
+        for child in worktrees
+            .iter()
+            .filter(|wt| wt.parent.as_deref() == Some(parent.name.as_str()))
+        {
+            // A parent pointer cycle would otherwise recurse forever
+            if !seen.insert(child.name.clone()) {
+                continue;
+            }
+            steps.push(RestackStep {
+                worktree: child.name.clone(),
+                parent: parent.name.clone(),
+                onto: parent.branch.clone(),
+            });
+            visit(child, worktrees, seen, steps);
+        }
+    }
+
+    let mut seen: HashSet<String> = roots.iter().map(|root| root.to_string()).collect();
+    let mut steps = Vec::new();
+    for root in roots {
+        if let Some(root) = worktrees.iter().find(|wt| wt.name == *root) {
+            visit(root, worktrees, &mut seen, &mut steps);
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn worktree(name: &str, parent: Option<&str>) -> Worktree {
+        Worktree {
+            name: name.to_string(),
+            path: PathBuf::from("/tmp").join(name),
+            branch: format!("{}-branch", name),
+            commit: "abc123".to_string(),
+            parent: parent.map(str::to_string),
+        }
+    }
+
+    fn order(steps: &[RestackStep]) -> Vec<&str> {
+        steps.iter().map(|step| step.worktree.as_str()).collect()
+    }
+
+    #[test]
+    fn test_plan_puts_parents_first() {
+        let worktrees = vec![
+            worktree("grandchild", Some("child")),
+            worktree("repo", None),
+            worktree("child", Some("feature")),
+            worktree("feature", None),
+            worktree("sibling", Some("feature")),
+        ];
+
+        let steps = plan(&worktrees, &roots(&worktrees));
+        assert_eq!(order(&steps), vec!["child", "grandchild", "sibling"]);
+        assert_eq!(steps[1].parent, "child");
+        assert_eq!(steps[1].onto, "child-branch");
+
+        let steps = plan(&worktrees, &["child"]);
+        assert_eq!(order(&steps), vec!["grandchild"]);
+    }
+
+    #[test]
+    fn test_orphans_are_roots_and_cycles_end() {
+        let worktrees = vec![
+            worktree("orphan", Some("removed")),
+            worktree("child", Some("orphan")),
+            worktree("a", Some("b")),
+            worktree("b", Some("a")),
+        ];
+
+        assert_eq!(roots(&worktrees), vec!["orphan"]);
+        assert_eq!(order(&plan(&worktrees, &["a"])), vec!["b"]);
+    }
+
+    #[test]
+    fn test_plan_save_load_clear() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(RestackPlan::load(temp.path()).unwrap(), None);
+
+        let plan = RestackPlan {
+            steps: vec![RestackStep {
+                worktree: "child".to_string(),
+                parent: "feature".to_string(),
+                onto: "feature".to_string(),
+            }],
+            started_at: 1,
+            restacked: 2,
+        };
+        plan.save(temp.path()).unwrap();
+        assert_eq!(RestackPlan::load(temp.path()).unwrap(), Some(plan));
+
+        RestackPlan::clear(temp.path()).unwrap();
+        assert_eq!(RestackPlan::load(temp.path()).unwrap(), None);
+    }
+}
@@ -2,10 +2,11 @@
 // Allows saving uncommitted changes and repository state
 
 use crate::errors::{HnError, Result};
+use crate::vcs::traits::VcsBackend;
+use crate::vcs::Worktree;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Snapshot {
@@ -70,6 +71,25 @@ impl SnapshotIndex {
             .find(|s| s.worktree == worktree && s.name == name)
     }
 
+    /// Move a worktree's snapshots over to its new name, and those of its
+    /// renamed branch over to the new branch name
+    pub fn rename_worktree(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        old_branch: &str,
+        new_branch: &str,
+    ) {
+        for snapshot in &mut self.snapshots {
+            if snapshot.worktree == old_name {
+                snapshot.worktree = new_name.to_string();
+                if snapshot.branch == old_branch {
+                    snapshot.branch = new_branch.to_string();
+                }
+            }
+        }
+    }
+
     pub fn remove(&mut self, worktree: &str, name: &str) -> bool {
         let original_len = self.snapshots.len();
         self.snapshots
@@ -79,7 +99,7 @@ impl SnapshotIndex {
 }
 
 /// Get snapshot index path for a worktree
-fn get_snapshot_index_path(state_dir: &Path) -> PathBuf {
+pub fn get_snapshot_index_path(state_dir: &Path) -> PathBuf {
     state_dir.join("snapshots.json")
 }
 
@@ -90,18 +110,20 @@ fn get_snapshot_index_path(state_dir: &Path) -> PathBuf {
 /// - Stable stash references: uses unique message-based stash identification
 /// - Non-destructive: working directory is not modified until snapshot is confirmed saved
 pub fn create_snapshot(
-    worktree_path: &Path,
-    worktree_name: &str,
+    backend: &dyn VcsBackend,
+    worktree: &Worktree,
     snapshot_name: Option<&str>,
     description: Option<&str>,
     state_dir: &Path,
 ) -> Result<Snapshot> {
+    let worktree_name = worktree.name.as_str();
+
     // Verify worktree exists
-    if !worktree_path.exists() {
+    if !worktree.path.exists() {
         return Err(HnError::WorktreeNotFound(worktree_name.to_string()));
     }
 
-    // Generate snapshot name and timestamp FIRST (before any git operations)
+    // Generate snapshot name and timestamp FIRST (before any VCS operations)
     let created_at = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
@@ -123,65 +145,15 @@ pub fn create_snapshot(
         )));
     }
 
-    // Get current branch
-    let branch_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("rev-parse")
-        .arg("--abbrev-ref")
-        .arg("HEAD")
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to get branch: {}", e)))?;
-
-    if !branch_output.status.success() {
-        return Err(HnError::CommandFailed(
-            "Failed to get current branch".to_string(),
-        ));
-    }
-
-    let branch = String::from_utf8(branch_output.stdout)
-        .map_err(|e| HnError::CommandFailed(format!("Invalid UTF-8 in branch name: {}", e)))?
-        .trim()
-        .to_string();
-
-    // Get current commit
-    let commit_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("rev-parse")
-        .arg("HEAD")
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to get commit: {}", e)))?;
-
-    if !commit_output.status.success() {
-        return Err(HnError::CommandFailed(
-            "Failed to get current commit".to_string(),
-        ));
-    }
-
-    let commit = String::from_utf8(commit_output.stdout)
-        .map_err(|e| HnError::CommandFailed(format!("Invalid UTF-8 in commit hash: {}", e)))?
-        .trim()
-        .to_string();
-
     // Check for uncommitted changes
-    let status_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("status")
-        .arg("--short")
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to get status: {}", e)))?;
-
-    let status = String::from_utf8(status_output.stdout).unwrap_or_default();
-    let has_uncommitted = !status.trim().is_empty();
+    let has_uncommitted = !backend.get_workspace_status(&worktree.path)?.is_clean();
 
     // Create snapshot object BEFORE any destructive operations
     let snapshot = Snapshot {
         name: name.clone(),
         worktree: worktree_name.to_string(),
-        branch,
-        commit,
+        branch: worktree.branch.clone(),
+        commit: worktree.commit.clone(),
         stash_ref: None, // Will be set if we create a stash
         has_uncommitted,
         created_at,
@@ -203,28 +175,14 @@ pub fn create_snapshot(
         );
 
         // Include untracked files in stash
-        let stash_output = Command::new("git")
-            .arg("-C")
-            .arg(worktree_path)
-            .arg("stash")
-            .arg("push")
-            .arg("--include-untracked")
-            .arg("-m")
-            .arg(&stash_message)
-            .output()
-            .map_err(|e| {
-                // Rollback: remove snapshot from index
-                let _ = delete_snapshot(worktree_name, &name, state_dir);
-                HnError::CommandFailed(format!("Failed to stash changes: {}", e))
-            })?;
-
-        if !stash_output.status.success() {
-            let stderr = String::from_utf8_lossy(&stash_output.stderr);
+        if let Err(e) = backend.stash(&worktree.path, &stash_message, true) {
             // Rollback: remove snapshot from index
-            let _ = delete_snapshot(worktree_name, &name, state_dir);
+            let mut index = SnapshotIndex::load(&index_path)?;
+            index.remove(worktree_name, &name);
+            index.save(&index_path)?;
             return Err(HnError::CommandFailed(format!(
                 "Failed to stash changes: {}",
-                stderr
+                e
             )));
         }
 
@@ -273,11 +231,13 @@ pub fn list_snapshots(state_dir: &Path, worktree: Option<&str>) -> Result<Vec<Sn
 /// - Uses message-based stash lookup for reliability
 /// - Provides clear error messages if restoration fails
 pub fn restore_snapshot(
-    worktree_path: &Path,
-    worktree_name: &str,
+    backend: &dyn VcsBackend,
+    worktree: &Worktree,
     snapshot_name: &str,
     state_dir: &Path,
 ) -> Result<()> {
+    let worktree_name = worktree.name.as_str();
+
     // Load snapshot from index
     let index_path = get_snapshot_index_path(state_dir);
     let index = SnapshotIndex::load(&index_path)?;
@@ -293,106 +253,40 @@ pub fn restore_snapshot(
         .clone(); // Clone to avoid lifetime issues
 
     // Verify worktree exists
-    if !worktree_path.exists() {
+    if !worktree.path.exists() {
         return Err(HnError::WorktreeNotFound(worktree_name.to_string()));
     }
 
     // Check for uncommitted changes
-    let status_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("status")
-        .arg("--short")
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to get status: {}", e)))?;
-
-    let status = String::from_utf8(status_output.stdout).unwrap_or_default();
-    if !status.trim().is_empty() {
+    if !backend.get_workspace_status(&worktree.path)?.is_clean() {
         return Err(HnError::ConfigError(
             "Worktree has uncommitted changes. Commit or stash them first.".to_string(),
         ));
     }
 
-    // Checkout the branch
-    let checkout_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("checkout")
-        .arg(&snapshot.branch)
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to checkout branch: {}", e)))?;
-
-    if !checkout_output.status.success() {
-        let stderr = String::from_utf8_lossy(&checkout_output.stderr);
-        return Err(HnError::CommandFailed(format!(
-            "Failed to checkout branch: {}",
-            stderr
-        )));
-    }
+    // Go back to the branch and commit
+    backend
+        .checkout_commit(&worktree.path, &snapshot.branch, &snapshot.commit)
+        .map_err(|e| HnError::CommandFailed(e.to_string()))?;
 
-    // Reset to the commit
-    let reset_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("reset")
-        .arg("--hard")
-        .arg(&snapshot.commit)
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to reset to commit: {}", e)))?;
-
-    if !reset_output.status.success() {
-        let stderr = String::from_utf8_lossy(&reset_output.stderr);
-        return Err(HnError::CommandFailed(format!(
-            "Failed to reset to commit: {}",
-            stderr
-        )));
-    }
-
-    // Restore stash if present
+    // Restore stash if present, found by message (stable reference)
     if let Some(ref stash_message) = snapshot.stash_ref {
-        // Find stash by message (stable reference)
-        let stash_list_output = Command::new("git")
-            .arg("-C")
-            .arg(worktree_path)
-            .arg("stash")
-            .arg("list")
-            .output()
-            .map_err(|e| HnError::CommandFailed(format!("Failed to list stashes: {}", e)))?;
-
-        let stash_list = String::from_utf8(stash_list_output.stdout).unwrap_or_default();
-
-        // Find the stash entry by looking for our unique message
-        let mut stash_index: Option<usize> = None;
-        for (idx, line) in stash_list.lines().enumerate() {
-            if line.contains(stash_message) {
-                stash_index = Some(idx);
-                break;
+        match backend.unstash(&worktree.path, stash_message, true) {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("Warning: Stash for snapshot not found");
+                eprintln!("The snapshot commit was restored successfully.");
+                eprintln!("Uncommitted changes from snapshot time may have been lost.");
+                eprintln!("Stash message: {}", stash_message);
             }
-        }
-
-        if let Some(idx) = stash_index {
-            // Apply stash by index (stash@{N})
-            let stash_ref = format!("stash@{{{}}}", idx);
-            let stash_output = Command::new("git")
-                .arg("-C")
-                .arg(worktree_path)
-                .arg("stash")
-                .arg("apply")
-                .arg(&stash_ref)
-                .output()
-                .map_err(|e| HnError::CommandFailed(format!("Failed to apply stash: {}", e)))?;
-
-            if !stash_output.status.success() {
-                let stderr = String::from_utf8_lossy(&stash_output.stderr);
-                eprintln!("Warning: Failed to restore uncommitted changes: {}", stderr);
+            Err(e) => {
+                eprintln!("Warning: Failed to restore uncommitted changes: {}", e);
                 eprintln!("The snapshot commit was restored successfully.");
-                eprintln!("You can try manually: git stash apply {}", stash_ref);
+                eprintln!(
+                    "The changes are still stashed under the message: {}",
+                    stash_message
+                );
             }
-        } else {
-            eprintln!("Warning: Stash for snapshot not found in git stash list");
-            eprintln!("The snapshot commit was restored successfully.");
-            eprintln!("Uncommitted changes from snapshot time may have been lost.");
-            eprintln!("Stash message: {}", stash_message);
         }
     }
 
@@ -402,10 +296,15 @@ pub fn restore_snapshot(
 /// Delete a snapshot
 ///
 /// Safety guarantees:
-/// - Cleans up associated git stash to prevent accumulation
+/// - Cleans up associated stash to prevent accumulation
 /// - Gracefully handles missing stashes (warns but doesn't fail)
 /// - Provides detailed feedback on cleanup status
-pub fn delete_snapshot(worktree_name: &str, snapshot_name: &str, state_dir: &Path) -> Result<()> {
+pub fn delete_snapshot(
+    backend: &dyn VcsBackend,
+    worktree_name: &str,
+    snapshot_name: &str,
+    state_dir: &Path,
+) -> Result<()> {
     let index_path = get_snapshot_index_path(state_dir);
     let mut index = SnapshotIndex::load(&index_path)?;
 
@@ -424,93 +323,20 @@ pub fn delete_snapshot(worktree_name: &str, snapshot_name: &str, state_dir: &Pat
     index.remove(worktree_name, snapshot_name);
     index.save(&index_path)?;
 
-    // Clean up associated git stash if present
+    // Clean up associated stash if the worktree is still around
     if let Some(ref stash_message) = snapshot.stash_ref {
-        // Find the worktree path using git worktree list
-        let repo_root = state_dir
-            .parent()
-            .ok_or_else(|| HnError::ConfigError("Invalid state directory path".to_string()))?;
-
-        // Use git worktree list to find the actual worktree path
-        let worktree_list_output = Command::new("git")
-            .arg("-C")
-            .arg(repo_root)
-            .arg("worktree")
-            .arg("list")
-            .arg("--porcelain")
-            .output();
-
-        if let Ok(output) = worktree_list_output {
-            let worktree_list = String::from_utf8(output.stdout).unwrap_or_default();
-
-            // Parse worktree list to find our worktree
-            let mut found_worktree_path: Option<std::path::PathBuf> = None;
-            for line in worktree_list.lines() {
-                if line.starts_with("worktree ") {
-                    let path = std::path::PathBuf::from(line.trim_start_matches("worktree "));
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if name == worktree_name {
-                            found_worktree_path = Some(path);
-                            break;
-                        }
-                    }
-                }
-            }
-
-            // If we found the worktree, try to clean up its stash
-            if let Some(worktree_path) = found_worktree_path {
-                // List stashes to find our stash
-                let stash_list_output = Command::new("git")
-                    .arg("-C")
-                    .arg(&worktree_path)
-                    .arg("stash")
-                    .arg("list")
-                    .output();
-
-                if let Ok(output) = stash_list_output {
-                    let stash_list = String::from_utf8(output.stdout).unwrap_or_default();
-
-                    // Find the stash index by message
-                    let mut stash_index: Option<usize> = None;
-                    for (idx, line) in stash_list.lines().enumerate() {
-                        if line.contains(stash_message) {
-                            stash_index = Some(idx);
-                            break;
-                        }
-                    }
-
-                    // Drop the stash if found
-                    if let Some(idx) = stash_index {
-                        let stash_ref = format!("stash@{{{}}}", idx);
-                        let drop_output = Command::new("git")
-                            .arg("-C")
-                            .arg(&worktree_path)
-                            .arg("stash")
-                            .arg("drop")
-                            .arg(&stash_ref)
-                            .output();
-
-                        match drop_output {
-                            Ok(output) if output.status.success() => {
-                                // Successfully dropped stash
-                            }
-                            Ok(output) => {
-                                let stderr = String::from_utf8_lossy(&output.stderr);
-                                eprintln!(
-                                    "Warning: Failed to drop associated git stash: {}",
-                                    stderr
-                                );
-                                eprintln!(
-                                    "You may want to manually clean up: git stash drop {}",
-                                    stash_ref
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!("Warning: Failed to execute git stash drop: {}", e);
-                            }
-                        }
-                    }
-                }
+        let worktree = backend
+            .list_workspaces()?
+            .into_iter()
+            .find(|wt| wt.name == worktree_name);
+
+        if let Some(worktree) = worktree {
+            if let Err(e) = backend.drop_stash(&worktree.path, stash_message) {
+                eprintln!("Warning: Failed to drop associated stash: {}", e);
+                eprintln!(
+                    "You may want to clean it up manually; its message is: {}",
+                    stash_message
+                );
             }
         }
     }
@@ -518,11 +344,33 @@ pub fn delete_snapshot(worktree_name: &str, snapshot_name: &str, state_dir: &Pat
     Ok(())
 }
 
+/// Move a worktree's snapshots over to its new name (and branch)
+pub fn rename_worktree(
+    state_dir: &Path,
+    old_name: &str,
+    new_name: &str,
+    old_branch: &str,
+    new_branch: &str,
+) -> Result<()> {
+    let index_path = get_snapshot_index_path(state_dir);
+    if !index_path.exists() {
+        return Ok(());
+    }
+
+    let mut index = SnapshotIndex::load(&index_path)?;
+    index.rename_worktree(old_name, new_name, old_branch, new_branch);
+    index.save(&index_path)
+}
+
 /// Clean up orphaned stashes for deleted snapshots
 ///
-/// This maintenance function scans git stashes and removes any that belong
-/// to snapshots that no longer exist in the index.
-pub fn cleanup_orphaned_stashes(state_dir: &Path, worktree_path: &Path) -> Result<usize> {
+/// This maintenance function scans the worktree's stashes and removes any
+/// that belong to snapshots that no longer exist in the index.
+pub fn cleanup_orphaned_stashes(
+    backend: &dyn VcsBackend,
+    state_dir: &Path,
+    worktree_path: &Path,
+) -> Result<usize> {
     let index_path = get_snapshot_index_path(state_dir);
     let index = SnapshotIndex::load(&index_path)?;
 
@@ -533,50 +381,20 @@ pub fn cleanup_orphaned_stashes(state_dir: &Path, worktree_path: &Path) -> Resul
         .filter_map(|s| s.stash_ref.clone())
         .collect();
 
-    // List all stashes
-    let stash_list_output = Command::new("git")
-        .arg("-C")
-        .arg(worktree_path)
-        .arg("stash")
-        .arg("list")
-        .output()
-        .map_err(|e| HnError::CommandFailed(format!("Failed to list stashes: {}", e)))?;
-
-    let stash_list = String::from_utf8(stash_list_output.stdout).unwrap_or_default();
-
     // Find orphaned hannahanna stashes
-    let mut orphaned_indices = Vec::new();
-    for (idx, line) in stash_list.lines().enumerate() {
-        // Check if this is a hannahanna stash
-        if line.contains("hannahanna-snapshot:") {
-            // Extract the message
-            let message_start = line.find(": ").map(|i| i + 2);
-            if let Some(start) = message_start {
-                let message = &line[start..];
-                // Check if this stash message is in our valid set
-                if !valid_stash_messages.iter().any(|m| message.contains(m)) {
-                    orphaned_indices.push(idx);
-                }
-            }
-        }
-    }
+    let orphaned: Vec<String> = backend
+        .stash_messages(worktree_path)
+        .map_err(|e| HnError::CommandFailed(format!("Failed to list stashes: {}", e)))?
+        .into_iter()
+        .filter(|message| message.starts_with("hannahanna-snapshot:"))
+        .filter(|message| !valid_stash_messages.contains(message))
+        .collect();
 
-    // Drop orphaned stashes (in reverse order to maintain indices)
+    // Drop orphaned stashes
     let mut cleaned = 0;
-    for idx in orphaned_indices.iter().rev() {
-        let stash_ref = format!("stash@{{{}}}", idx);
-        let drop_output = Command::new("git")
-            .arg("-C")
-            .arg(worktree_path)
-            .arg("stash")
-            .arg("drop")
-            .arg(&stash_ref)
-            .output();
-
-        if let Ok(output) = drop_output {
-            if output.status.success() {
-                cleaned += 1;
-            }
+    for message in &orphaned {
+        if let Ok(true) = backend.drop_stash(worktree_path, message) {
+            cleaned += 1;
         }
     }
 
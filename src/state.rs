@@ -86,6 +86,17 @@ impl StateManager {
         Ok(())
     }
 
+    /// Move a worktree's state directory over to its new name
+    pub fn rename_state_dir(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let old_dir = self.state_root.join(old_name);
+
+        if old_dir.exists() {
+            fs::rename(&old_dir, self.state_root.join(new_name))?;
+        }
+
+        Ok(())
+    }
+
     /// List all worktrees that have state directories
     pub fn list_worktrees(&self) -> Result<Vec<String>> {
         let mut worktrees = Vec::new();
@@ -95,6 +95,20 @@ impl TagIndex {
         }
     }
 
+    /// Move a worktree's tags over to its new name
+    pub fn rename_worktree(&mut self, old_name: &str, new_name: &str) {
+        let Some(tags) = self.worktrees.remove(old_name) else {
+            return;
+        };
+        for tag in &tags {
+            if let Some(tag_worktrees) = self.tags.get_mut(tag) {
+                tag_worktrees.remove(old_name);
+                tag_worktrees.insert(new_name.to_string());
+            }
+        }
+        self.worktrees.insert(new_name.to_string(), tags);
+    }
+
     /// Get all tags for a worktree
     pub fn get_worktree_tags(&self, worktree: &str) -> Vec<String> {
         self.worktrees
@@ -186,6 +200,13 @@ pub fn remove_tags(state_dir: &Path, worktree: &str, tags: &[String]) -> Result<
     Ok(())
 }
 
+/// Move a worktree's tags over to its new name
+pub fn rename_worktree(state_dir: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    let mut index = TagIndex::load(state_dir)?;
+    index.rename_worktree(old_name, new_name);
+    index.save(state_dir)
+}
+
 /// Get all tags for a worktree
 pub fn get_worktree_tags(state_dir: &Path, worktree: &str) -> Result<Vec<String>> {
     let index = TagIndex::load(state_dir)?;
@@ -268,6 +289,28 @@ mod tests {
         assert_eq!(loaded.get_worktree_tags("wt1"), vec!["backend", "urgent"]);
     }
 
+    #[test]
+    fn test_rename_worktree() {
+        let mut index = TagIndex::default();
+        index.add_tags("wt1", &["backend".to_string(), "urgent".to_string()]);
+        index.add_tags("wt2", &["backend".to_string()]);
+
+        index.rename_worktree("wt1", "renamed");
+        index.rename_worktree("missing", "other");
+
+        assert!(index.get_worktree_tags("wt1").is_empty());
+        assert_eq!(
+            index.get_worktree_tags("renamed"),
+            vec!["backend", "urgent"]
+        );
+        assert_eq!(
+            index.get_worktrees_by_tag("backend"),
+            vec!["renamed", "wt2"]
+        );
+        assert_eq!(index.get_worktrees_by_tag("urgent"), vec!["renamed"]);
+        assert!(index.get_worktree_tags("other").is_empty());
+    }
+
     #[test]
     fn test_tag_validation() {
         assert!(validate_tag_name("backend").is_ok());
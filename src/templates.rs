@@ -457,13 +457,13 @@ pub fn validate_template(repo_root: &Path, template_name: &str) -> Result<Vec<St
         .map_err(|e| HnError::ConfigError(format!("Failed to parse config: {}", e)))?;
 
     if let Some(ref hook) = config.hooks.post_create {
-        if hook.trim().is_empty() {
+        if hook.is_empty() {
             warnings.push("post_create hook is empty".to_string());
         }
     }
 
     if let Some(ref hook) = config.hooks.pre_create {
-        if hook.trim().is_empty() {
+        if hook.is_empty() {
             warnings.push("pre_create hook is empty".to_string());
         }
     }
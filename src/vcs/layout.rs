@@ -0,0 +1,268 @@
+/// Worktree directory layout
+///
+/// Resolves where new worktrees are placed on disk from the `worktrees.root`
+/// config pattern, and maps existing worktree paths back to their names.
+///
+/// Supported placeholders:
+/// - `{name}`: worktree name (required)
+/// - `{repo}`: directory name of the main repository
+/// - `{repo_root}`: absolute path of the main repository
+///
+/// A leading `~` expands to the home directory and relative patterns are
+/// resolved against the repository root. The default pattern keeps the
+/// historical behaviour of placing worktrees next to the repository.
+use crate::config::Config;
+use crate::errors::{HnError, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// Pattern used when `worktrees.root` is not configured
+pub const DEFAULT_PATTERN: &str = "{repo_root}/../{name}";
+
+const NAME_PLACEHOLDER: &str = "{name}";
+
+#[derive(Debug, Clone)]
+pub struct WorktreeLayout {
+    repo_root: PathBuf,
+    pattern: String,
+}
+
+impl WorktreeLayout {
+    /// Create a layout for the given main repository root and pattern
+    pub fn new(repo_root: &Path, pattern: Option<&str>) -> Result<Self> {
+        let pattern = pattern.unwrap_or(DEFAULT_PATTERN);
+        validate_pattern(pattern)?;
+
+        Ok(Self {
+            repo_root: repo_root.to_path_buf(),
+            pattern: pattern.to_string(),
+        })
+    }
+
+    /// Load the layout from the repository's config hierarchy
+    ///
+    /// A config that can't be read is an error, so worktrees are never
+    /// created anywhere but where `worktrees.root` says.
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        let config = Config::load(repo_root)?;
+        Self::new(repo_root, config.worktrees.root.as_deref())
+    }
+
+    /// Load the layout, falling back to the default one if the config can't
+    /// be read, so that listing worktrees keeps working with a broken config
+    pub fn load_or_default(repo_root: &Path) -> Self {
+        Self::load(repo_root).unwrap_or_else(|_| Self {
+            repo_root: repo_root.to_path_buf(),
+            pattern: DEFAULT_PATTERN.to_string(),
+        })
+    }
+
+    /// Path where a worktree with the given name should live
+    pub fn path_for(&self, name: &str) -> PathBuf {
+        let (prefix, suffix) = self.split_pattern();
+        let mut expanded = prefix;
+        expanded.push_str(name);
+        expanded.push_str(&suffix);
+        self.absolutize(&expanded)
+    }
+
+    /// Recover a worktree name from its path
+    ///
+    /// Returns `None` when the path doesn't follow the configured layout
+    /// (e.g. the main repository or a worktree created outside hn).
+    pub fn name_for(&self, path: &Path) -> Option<String> {
+        let (raw_prefix, suffix) = self.split_pattern();
+        let raw_prefix = if raw_prefix.is_empty() {
+            "./".to_string()
+        } else {
+            raw_prefix
+        };
+
+        // Normalization drops a trailing separator, so re-add it when the
+        // pattern places {name} in its own path component
+        let mut prefix = self.absolutize(&raw_prefix).to_string_lossy().into_owned();
+        if raw_prefix.ends_with('/') && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        let path = normalize(path);
+        let name = path
+            .to_str()?
+            .strip_prefix(&prefix)?
+            .strip_suffix(&suffix)?;
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    /// Name for a worktree path, falling back to the directory name
+    pub fn name_or_dir_name(&self, path: &Path) -> String {
+        self.name_for(path).unwrap_or_else(|| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+                .to_string()
+        })
+    }
+
+    /// Expand everything except `{name}`, returning the text before and after it
+    fn split_pattern(&self) -> (String, String) {
+        let repo_name = self
+            .repo_root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("repo");
+        let expanded = self
+            .pattern
+            .replace("{repo_root}", &self.repo_root.to_string_lossy())
+            .replace("{repo}", repo_name);
+        let expanded = expand_home(&expanded);
+
+        match expanded.split_once(NAME_PLACEHOLDER) {
+            Some((prefix, suffix)) => (prefix.to_string(), suffix.to_string()),
+            None => (expanded, String::new()),
+        }
+    }
+
+    /// Resolve a (possibly relative) expanded pattern against the repo root
+    fn absolutize(&self, expanded: &str) -> PathBuf {
+        let path = Path::new(expanded);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.repo_root.join(path)
+        };
+        normalize(&path)
+    }
+}
+
+/// Check that a `worktrees.root` pattern is usable
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let occurrences = pattern.matches(NAME_PLACEHOLDER).count();
+    if occurrences != 1 {
+        return Err(HnError::ConfigError(format!(
+            "worktrees.root must contain {{name}} exactly once: '{}'",
+            pattern
+        )));
+    }
+
+    // Reject unknown placeholders early instead of creating odd directories
+    let stripped = pattern
+        .replace("{name}", "")
+        .replace("{repo_root}", "")
+        .replace("{repo}", "");
+    if stripped.contains('{') || stripped.contains('}') {
+        return Err(HnError::ConfigError(format!(
+            "worktrees.root contains an unknown placeholder: '{}' (supported: {{name}}, {{repo}}, {{repo_root}})",
+            pattern
+        )));
+    }
+
+    Ok(())
+}
+
+/// Expand a leading `~` to the user's home directory
+fn expand_home(path: &str) -> String {
+    if path == "~" || path.starts_with("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return format!("{}{}", home.to_string_lossy(), &path[1..]);
+        }
+    }
+    path.to_string()
+}
+
+/// Lexically normalize a path, resolving `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_layout_is_sibling_directory() {
+        let layout = WorktreeLayout::new(Path::new("/src/myrepo"), None).unwrap();
+        assert_eq!(layout.path_for("feature"), PathBuf::from("/src/feature"));
+        assert_eq!(
+            layout.name_for(Path::new("/src/feature")),
+            Some("feature".to_string())
+        );
+    }
+
+    #[test]
+    fn test_repo_prefixed_layout() {
+        let layout = WorktreeLayout::new(
+            Path::new("/src/myrepo"),
+            Some("{repo_root}/../{repo}-{name}"),
+        )
+        .unwrap();
+        let path = layout.path_for("feature");
+        assert_eq!(path, PathBuf::from("/src/myrepo-feature"));
+        assert_eq!(layout.name_for(&path), Some("feature".to_string()));
+        // The main repository doesn't follow the pattern
+        assert_eq!(layout.name_for(Path::new("/src/myrepo")), None);
+        assert_eq!(layout.name_or_dir_name(Path::new("/src/myrepo")), "myrepo");
+    }
+
+    #[test]
+    fn test_home_layout() {
+        let home = dirs::home_dir().unwrap();
+        let layout =
+            WorktreeLayout::new(Path::new("/src/myrepo"), Some("~/wt/{repo}/{name}")).unwrap();
+        let path = layout.path_for("feature");
+        assert_eq!(path, home.join("wt/myrepo/feature"));
+        assert_eq!(layout.name_for(&path), Some("feature".to_string()));
+        assert_eq!(layout.name_for(&home.join("wt/other/feature")), None);
+    }
+
+    #[test]
+    fn test_relative_layout_resolves_against_repo_root() {
+        let layout =
+            WorktreeLayout::new(Path::new("/src/myrepo"), Some(".worktrees/{name}")).unwrap();
+        assert_eq!(
+            layout.path_for("feature"),
+            PathBuf::from("/src/myrepo/.worktrees/feature")
+        );
+    }
+
+    #[test]
+    fn test_name_must_be_single_component() {
+        let layout = WorktreeLayout::new(Path::new("/src/myrepo"), Some("/wt/{name}")).unwrap();
+        assert_eq!(layout.name_for(Path::new("/wt/a/b")), None);
+        assert_eq!(layout.name_for(Path::new("/wt")), None);
+    }
+
+    #[test]
+    fn test_load_rejects_broken_config() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join(".hannahanna.yml"),
+            "worktrees:\n  root: [unclosed\n",
+        )
+        .unwrap();
+
+        assert!(WorktreeLayout::load(temp.path()).is_err());
+        let layout = WorktreeLayout::load_or_default(temp.path());
+        assert_eq!(layout.pattern, DEFAULT_PATTERN);
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("~/wt/{repo}/{name}").is_ok());
+        assert!(validate_pattern("{repo_root}/../{repo}-{name}").is_ok());
+        assert!(validate_pattern("~/wt/{repo}").is_err());
+        assert!(validate_pattern("{name}/{name}").is_err());
+        assert!(validate_pattern("~/wt/{branch}/{name}").is_err());
+    }
+}
@@ -1,12 +1,82 @@
 /// Mercurial (hg) backend implementation
 /// Uses `hg share` for workspace creation and registry for tracking
+use crate::conflict::ConflictOperation;
 use crate::errors::{HnError, Result};
-use crate::vcs::traits::{VcsBackend, VcsType};
-use crate::vcs::{WorkspaceStatus, Worktree};
+use crate::vcs::traits::{MergeOptions, MergeOutcome, VcsBackend, VcsType};
+use crate::vcs::{AheadBehind, WorkspaceStatus, Worktree, WorktreeLayout};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
+
+/// Merge tool that leaves conflict markers instead of prompting
+const MERGE_TOOL: &str = ":merge";
+
+/// `hg` in a share with plain output, rebase and shelve enabled
+fn hg_in(path: &Path) -> Command {
+    let mut cmd = Command::new("hg");
+    cmd.current_dir(path).env("HGPLAIN", "1").args([
+        "--config",
+        "extensions.rebase=",
+        "--config",
+        "extensions.shelve=",
+    ]);
+    cmd
+}
+
+fn hg_error(output: &Output, context: &str) -> HnError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let detail = if stderr.trim().is_empty() {
+        stdout.trim()
+    } else {
+        stderr.trim()
+    };
+    HnError::ConfigError(format!("{}: {}", context, detail))
+}
+
+/// Shelve name for a stash message
+///
+/// Shelve names become file names, so `/`, `\` and a leading `.` are
+/// percent-encoded; `shelve_message` reverses it.
+fn shelve_name(message: &str) -> String {
+    let mut name = String::with_capacity(message.len());
+    for (i, c) in message.chars().enumerate() {
+        match c {
+            '%' => name.push_str("%25"),
+            '/' => name.push_str("%2F"),
+            '\\' => name.push_str("%5C"),
+            '.' if i == 0 => name.push_str("%2E"),
+            _ => name.push(c),
+        }
+    }
+    name
+}
+
+/// Stash message for a shelve name created by `shelve_name`
+fn shelve_message(name: &str) -> String {
+    name.replace("%2E", ".")
+        .replace("%5C", "\\")
+        .replace("%2F", "/")
+        .replace("%25", "%")
+}
+
+/// Quote a branch or bookmark name for a revset, so names like
+/// "feature-x" aren't read as operators
+fn revset_symbol(name: &str) -> String {
+    format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Files marked unresolved in `hg resolve --list` output
+fn parse_unresolved(output: &str) -> Vec<String> {
+    let mut files: Vec<String> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("U "))
+        .map(|file| file.to_string())
+        .collect();
+    files.sort();
+    files
+}
 
 /// Registry entry for a Mercurial share
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +128,10 @@ impl ShareRegistry {
         self.shares.iter().find(|e| e.name == name)
     }
 
+    fn find_mut(&mut self, name: &str) -> Option<&mut ShareEntry> {
+        self.shares.iter_mut().find(|e| e.name == name)
+    }
+
     fn find_by_path(&self, path: &Path) -> Option<&ShareEntry> {
         self.shares.iter().find(|e| path.starts_with(&e.path))
     }
@@ -94,6 +168,16 @@ impl MercurialBackend {
         }
     }
 
+    /// Get the main repository path, even when opened from a share
+    ///
+    /// Shares record the main repository's `.hg` directory in `.hg/sharedpath`.
+    fn main_repo_root(&self) -> PathBuf {
+        fs::read_to_string(self.repo_path.join(".hg").join("sharedpath"))
+            .ok()
+            .and_then(|shared| Path::new(shared.trim()).parent().map(|p| p.to_path_buf()))
+            .unwrap_or_else(|| self.repo_path.clone())
+    }
+
     /// Get the path to the registry file
     fn registry_path(&self) -> PathBuf {
         self.repo_path.join(".hg").join("wt-registry.json")
@@ -141,6 +225,64 @@ impl MercurialBackend {
 
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
+
+    /// Number of changesets in `revset`, or None if it names an unknown revision
+    fn count_revisions(&self, path: &Path, revset: &str) -> Result<Option<usize>> {
+        let output = hg_in(path)
+            .args(["log", "--rev", revset, "--template", "{rev}\\n"])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("unknown revision") {
+                return Ok(None);
+            }
+            return Err(hg_error(&output, "Failed to count changesets"));
+        }
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).lines().count(),
+        ))
+    }
+
+    /// Interpret the result of `hg merge`, `hg rebase` or `hg rebase --continue`
+    ///
+    /// Mercurial exits with 1 both for unresolved conflicts and for "nothing
+    /// to rebase", so conflicts are told apart by the merge state.
+    fn merge_outcome(&self, path: &Path, output: &Output, context: &str) -> Result<MergeOutcome> {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.status.success()
+            || stdout.contains("nothing to rebase")
+            || stderr.contains("has no effect")
+        {
+            if !stdout.trim().is_empty() {
+                eprintln!("{}", stdout.trim_end());
+            }
+            return Ok(MergeOutcome::Success);
+        }
+
+        if !self.conflicted_files(path)?.is_empty() {
+            return Ok(MergeOutcome::Conflict);
+        }
+        Err(hg_error(output, context))
+    }
+
+    /// Names of the shelves in a share, newest first
+    fn shelve_names(&self, path: &Path) -> Result<Vec<String>> {
+        let output = hg_in(path).args(["shelve", "--list", "--quiet"]).output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to list shelved changes"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    /// Whether a shelve for `message` exists in the share
+    fn has_shelve(&self, path: &Path, message: &str) -> Result<bool> {
+        Ok(self.shelve_names(path)?.contains(&shelve_name(message)))
+    }
 }
 
 impl VcsBackend for MercurialBackend {
@@ -148,8 +290,8 @@ impl VcsBackend for MercurialBackend {
         VcsType::Mercurial
     }
 
-    fn repo_root(&self) -> Result<PathBuf> {
-        Ok(self.repo_path.clone())
+    fn main_repo_root(&self) -> Result<PathBuf> {
+        Ok(self.main_repo_root())
     }
 
     fn create_workspace(
@@ -159,19 +301,16 @@ impl VcsBackend for MercurialBackend {
         _from: Option<&str>,
         _no_branch: bool,
     ) -> Result<Worktree> {
-        // Determine the share path (sibling directory)
-        let share_path = self
-            .repo_path
-            .parent()
-            .ok_or_else(|| {
-                HnError::ConfigError("Could not determine parent directory".to_string())
-            })?
-            .join(name);
+        // Determine the share path from the configured layout
+        let share_path = WorktreeLayout::load(&self.main_repo_root())?.path_for(name);
 
         // Check if share already exists
         if share_path.exists() {
             return Err(HnError::WorktreeAlreadyExists(name.to_string()));
         }
+        if let Some(parent_dir) = share_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
 
         // Create share using `hg share`
         let output = Command::new("hg")
@@ -311,6 +450,59 @@ impl VcsBackend for MercurialBackend {
         Ok(())
     }
 
+    fn rename_workspace(
+        &self,
+        name: &str,
+        new_name: &str,
+        rename_branch: bool,
+    ) -> Result<Worktree> {
+        if rename_branch {
+            return Err(HnError::CommandFailed(
+                "Mercurial branches are recorded in every commit on them and can't be renamed"
+                    .to_string(),
+            ));
+        }
+
+        let mut registry = self.load_registry()?;
+        let new_path = WorktreeLayout::load(&self.main_repo_root())?.path_for(new_name);
+        if registry.find(new_name).is_some() || new_path.exists() {
+            return Err(HnError::WorktreeAlreadyExists(new_name.to_string()));
+        }
+        let entry = registry
+            .find_mut(name)
+            .ok_or_else(|| HnError::WorktreeNotFound(name.to_string()))?;
+
+        // A share only points at the main repository, so moving it is enough
+        if let Some(parent_dir) = new_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        fs::rename(&entry.path, &new_path)?;
+
+        entry.name = new_name.to_string();
+        entry.path = new_path;
+        let entry = entry.clone();
+        self.save_registry(&registry)?;
+
+        Ok(Worktree {
+            commit: self
+                .get_current_commit(&entry.path)
+                .unwrap_or_else(|_| "unknown".to_string()),
+            name: entry.name,
+            path: entry.path,
+            branch: entry.branch,
+            parent: entry.parent,
+        })
+    }
+
+    fn reparent_workspace(&self, worktree: &Worktree, parent: &str) -> Result<()> {
+        let mut registry = self.load_registry()?;
+        let entry = registry
+            .find_mut(&worktree.name)
+            .ok_or_else(|| HnError::WorktreeNotFound(worktree.name.clone()))?;
+        entry.parent = Some(parent.to_string());
+        self.save_registry(&registry)
+    }
+
     fn get_workspace_by_name(&self, name: &str) -> Result<Worktree> {
         let registry = self.load_registry()?;
         let entry = registry
@@ -456,6 +648,218 @@ impl VcsBackend for MercurialBackend {
 
         Ok(())
     }
+
+    fn fetch(&self, worktree_path: &Path, _branch: &str) -> Result<()> {
+        // Shares pull into the repository store they all use
+        let output = hg_in(worktree_path).arg("pull").output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to pull"));
+        }
+        Ok(())
+    }
+
+    fn merge(
+        &self,
+        worktree_path: &Path,
+        source: &str,
+        options: &MergeOptions,
+    ) -> Result<MergeOutcome> {
+        if options.squash {
+            return Err(HnError::ConfigError(
+                "Squash merges are not supported for Mercurial".to_string(),
+            ));
+        }
+
+        let tool = options.strategy.as_deref().unwrap_or(MERGE_TOOL);
+        let output = hg_in(worktree_path)
+            .args(["merge", "--tool", tool, source])
+            .output()?;
+
+        let outcome = self.merge_outcome(worktree_path, &output, "Failed to merge")?;
+        // `hg merge` never commits; merging an ancestor leaves nothing to commit
+        let merged = self.operation_in_progress(worktree_path).is_some();
+        if outcome == MergeOutcome::Success && merged && !options.no_commit {
+            self.commit(worktree_path, &format!("Merge {}", source))?;
+        }
+        Ok(outcome)
+    }
+
+    fn rebase(&self, worktree_path: &Path, onto: &str) -> Result<MergeOutcome> {
+        let output = hg_in(worktree_path)
+            .args(["rebase", "--dest", onto, "--tool", MERGE_TOOL])
+            .output()?;
+        self.merge_outcome(worktree_path, &output, "Failed to rebase")
+    }
+
+    fn rebase_onto(&self, worktree_path: &Path, onto: &str, base: &str) -> Result<MergeOutcome> {
+        let revs = format!("only(., {})", revset_symbol(base));
+        let output = hg_in(worktree_path)
+            .args([
+                "rebase", "--rev", &revs, "--dest", onto, "--tool", MERGE_TOOL,
+            ])
+            .output()?;
+        self.merge_outcome(worktree_path, &output, "Failed to rebase")
+    }
+
+    fn continue_operation(
+        &self,
+        worktree_path: &Path,
+        operation: ConflictOperation,
+        source: &str,
+    ) -> Result<MergeOutcome> {
+        match operation {
+            ConflictOperation::Merge => {
+                self.commit(worktree_path, &format!("Merge {}", source))?;
+                Ok(MergeOutcome::Success)
+            }
+            ConflictOperation::Rebase => {
+                let output = hg_in(worktree_path)
+                    .args(["rebase", "--continue", "--tool", MERGE_TOOL])
+                    .output()?;
+                self.merge_outcome(worktree_path, &output, "Failed to continue rebase")
+            }
+        }
+    }
+
+    fn abort_operation(&self, worktree_path: &Path, operation: ConflictOperation) -> Result<()> {
+        let mut cmd = hg_in(worktree_path);
+        match operation {
+            // Back to the first parent, which is where the merge started
+            ConflictOperation::Merge => cmd.args(["update", "--clean", "."]),
+            ConflictOperation::Rebase => cmd.args(["rebase", "--abort"]),
+        };
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(hg_error(
+                &output,
+                &format!("Failed to abort {}", operation.as_str()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn operation_in_progress(&self, worktree_path: &Path) -> Option<ConflictOperation> {
+        let hg_dir = worktree_path.join(".hg");
+        if hg_dir.join("rebasestate").exists() {
+            Some(ConflictOperation::Rebase)
+        } else if hg_dir.join("merge").join("state").exists()
+            || hg_dir.join("merge").join("state2").exists()
+        {
+            Some(ConflictOperation::Merge)
+        } else {
+            None
+        }
+    }
+
+    fn conflicted_files(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        let output = hg_in(worktree_path).args(["resolve", "--list"]).output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to list conflicts"));
+        }
+        Ok(parse_unresolved(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn commit(&self, worktree_path: &Path, message: &str) -> Result<()> {
+        let output = hg_in(worktree_path)
+            .args(["commit", "--addremove", "-m", message])
+            .output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to commit"));
+        }
+        Ok(())
+    }
+
+    fn stash(&self, worktree_path: &Path, message: &str, include_untracked: bool) -> Result<()> {
+        let mut cmd = hg_in(worktree_path);
+        cmd.arg("shelve");
+        if include_untracked {
+            cmd.arg("--addremove");
+        }
+        let output = cmd
+            .arg("--name")
+            .arg(shelve_name(message))
+            .arg("-m")
+            .arg(message)
+            .output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to shelve changes"));
+        }
+        Ok(())
+    }
+
+    fn stash_messages(&self, worktree_path: &Path) -> Result<Vec<String>> {
+        Ok(self
+            .shelve_names(worktree_path)?
+            .iter()
+            .map(|name| shelve_message(name))
+            .collect())
+    }
+
+    fn unstash(&self, worktree_path: &Path, message: &str, keep: bool) -> Result<bool> {
+        if !self.has_shelve(worktree_path, message)? {
+            return Ok(false);
+        }
+
+        let mut cmd = hg_in(worktree_path);
+        cmd.args(["unshelve", "--tool", MERGE_TOOL, "--name"])
+            .arg(shelve_name(message));
+        if keep {
+            cmd.arg("--keep");
+        }
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to unshelve changes"));
+        }
+        Ok(true)
+    }
+
+    fn drop_stash(&self, worktree_path: &Path, message: &str) -> Result<bool> {
+        if !self.has_shelve(worktree_path, message)? {
+            return Ok(false);
+        }
+
+        let output = hg_in(worktree_path)
+            .args(["shelve", "--delete"])
+            .arg(shelve_name(message))
+            .output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to delete shelved changes"));
+        }
+        Ok(true)
+    }
+
+    fn checkout_commit(&self, worktree_path: &Path, _branch: &str, commit: &str) -> Result<()> {
+        // `hg id -i` marks a dirty working directory with a trailing '+'
+        let output = hg_in(worktree_path)
+            .args(["update", "--clean", "--rev", commit.trim_end_matches('+')])
+            .output()?;
+        if !output.status.success() {
+            return Err(hg_error(&output, "Failed to update to commit"));
+        }
+        Ok(())
+    }
+
+    fn default_branch(&self) -> Option<String> {
+        Some("default".to_string())
+    }
+
+    fn upstream_branch(&self, _worktree: &Worktree) -> Option<String> {
+        // Mercurial branches don't track a remote branch
+        None
+    }
+
+    fn ahead_behind(&self, worktree: &Worktree, base: &str) -> Result<Option<AheadBehind>> {
+        let base = revset_symbol(base);
+        let Some(ahead) = self.count_revisions(&worktree.path, &format!("only(., {})", base))?
+        else {
+            return Ok(None);
+        };
+        let Some(behind) = self.count_revisions(&worktree.path, &format!("only({}, .)", base))?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(AheadBehind { ahead, behind }))
+    }
 }
 
 #[cfg(test)]
@@ -474,7 +878,7 @@ mod tests {
 
         let backend = backend.unwrap();
         assert_eq!(backend.vcs_type(), VcsType::Mercurial);
-        assert_eq!(backend.repo_root().unwrap(), repo_path);
+        assert_eq!(backend.repo_path, repo_path);
     }
 
     #[test]
@@ -518,4 +922,34 @@ mod tests {
         assert_eq!(registry.shares.len(), 1);
         assert!(!registry.remove("nonexistent"));
     }
+
+    #[test]
+    fn test_shelve_name_round_trip() {
+        for message in [
+            "hn sync autostash - 2026-10-16T09:30:00+00:00",
+            "hannahanna-snapshot:feature/login:before-refactor:1700000000",
+            ".hidden 100% \\ done",
+        ] {
+            let name = shelve_name(message);
+            assert!(!name.contains('/') && !name.contains('\\'), "{}", name);
+            assert!(!name.starts_with('.'), "{}", name);
+            assert_eq!(shelve_message(&name), message);
+        }
+    }
+
+    #[test]
+    fn test_revset_symbol() {
+        assert_eq!(revset_symbol("feature-x"), "'feature-x'");
+        assert_eq!(revset_symbol("it's"), "'it\\'s'");
+    }
+
+    #[test]
+    fn test_parse_unresolved() {
+        let output = "U src/main.rs\nR README.md\nU a file.txt\n";
+        assert_eq!(
+            parse_unresolved(output),
+            vec!["a file.txt".to_string(), "src/main.rs".to_string()]
+        );
+        assert!(parse_unresolved("").is_empty());
+    }
 }
@@ -2,12 +2,14 @@ pub mod backend_init;
 pub mod cache;
 pub mod git;
 pub mod jujutsu;
+pub mod layout;
 pub mod mercurial;
 pub mod traits;
 
 // Re-export for convenience
-pub use backend_init::{init_backend_from_current_dir, init_backend_with_detection};
+pub use backend_init::{init_backend, init_backend_from_current_dir, init_backend_with_detection};
 pub use cache::RegistryCache;
+pub use layout::WorktreeLayout;
 pub use traits::VcsType;
 
 use serde::{Deserialize, Serialize};
@@ -23,6 +25,61 @@ pub struct Worktree {
     pub parent: Option<String>,
 }
 
+/// Commits a branch has that its base lacks (ahead) and the other way round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AheadBehind {
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl std::fmt::Display for AheadBehind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ahead == 0 && self.behind == 0 {
+            write!(f, "=")
+        } else {
+            write!(f, "↑{} ↓{}", self.ahead, self.behind)
+        }
+    }
+}
+
+/// How far a worktree has moved from the branches it is compared against
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    /// Against the remote branch it tracks
+    pub upstream: Option<AheadBehind>,
+    /// Against the branch of its hn parent worktree
+    pub parent: Option<AheadBehind>,
+    /// Against the repository's default branch
+    pub default_branch: Option<AheadBehind>,
+    /// Name of the default branch
+    pub default_branch_name: Option<String>,
+}
+
+impl Divergence {
+    /// One-line summary such as "upstream ↑1 ↓0, main ↑3 ↓5"
+    pub fn summary(&self) -> String {
+        let default_label = self.default_branch_name.as_deref().unwrap_or("default");
+        [
+            ("upstream", self.upstream),
+            ("parent", self.parent),
+            (default_label, self.default_branch),
+        ]
+        .iter()
+        .filter_map(|(label, counts)| counts.map(|counts| format!("{} {}", label, counts)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Branch of a worktree's hn parent, looked up among `worktrees`
+pub fn parent_branch<'a>(worktree: &Worktree, worktrees: &'a [Worktree]) -> Option<&'a str> {
+    let parent = worktree.parent.as_deref()?;
+    worktrees
+        .iter()
+        .find(|wt| wt.name == parent)
+        .map(|wt| wt.branch.as_str())
+}
+
 /// VCS-agnostic workspace/worktree status
 /// Represents the state of working directory changes across all VCS types
 #[derive(Debug, Clone, PartialEq)]
@@ -1,6 +1,7 @@
 /// VCS abstraction layer - trait that all VCS backends must implement
+use crate::conflict::ConflictOperation;
 use crate::errors::Result;
-use crate::vcs::Worktree;
+use crate::vcs::{AheadBehind, Divergence, Worktree};
 use std::path::{Path, PathBuf};
 
 /// Enum representing the supported VCS types
@@ -31,6 +32,15 @@ impl VcsType {
             VcsType::Jujutsu => "jujutsu",
         }
     }
+
+    /// How to mark conflicts as resolved with this VCS
+    pub fn resolve_hint(&self) -> &'static str {
+        match self {
+            VcsType::Git => "stage them with 'git add'",
+            VcsType::Mercurial => "mark them with 'hg resolve --mark'",
+            VcsType::Jujutsu => "save the files",
+        }
+    }
 }
 
 impl std::str::FromStr for VcsType {
@@ -41,13 +51,34 @@ impl std::str::FromStr for VcsType {
     }
 }
 
+/// How a merge or rebase ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeOutcome {
+    Success,
+    /// Stopped for manual conflict resolution
+    Conflict,
+}
+
+/// Options for merging a branch into a workspace
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// Leave the merge uncommitted
+    pub no_commit: bool,
+    /// Create a merge commit even when a fast-forward is possible
+    pub no_ff: bool,
+    /// Stage the combined changes without recording a merge
+    pub squash: bool,
+    /// Merge strategy (git) or merge tool (Mercurial)
+    pub strategy: Option<String>,
+}
+
 /// Trait that all VCS backends must implement
 pub trait VcsBackend {
     /// Get the VCS type
     fn vcs_type(&self) -> VcsType;
 
-    /// Get the repository root path
-    fn repo_root(&self) -> Result<PathBuf>;
+    /// Get the main repository's root, even when opened from a worktree
+    fn main_repo_root(&self) -> Result<PathBuf>;
 
     /// Create a new workspace/worktree
     ///
@@ -70,6 +101,22 @@ pub trait VcsBackend {
     /// Remove a workspace
     fn remove_workspace(&self, name: &str, force: bool) -> Result<()>;
 
+    /// Rename a workspace, moving its directory to where the layout puts
+    /// `new_name`
+    ///
+    /// With `rename_branch`, the workspace's branch is renamed to `new_name`
+    /// as well.
+    fn rename_workspace(&self, name: &str, new_name: &str, rename_branch: bool)
+        -> Result<Worktree>;
+
+    /// Record `parent` as the parent of a workspace
+    fn reparent_workspace(&self, worktree: &Worktree, parent: &str) -> Result<()>;
+
+    /// Whether workspaces can have a parent recorded at all
+    fn records_parents(&self) -> bool {
+        true
+    }
+
     /// Get a workspace by name
     fn get_workspace_by_name(&self, name: &str) -> Result<Worktree>;
 
@@ -104,6 +151,101 @@ pub trait VcsBackend {
         }
         Ok(())
     }
+
+    /// Fetch the latest changes for `branch` from the default remote
+    fn fetch(&self, worktree_path: &Path, branch: &str) -> Result<()>;
+
+    /// Merge `source` into the workspace
+    fn merge(
+        &self,
+        worktree_path: &Path,
+        source: &str,
+        options: &MergeOptions,
+    ) -> Result<MergeOutcome>;
+
+    /// Rebase the workspace's changes onto `onto`
+    fn rebase(&self, worktree_path: &Path, onto: &str) -> Result<MergeOutcome>;
+
+    /// Rebase only the workspace's changes since `base` onto `onto`
+    ///
+    /// Commits up to `base` are left out, so ones that `onto` has since
+    /// rewritten aren't replayed (`git rebase --onto`).
+    fn rebase_onto(&self, worktree_path: &Path, onto: &str, base: &str) -> Result<MergeOutcome>;
+
+    /// Finish a merge of `source` or a rebase once its conflicts are resolved
+    fn continue_operation(
+        &self,
+        worktree_path: &Path,
+        operation: ConflictOperation,
+        source: &str,
+    ) -> Result<MergeOutcome>;
+
+    /// Give up on a merge or rebase, going back to where the workspace was
+    fn abort_operation(&self, worktree_path: &Path, operation: ConflictOperation) -> Result<()>;
+
+    /// The merge or rebase the workspace is in the middle of, if any
+    fn operation_in_progress(&self, worktree_path: &Path) -> Option<ConflictOperation>;
+
+    /// Files with unresolved conflicts in the workspace, sorted
+    fn conflicted_files(&self, worktree_path: &Path) -> Result<Vec<String>>;
+
+    /// Commit all changes in the workspace
+    fn commit(&self, worktree_path: &Path, message: &str) -> Result<()>;
+
+    /// Set the workspace's uncommitted changes aside under `message`
+    /// (git stash, hg shelve)
+    fn stash(&self, worktree_path: &Path, message: &str, include_untracked: bool) -> Result<()>;
+
+    /// Messages of the stashes visible from the workspace, newest first
+    fn stash_messages(&self, worktree_path: &Path) -> Result<Vec<String>>;
+
+    /// Reapply the stash saved under `message`, dropping it unless `keep`
+    ///
+    /// Returns false if there is no such stash.
+    fn unstash(&self, worktree_path: &Path, message: &str, keep: bool) -> Result<bool>;
+
+    /// Delete the stash saved under `message`; false if there is no such stash
+    fn drop_stash(&self, worktree_path: &Path, message: &str) -> Result<bool>;
+
+    /// Check out `commit` on `branch`, discarding uncommitted changes
+    fn checkout_commit(&self, worktree_path: &Path, branch: &str, commit: &str) -> Result<()>;
+
+    /// The repository's default branch (e.g. main), if it can be determined
+    fn default_branch(&self) -> Option<String>;
+
+    /// The remote branch the workspace's branch tracks, if any
+    fn upstream_branch(&self, worktree: &Worktree) -> Option<String>;
+
+    /// Commits the workspace has that `base` lacks, and the other way round
+    ///
+    /// Returns None if `base` doesn't resolve to a commit.
+    fn ahead_behind(&self, worktree: &Worktree, base: &str) -> Result<Option<AheadBehind>>;
+
+    /// Ahead/behind counts against the upstream, the hn parent's branch
+    /// and the default branch
+    ///
+    /// `default_branch` is what `default_branch()` returned, looked up once
+    /// by callers comparing several worktrees. Counts that can't be computed
+    /// are left empty rather than failing.
+    fn divergence(
+        &self,
+        worktree: &Worktree,
+        parent_branch: Option<&str>,
+        default_branch: Option<&str>,
+    ) -> Divergence {
+        let against = |base: Option<String>| {
+            base.and_then(|base| self.ahead_behind(worktree, &base).ok().flatten())
+        };
+
+        Divergence {
+            upstream: against(self.upstream_branch(worktree)),
+            parent: against(parent_branch.map(str::to_string)),
+            default_branch: default_branch
+                .filter(|branch| *branch != worktree.branch)
+                .and_then(|branch| against(Some(branch.to_string()))),
+            default_branch_name: default_branch.map(str::to_string),
+        }
+    }
 }
 
 /// Auto-detect VCS type by checking for VCS directories
@@ -0,0 +1,217 @@
+/// Integration tests for `hn adopt`
+mod common;
+
+use common::TestRepo;
+
+/// Create a worktree with plain git, behind hn's back
+fn git_worktree_add(repo: &TestRepo, name: &str, from: &str) {
+    let path = repo.worktree_path(name);
+    repo.git(&["worktree", "add", "-b", name, path.to_str().unwrap(), from])
+        .assert_success();
+}
+
+/// Write a file in a worktree and commit it
+fn commit_in(repo: &TestRepo, worktree: &str, file: &str) {
+    std::fs::write(repo.worktree_path(worktree).join(file), file).unwrap();
+    repo.git_in_worktree(worktree, &["add", file])
+        .assert_success();
+    repo.git_in_worktree(worktree, &["commit", "-m", &format!("Add {}", file)])
+        .assert_success();
+}
+
+#[test]
+fn test_adopt_creates_state_and_clears_list_marker() {
+    let repo = TestRepo::new();
+    git_worktree_add(&repo, "outside", "main");
+
+    repo.hn(&["list"]).assert_stdout_contains("unadopted");
+    assert!(!repo.state_exists("outside"));
+
+    let path = repo.worktree_path("outside");
+    let result = repo.hn(&["adopt", path.to_str().unwrap()]);
+    result.assert_success();
+    result.assert_stderr_contains("Adopted 'outside'");
+
+    assert!(repo.state_exists("outside"));
+    let list = repo.hn(&["list"]);
+    list.assert_stdout_contains("outside");
+    assert!(!list.stdout.contains("unadopted"));
+    repo.hn(&["activity", "outside"])
+        .assert_stdout_contains("Adopted on outside");
+
+    // Adopting again is a no-op
+    repo.hn(&["adopt", path.to_str().unwrap()])
+        .assert_stderr_contains("already managed");
+}
+
+#[test]
+fn test_adopt_infers_parent_from_merge_base() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    commit_in(&repo, "feature", "feature.txt");
+    git_worktree_add(&repo, "sub", "feature");
+    commit_in(&repo, "sub", "sub.txt");
+
+    let path = repo.worktree_path("sub");
+    let result = repo.hn(&["adopt", path.to_str().unwrap()]);
+    result.assert_success();
+    result.assert_stderr_contains("Parent: feature");
+
+    let parent = repo.git_in_worktree("sub", &["config", "--worktree", "worktree.parent"]);
+    assert_eq!(parent.stdout.trim(), "feature");
+    repo.hn(&["list", "--tree"])
+        .assert_stdout_contains("└── sub");
+
+    // Restack moves only sub's own commit from now on
+    let feature = repo.git_in_worktree("feature", &["rev-parse", "HEAD"]);
+    let base = std::fs::read_to_string(repo.path().join(".hn-state/sub/restack-base")).unwrap();
+    assert_eq!(base, feature.stdout.trim());
+}
+
+#[test]
+fn test_adopt_from_main_has_no_parent() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    commit_in(&repo, "feature", "feature.txt");
+    git_worktree_add(&repo, "outside", "main");
+    commit_in(&repo, "outside", "outside.txt");
+
+    let path = repo.worktree_path("outside");
+    let result = repo.hn(&["adopt", path.to_str().unwrap()]);
+    result.assert_success();
+    result.assert_stderr_contains("No parent worktree found");
+    assert!(
+        !repo
+            .git_in_worktree("outside", &["config", "--worktree", "worktree.parent"])
+            .success
+    );
+}
+
+#[test]
+fn test_adopt_all() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "managed"]).assert_success();
+    git_worktree_add(&repo, "one", "main");
+    git_worktree_add(&repo, "two", "main");
+
+    let result = repo.hn(&["adopt", "--all"]);
+    result.assert_success();
+    result.assert_stderr_contains("Adopted 'one'");
+    result.assert_stderr_contains("Adopted 'two'");
+    assert!(!result.stderr.contains("Adopting 'managed'"));
+    assert!(repo.state_exists("one"));
+    assert!(repo.state_exists("two"));
+
+    repo.hn(&["adopt", "--all"])
+        .assert_stderr_contains("Nothing to adopt");
+}
+
+#[test]
+fn test_adopt_runs_post_create_hook_and_shared_resources() {
+    let repo = TestRepo::new();
+    std::fs::write(repo.path().join(".env"), "SECRET=1").unwrap();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+shared:
+  copy:
+    - .env -> .env
+"#,
+    );
+    git_worktree_add(&repo, "outside", "main");
+    let path = repo.worktree_path("outside");
+
+    repo.hn(&["adopt", path.to_str().unwrap(), "--run-hooks"])
+        .assert_success();
+
+    assert!(path.join("hook-ran").exists());
+    assert_eq!(
+        std::fs::read_to_string(path.join(".env")).unwrap(),
+        "SECRET=1"
+    );
+}
+
+#[test]
+fn test_adopt_logs_activity_only_once_set_up() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "exit 1"
+"#,
+    );
+    git_worktree_add(&repo, "outside", "main");
+    let path = repo.worktree_path("outside");
+
+    repo.hn(&["adopt", path.to_str().unwrap(), "--run-hooks"])
+        .assert_failure();
+
+    let activity = repo.hn(&["activity", "outside"]);
+    assert!(!activity.stdout.contains("Adopted"));
+}
+
+#[test]
+fn test_adopt_skips_hooks_by_default() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+    git_worktree_add(&repo, "outside", "main");
+    let path = repo.worktree_path("outside");
+
+    repo.hn(&["adopt", path.to_str().unwrap()]).assert_success();
+
+    assert!(!path.join("hook-ran").exists());
+}
+
+#[test]
+fn test_adopt_rejects_main_repo_and_unknown_paths() {
+    let repo = TestRepo::new();
+    repo.hn(&["adopt", repo.path().to_str().unwrap()])
+        .assert_stderr_contains("main repository doesn't need adopting");
+
+    let stray = tempfile::TempDir::new().unwrap();
+    repo.hn(&["adopt", stray.path().to_str().unwrap()])
+        .assert_failure();
+}
+
+#[test]
+fn test_failed_adoption_can_be_retried() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    commit_in(&repo, "feature", "feature.txt");
+    git_worktree_add(&repo, "sub", "feature");
+    commit_in(&repo, "sub", "sub.txt");
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "exit 1"
+"#,
+    );
+    let path = repo.worktree_path("sub");
+
+    repo.hn(&["adopt", path.to_str().unwrap(), "--run-hooks"])
+        .assert_failure();
+    assert!(!repo.state_exists("sub"));
+    let parent = repo.git_in_worktree("sub", &["config", "--worktree", "worktree.parent"]);
+    assert!(parent.stdout.trim().is_empty());
+    repo.hn(&["list"]).assert_stdout_contains("unadopted");
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+    let result = repo.hn(&["adopt", path.to_str().unwrap(), "--run-hooks"]);
+    result.assert_success();
+    result.assert_stderr_contains("Adopted 'sub'");
+    assert!(repo.state_exists("sub"));
+    assert!(path.join("hook-ran").exists());
+    let parent = repo.git_in_worktree("sub", &["config", "--worktree", "worktree.parent"]);
+    assert_eq!(parent.stdout.trim(), "feature");
+}
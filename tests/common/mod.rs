@@ -86,9 +86,18 @@ impl TestRepo {
     }
 
     /// Run hn command in this repository
+    ///
+    /// Repository hooks are trusted without prompting; use `hn_with_env` to
+    /// exercise the trust store.
     pub fn hn(&self, args: &[&str]) -> CommandResult {
+        self.hn_with_env(args, &[("HNHN_TRUST_ALL_HOOKS", "1")])
+    }
+
+    /// Run hn command in this repository with extra environment variables
+    pub fn hn_with_env(&self, args: &[&str], envs: &[(&str, &str)]) -> CommandResult {
         let output = Command::new(env!("CARGO_BIN_EXE_hn"))
             .args(args)
+            .envs(envs.iter().copied())
             .current_dir(&self.repo_path)
             .output()
             .expect("Failed to execute hn command");
@@ -57,7 +57,7 @@ hooks:
 
     assert_eq!(config.shared_resources.len(), 1);
     assert_eq!(config.shared_resources[0].source, "node_modules");
-    assert_eq!(config.hooks.post_create, Some("npm install".to_string()));
+    assert_eq!(config.hooks.post_create, Some("npm install".into()));
 }
 
 #[test]
@@ -86,7 +86,7 @@ hooks:
     let config = Config::load(temp_dir.path()).unwrap();
 
     // Local hook should override repo hook
-    assert_eq!(config.hooks.post_create, Some("yarn install".to_string()));
+    assert_eq!(config.hooks.post_create, Some("yarn install".into()));
 
     // Shared resources from repo should still be present
     assert_eq!(config.shared_resources.len(), 1);
@@ -462,7 +462,7 @@ shared_resources:
     let config = Config::load(temp_dir.path()).unwrap();
 
     // Local should win for primitives
-    assert_eq!(config.hooks.post_create, Some("from_local".to_string()));
+    assert_eq!(config.hooks.post_create, Some("from_local".into()));
     assert_eq!(config.hooks.timeout_seconds, 200);
 
     // Arrays should be merged (both present)
@@ -704,3 +704,895 @@ docker:
     // Should only run on worktrees with docker running
     assert!(result.success);
 }
+
+// ============ Shared Services Tests ============
+
+#[test]
+fn test_docker_hybrid_strategy_shares_services() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  strategy: hybrid
+  ports:
+    base:
+      app: 3000
+      postgres: 5432
+      redis: 6379
+  shared:
+    services: [postgres, redis]
+  env:
+    DATABASE_URL: "postgres://postgres:5432/{{isolation.postgres}}"
+"#,
+    );
+
+    repo.hn(&["add", "feature-a"]).assert_success();
+    repo.hn(&["add", "feature-b"]).assert_success();
+
+    let state_dir = repo.path().join(".hn-state");
+    let override_file =
+        fs::read_to_string(state_dir.join("feature-b/docker-compose.override.yml")).unwrap();
+    let compose: serde_yml::Value = serde_yml::from_str(&override_file).unwrap();
+    let services = &compose["services"];
+    assert!(services.get("postgres").is_none(), "{}", override_file);
+    let app = &services["app"];
+    assert_eq!(
+        app["environment"]["DATABASE_URL"].as_str(),
+        Some("postgres://postgres:5432/feature_b")
+    );
+    assert_eq!(
+        app["environment"]["HNHN_REDIS_DB_INDEX"].as_str(),
+        Some("2")
+    );
+    assert_eq!(app["networks"][1].as_str(), Some("hn-shared"));
+
+    let env_file = fs::read_to_string(state_dir.join("feature-b/docker.env")).unwrap();
+    assert!(env_file.contains("HNHN_POSTGRES_DATABASE=feature_b\n"));
+
+    // Shared services get no per-worktree ports
+    let ports = repo.hn(&["ports", "show", "feature-b"]);
+    assert!(!ports.stdout.contains("postgres"), "{}", ports.stdout);
+
+    // Removing a worktree hands its DB index back
+    repo.hn(&["remove", "feature-a"]).assert_success();
+    repo.hn(&["add", "feature-c"]).assert_success();
+    let env_file = fs::read_to_string(state_dir.join("feature-c/docker.env")).unwrap();
+    assert!(env_file.contains("HNHN_REDIS_DB_INDEX=1\n"));
+}
+
+#[test]
+fn test_docker_hybrid_strategy_needs_shared_services() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  strategy: hybrid
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    result.assert_failure();
+    assert!(
+        format!("{}{}", result.stdout, result.stderr).contains("docker.shared.services"),
+        "stdout: {} stderr: {}",
+        result.stdout,
+        result.stderr
+    );
+}
+
+#[test]
+fn test_docker_hash_port_strategy_survives_recreate() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  ports:
+    strategy: hash
+    base:
+      app: 3000
+"#,
+    );
+
+    repo.hn(&["add", "feature-x"]).assert_success();
+    let before = repo.hn(&["ports", "show", "feature-x"]);
+    before.assert_success();
+
+    repo.hn(&["remove", "feature-x"]).assert_success();
+    repo.hn(&["add", "other"]).assert_success();
+    repo.hn(&["add", "feature-x", "feature-x-again"])
+        .assert_success();
+
+    let after = repo.hn(&["ports", "show", "feature-x"]);
+    after.assert_success();
+    let sorted = |stdout: &str| {
+        let mut lines: Vec<String> = stdout.lines().map(String::from).collect();
+        lines.sort();
+        lines
+    };
+    assert_eq!(sorted(&before.stdout), sorted(&after.stdout));
+}
+
+#[test]
+fn test_docker_global_port_registry_across_repos() {
+    let data_dir = tempfile::TempDir::new().unwrap();
+    let data_home = data_dir.path().to_string_lossy().into_owned();
+    let env = [("XDG_DATA_HOME", data_home.as_str())];
+    let config = r#"
+docker:
+  enabled: true
+  ports:
+    global: true
+    base:
+      app: 3000
+"#;
+
+    let first = TestRepo::new();
+    first.create_config(config);
+    first
+        .hn_with_env(&["add", "feature"], &env)
+        .assert_success();
+
+    let second = TestRepo::new();
+    second.create_config(config);
+    second
+        .hn_with_env(&["add", "feature"], &env)
+        .assert_success();
+
+    let port_of = |repo: &TestRepo| {
+        let show = repo.hn(&["ports", "show", "feature"]);
+        show.stdout
+            .lines()
+            .find(|l| l.starts_with("app"))
+            .unwrap()
+            .split_whitespace()
+            .nth(1)
+            .unwrap()
+            .to_string()
+    };
+    assert_ne!(port_of(&first), port_of(&second));
+
+    let list = second.hn_with_env(&["ports", "list", "--global"], &env);
+    list.assert_success();
+    assert!(list.stdout.contains(&port_of(&first)), "{}", list.stdout);
+    assert!(list.stdout.contains(&port_of(&second)), "{}", list.stdout);
+
+    // Ports of a deleted repository are reclaimed
+    let first_port = port_of(&first);
+    drop(first);
+    let list = second.hn_with_env(&["ports", "list", "--global"], &env);
+    list.assert_success();
+    assert!(list.stdout.contains("Reclaimed"), "{}", list.stdout);
+    assert!(list.stdout.contains(&port_of(&second)));
+    let table = list.stdout.split("Reclaimed").next().unwrap();
+    assert!(!table.contains(&first_port), "{}", list.stdout);
+}
+
+#[test]
+fn test_docker_regenerate_shows_diff() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  env:
+    LOG_LEVEL: "info"
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+    repo.hn(&["add", "feature-b"]).assert_success();
+
+    let unchanged = repo.hn(&["docker", "regenerate", "feature-a"]);
+    unchanged.assert_success();
+    assert!(unchanged.stdout.contains("feature-a is up to date"));
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  env:
+    LOG_LEVEL: "debug"
+"#,
+    );
+
+    let result = repo.hn(&["docker", "regenerate", "--all"]);
+    result.assert_success();
+    assert!(result.stdout.contains("Regenerated override for feature-a"));
+    assert!(result.stdout.contains("Regenerated override for feature-b"));
+    let diff_line = |prefix: &str, text: &str| {
+        result
+            .stdout
+            .lines()
+            .any(|l| l.trim_start().starts_with(prefix) && l.contains(text))
+    };
+    assert!(diff_line("- ", "LOG_LEVEL: info"), "{}", result.stdout);
+    assert!(diff_line("+ ", "LOG_LEVEL: debug"), "{}", result.stdout);
+
+    let override_file = std::fs::read_to_string(
+        repo.path()
+            .join(".hn-state/feature-b/docker-compose.override.yml"),
+    )
+    .unwrap();
+    assert!(override_file.contains("LOG_LEVEL: debug"));
+}
+
+#[test]
+fn test_docker_regenerate_updates_port_allocation() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  ports:
+    base:
+      app: 3000
+"#,
+    );
+    repo.hn(&["add", "wt1"]).assert_success();
+
+    // A new service and a moved base port both reach the allocation
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  ports:
+    base:
+      app: 7000
+      web: 4000
+"#,
+    );
+    let result = repo.hn(&["docker", "regenerate", "wt1"]);
+    result.assert_success();
+    assert!(
+        result.stdout.contains("Regenerated override for wt1"),
+        "{}",
+        result.stdout
+    );
+
+    let ports = repo.hn(&["ports", "show", "wt1"]);
+    ports.assert_success();
+    let port_of = |service: &str| -> u16 {
+        ports
+            .stdout
+            .lines()
+            .find(|l| l.split_whitespace().next() == Some(service))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|p| p.parse().ok())
+            .unwrap_or_else(|| panic!("no {} port in:\n{}", service, ports.stdout))
+    };
+    assert!(port_of("web") >= 4000);
+    assert!(port_of("app") >= 7000);
+
+    // Dropping a service gives its port back
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  ports:
+    base:
+      app: 7000
+"#,
+    );
+    repo.hn(&["docker", "regenerate", "wt1"]).assert_success();
+    let ports = repo.hn(&["ports", "show", "wt1"]);
+    assert!(!ports.stdout.contains("web"), "{}", ports.stdout);
+}
+
+#[test]
+fn test_docker_service_settings_in_override() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  env:
+    WORKTREE: "{{worktree_name}}"
+  isolated:
+    volumes: [cache]
+  services:
+    app:
+      ports: [3000, 9229]
+      env:
+        INSPECT_PORT: "{{port.app-9229}}"
+      labels:
+        traefik.http.routers.app.rule: "Host(`{{worktree_name}}.localhost`)"
+      volumes: ["cache:/app/cache"]
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+
+    let override_file = fs::read_to_string(
+        repo.path()
+            .join(".hn-state/feature-a/docker-compose.override.yml"),
+    )
+    .unwrap();
+    let compose: serde_yml::Value = serde_yml::from_str(&override_file).unwrap();
+    let app = &compose["services"]["app"];
+    let ports: Vec<&str> = app["ports"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .filter_map(|p| p.as_str())
+        .collect();
+    assert_eq!(ports.len(), 2, "{}", override_file);
+    assert!(ports[0].ends_with(":3000"));
+    assert!(ports[1].ends_with(":9229"));
+
+    // The debugger port is allocated and substituted under `app-9229`
+    let inspect_port = ports[1].split(':').next().unwrap();
+    assert_eq!(
+        app["environment"]["INSPECT_PORT"].as_str(),
+        Some(inspect_port)
+    );
+    assert_eq!(app["environment"]["WORKTREE"].as_str(), Some("feature-a"));
+    assert_eq!(
+        app["labels"]["traefik.http.routers.app.rule"].as_str(),
+        Some("Host(`feature-a.localhost`)")
+    );
+    assert_eq!(app["volumes"][0].as_str(), Some("cache:/app/cache"));
+    assert_eq!(
+        compose["volumes"]["cache"]["name"].as_str(),
+        Some("feature-a-cache")
+    );
+
+    let shown = repo.hn(&["ports", "show", "feature-a"]);
+    assert!(shown.stdout.contains("app-9229"), "{}", shown.stdout);
+}
+
+#[test]
+fn test_docker_services_discovered_from_compose_files() {
+    let repo = TestRepo::new();
+
+    repo.create_and_commit(
+        "docker-compose.yml",
+        "services:\n  web:\n    image: web\n    ports: ['8080:80']\n  worker:\n    image: worker\n",
+        "Add compose file",
+    );
+    repo.create_and_commit(
+        "docker-compose.dev.yml",
+        "services:\n  web:\n    ports: ['9229:9229']\n",
+        "Add dev overlay",
+    );
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  compose_overlays: [docker-compose.dev.yml]
+  ports:
+    base:
+      search: 9200
+"#,
+    );
+
+    repo.hn(&["add", "feature-a"]).assert_success();
+
+    // Only the services publishing ports get host ports
+    let ports = repo.hn(&["ports", "show", "feature-a"]);
+    ports.assert_success();
+    assert!(ports.stdout.contains("web"), "{}", ports.stdout);
+    assert!(ports.stdout.contains("web-9229"), "{}", ports.stdout);
+    assert!(!ports.stdout.contains("app"), "{}", ports.stdout);
+    assert!(!ports.stdout.contains("worker"), "{}", ports.stdout);
+
+    let services = repo.hn(&["docker", "services", "feature-a"]);
+    services.assert_success();
+    let web = services
+        .stdout
+        .lines()
+        .find(|l| l.starts_with("web"))
+        .unwrap();
+    assert!(web.contains("80, 9229"), "{}", services.stdout);
+    assert!(services.stdout.contains("worker"));
+
+    let validate = repo.hn(&["config", "validate"]);
+    validate.assert_success();
+    assert!(
+        validate
+            .stdout
+            .contains("docker.ports.base: service 'search' is not defined"),
+        "{}",
+        validate.stdout
+    );
+}
+
+// ============ Container Engine Tests ============
+
+/// Put a fake container engine CLI named `name` in `dir`: it logs its
+/// arguments, reports one running container and prints Podman-style stats
+#[cfg(unix)]
+fn fake_engine(dir: &std::path::Path, name: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let log = dir.join("engine.log");
+    let script = format!(
+        r#"#!/bin/sh
+echo "{name} $*" >> "{log}"
+case "$1" in
+  ps) echo "feature-a-app-1" ;;
+  stats) printf '51.2MB / 2.1GB\t1.50%%\n' ;;
+esac
+exit 0
+"#,
+        name = name,
+        log = log.display()
+    );
+    let path = dir.join(name);
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// PATH with only the fake engines and links to the tools hn needs, so a
+/// real engine installed next to those tools can't win engine detection
+#[cfg(unix)]
+fn fake_engine_path(dir: &std::path::Path) -> String {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    for tool in ["sh", "git"] {
+        let real = std::env::split_paths(&path)
+            .map(|entry| entry.join(tool))
+            .find(|candidate| candidate.is_file())
+            .unwrap_or_else(|| panic!("{} not found on PATH", tool));
+        let link = dir.join(tool);
+        if !link.exists() {
+            std::os::unix::fs::symlink(real, link).unwrap();
+        }
+    }
+    dir.display().to_string()
+}
+
+#[test]
+#[cfg(unix)]
+fn test_docker_podman_engine() {
+    let repo = TestRepo::new();
+    let bin = repo.temp_dir.path().join("fake-bin");
+    fs::create_dir_all(&bin).unwrap();
+    fake_engine(&bin, "podman");
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  engine: podman
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+
+    let env = [("PATH", fake_engine_path(&bin))];
+    let env: Vec<(&str, &str)> = env.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+    repo.hn_with_env(&["docker", "start", "feature-a"], &env)
+        .assert_success();
+    let log = fs::read_to_string(bin.join("engine.log")).unwrap();
+    assert!(
+        log.contains("podman compose -p feature-a -f docker-compose.yml"),
+        "{}",
+        log
+    );
+
+    let ps = repo.hn_with_env(&["docker", "ps"], &env);
+    ps.assert_success();
+    let row = ps
+        .stdout
+        .lines()
+        .find(|l| l.starts_with("feature-a"))
+        .unwrap_or_else(|| panic!("{}\n{}", ps.stdout, ps.stderr));
+    assert!(row.contains("Running"), "{}", ps.stdout);
+
+    // Podman reports decimal units
+    let info = repo.hn_with_env(&["info", "feature-a"], &env);
+    info.assert_success();
+    assert!(info.stdout.contains("48.8 MiB"), "{}", info.stdout);
+    assert!(info.stdout.contains("1.50%"), "{}", info.stdout);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_docker_engine_auto_detection() {
+    let repo = TestRepo::new();
+    let bin = repo.temp_dir.path().join("fake-bin");
+    fs::create_dir_all(&bin).unwrap();
+    fake_engine(&bin, "nerdctl");
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+
+    let path = fake_engine_path(&bin);
+    repo.hn_with_env(&["docker", "stop", "feature-a"], &[("PATH", &path)])
+        .assert_success();
+    let log = fs::read_to_string(bin.join("engine.log")).unwrap();
+    assert!(log.contains("nerdctl compose version"), "{}", log);
+    assert!(log.contains("nerdctl compose -p feature-a down"), "{}", log);
+}
+
+#[test]
+fn test_docker_unknown_engine_rejected() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  engine: lxc
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    assert!(!result.success);
+    assert!(result.stdout.contains("docker.engine"), "{}", result.stdout);
+}
+
+// ============ Proxy Tests ============
+
+/// Allocated port of a worktree's service, read from `hn ports show`
+fn allocated_port(repo: &TestRepo, worktree: &str, service: &str) -> u16 {
+    let show = repo.hn(&["ports", "show", worktree]);
+    show.assert_success();
+    show.stdout
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            (fields.next() == Some(service)).then(|| fields.next()?.parse().ok())?
+        })
+        .unwrap_or_else(|| panic!("no {} port in:\n{}", service, show.stdout))
+}
+
+/// Send a GET with the given Host header, returning the raw response
+fn http_get(port: u16, host: &str) -> String {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(
+        stream,
+        "GET /callback HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        host
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_proxy_routes_command() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  proxy:
+    domain: dev.test
+    port: 8443
+"#,
+    );
+
+    let result = repo.hn(&["proxy", "routes"]);
+    result.assert_success();
+    assert!(result.stdout.contains("No routes"), "{}", result.stdout);
+
+    repo.hn(&["add", "feature-auth"]).assert_success();
+    let port = allocated_port(&repo, "feature-auth", "app");
+
+    let result = repo.hn(&["proxy", "routes"]);
+    result.assert_success();
+    let row = result
+        .stdout
+        .lines()
+        .find(|l| l.contains("http://feature-auth.dev.test:8443"))
+        .unwrap_or_else(|| panic!("{}", result.stdout));
+    assert!(row.contains(&port.to_string()), "{}", row);
+}
+
+#[test]
+fn test_proxy_forwards_by_host() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+"#,
+    );
+    repo.hn(&["add", "feature-auth"]).assert_success();
+    let app_port = allocated_port(&repo, "feature-auth", "app");
+
+    // The worktree's "app": echoes the request line and forwarded host
+    let upstream = TcpListener::bind(("127.0.0.1", app_port)).unwrap();
+    std::thread::spawn(move || {
+        for stream in upstream.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut body = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if line.starts_with("GET") || line.starts_with("X-Forwarded-Host") {
+                    body.push_str(&line);
+                }
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    let proxy_port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut proxy = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(["proxy", "--port", &proxy_port.to_string()])
+        .current_dir(repo.path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while std::net::TcpStream::connect(("127.0.0.1", proxy_port)).is_err() {
+        assert!(Instant::now() < deadline, "proxy did not start");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let host = format!("feature-auth.localhost:{}", proxy_port);
+    let response = http_get(proxy_port, &host);
+    let unknown = http_get(proxy_port, "nope.localhost");
+    proxy.kill().unwrap();
+    proxy.wait().unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.contains("GET /callback HTTP/1.1"), "{}", response);
+    assert!(
+        response.contains("X-Forwarded-Host: feature-auth.localhost"),
+        "{}",
+        response
+    );
+
+    assert!(unknown.starts_with("HTTP/1.1 404"), "{}", unknown);
+    assert!(unknown.contains("feature-auth.localhost"), "{}", unknown);
+}
+
+#[test]
+fn test_proxy_invalid_domain_rejected() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  proxy:
+    domain: .localhost
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    assert!(!result.success);
+    assert!(
+        result.stdout.contains("docker.proxy.domain"),
+        "{}",
+        result.stdout
+    );
+}
+
+// ============ Readiness Probe Tests ============
+
+#[test]
+#[cfg(unix)]
+fn test_docker_start_wait_runs_probes() {
+    let repo = TestRepo::new();
+    let bin = repo.temp_dir.path().join("fake-bin");
+    fs::create_dir_all(&bin).unwrap();
+    fake_engine(&bin, "podman");
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  engine: podman
+  healthcheck:
+    probes:
+      app:
+        type: tcp
+        timeout: 5s
+        interval: 1s
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+    let app_port = allocated_port(&repo, "feature-a", "app");
+    let _app = std::net::TcpListener::bind(("127.0.0.1", app_port)).unwrap();
+
+    let path = fake_engine_path(&bin);
+    let result = repo.hn_with_env(
+        &["docker", "start", "feature-a", "--wait"],
+        &[("PATH", &path)],
+    );
+    result.assert_success();
+    assert!(
+        result
+            .stderr
+            .contains(&format!("✓ app ready (tcp :{}", app_port)),
+        "{}",
+        result.stderr
+    );
+
+    // Without --wait (and with health checks disabled) nothing is probed
+    let result = repo.hn_with_env(&["docker", "start", "feature-a"], &[("PATH", &path)]);
+    result.assert_success();
+    assert!(!result.stderr.contains("ready"), "{}", result.stderr);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_docker_start_wait_reports_blocking_service() {
+    let repo = TestRepo::new();
+    let bin = repo.temp_dir.path().join("fake-bin");
+    fs::create_dir_all(&bin).unwrap();
+    fake_engine(&bin, "podman");
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  engine: podman
+  healthcheck:
+    enabled: true
+    probes:
+      postgres:
+        type: http
+        path: /ready
+        timeout: 1s
+        interval: 1s
+"#,
+    );
+    repo.hn(&["add", "feature-a"]).assert_success();
+
+    let path = fake_engine_path(&bin);
+    let result = repo.hn_with_env(&["docker", "start", "feature-a"], &[("PATH", &path)]);
+    assert!(!result.success);
+    let output = format!("{}{}", result.stdout, result.stderr);
+    assert!(
+        output.contains("Services not ready: 'postgres'"),
+        "{}",
+        output
+    );
+    assert!(output.contains("/ready"), "{}", output);
+}
+
+// ============ Idle Reaping Tests ============
+
+/// Replace a worktree's activity log with one event of `kind` `age` seconds ago
+fn set_last_activity(repo: &TestRepo, worktree: &str, kind: &str, age: u64) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let event = match kind {
+        "DockerStarted" => format!(
+            r#"{{"type": "DockerStarted", "timestamp": {}, "services": []}}"#,
+            now - age
+        ),
+        _ => format!(
+            r#"{{"type": "WorktreeSwitched", "timestamp": {}, "from": null}}"#,
+            now - age
+        ),
+    };
+    fs::write(
+        repo.path()
+            .join(".hn-state")
+            .join(worktree)
+            .join("activity.json"),
+        format!(r#"{{"worktree": "{}", "events": [{}]}}"#, worktree, event),
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_docker_reap_stops_idle_worktrees() {
+    let repo = TestRepo::new();
+    let bin = repo.temp_dir.path().join("fake-bin");
+    fs::create_dir_all(&bin).unwrap();
+    fake_engine(&bin, "podman");
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  engine: podman
+  idle_stop_after: 2h
+"#,
+    );
+    repo.hn(&["add", "feature-old"]).assert_success();
+    repo.hn(&["add", "feature-new"]).assert_success();
+    set_last_activity(&repo, "feature-old", "DockerStarted", 3 * 86400);
+    set_last_activity(&repo, "feature-new", "WorktreeSwitched", 600);
+
+    let path = fake_engine_path(&bin);
+    let env = [("PATH", path.as_str())];
+
+    let dry = repo.hn_with_env(&["docker", "reap", "--dry-run"], &env);
+    dry.assert_success();
+    assert!(dry.stdout.contains("Would stop"), "{}", dry.stdout);
+    let row = dry
+        .stdout
+        .lines()
+        .find(|l| l.starts_with("feature-old"))
+        .unwrap_or_else(|| panic!("{}", dry.stdout));
+    assert!(row.contains("3d"), "{}", row);
+    assert!(!dry.stdout.contains("feature-new"), "{}", dry.stdout);
+    let log = fs::read_to_string(bin.join("engine.log")).unwrap();
+    assert!(!log.contains("down"), "{}", log);
+
+    let reap = repo.hn_with_env(&["docker", "reap"], &env);
+    reap.assert_success();
+    let log = fs::read_to_string(bin.join("engine.log")).unwrap();
+    assert!(
+        log.contains("podman compose -p feature-old down"),
+        "{}",
+        log
+    );
+    assert!(!log.contains("-p feature-new down"), "{}", log);
+
+    let activity =
+        fs::read_to_string(repo.path().join(".hn-state/feature-old/activity.json")).unwrap();
+    assert!(activity.contains("DockerStopped"), "{}", activity);
+
+    // --after overrides the configured policy
+    let all = repo.hn_with_env(&["docker", "reap", "--dry-run", "--after", "5m"], &env);
+    all.assert_success();
+    assert!(all.stdout.contains("feature-new"), "{}", all.stdout);
+}
+
+#[test]
+fn test_docker_reap_requires_policy() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+"#,
+    );
+
+    let result = repo.hn(&["docker", "reap"]);
+    assert!(!result.success);
+    assert!(
+        result.stderr.contains("docker.idle_stop_after"),
+        "{}",
+        result.stderr
+    );
+
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  idle_stop_after: soon
+"#,
+    );
+    let result = repo.hn(&["config", "validate"]);
+    assert!(!result.success);
+    assert!(
+        result.stdout.contains("docker.idle_stop_after"),
+        "{}",
+        result.stdout
+    );
+}
@@ -450,6 +450,119 @@ hooks:
     );
 }
 
+#[test]
+fn test_conditional_hook_expression() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create_conditions:
+    - condition: "branch =~ '^feature-\\d+$' && vcs == 'git' && exists('README.md')"
+      command: "echo 'matched' > expr_marker.txt"
+    - condition: "(branch.startsWith('feature-') || name == 'other') && tags.contains('ci')"
+      command: "echo 'tagged' > tags_marker.txt"
+"#,
+    );
+
+    repo.hn(&["add", "feature-42"]).assert_success();
+    repo.hn(&["add", "feature-api"]).assert_success();
+
+    assert!(repo
+        .worktree_path("feature-42")
+        .join("expr_marker.txt")
+        .exists());
+    assert!(!repo
+        .worktree_path("feature-api")
+        .join("expr_marker.txt")
+        .exists());
+
+    // Tags are added after creation, so the tag condition can't match yet
+    assert!(!repo
+        .worktree_path("feature-42")
+        .join("tags_marker.txt")
+        .exists());
+}
+
+#[test]
+fn test_config_validate_rejects_invalid_condition() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create_conditions:
+    - condition: "branch =~ '^release/' &&"
+      command: "echo 'never'"
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    result.assert_failure();
+    let output = format!("{}{}", result.stdout, result.stderr);
+    assert!(
+        output.contains("hooks.post_create_conditions[0]"),
+        "error should name the offending hook: {}",
+        output
+    );
+    assert!(output.contains("unexpected end of condition"), "{}", output);
+}
+
+#[test]
+fn test_post_create_steps() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create:
+    - name: install
+      run: echo installed > install.txt
+    - name: db
+      run: echo "$DB_NAME" > db.txt
+      env:
+        DB_NAME: app_test
+    - name: seed
+      needs: [install, db]
+      run: cat install.txt db.txt > seed.txt
+"#,
+    );
+
+    repo.hn(&["add", "feature-steps"]).assert_success();
+
+    let worktree_path = repo.worktree_path("feature-steps");
+    let seed = fs::read_to_string(worktree_path.join("seed.txt")).expect("seed step should run");
+    assert_eq!(seed, "installed\napp_test\n");
+
+    let activity = repo.hn(&["activity", "feature-steps"]);
+    activity.assert_success();
+    assert!(activity.stdout.contains("post_create:seed"));
+}
+
+#[test]
+fn test_config_validate_rejects_unknown_step_dependency() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create:
+    - name: seed
+      needs: [migrate]
+      run: echo seed
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    result.assert_failure();
+    let output = format!("{}{}", result.stdout, result.stderr);
+    assert!(
+        output.contains("hooks.post_create: hook step 'seed' needs unknown step 'migrate'"),
+        "{}",
+        output
+    );
+}
+
 // Note: Tests for pre_create, post_remove, pre_integrate, and post_integrate hooks
 // are not included due to test environment constraints. These hooks ARE implemented
 // and wired up correctly in the CLI (see src/cli/add.rs, src/cli/remove.rs,
@@ -458,3 +571,115 @@ hooks:
 // - post_remove: Hook runs after worktree directory is deleted
 // - pre/post_integrate: Require complex multi-worktree setup
 // Manual testing confirms these hooks work as expected.
+
+#[test]
+fn test_hook_logs_are_persisted() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "echo building $HNHN_NAME && echo warning >&2"
+"#,
+    );
+
+    repo.hn(&["add", "feature-logs"]).assert_success();
+
+    let logs = repo.hn(&["hooks", "logs", "feature-logs"]);
+    logs.assert_success();
+    assert!(logs.stdout.contains("==> post_create"));
+    assert!(logs.stdout.contains("building feature-logs"));
+    assert!(logs.stdout.contains("warning"));
+    assert!(logs.stdout.contains("exit code 0"));
+
+    let filtered = repo.hn(&["hooks", "logs", "feature-logs", "--hook", "pre_remove"]);
+    filtered.assert_success();
+    assert!(!filtered.stdout.contains("building"));
+    assert!(filtered.stderr.contains("No pre_remove hook logs"));
+}
+
+#[test]
+fn test_failed_hook_log_is_kept() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "echo about to fail && exit 3"
+"#,
+    );
+
+    let result = repo.hn(&["add", "feature-fail"]);
+    result.assert_failure();
+    assert!(result.stderr.contains("about to fail"));
+    assert!(result.stderr.contains("hook-logs"));
+
+    let logs = repo.hn(&["hooks", "logs", "feature-fail", "--hook", "post_create"]);
+    logs.assert_success();
+    assert!(logs.stdout.contains("about to fail"));
+    assert!(logs.stdout.contains("exit code 3"));
+}
+
+#[test]
+fn test_stream_output_prefixes_hook_lines() {
+    let repo = TestRepo::new();
+
+    repo.create_config(
+        r#"
+hooks:
+  stream_output: true
+  post_create:
+    - name: install
+      run: echo installing
+"#,
+    );
+
+    let result = repo.hn(&["add", "feature-stream"]);
+    result.assert_success();
+    assert!(
+        result.stderr.contains("[post_create:install]") && result.stderr.contains("installing"),
+        "stderr: {}",
+        result.stderr
+    );
+
+    // Streamed output is still logged
+    let logs = repo.hn(&["hooks", "logs", "feature-stream"]);
+    logs.assert_success();
+    assert!(logs.stdout.contains("installing"));
+}
+
+#[test]
+fn test_post_snapshot_restore_hook_gets_snapshot_name() {
+    let repo = TestRepo::new();
+    let marker = repo.temp_dir.path().join("restored.txt");
+
+    repo.create_config(&format!(
+        r#"
+hooks:
+  post_snapshot_restore: "echo $HNHN_NAME:$HNHN_SNAPSHOT > {}"
+"#,
+        marker.display()
+    ));
+
+    repo.hn(&["add", "feature-snap"]).assert_success();
+    repo.hn(&["snapshot", "create", "feature-snap", "before"])
+        .assert_success();
+    assert!(!marker.exists(), "hook should only run on restore");
+
+    repo.hn(&["snapshot", "restore", "feature-snap", "before"])
+        .assert_success();
+    let content = std::fs::read_to_string(&marker).unwrap();
+    assert_eq!(content.trim(), "feature-snap:before");
+
+    // --no-hooks skips it
+    std::fs::remove_file(&marker).unwrap();
+    repo.hn(&[
+        "--no-hooks",
+        "snapshot",
+        "restore",
+        "feature-snap",
+        "before",
+    ])
+    .assert_success();
+    assert!(!marker.exists());
+}
@@ -313,3 +313,266 @@ fn test_sync_rebase_strategy() {
 //
 // Manual testing and real-world usage provide coverage for the full
 // merge/sync functionality with conflict handling.
+
+#[test]
+fn test_sync_runs_sync_hooks_with_context() {
+    let repo = TestRepo::new();
+    let marker = repo.temp_dir.path().join("sync-hooks.txt");
+
+    // sync reads config from the worktree, so commit it
+    repo.create_and_commit(
+        ".hannahanna.yml",
+        &format!(
+            r#"
+hooks:
+  pre_sync: "echo pre:$HNHN_SYNC_SOURCE:$HNHN_SYNC_STRATEGY >> {marker}"
+  post_sync: "echo post:$HNHN_SYNC_RESULT >> {marker}"
+  pre_integrate: "echo pre-integrate >> {marker}"
+  post_integrate: "echo post-integrate >> {marker}"
+"#,
+            marker = marker.display()
+        ),
+        "Add hooks",
+    );
+    repo.hn(&["add", "test-sync-hooks"]).assert_success();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(["sync", "main"])
+        .current_dir(repo.worktree_path("test-sync-hooks"))
+        .env("HNHN_TRUST_ALL_HOOKS", "1")
+        .output()
+        .expect("Failed to run command");
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let content = fs::read_to_string(&marker).unwrap();
+    assert_eq!(
+        content,
+        "pre-integrate\npre:main:merge\npost:success\npost-integrate\n"
+    );
+}
+
+#[test]
+fn test_failed_pre_sync_hook_leaves_changes_unstashed() {
+    let repo = TestRepo::new();
+    repo.create_and_commit(
+        ".hannahanna.yml",
+        "hooks:\n  pre_sync: \"exit 1\"\n",
+        "Add failing hook",
+    );
+    repo.hn(&["add", "hooked"]).assert_success();
+
+    let worktree_path = repo.worktree_path("hooked");
+    fs::write(worktree_path.join("notes.txt"), "work in progress\n").unwrap();
+    repo.git_in_worktree("hooked", &["add", "notes.txt"])
+        .assert_success();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(["sync", "main", "--autostash"])
+        .current_dir(&worktree_path)
+        .env("HNHN_TRUST_ALL_HOOKS", "1")
+        .output()
+        .expect("Failed to run command");
+    assert!(!result.status.success());
+
+    assert_eq!(
+        fs::read_to_string(worktree_path.join("notes.txt")).unwrap(),
+        "work in progress\n"
+    );
+    let stashes = repo.git_in_worktree("hooked", &["stash", "list"]);
+    assert!(stashes.stdout.trim().is_empty(), "{}", stashes.stdout);
+}
+
+// ===== CONFLICT HANDLING TESTS =====
+
+/// Run hn inside a worktree
+fn hn_in(repo: &TestRepo, worktree: &str, args: &[&str]) -> common::CommandResult {
+    let output = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(args)
+        .current_dir(repo.worktree_path(worktree))
+        .output()
+        .expect("Failed to run command");
+
+    common::CommandResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+    }
+}
+
+/// Make `shared.txt` conflict between main and the worktree `name`
+fn setup_conflict(repo: &TestRepo, name: &str) {
+    repo.create_and_commit("shared.txt", "base\n", "Add shared file");
+    repo.hn(&["add", name]).assert_success();
+
+    let worktree_path = repo.worktree_path(name);
+    fs::write(worktree_path.join("shared.txt"), "feature\n").unwrap();
+    repo.git_in_worktree(name, &["commit", "-am", "Feature change"])
+        .assert_success();
+
+    repo.create_and_commit("shared.txt", "main\n", "Main change");
+}
+
+#[test]
+fn test_sync_conflict_is_recorded_and_continued() {
+    let repo = TestRepo::new();
+    setup_conflict(&repo, "conflicted");
+
+    let result = hn_in(&repo, "conflicted", &["sync"]);
+    result.assert_failure();
+    assert!(result.stderr.contains("conflicts in 1 file(s)"));
+    assert!(result.stderr.contains("shared.txt"));
+    assert!(result.stderr.contains("hn sync --continue"));
+
+    let list = repo.hn(&["list"]);
+    assert!(list.stdout.contains("sync stopped in merge of main"));
+    let info = repo.hn(&["info", "conflicted"]);
+    assert!(info.stdout.contains("Conflicts"));
+    assert!(info.stdout.contains("shared.txt"));
+    assert!(info.stdout.contains("stage them with 'git add'"));
+
+    // The conflict shows up from inside the worktree too
+    let list = hn_in(&repo, "conflicted", &["list"]);
+    assert!(list.stdout.contains("sync stopped in merge of main"));
+    let info = hn_in(&repo, "conflicted", &["info"]);
+    assert!(info.stdout.contains("shared.txt"));
+
+    // Starting another sync is refused until this one is finished
+    let again = hn_in(&repo, "conflicted", &["sync"]);
+    again.assert_failure();
+    assert!(again.stderr.contains("unfinished"));
+
+    // Unresolved files block --continue
+    hn_in(&repo, "conflicted", &["sync", "--continue"]).assert_failure();
+
+    let worktree_path = repo.worktree_path("conflicted");
+    fs::write(worktree_path.join("shared.txt"), "resolved\n").unwrap();
+    repo.git_in_worktree("conflicted", &["add", "shared.txt"])
+        .assert_success();
+
+    hn_in(&repo, "conflicted", &["sync", "--continue"]).assert_success();
+    let log = repo.git_in_worktree("conflicted", &["log", "-1", "--format=%P"]);
+    assert_eq!(log.stdout.split_whitespace().count(), 2, "merge commit");
+
+    let list = repo.hn(&["list"]);
+    assert!(!list.stdout.contains("conflicts"));
+}
+
+#[test]
+fn test_sync_abort_restores_autostash() {
+    let repo = TestRepo::new();
+    setup_conflict(&repo, "aborting");
+
+    let worktree_path = repo.worktree_path("aborting");
+    let before = repo
+        .git_in_worktree("aborting", &["rev-parse", "HEAD"])
+        .stdout;
+    fs::write(worktree_path.join("notes.txt"), "work in progress\n").unwrap();
+    repo.git_in_worktree("aborting", &["add", "notes.txt"])
+        .assert_success();
+
+    let result = hn_in(
+        &repo,
+        "aborting",
+        &["sync", "--strategy", "rebase", "--autostash"],
+    );
+    result.assert_failure();
+    assert!(result.stderr.contains("shared.txt"));
+    assert!(!worktree_path.join("notes.txt").exists());
+
+    hn_in(&repo, "aborting", &["sync", "--abort"]).assert_success();
+
+    let after = repo
+        .git_in_worktree("aborting", &["rev-parse", "HEAD"])
+        .stdout;
+    assert_eq!(before, after);
+    assert_eq!(
+        fs::read_to_string(worktree_path.join("notes.txt")).unwrap(),
+        "work in progress\n"
+    );
+    assert_eq!(
+        fs::read_to_string(worktree_path.join("shared.txt")).unwrap(),
+        "feature\n"
+    );
+
+    // Nothing left to continue
+    hn_in(&repo, "aborting", &["sync", "--continue"]).assert_failure();
+}
+
+#[test]
+fn test_abort_on_conflict_leaves_worktree_clean() {
+    let repo = TestRepo::new();
+    setup_conflict(&repo, "cautious");
+
+    let result = hn_in(&repo, "cautious", &["sync", "--abort-on-conflict"]);
+    result.assert_failure();
+    assert!(result.stderr.contains("Sync aborted"));
+
+    let status = repo.git_in_worktree("cautious", &["status", "--porcelain"]);
+    assert!(status.stdout.trim().is_empty(), "{}", status.stdout);
+    assert!(!repo.hn(&["list"]).stdout.contains("conflicts"));
+
+    let result = repo.hn(&[
+        "integrate",
+        "main",
+        "--into",
+        "cautious",
+        "--abort-on-conflict",
+    ]);
+    result.assert_failure();
+    assert!(result.stderr.contains("Integration aborted"));
+    let status = repo.git_in_worktree("cautious", &["status", "--porcelain"]);
+    assert!(status.stdout.trim().is_empty(), "{}", status.stdout);
+}
+
+#[test]
+fn test_integrate_conflict_continues_from_target_with_post_integrate() {
+    let repo = TestRepo::new();
+    let marker = repo.temp_dir.path().join("integrate-hooks.txt");
+    repo.create_and_commit(
+        ".hannahanna.yml",
+        &format!(
+            "hooks:\n  post_integrate: \"echo post-integrate >> {}\"\n",
+            marker.display()
+        ),
+        "Add hooks",
+    );
+    setup_conflict(&repo, "target");
+
+    let result = repo.hn(&["integrate", "main", "--into", "target"]);
+    result.assert_failure();
+    // The cd comes before the command it's needed for
+    let cd = format!("cd {}", repo.worktree_path("target").display());
+    let cd_at = result.stderr.find(&cd).expect("cd hint");
+    let continue_at = result
+        .stderr
+        .find("hn sync --continue")
+        .expect("continue hint");
+    assert!(cd_at < continue_at, "{}", result.stderr);
+    assert!(!marker.exists());
+
+    fs::write(
+        repo.worktree_path("target").join("shared.txt"),
+        "resolved\n",
+    )
+    .unwrap();
+    repo.git_in_worktree("target", &["add", "shared.txt"])
+        .assert_success();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(["sync", "--continue"])
+        .current_dir(repo.worktree_path("target"))
+        .env("HNHN_TRUST_ALL_HOOKS", "1")
+        .output()
+        .expect("Failed to run command");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read_to_string(&marker).unwrap(), "post-integrate\n");
+}
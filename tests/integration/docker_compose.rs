@@ -211,8 +211,16 @@ fn test_port_mappings() {
     let pg_port = ports.get("postgres").unwrap();
     let redis_port = ports.get("redis").unwrap();
 
-    // Should contain port mappings like "3000:3000" or similar
-    assert!(override_content.contains(&format!("\"{}:", app_port)));
-    assert!(override_content.contains(&format!("\"{}:", pg_port)));
-    assert!(override_content.contains(&format!("\"{}:", redis_port)));
+    // Should contain port mappings like "3000:3000", quoted as strings
+    let compose: serde_yml::Value = serde_yml::from_str(&override_content).unwrap();
+    let mapping = |service: &str| compose["services"][service]["ports"][0].as_str();
+    assert_eq!(mapping("app"), Some(format!("{}:3000", app_port).as_str()));
+    assert_eq!(
+        mapping("postgres"),
+        Some(format!("{}:5432", pg_port).as_str())
+    );
+    assert_eq!(
+        mapping("redis"),
+        Some(format!("{}:6379", redis_port).as_str())
+    );
 }
@@ -0,0 +1,24 @@
+/// Integration tests for `hn monitor`
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_monitor_snapshot() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature-mon"]).assert_success();
+
+    let result = repo.hn(&["monitor"]);
+    result.assert_success();
+    assert!(result.stdout.contains("feature-mon"));
+}
+
+#[test]
+fn test_monitor_live_requires_terminal() {
+    let repo = TestRepo::new();
+
+    // Test output is captured, so there is no terminal to draw on
+    let result = repo.hn(&["monitor", "--live", "--refresh", "1"]);
+    result.assert_failure();
+    assert!(result.stderr.contains("interactive terminal"));
+}
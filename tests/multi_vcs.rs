@@ -5,8 +5,12 @@
 mod common;
 
 use common::TestRepo;
+use hannahanna::conflict::ConflictOperation;
 use hannahanna::vcs::git::GitBackend;
-use hannahanna::vcs::traits::{create_backend, detect_vcs_type, VcsBackend, VcsType};
+use hannahanna::vcs::mercurial::MercurialBackend;
+use hannahanna::vcs::traits::{
+    create_backend, detect_vcs_type, MergeOptions, MergeOutcome, VcsBackend, VcsType,
+};
 use serial_test::serial;
 use std::fs;
 use std::path::Path;
@@ -27,8 +31,8 @@ fn test_vcs_backend_trait_with_git() {
     // Test vcs_type
     assert_eq!(backend.vcs_type(), VcsType::Git);
 
-    // Test repo_root
-    let root = backend.repo_root().expect("Failed to get repo root");
+    // Test main_repo_root
+    let root = backend.main_repo_root().expect("Failed to get repo root");
     assert!(root.ends_with("repo"));
 
     // Test list_workspaces (should have just the main worktree)
@@ -149,6 +153,125 @@ fn test_get_workspace_status_via_trait() {
     assert_eq!(status.untracked, 0);
 }
 
+#[test]
+fn test_merge_conflict_via_trait() {
+    let test_repo = TestRepo::new();
+    test_repo.create_and_commit("shared.txt", "base\n", "Add shared file");
+    let git = GitBackend::open(&test_repo.repo_path).expect("Failed to open git backend");
+    let backend: Box<dyn VcsBackend> = Box::new(git);
+
+    let feature = backend
+        .create_workspace("feature", None, None, false)
+        .expect("Failed to create workspace");
+    fs::write(feature.path.join("shared.txt"), "feature\n").unwrap();
+    backend
+        .commit(&feature.path, "Change shared file on feature")
+        .expect("Failed to commit");
+    test_repo.create_and_commit("shared.txt", "main\n", "Change shared file on main");
+
+    let outcome = backend
+        .merge(&test_repo.repo_path, "feature", &MergeOptions::default())
+        .expect("Merge failed");
+    assert_eq!(outcome, MergeOutcome::Conflict);
+    assert_eq!(
+        backend.conflicted_files(&test_repo.repo_path).unwrap(),
+        vec!["shared.txt".to_string()]
+    );
+    assert_eq!(
+        backend.operation_in_progress(&test_repo.repo_path),
+        Some(ConflictOperation::Merge)
+    );
+
+    backend
+        .abort_operation(&test_repo.repo_path, ConflictOperation::Merge)
+        .expect("Failed to abort merge");
+    assert_eq!(backend.operation_in_progress(&test_repo.repo_path), None);
+    assert_eq!(
+        fs::read_to_string(test_repo.repo_path.join("shared.txt")).unwrap(),
+        "main\n"
+    );
+}
+
+#[test]
+fn test_rebase_via_trait() {
+    let test_repo = TestRepo::new();
+    let git = GitBackend::open(&test_repo.repo_path).expect("Failed to open git backend");
+    let backend: Box<dyn VcsBackend> = Box::new(git);
+
+    let feature = backend
+        .create_workspace("feature", None, None, false)
+        .expect("Failed to create workspace");
+    fs::write(feature.path.join("feature.txt"), "feature\n").unwrap();
+    backend
+        .commit(&feature.path, "Add feature file")
+        .expect("Failed to commit");
+    test_repo.create_and_commit("main.txt", "main\n", "Add main file");
+
+    let outcome = backend
+        .rebase(&feature.path, "main")
+        .expect("Rebase failed");
+    assert_eq!(outcome, MergeOutcome::Success);
+    assert!(feature.path.join("main.txt").exists());
+    assert!(feature.path.join("feature.txt").exists());
+}
+
+#[test]
+fn test_stash_via_trait() {
+    let test_repo = TestRepo::new();
+    let git = GitBackend::open(&test_repo.repo_path).expect("Failed to open git backend");
+    let backend: Box<dyn VcsBackend> = Box::new(git);
+    let path = &test_repo.repo_path;
+
+    fs::write(path.join("untracked.txt"), "new\n").unwrap();
+    backend
+        .stash(path, "hn test stash", true)
+        .expect("Failed to stash");
+    assert!(backend.get_workspace_status(path).unwrap().is_clean());
+    assert_eq!(
+        backend.stash_messages(path).unwrap(),
+        vec!["hn test stash".to_string()]
+    );
+
+    // Apply keeps the stash around, drop removes it
+    assert!(backend.unstash(path, "hn test stash", true).unwrap());
+    assert!(path.join("untracked.txt").exists());
+    assert!(backend.drop_stash(path, "hn test stash").unwrap());
+    assert!(backend.stash_messages(path).unwrap().is_empty());
+    assert!(!backend.unstash(path, "hn test stash", false).unwrap());
+}
+
+#[test]
+fn test_mercurial_shelve_via_trait() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let repo_path = temp.path().join("hg-repo");
+    fs::create_dir(&repo_path).expect("Failed to create repo dir");
+
+    if !setup_hg_repo(&repo_path) {
+        eprintln!("Skipping test: Mercurial not available");
+        return;
+    }
+
+    let backend = MercurialBackend::open(&repo_path).expect("Failed to open hg backend");
+    let message = "hannahanna-snapshot:feature/x:snap:1";
+
+    fs::write(repo_path.join("README"), "Changed\n").unwrap();
+    backend
+        .stash(&repo_path, message, false)
+        .expect("Failed to shelve");
+    assert!(backend.get_workspace_status(&repo_path).unwrap().is_clean());
+    assert_eq!(
+        backend.stash_messages(&repo_path).unwrap(),
+        vec![message.to_string()]
+    );
+
+    assert!(backend.unstash(&repo_path, message, false).unwrap());
+    assert_eq!(
+        fs::read_to_string(repo_path.join("README")).unwrap(),
+        "Changed\n"
+    );
+    assert!(backend.stash_messages(&repo_path).unwrap().is_empty());
+}
+
 // ===== VCS FACTORY FUNCTION TESTS =====
 
 #[test]
@@ -0,0 +1,127 @@
+/// Integration tests for the global `--format` option
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_list_format_json() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature-json"]).assert_success();
+    repo.hn(&["tag", "feature-json", "ci"]).assert_success();
+
+    let result = repo.hn(&["list", "--format", "json"]);
+    result.assert_success();
+
+    let value: serde_json::Value =
+        serde_json::from_str(&result.stdout).expect("list output should be valid JSON");
+    assert_eq!(value["schema_version"], 1);
+
+    let worktrees = value["worktrees"].as_array().unwrap();
+    let wt = worktrees
+        .iter()
+        .find(|wt| wt["name"] == "feature-json")
+        .expect("feature-json should be listed");
+    assert_eq!(wt["branch"], "feature-json");
+    assert_eq!(wt["status"]["clean"], true);
+    assert_eq!(wt["tags"][0], "ci");
+    assert!(wt["disk_usage"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_list_format_json_with_tag_filter() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "tagged"]).assert_success();
+    repo.hn(&["add", "untagged"]).assert_success();
+    repo.hn(&["tag", "tagged", "backend"]).assert_success();
+
+    let result = repo.hn(&["--format", "json", "list", "--tag", "backend"]);
+    result.assert_success();
+
+    let value: serde_json::Value = serde_json::from_str(&result.stdout).unwrap();
+    let names: Vec<&str> = value["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|wt| wt["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["tagged"]);
+}
+
+#[test]
+fn test_info_format_yaml() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature-yaml"]).assert_success();
+
+    let result = repo.hn(&["info", "feature-yaml", "--format", "yaml"]);
+    result.assert_success();
+
+    let value: serde_yml::Value =
+        serde_yml::from_str(&result.stdout).expect("info output should be valid YAML");
+    assert_eq!(value["schema_version"].as_u64(), Some(1));
+    assert_eq!(value["vcs"].as_str(), Some("git"));
+    assert_eq!(value["worktree"]["name"].as_str(), Some("feature-yaml"));
+}
+
+#[test]
+fn test_stats_format_json() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature-stats"]).assert_success();
+
+    let result = repo.hn(&["stats", "--all", "--format", "json"]);
+    result.assert_success();
+
+    let value: serde_json::Value = serde_json::from_str(&result.stdout).unwrap();
+    assert_eq!(value["schema_version"], 1);
+    let worktrees = value["worktrees"].as_array().unwrap();
+    assert!(worktrees.iter().any(|wt| wt["name"] == "feature-stats"));
+    assert!(value["total_disk_usage"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_templates_list_json_flag_still_supported() {
+    let repo = TestRepo::new();
+
+    let result = repo.hn(&["templates", "list", "--json"]);
+    result.assert_success();
+
+    let value: serde_json::Value = serde_json::from_str(&result.stdout).unwrap();
+    assert!(value.is_array());
+}
+
+#[test]
+fn test_list_reports_ahead_behind() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature-ahead"]).assert_success();
+    repo.hn(&["add", "feature-fresh"]).assert_success();
+
+    // feature-ahead gets one commit of its own, main moves on by two
+    repo.git_in_worktree("feature-ahead", &["commit", "--allow-empty", "-m", "work"])
+        .assert_success();
+    repo.create_and_commit("a.txt", "a", "Main 1");
+    repo.create_and_commit("b.txt", "b", "Main 2");
+    repo.git_in_worktree("feature-fresh", &["merge", "--ff-only", "main"])
+        .assert_success();
+
+    let result = repo.hn(&["list", "--format", "json"]);
+    result.assert_success();
+    let value: serde_json::Value = serde_json::from_str(&result.stdout).unwrap();
+    let wt = value["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["name"] == "feature-ahead")
+        .unwrap();
+    assert_eq!(wt["divergence"]["default_branch_name"], "main");
+    assert_eq!(wt["divergence"]["default_branch"]["ahead"], 1);
+    assert_eq!(wt["divergence"]["default_branch"]["behind"], 2);
+
+    let result = repo.hn(&["list", "--stale", "1"]);
+    result.assert_success();
+    result.assert_stdout_contains("feature-ahead");
+    result.assert_stdout_contains("main ↑1 ↓2");
+    assert!(!result.stdout.contains("feature-fresh"));
+
+    let result = repo.hn(&["list", "--stale", "2"]);
+    result.assert_success();
+    assert!(!result.stdout.contains("feature-ahead"));
+}
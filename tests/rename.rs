@@ -0,0 +1,198 @@
+/// Integration tests for `hn rename`
+mod common;
+
+use common::{CommandResult, TestRepo};
+use std::path::Path;
+use std::process::Command;
+
+/// Run hn from inside a worktree
+fn hn_in(path: &Path, args: &[&str]) -> CommandResult {
+    let output = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(args)
+        .current_dir(path)
+        .output()
+        .expect("Failed to execute hn command");
+
+    CommandResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+    }
+}
+
+#[test]
+fn test_rename_moves_worktree_and_state() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    repo.hn(&["tag", "feature", "backend"]).assert_success();
+    repo.hn(&["snapshot", "create", "feature", "before"])
+        .assert_success();
+
+    let result = repo.hn(&["rename", "feature", "renamed"]);
+    result.assert_success();
+    result.assert_stdout_contains("Renamed worktree 'feature' to 'renamed'");
+
+    assert!(!repo.worktree_path("feature").exists());
+    assert!(repo.worktree_path("renamed").join(".git").exists());
+    assert!(!repo.state_exists("feature"));
+    assert!(repo.state_exists("renamed"));
+
+    let list = repo.hn(&["list"]);
+    list.assert_stdout_contains("renamed");
+    assert!(!list.stdout.lines().any(|line| line.starts_with("feature ")));
+    repo.hn(&["list", "--tag", "backend"])
+        .assert_stdout_contains("renamed");
+    repo.hn(&["snapshot", "list", "renamed"])
+        .assert_stdout_contains("before");
+    repo.hn(&["activity", "renamed"])
+        .assert_stdout_contains("Renamed from feature");
+
+    // Without --branch the branch keeps its name
+    let branch = repo.git_in_worktree("renamed", &["branch", "--show-current"]);
+    assert_eq!(branch.stdout.trim(), "feature");
+}
+
+#[test]
+fn test_rename_branch() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+
+    // A branch that's taken stops the rename before anything moves
+    repo.git(&["branch", "taken"]).assert_success();
+    repo.hn(&["rename", "feature", "taken", "--branch"])
+        .assert_stderr_contains("Branch 'taken' already exists");
+    assert!(repo.worktree_path("feature").exists());
+    assert!(!repo.worktree_path("taken").exists());
+
+    repo.hn(&["rename", "feature", "renamed", "--branch"])
+        .assert_success();
+
+    let branch = repo.git_in_worktree("renamed", &["branch", "--show-current"]);
+    assert_eq!(branch.stdout.trim(), "renamed");
+    assert!(
+        !repo
+            .git(&["rev-parse", "--verify", "refs/heads/feature"])
+            .success
+    );
+}
+
+#[test]
+fn test_rename_reparents_children() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    hn_in(&repo.worktree_path("feature"), &["add", "child"]).assert_success();
+
+    let result = repo.hn(&["rename", "feature", "renamed"]);
+    result.assert_success();
+    result.assert_stderr_contains("Reparented 'child'");
+
+    repo.hn(&["info", "child"])
+        .assert_stdout_contains("renamed");
+    let parent = repo.git_in_worktree("child", &["config", "--worktree", "worktree.parent"]);
+    assert_eq!(parent.stdout.trim(), "renamed");
+}
+
+#[test]
+fn test_rename_migrates_docker_ports_and_override() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+docker:
+  enabled: true
+  ports:
+    base:
+      app: 3000
+  isolated:
+    volumes:
+      - cache
+"#,
+    );
+    repo.hn(&["add", "feature"]).assert_success();
+    let before = repo.hn(&["ports", "show", "feature"]);
+    before.assert_success();
+
+    let result = repo.hn(&["rename", "feature", "renamed"]);
+    result.assert_success();
+    // Volumes are named after the worktree and can't be renamed
+    result.assert_stderr_contains("volume 'feature-cache'");
+
+    let after = repo.hn(&["ports", "show", "renamed"]);
+    after.assert_success();
+    let sorted = |output: &str| {
+        let mut lines: Vec<String> = output.lines().map(String::from).collect();
+        lines.sort();
+        lines
+    };
+    assert_eq!(
+        sorted(&before.stdout.replace("feature", "renamed")),
+        sorted(&after.stdout),
+        "ports should carry over unchanged"
+    );
+
+    let override_file = repo
+        .path()
+        .join(".hn-state/renamed/docker-compose.override.yml");
+    let content = std::fs::read_to_string(override_file).unwrap();
+    assert!(content.contains("# Worktree: renamed"));
+    assert!(!content.contains("feature"));
+}
+
+#[test]
+fn test_rename_refuses_taken_names_and_main_repo() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "one"]).assert_success();
+    repo.hn(&["add", "two"]).assert_success();
+
+    repo.hn(&["rename", "one", "two"]).assert_failure();
+    assert!(repo.worktree_path("one").exists());
+
+    let main_name = repo
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    repo.hn(&["rename", &main_name, "elsewhere"])
+        .assert_stderr_contains("main repository can't be renamed");
+}
+
+#[test]
+fn test_rename_checks_state_before_moving() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    std::fs::write(
+        repo.path().join(".hn-state/shared-registry.yaml"),
+        "db_indexes: [not, a, map",
+    )
+    .unwrap();
+
+    repo.hn(&["rename", "feature", "renamed"]).assert_failure();
+    assert!(repo.worktree_path("feature").join(".git").exists());
+    assert!(!repo.worktree_path("renamed").exists());
+    assert!(repo.state_exists("feature"));
+}
+
+#[test]
+fn test_rename_puts_everything_back_on_failure() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    repo.hn(&["tag", "feature", "backend"]).assert_success();
+    // Only read once the ports are moved, after the state and tags
+    std::fs::write(
+        repo.path().join(".hn-state/port-registry.yaml"),
+        "allocations: [not, a, map",
+    )
+    .unwrap();
+
+    let result = repo.hn(&["rename", "feature", "renamed"]);
+    result.assert_failure();
+    result.assert_stderr_contains("Moved the worktree back");
+
+    assert!(repo.worktree_path("feature").join(".git").exists());
+    assert!(!repo.worktree_path("renamed").exists());
+    assert!(repo.state_exists("feature"));
+    assert!(!repo.state_exists("renamed"));
+    repo.hn(&["list", "--tag", "backend"])
+        .assert_stdout_contains("feature");
+}
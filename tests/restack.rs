@@ -0,0 +1,208 @@
+/// Integration tests for `hn restack`
+mod common;
+
+use common::{CommandResult, TestRepo};
+use std::path::Path;
+use std::process::Command;
+
+/// Run hn from inside a worktree
+fn hn_in(path: &Path, args: &[&str]) -> CommandResult {
+    let output = Command::new(env!("CARGO_BIN_EXE_hn"))
+        .args(args)
+        .current_dir(path)
+        .output()
+        .expect("Failed to execute hn command");
+
+    CommandResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+    }
+}
+
+/// Write a file in a worktree and commit it
+fn commit_in(repo: &TestRepo, worktree: &str, file: &str, content: &str) {
+    std::fs::write(repo.worktree_path(worktree).join(file), content).unwrap();
+    repo.git_in_worktree(worktree, &["add", file])
+        .assert_success();
+    repo.git_in_worktree(worktree, &["commit", "-m", &format!("Edit {}", file)])
+        .assert_success();
+}
+
+/// Whether `ancestor` is an ancestor of HEAD in `worktree`
+fn contains(repo: &TestRepo, worktree: &str, ancestor: &str) -> bool {
+    repo.git_in_worktree(worktree, &["merge-base", "--is-ancestor", ancestor, "HEAD"])
+        .success
+}
+
+/// feature <- child <- grandchild
+fn stack(repo: &TestRepo) {
+    repo.hn(&["add", "feature"]).assert_success();
+    hn_in(&repo.worktree_path("feature"), &["add", "child"]).assert_success();
+    hn_in(&repo.worktree_path("child"), &["add", "grandchild"]).assert_success();
+    commit_in(repo, "child", "child.txt", "child");
+    commit_in(repo, "grandchild", "grandchild.txt", "grandchild");
+}
+
+#[test]
+fn test_restack_rebases_descendants_in_order() {
+    let repo = TestRepo::new();
+    stack(&repo);
+    commit_in(&repo, "feature", "feature.txt", "feature");
+
+    let result = hn_in(&repo.worktree_path("feature"), &["restack"]);
+    result.assert_success();
+    result.assert_stderr_contains("Restacked 2 worktree(s)");
+
+    assert!(contains(&repo, "child", "feature"));
+    assert!(contains(&repo, "grandchild", "child"));
+    assert!(contains(&repo, "grandchild", "feature"));
+}
+
+#[test]
+fn test_restack_dry_run_prints_plan() {
+    let repo = TestRepo::new();
+    stack(&repo);
+    commit_in(&repo, "feature", "feature.txt", "feature");
+    let before = repo.git_in_worktree("child", &["rev-parse", "HEAD"]).stdout;
+
+    let result = hn_in(&repo.worktree_path("feature"), &["restack", "--dry-run"]);
+    result.assert_success();
+    result.assert_stdout_contains("1. rebase child onto feature (parent feature): 1 behind");
+    result.assert_stdout_contains("2. rebase grandchild onto child");
+
+    let after = repo.git_in_worktree("child", &["rev-parse", "HEAD"]).stdout;
+    assert_eq!(before, after, "dry run must not rebase");
+}
+
+#[test]
+fn test_restack_stops_on_conflict_and_continues() {
+    let repo = TestRepo::new();
+    stack(&repo);
+    commit_in(&repo, "feature", "child.txt", "feature's version");
+
+    let result = repo.hn(&["restack", "--all"]);
+    result.assert_failure();
+    result.assert_stderr_contains("hn restack --continue");
+    assert!(repo.path().join(".hn-state/restack.json").exists());
+
+    // A new restack can't start while one is waiting
+    repo.hn(&["restack", "--all"])
+        .assert_stderr_contains("waiting on conflicts in 'child'");
+
+    // Nor can sync finish, abort or start over it
+    let child = repo.worktree_path("child");
+    for args in [&["sync", "--continue"][..], &["sync", "--abort"], &["sync"]] {
+        let result = hn_in(&child, args);
+        result.assert_failure();
+        result.assert_stderr_contains("hn restack --continue");
+    }
+    assert!(repo.path().join(".hn-state/restack.json").exists());
+
+    std::fs::write(repo.worktree_path("child").join("child.txt"), "resolved").unwrap();
+    repo.git_in_worktree("child", &["add", "child.txt"])
+        .assert_success();
+
+    let result = repo.hn(&["restack", "--continue"]);
+    result.assert_success();
+    result.assert_stderr_contains("Restacked 2 worktree(s)");
+    assert!(!repo.path().join(".hn-state/restack.json").exists());
+    assert!(contains(&repo, "child", "feature"));
+    assert!(contains(&repo, "grandchild", "child"));
+}
+
+#[test]
+fn test_restack_abort() {
+    let repo = TestRepo::new();
+    stack(&repo);
+    commit_in(&repo, "feature", "child.txt", "feature's version");
+    let before = repo.git_in_worktree("child", &["rev-parse", "HEAD"]).stdout;
+
+    repo.hn(&["restack", "--all"]).assert_failure();
+
+    let result = repo.hn(&["restack", "--abort"]);
+    result.assert_success();
+    result.assert_stderr_contains("Skipped 1 remaining worktree(s)");
+
+    let after = repo.git_in_worktree("child", &["rev-parse", "HEAD"]).stdout;
+    assert_eq!(before, after);
+    assert!(!repo.path().join(".hn-state/restack.json").exists());
+    repo.hn(&["restack", "--continue"])
+        .assert_stderr_contains("No restack in progress");
+}
+
+#[test]
+fn test_restack_refuses_dirty_worktrees() {
+    let repo = TestRepo::new();
+    stack(&repo);
+    commit_in(&repo, "feature", "feature.txt", "feature");
+    std::fs::write(repo.worktree_path("grandchild").join("wip.txt"), "wip").unwrap();
+
+    let result = hn_in(&repo.worktree_path("feature"), &["restack"]);
+    result.assert_failure();
+    result.assert_stderr_contains("uncommitted changes can't be restacked: grandchild");
+    assert!(!contains(&repo, "child", "feature"));
+}
+
+#[test]
+fn test_restack_leaves_out_amended_parent_commits() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    commit_in(&repo, "feature", "shared.txt", "first draft");
+    hn_in(&repo.worktree_path("feature"), &["add", "child"]).assert_success();
+    commit_in(&repo, "child", "child.txt", "child");
+
+    // Rewriting the parent's commit must not replay the old one in child
+    std::fs::write(repo.worktree_path("feature").join("shared.txt"), "amended").unwrap();
+    repo.git_in_worktree("feature", &["commit", "-a", "--amend", "--no-edit"])
+        .assert_success();
+
+    let result = hn_in(&repo.worktree_path("feature"), &["restack"]);
+    result.assert_success();
+    result.assert_stderr_contains("Restacked 1 worktree(s)");
+
+    assert!(contains(&repo, "child", "feature"));
+    let shared = std::fs::read_to_string(repo.worktree_path("child").join("shared.txt")).unwrap();
+    assert_eq!(shared, "amended");
+    let own = repo.git_in_worktree("child", &["rev-list", "--count", "feature..HEAD"]);
+    assert_eq!(own.stdout.trim(), "1");
+}
+
+#[test]
+fn test_parents_stored_in_shared_config_are_kept() {
+    let repo = TestRepo::new();
+    repo.hn(&["add", "feature"]).assert_success();
+    repo.hn(&["add", "other"]).assert_success();
+
+    // Where parents were kept before per-worktree config
+    repo.git(&["config", "worktree.parent", "feature"])
+        .assert_success();
+
+    let result = hn_in(&repo.worktree_path("feature"), &["add", "child"]);
+    result.assert_success();
+    result.assert_stderr_contains("Enabling extensions.worktreeConfig");
+
+    let parent_of = |worktree: &str| {
+        repo.git_in_worktree(
+            worktree,
+            &["config", "--worktree", "--get", "worktree.parent"],
+        )
+        .stdout
+        .trim()
+        .to_string()
+    };
+    assert_eq!(parent_of("child"), "feature");
+    assert_eq!(parent_of("other"), "feature");
+    assert_eq!(parent_of("feature"), "");
+    assert!(
+        !repo
+            .git(&["config", "--local", "--get", "worktree.parent"])
+            .success
+    );
+
+    // Only the first parent changes the repository config
+    let result = hn_in(&repo.worktree_path("child"), &["add", "grandchild"]);
+    result.assert_success();
+    assert!(!result.stderr.contains("extensions.worktreeConfig"));
+}
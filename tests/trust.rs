@@ -0,0 +1,142 @@
+/// Integration tests for the hook trust store
+mod common;
+
+use common::TestRepo;
+use std::fs;
+
+/// Environment for running hn with an isolated, empty trust store
+fn untrusted_env(repo: &TestRepo) -> Vec<(&'static str, String)> {
+    let home = repo.temp_dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    vec![
+        ("HOME", home.to_string_lossy().into_owned()),
+        ("HNHN_TRUST_ALL_HOOKS", "0".to_string()),
+    ]
+}
+
+fn hn(repo: &TestRepo, args: &[&str]) -> common::CommandResult {
+    let env = untrusted_env(repo);
+    let env: Vec<(&str, &str)> = env.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    repo.hn_with_env(args, &env)
+}
+
+#[test]
+fn test_untrusted_hooks_are_refused() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+
+    let result = hn(&repo, &["add", "feature"]);
+    result.assert_failure();
+    assert!(
+        result.stderr.contains("not trusted"),
+        "stderr: {}",
+        result.stderr
+    );
+    // Nothing is created before trust is established
+    assert!(!repo.worktree_path("feature").exists());
+
+    // --no-hooks still works without trust
+    hn(&repo, &["add", "feature", "--no-hooks"]).assert_success();
+    assert!(!repo.worktree_path("feature").join("hook-ran").exists());
+}
+
+#[test]
+fn test_trust_then_run_and_revoke() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+
+    let trust = hn(&repo, &["trust", "--yes"]);
+    trust.assert_success();
+    assert!(trust.stderr.contains("touch hook-ran"));
+
+    hn(&repo, &["add", "feature"]).assert_success();
+    assert!(repo.worktree_path("feature").join("hook-ran").exists());
+
+    let list = hn(&repo, &["trust", "list"]);
+    list.assert_success();
+    assert!(list.stdout.contains("trusted"));
+
+    hn(&repo, &["trust", "--revoke"]).assert_success();
+    hn(&repo, &["add", "other"]).assert_failure();
+}
+
+#[test]
+fn test_changed_hooks_need_trust_again() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+    hn(&repo, &["trust", "--yes"]).assert_success();
+
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch something-else"
+"#,
+    );
+
+    let result = hn(&repo, &["add", "feature"]);
+    result.assert_failure();
+    assert!(result.stderr.contains("changed since they were trusted"));
+
+    let list = hn(&repo, &["trust", "list"]);
+    assert!(list.stdout.contains("changed"));
+
+    // Re-trusting shows what changed
+    let trust = hn(&repo, &["trust", "--yes"]);
+    trust.assert_success();
+    assert!(trust
+        .stderr
+        .contains("- .hannahanna.yml hooks.post_create: touch hook-ran"));
+    assert!(trust
+        .stderr
+        .contains("+ .hannahanna.yml hooks.post_create: touch something-else"));
+    hn(&repo, &["add", "feature"]).assert_success();
+}
+
+#[test]
+fn test_user_config_hooks_are_trusted() {
+    let repo = TestRepo::new();
+    let env = untrusted_env(&repo);
+    let home = std::path::PathBuf::from(&env[0].1);
+    fs::create_dir_all(home.join(".config/hannahanna")).unwrap();
+    fs::write(
+        home.join(".config/hannahanna/config.yml"),
+        "hooks:\n  post_create: \"touch user-hook-ran\"\n",
+    )
+    .unwrap();
+
+    hn(&repo, &["add", "feature"]).assert_success();
+    assert!(repo.worktree_path("feature").join("user-hook-ran").exists());
+}
+
+#[test]
+fn test_trust_needs_confirmation() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+hooks:
+  post_create: "touch hook-ran"
+"#,
+    );
+
+    // Without a terminal there is nobody to confirm, so nothing is trusted
+    let trust = hn(&repo, &["trust"]);
+    trust.assert_failure();
+    assert!(trust.stderr.contains("touch hook-ran"));
+    assert!(trust.stderr.contains("--yes"));
+    hn(&repo, &["add", "feature"]).assert_failure();
+}
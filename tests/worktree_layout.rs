@@ -0,0 +1,90 @@
+/// Integration tests for the `worktrees.root` layout setting
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_repo_prefixed_layout() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+worktrees:
+  root: "{repo_root}/../{repo}-{name}"
+"#,
+    );
+
+    repo.hn(&["add", "feature"]).assert_success();
+
+    let expected = repo.temp_dir.path().join("repo-feature");
+    assert!(
+        expected.exists(),
+        "worktree should be created at {:?}",
+        expected
+    );
+    assert!(!repo.worktree_path("feature").exists());
+
+    // The worktree is still known by its short name
+    let list = repo.hn(&["list"]);
+    list.assert_success();
+    assert!(list.stdout.contains("feature"));
+    assert!(!list.stdout.contains("repo-feature"));
+
+    repo.hn(&["info", "feature"]).assert_success();
+
+    repo.hn(&["remove", "feature"]).assert_success();
+    assert!(!expected.exists());
+    assert!(!repo.state_exists("feature"));
+}
+
+#[test]
+fn test_nested_root_directory() {
+    let repo = TestRepo::new();
+    let root = repo.temp_dir.path().join("wt");
+    repo.create_config(&format!(
+        r#"
+worktrees:
+  root: "{}/{{repo}}/{{name}}"
+"#,
+        root.display()
+    ));
+
+    repo.hn(&["add", "nested"]).assert_success();
+
+    let expected = root.join("repo").join("nested");
+    assert!(
+        expected.exists(),
+        "worktree should be created at {:?}",
+        expected
+    );
+    assert!(repo.state_exists("nested"));
+
+    // Relocated worktrees are not mistaken for orphans
+    let prune = repo.hn(&["prune"]);
+    prune.assert_success();
+    assert!(repo.state_exists("nested"));
+
+    let state = repo.hn(&["state", "list"]);
+    state.assert_success();
+    assert!(state.stdout.contains("nested"));
+    assert!(state.stdout.contains("active"));
+}
+
+#[test]
+fn test_config_validate_rejects_pattern_without_name() {
+    let repo = TestRepo::new();
+    repo.create_config(
+        r#"
+worktrees:
+  root: "~/wt/{repo}"
+"#,
+    );
+
+    let result = repo.hn(&["config", "validate"]);
+    result.assert_failure();
+    assert!(
+        result.stdout.contains("{name}") || result.stderr.contains("{name}"),
+        "error should mention the missing placeholder: {}{}",
+        result.stdout,
+        result.stderr
+    );
+}